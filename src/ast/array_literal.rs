@@ -1,16 +1,15 @@
+use std::rc::Rc;
+
 use crate::token::Token;
 
 use super::*;
 
 pub struct ArrayLiteral {
     pub token: Token,
-    pub elements: Vec<Rc<dyn Expression>>,
+    pub elements: Vec<Rc<Expr>>,
 }
 
 impl Node for ArrayLiteral {
-    fn get_type(&self) -> NodeType {
-        return NodeType::ArrayLiteral;
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -23,14 +22,4 @@ impl Node for ArrayLiteral {
 
         format!("[{}]", elements.join(", "))
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_array_literal(&self) -> Result<&ArrayLiteral, Error> {
-        Ok(self)
-    }
-}
-
-impl Expression for ArrayLiteral {
-    fn exporession_node(&mut self) {}
 }
@@ -0,0 +1,24 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+pub struct AssignExpression {
+    pub token: Token,
+    pub target: Rc<Expr>,
+    pub value: Rc<Expr>,
+}
+
+impl Node for AssignExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        format!(
+            "({} = {})",
+            self.target.to_string(),
+            self.value.to_string()
+        )
+    }
+}
@@ -6,13 +6,10 @@ use super::*;
 
 pub struct BlockStatement {
     pub token: Token,
-    pub statements: Vec<Rc<dyn Statement>>,
+    pub statements: Vec<Rc<Stmt>>,
 }
 
 impl Node for BlockStatement {
-    fn get_type(&self) -> NodeType {
-        return NodeType::BlockStatement;
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -24,14 +21,4 @@ impl Node for BlockStatement {
         }
         out
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_block_stmt(&self) -> Result<&BlockStatement, Error> {
-        Ok(self)
-    }
-}
-
-impl Statement for BlockStatement {
-    fn statement_node(&mut self) {}
 }
@@ -6,14 +6,11 @@ use super::*;
 
 pub struct CallExpression {
     pub token: Token,
-    pub function: Rc<dyn Expression>,
-    pub arguments: Vec<Rc<dyn Expression>>,
+    pub function: Rc<Expr>,
+    pub arguments: Vec<Rc<Expr>>,
 }
 
 impl Node for CallExpression {
-    fn get_type(&self) -> NodeType {
-        return NodeType::CallExpression
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -26,14 +23,4 @@ impl Node for CallExpression {
 
         format!("{}({})", self.function.to_string(), args.join(", "),)
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_call_expr(&self) -> Result<&CallExpression, Error> {
-        Ok(self)
-    }
-}
-
-impl Expression for CallExpression {
-    fn exporession_node(&mut self) {}
 }
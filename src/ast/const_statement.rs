@@ -0,0 +1,29 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+pub struct ConstStatement {
+    pub token: Token,
+    pub name: Identifier,
+    pub value: Option<Rc<Expr>>,
+}
+
+impl Node for ConstStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn to_string(&self) -> String {
+        match &self.value {
+            Some(value) => format!(
+                "{} {} = {};",
+                self.token_literal(),
+                self.name.to_string(),
+                value.to_string()
+            ),
+            None => format!("{} {} = ;", self.token_literal(), self.name.to_string()),
+        }
+    }
+}
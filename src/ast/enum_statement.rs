@@ -0,0 +1,45 @@
+use crate::token::Token;
+
+use super::*;
+
+pub struct EnumVariant {
+    pub name: Identifier,
+    pub fields: Vec<Identifier>,
+}
+
+impl fmt::Display for EnumVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.fields.is_empty() {
+            return write!(f, "{}", self.name.to_string());
+        }
+
+        let fields: Vec<String> = self.fields.iter().map(|field| field.to_string()).collect();
+        write!(f, "{}({})", self.name.to_string(), fields.join(", "))
+    }
+}
+
+pub struct EnumStatement {
+    pub token: Token,
+    pub name: Identifier,
+    pub variants: Vec<EnumVariant>,
+}
+
+impl Node for EnumStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        let variants: Vec<String> = self
+            .variants
+            .iter()
+            .map(|variant| variant.to_string())
+            .collect();
+
+        format!(
+            "{} {} {{ {} }}",
+            self.token_literal(),
+            self.name.to_string(),
+            variants.join(", ")
+        )
+    }
+}
@@ -0,0 +1,20 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+pub struct ExportStatement {
+    pub token: Token,
+    pub name: Identifier,
+    pub statement: Rc<Stmt>,
+}
+
+impl Node for ExportStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        format!("{} {}", self.token_literal(), self.statement.to_string())
+    }
+}
@@ -1,19 +1,30 @@
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc};
 
 use crate::token::Token;
 
 use super::*;
 
+// the bindings outside the function its body can read, as the name and how many
+// environments out from the one the function is created in. Filled in by the
+// resolver. `Dynamic` marks bodies it can't see all of, like quoted code or macros
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum Captures {
+    #[default]
+    Unresolved,
+    Resolved(Vec<(String, usize)>),
+    Dynamic,
+}
+
 pub struct FunctionLiteral {
     pub token: Token,
     pub parameters: Rc<Vec<Rc<Identifier>>>,
     pub body: Rc<BlockStatement>,
+    pub return_type: Option<TypeAnnotation>,
+    pub generator: bool,
+    pub captures: RefCell<Captures>,
 }
 
 impl Node for FunctionLiteral {
-    fn get_type(&self) -> NodeType {
-        return NodeType::FunctionLiteral;
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -24,21 +35,26 @@ impl Node for FunctionLiteral {
             params.push(p.to_string());
         }
 
-        format!(
-            "{} ( {} ) {}",
-            self.token_literal(),
-            params.join(", "),
-            self.body.to_string()
-        )
-    }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_fn_literal(&self) -> Result<&FunctionLiteral, Error> {
-        Ok(self)
-    }
-}
+        let keyword = if self.generator {
+            format!("{}*", self.token_literal())
+        } else {
+            self.token_literal()
+        };
 
-impl Expression for FunctionLiteral {
-    fn exporession_node(&mut self) {}
+        match &self.return_type {
+            Some(return_type) => format!(
+                "{} ( {} ) -> {} {}",
+                keyword,
+                params.join(", "),
+                return_type,
+                self.body.to_string()
+            ),
+            None => format!(
+                "{} ( {} ) {}",
+                keyword,
+                params.join(", "),
+                self.body.to_string()
+            ),
+        }
+    }
 }
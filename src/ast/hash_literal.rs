@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::rc::Rc;
 
 use crate::token::Token;
 
@@ -6,13 +6,10 @@ use super::*;
 
 pub struct HashLiteral {
     pub token: Token,
-    pub pairs: HashMap<Rc<dyn Expression>, Rc<dyn Expression>>,
+    pub pairs: Vec<(Rc<Expr>, Rc<Expr>)>,
 }
 
 impl Node for HashLiteral {
-    fn get_type(&self) -> NodeType {
-        return NodeType::HashLiteral;
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -25,14 +22,4 @@ impl Node for HashLiteral {
 
         format!("{{{}}}", pairs.join(", "))
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_hash_literal(&self) -> Result<&HashLiteral, Error> {
-        Ok(self)
-    }
-}
-
-impl Expression for HashLiteral {
-    fn exporession_node(&mut self) {}
 }
@@ -1,31 +1,37 @@
+use std::cell::Cell;
+
 use crate::token::Token;
 
 use super::*;
 
+// where the resolver found the binding: `depth` environments out, at `slot`.
+// `Dynamic` marks identifiers resolved differently at different sites, which happens
+// when macro expansion shares one node between several places
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Slot {
+    #[default]
+    Unresolved,
+    Resolved(usize, usize),
+    Dynamic,
+}
+
+#[derive(Clone)]
 pub struct Identifier {
     pub token: Token,
     pub value: String,
+    pub type_annotation: Option<TypeAnnotation>,
+    pub slot: Cell<Slot>,
 }
 
 impl Node for Identifier {
-    fn get_type(&self) -> NodeType {
-        return NodeType::Identifier
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
 
     fn to_string(&self) -> String {
-        self.value.clone()
+        match &self.type_annotation {
+            Some(annotation) => format!("{}: {}", self.value, annotation),
+            None => self.value.clone(),
+        }
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_identifier(&self) -> Result<&Identifier, Error> {
-        Ok(self)
-    }
-}
-
-impl Expression for Identifier {
-    fn exporession_node(&mut self) {}
 }
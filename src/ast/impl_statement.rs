@@ -0,0 +1,53 @@
+use crate::token::Token;
+
+use super::*;
+
+pub struct ImplMethod {
+    pub name: Identifier,
+    pub function: FunctionLiteral,
+}
+
+impl fmt::Display for ImplMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params: Vec<String> = self
+            .function
+            .parameters
+            .iter()
+            .map(|param| param.to_string())
+            .collect();
+
+        let keyword = if self.function.generator {
+            format!("{}*", self.function.token_literal())
+        } else {
+            self.function.token_literal()
+        };
+
+        write!(f, "{} {} ( {} ) ", keyword, self.name.to_string(), params.join(", "))?;
+        if let Some(return_type) = &self.function.return_type {
+            write!(f, "-> {} ", return_type)?;
+        }
+        write!(f, "{}", self.function.body.to_string())
+    }
+}
+
+pub struct ImplStatement {
+    pub token: Token,
+    pub type_name: Identifier,
+    pub methods: Vec<ImplMethod>,
+}
+
+impl Node for ImplStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        let methods: Vec<String> = self.methods.iter().map(|method| method.to_string()).collect();
+
+        format!(
+            "{} {} {{ {} }}",
+            self.token_literal(),
+            self.type_name.to_string(),
+            methods.join(" ")
+        )
+    }
+}
@@ -0,0 +1,23 @@
+use crate::token::Token;
+
+use super::*;
+
+pub struct ImportStatement {
+    pub token: Token,
+    pub path: String,
+    pub alias: Identifier,
+}
+
+impl Node for ImportStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        format!(
+            "{} \"{}\" as {};",
+            self.token_literal(),
+            self.path,
+            self.alias.to_string()
+        )
+    }
+}
@@ -6,15 +6,12 @@ use super::*;
 
 pub struct InfixExpression {
     pub token: Token,
-    pub right: Option<Rc<dyn Expression>>,
+    pub right: Option<Rc<Expr>>,
     pub operator: String,
-    pub left: Option<Rc<dyn Expression>>,
+    pub left: Option<Rc<Expr>>,
 }
 
 impl Node for InfixExpression {
-    fn get_type(&self) -> NodeType {
-        return NodeType::InfixExpression
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -26,14 +23,4 @@ impl Node for InfixExpression {
             self.right.as_ref().unwrap().to_string()
         )
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_infix_expr(&self) -> Result<&InfixExpression, Error> {
-        Ok(self)
-    }
-}
-
-impl Expression for InfixExpression {
-    fn exporession_node(&mut self) {}
 }
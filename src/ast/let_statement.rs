@@ -7,13 +7,10 @@ use super::*;
 pub struct LetStatement {
     pub token: Token,
     pub name: Identifier,
-    pub value: Option<Rc<dyn Expression>>,
+    pub value: Option<Rc<Expr>>,
 }
 
 impl Node for LetStatement {
-    fn get_type(&self) -> NodeType {
-        return NodeType::LetStatement
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -30,14 +27,4 @@ impl Node for LetStatement {
             return format!("{} {} = ;", self.token_literal(), self.name.to_string(),);
         }
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_let_statement(&self) -> Result<&LetStatement, Error> {
-        Ok(self)
-    }
-}
-
-impl Statement for LetStatement {
-    fn statement_node(&mut self) {}
 }
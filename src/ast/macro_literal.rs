@@ -0,0 +1,31 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+pub struct MacroLiteral {
+    pub token: Token,
+    pub parameters: Rc<Vec<Rc<Identifier>>>,
+    pub body: Rc<BlockStatement>,
+}
+
+impl Node for MacroLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        let mut params = Vec::new();
+
+        for p in self.parameters.as_ref() {
+            params.push(p.to_string());
+        }
+
+        format!(
+            "{} ( {} ) {}",
+            self.token_literal(),
+            params.join(", "),
+            self.body.to_string()
+        )
+    }
+}
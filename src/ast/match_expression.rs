@@ -0,0 +1,111 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+#[derive(Clone)]
+pub enum Pattern {
+    Wildcard(Token),
+    Literal(Rc<Expr>),
+    Binding(Identifier),
+    Array {
+        token: Token,
+        elements: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    Hash {
+        token: Token,
+        pairs: Vec<(Rc<Expr>, Pattern)>,
+    },
+    Variant {
+        token: Token,
+        enum_name: Option<Identifier>,
+        name: Identifier,
+        fields: Vec<Pattern>,
+    },
+    Or(Vec<Pattern>),
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Wildcard(_) => write!(f, "_"),
+            Pattern::Literal(expr) => write!(f, "{}", expr.to_string()),
+            Pattern::Binding(ident) => write!(f, "{}", ident.to_string()),
+            Pattern::Array { elements, rest, .. } => {
+                let mut parts: Vec<String> = elements.iter().map(|el| el.to_string()).collect();
+                if let Some(rest) = rest {
+                    parts.push(format!("...{}", rest));
+                }
+                write!(f, "[{}]", parts.join(", "))
+            }
+            Pattern::Hash { pairs, .. } => {
+                let parts: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, pattern)| format!("{}:{}", key.to_string(), pattern))
+                    .collect();
+                write!(f, "{{{}}}", parts.join(", "))
+            }
+            Pattern::Variant {
+                enum_name,
+                name,
+                fields,
+                ..
+            } => {
+                if let Some(enum_name) = enum_name {
+                    write!(f, "{}.", enum_name.to_string())?;
+                }
+                write!(f, "{}", name.to_string())?;
+                if !fields.is_empty() {
+                    let parts: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+                    write!(f, "({})", parts.join(", "))?;
+                }
+                Ok(())
+            }
+            Pattern::Or(alternatives) => {
+                let parts: Vec<String> = alternatives.iter().map(|alt| alt.to_string()).collect();
+                write!(f, "{}", parts.join(" | "))
+            }
+        }
+    }
+}
+
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Rc<Expr>>,
+    pub body: BlockStatement,
+}
+
+pub struct MatchExpression {
+    pub token: Token,
+    pub subject: Rc<Expr>,
+    pub arms: Vec<MatchArm>,
+}
+
+impl Node for MatchExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        let mut arms = Vec::new();
+
+        for arm in &self.arms {
+            match &arm.guard {
+                Some(guard) => arms.push(format!(
+                    "{} if {} => {}",
+                    arm.pattern,
+                    guard.to_string(),
+                    arm.body.to_string()
+                )),
+                None => arms.push(format!("{} => {}", arm.pattern, arm.body.to_string())),
+            }
+        }
+
+        format!(
+            "match ({}) {{ {} }}",
+            self.subject.to_string(),
+            arms.join(", ")
+        )
+    }
+}
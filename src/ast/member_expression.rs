@@ -0,0 +1,24 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+pub struct MemberExpression {
+    pub token: Token,
+    pub object: Rc<Expr>,
+    pub property: Identifier,
+}
+
+impl Node for MemberExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        format!(
+            "({}.{})",
+            self.object.to_string(),
+            self.property.to_string()
+        )
+    }
+}
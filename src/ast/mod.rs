@@ -1,11 +1,20 @@
 mod program;
 
 mod block_statement;
+mod const_statement;
+mod enum_statement;
+mod export_statement;
 mod expression_statement;
+mod impl_statement;
+mod import_statement;
 mod let_statement;
 mod return_statement;
+mod struct_statement;
+mod throw_statement;
+mod yield_statement;
 
 mod array_literal;
+mod assign_expression;
 mod boolean;
 mod call_expression;
 mod function_literal;
@@ -15,206 +24,169 @@ mod if_expression;
 mod index_expression;
 mod infix_expression;
 mod integer_literal;
+mod macro_literal;
+mod match_expression;
+mod member_expression;
 mod prefix_expression;
 mod string_literal;
+mod try_expression;
+mod type_annotation;
+
+pub mod modify;
 
 use core::fmt;
-use std::{hash::Hash, rc::Rc};
+
+use crate::token::Token;
+
 
 pub use program::Program;
 
 pub use block_statement::BlockStatement;
 pub use call_expression::CallExpression;
+pub use const_statement::ConstStatement;
+pub use enum_statement::{EnumStatement, EnumVariant};
+pub use export_statement::ExportStatement;
 pub use expression_statement::ExpressionStetement;
-pub use function_literal::FunctionLiteral;
+pub use function_literal::{Captures, FunctionLiteral};
+pub use impl_statement::{ImplMethod, ImplStatement};
+pub use import_statement::ImportStatement;
 pub use let_statement::LetStatement;
 pub use return_statement::ReturnStatement;
+pub use struct_statement::StructStatement;
+pub use throw_statement::ThrowStatement;
+pub use yield_statement::YieldStatement;
 
 pub use array_literal::ArrayLiteral;
+pub use assign_expression::AssignExpression;
 pub use boolean::Boolean;
 pub use hash_literal::HashLiteral;
-pub use identifier::Identifier;
+pub use identifier::{Identifier, Slot};
 pub use if_expression::IfExpression;
 pub use index_expression::IndexExpression;
 pub use infix_expression::InfixExpression;
 pub use integer_literal::IntegerLiteral;
+pub use macro_literal::MacroLiteral;
+pub use match_expression::{MatchArm, MatchExpression, Pattern};
+pub use member_expression::MemberExpression;
 pub use prefix_expression::PrefixExpression;
 pub use string_literal::StringLiteral;
+pub use try_expression::TryExpression;
+pub use type_annotation::TypeAnnotation;
 
-type Error = String;
-
-#[derive(PartialEq, Debug, Eq, Clone)]
-pub enum NodeType {
-    ExpressionStetement,
-    IndexExpression,
-    PrefixExpression,
-    InfixExpression,
-    FunctionLiteral,
-    ReturnStatement,
-    IntegerLiteral,
-    BlockStatement,
-    CallExpression,
-    StringLiteral,
-    ArrayLiteral,
-    IfExpression,
-    LetStatement,
-    HashLiteral,
-    Identifier,
-    Boolean,
-    Program,
+pub trait Node {
+    fn token_literal(&self) -> String;
+    fn to_string(&self) -> String;
+}
+
+pub enum Stmt {
+    Let(LetStatement),
+    Const(ConstStatement),
+    Return(ReturnStatement),
+    Expression(ExpressionStetement),
+    Block(BlockStatement),
+    Struct(StructStatement),
+    Enum(EnumStatement),
+    Impl(ImplStatement),
+    Import(ImportStatement),
+    Export(ExportStatement),
+    Throw(ThrowStatement),
+    Yield(YieldStatement),
 }
 
-impl NodeType {
-    pub fn as_str(&self) -> &'static str {
+impl Stmt {
+    fn as_node(&self) -> &dyn Node {
         match self {
-            NodeType::ExpressionStetement => "ExpressionStetement",
-            NodeType::PrefixExpression => "PrefixExpression",
-            NodeType::InfixExpression => "InfixExpression",
-            NodeType::FunctionLiteral => "FunctionLiteral",
-            NodeType::IndexExpression => "IndexExpression",
-            NodeType::ReturnStatement => "ReturnStatement",
-            NodeType::IntegerLiteral => "IntegerLiteral",
-            NodeType::BlockStatement => "BlockStatement",
-            NodeType::CallExpression => "CallExpression",
-            NodeType::StringLiteral => "StringLiteral",
-            NodeType::ArrayLiteral => "ArrayLiteral",
-            NodeType::IfExpression => "IfExpression",
-            NodeType::LetStatement => "LetStatement",
-            NodeType::HashLiteral => "HashLiteral",
-            NodeType::Identifier => "Identifier",
-            NodeType::Boolean => "Boolean",
-            NodeType::Program => "Program",
+            Stmt::Let(stmt) => stmt,
+            Stmt::Const(stmt) => stmt,
+            Stmt::Return(stmt) => stmt,
+            Stmt::Expression(stmt) => stmt,
+            Stmt::Block(stmt) => stmt,
+            Stmt::Struct(stmt) => stmt,
+            Stmt::Enum(stmt) => stmt,
+            Stmt::Impl(stmt) => stmt,
+            Stmt::Import(stmt) => stmt,
+            Stmt::Export(stmt) => stmt,
+            Stmt::Throw(stmt) => stmt,
+            Stmt::Yield(stmt) => stmt,
         }
     }
-}
 
-impl fmt::Display for NodeType {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.as_str())
+    pub fn token(&self) -> &Token {
+        match self {
+            Stmt::Let(stmt) => &stmt.token,
+            Stmt::Const(stmt) => &stmt.token,
+            Stmt::Return(stmt) => &stmt.token,
+            Stmt::Expression(stmt) => &stmt.token,
+            Stmt::Block(stmt) => &stmt.token,
+            Stmt::Struct(stmt) => &stmt.token,
+            Stmt::Enum(stmt) => &stmt.token,
+            Stmt::Impl(stmt) => &stmt.token,
+            Stmt::Import(stmt) => &stmt.token,
+            Stmt::Export(stmt) => &stmt.token,
+            Stmt::Throw(stmt) => &stmt.token,
+            Stmt::Yield(stmt) => &stmt.token,
+        }
     }
 }
 
-pub trait Node {
-    fn get_type(&self) -> NodeType;
-    fn token_literal(&self) -> String;
-    fn to_string(&self) -> String;
-    fn as_node(&self) -> Rc<&dyn Node>;
-    fn try_into_if_expr(&self) -> Result<&IfExpression, Error> {
-        Err(format!(
-            "can't cast from {} to IfExpression",
-            self.get_type()
-        ))
-    }
-    fn try_into_prefix_expr(&self) -> Result<&PrefixExpression, Error> {
-        Err(format!(
-            "can't cast from {} to PrefixExpression",
-            self.get_type()
-        ))
-    }
-    fn try_into_infix_expr(&self) -> Result<&InfixExpression, Error> {
-        Err(format!(
-            "can't cast from {} to InfixExpression",
-            self.get_type()
-        ))
-    }
-    fn try_into_expr_stmt(&self) -> Result<&ExpressionStetement, Error> {
-        Err(format!(
-            "can't cast from {} to ExpressionStetement",
-            self.get_type()
-        ))
-    }
-    fn try_into_int_literal(&self) -> Result<&IntegerLiteral, Error> {
-        Err(format!(
-            "can't cast from {} to IntegerLiteral",
-            self.get_type()
-        ))
+impl Node for Stmt {
+    fn token_literal(&self) -> String {
+        self.as_node().token_literal()
     }
-    fn try_into_program(&self) -> Result<&Program, Error> {
-        Err(format!("can't cast from {} to Program", self.get_type()))
+    fn to_string(&self) -> String {
+        self.as_node().to_string()
     }
-    fn try_into_boolean(&self) -> Result<&Boolean, Error> {
-        Err(format!("can't cast from {} to Boolean", self.get_type()))
-    }
-    fn try_into_return_stmt(&self) -> Result<&ReturnStatement, Error> {
-        Err(format!(
-            "can't cast from {} to ReturnStatement",
-            self.get_type()
-        ))
-    }
-    fn try_into_let_statement(&self) -> Result<&LetStatement, Error> {
-        Err(format!(
-            "can't cast from {} to LetStatement",
-            self.get_type()
-        ))
-    }
-    fn try_into_identifier(&self) -> Result<&Identifier, Error> {
-        Err(format!("can't cast from {} to Identifier", self.get_type()))
-    }
-    fn try_into_fn_literal(&self) -> Result<&FunctionLiteral, Error> {
-        Err(format!(
-            "can't cast from {} to FunctionLiteral",
-            self.get_type()
-        ))
-    }
-    fn try_into_call_expr(&self) -> Result<&CallExpression, Error> {
-        Err(format!(
-            "can't cast from {} to CallExpression",
-            self.get_type()
-        ))
-    }
-    fn try_into_block_stmt(&self) -> Result<&BlockStatement, Error> {
-        Err(format!(
-            "can't cast from {} to BlockStatement",
-            self.get_type()
-        ))
-    }
-    fn try_into_str_literal(&self) -> Result<&StringLiteral, Error> {
-        Err(format!(
-            "can't cast from {} to StringLiteral",
-            self.get_type()
-        ))
-    }
-    fn try_into_array_literal(&self) -> Result<&ArrayLiteral, Error> {
-        Err(format!(
-            "can't cast from {} to ArrayLiteral",
-            self.get_type()
-        ))
-    }
-    fn try_into_index_expr(&self) -> Result<&IndexExpression, Error> {
-        Err(format!(
-            "can't cast from {} to IndexExpression",
-            self.get_type()
-        ))
-    }
-    fn try_into_hash_literal(&self) -> Result<&HashLiteral, Error> {
-        Err(format!(
-            "can't cast from {} to HashLiteral",
-            self.get_type()
-        ))
-    }
-}
-
-pub trait Statement: Node {
-    fn statement_node(&mut self) {}
 }
 
-pub trait Expression: Node {
-    fn exporession_node(&mut self) {}
+pub enum Expr {
+    Identifier(Identifier),
+    Integer(IntegerLiteral),
+    String(StringLiteral),
+    Boolean(Boolean),
+    Prefix(PrefixExpression),
+    Infix(InfixExpression),
+    If(IfExpression),
+    Function(FunctionLiteral),
+    Call(CallExpression),
+    Array(ArrayLiteral),
+    Hash(HashLiteral),
+    Index(IndexExpression),
+    Member(MemberExpression),
+    Assign(AssignExpression),
+    Match(MatchExpression),
+    Try(TryExpression),
+    Macro(MacroLiteral),
 }
 
-impl Hash for dyn Expression {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        let ptr = self as *const dyn Expression as *const ();
-        ptr.hash(state)
+impl Expr {
+    fn as_node(&self) -> &dyn Node {
+        match self {
+            Expr::Identifier(expr) => expr,
+            Expr::Integer(expr) => expr,
+            Expr::String(expr) => expr,
+            Expr::Boolean(expr) => expr,
+            Expr::Prefix(expr) => expr,
+            Expr::Infix(expr) => expr,
+            Expr::If(expr) => expr,
+            Expr::Function(expr) => expr,
+            Expr::Call(expr) => expr,
+            Expr::Array(expr) => expr,
+            Expr::Hash(expr) => expr,
+            Expr::Index(expr) => expr,
+            Expr::Member(expr) => expr,
+            Expr::Assign(expr) => expr,
+            Expr::Match(expr) => expr,
+            Expr::Try(expr) => expr,
+            Expr::Macro(expr) => expr,
+        }
     }
 }
 
-impl PartialEq for dyn Expression {
-    fn eq(&self, other: &Self) -> bool {
-        let ptr1 = self as *const dyn Expression as *const ();
-        let ptr2 = other as *const dyn Expression as *const ();
-        ptr1 == ptr2
+impl Node for Expr {
+    fn token_literal(&self) -> String {
+        self.as_node().token_literal()
+    }
+    fn to_string(&self) -> String {
+        self.as_node().to_string()
     }
 }
-
-impl Eq for dyn Expression {}
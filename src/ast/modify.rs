@@ -0,0 +1,203 @@
+use std::rc::Rc;
+
+use super::*;
+
+pub type Modifier<'a> = dyn FnMut(Rc<Expr>) -> Rc<Expr> + 'a;
+
+pub fn modify_program(program: &Program, modifier: &mut Modifier) -> Program {
+    Program {
+        statements: program
+            .statements
+            .iter()
+            .map(|stmt| modify_statement(stmt.clone(), modifier))
+            .collect(),
+    }
+}
+
+pub fn modify_statement(stmt: Rc<Stmt>, modifier: &mut Modifier) -> Rc<Stmt> {
+    let modified = match stmt.as_ref() {
+        Stmt::Expression(expr_stmt) => Stmt::Expression(ExpressionStetement {
+            token: expr_stmt.token.clone(),
+            expression: modify_optional(&expr_stmt.expression, modifier),
+        }),
+        Stmt::Let(let_stmt) => Stmt::Let(LetStatement {
+            token: let_stmt.token.clone(),
+            name: let_stmt.name.clone(),
+            value: modify_optional(&let_stmt.value, modifier),
+        }),
+        Stmt::Const(const_stmt) => Stmt::Const(ConstStatement {
+            token: const_stmt.token.clone(),
+            name: const_stmt.name.clone(),
+            value: modify_optional(&const_stmt.value, modifier),
+        }),
+        Stmt::Return(return_stmt) => Stmt::Return(ReturnStatement {
+            token: return_stmt.token.clone(),
+            return_value: modify_optional(&return_stmt.return_value, modifier),
+        }),
+        Stmt::Throw(throw_stmt) => Stmt::Throw(ThrowStatement {
+            token: throw_stmt.token.clone(),
+            value: modify_optional(&throw_stmt.value, modifier),
+        }),
+        Stmt::Yield(yield_stmt) => Stmt::Yield(YieldStatement {
+            token: yield_stmt.token.clone(),
+            value: modify_optional(&yield_stmt.value, modifier),
+            delegate: yield_stmt.delegate,
+        }),
+        Stmt::Export(export_stmt) => Stmt::Export(ExportStatement {
+            token: export_stmt.token.clone(),
+            name: export_stmt.name.clone(),
+            statement: modify_statement(export_stmt.statement.clone(), modifier),
+        }),
+        Stmt::Impl(impl_stmt) => Stmt::Impl(ImplStatement {
+            token: impl_stmt.token.clone(),
+            type_name: impl_stmt.type_name.clone(),
+            methods: impl_stmt
+                .methods
+                .iter()
+                .map(|method| ImplMethod {
+                    name: method.name.clone(),
+                    function: modify_function(&method.function, modifier),
+                })
+                .collect(),
+        }),
+        Stmt::Block(block) => Stmt::Block(modify_block(block, modifier)),
+        Stmt::Struct(_) | Stmt::Enum(_) | Stmt::Import(_) => return stmt,
+    };
+
+    Rc::new(modified)
+}
+
+pub fn modify_block(block: &BlockStatement, modifier: &mut Modifier) -> BlockStatement {
+    BlockStatement {
+        token: block.token.clone(),
+        statements: block
+            .statements
+            .iter()
+            .map(|stmt| modify_statement(stmt.clone(), modifier))
+            .collect(),
+    }
+}
+
+fn modify_function(function: &FunctionLiteral, modifier: &mut Modifier) -> FunctionLiteral {
+    FunctionLiteral {
+        token: function.token.clone(),
+        parameters: function.parameters.clone(),
+        body: Rc::new(modify_block(&function.body, modifier)),
+        return_type: function.return_type.clone(),
+        generator: function.generator,
+        captures: function.captures.clone(),
+    }
+}
+
+pub fn modify_expression(expr: Rc<Expr>, modifier: &mut Modifier) -> Rc<Expr> {
+    let modified = match expr.as_ref() {
+        Expr::Prefix(prefix_expr) => Rc::new(Expr::Prefix(PrefixExpression {
+            token: prefix_expr.token.clone(),
+            operator: prefix_expr.operator.clone(),
+            right: modify_expression(prefix_expr.right.clone(), modifier),
+        })),
+        Expr::Infix(infix_expr) => Rc::new(Expr::Infix(InfixExpression {
+            token: infix_expr.token.clone(),
+            operator: infix_expr.operator.clone(),
+            left: modify_optional(&infix_expr.left, modifier),
+            right: modify_optional(&infix_expr.right, modifier),
+        })),
+        Expr::Index(index_expr) => Rc::new(Expr::Index(IndexExpression {
+            token: index_expr.token.clone(),
+            left: modify_expression(index_expr.left.clone(), modifier),
+            index: modify_expression(index_expr.index.clone(), modifier),
+        })),
+        Expr::Member(member_expr) => Rc::new(Expr::Member(MemberExpression {
+            token: member_expr.token.clone(),
+            object: modify_expression(member_expr.object.clone(), modifier),
+            property: member_expr.property.clone(),
+        })),
+        Expr::Assign(assign_expr) => Rc::new(Expr::Assign(AssignExpression {
+            token: assign_expr.token.clone(),
+            target: modify_expression(assign_expr.target.clone(), modifier),
+            value: modify_expression(assign_expr.value.clone(), modifier),
+        })),
+        Expr::If(if_expr) => Rc::new(Expr::If(IfExpression {
+            token: if_expr.token.clone(),
+            condition: modify_optional(&if_expr.condition, modifier),
+            consequence: if_expr
+                .consequence
+                .as_ref()
+                .map(|block| modify_block(block, modifier)),
+            alternative: if_expr
+                .alternative
+                .as_ref()
+                .map(|block| modify_block(block, modifier)),
+        })),
+        Expr::Try(try_expr) => Rc::new(Expr::Try(TryExpression {
+            token: try_expr.token.clone(),
+            block: modify_block(&try_expr.block, modifier),
+            catch_param: try_expr.catch_param.clone(),
+            catch_block: try_expr
+                .catch_block
+                .as_ref()
+                .map(|block| modify_block(block, modifier)),
+            finally_block: try_expr
+                .finally_block
+                .as_ref()
+                .map(|block| modify_block(block, modifier)),
+        })),
+        Expr::Function(fn_literal) => {
+            Rc::new(Expr::Function(modify_function(fn_literal, modifier)))
+        }
+        Expr::Call(call_expr) => Rc::new(Expr::Call(CallExpression {
+            token: call_expr.token.clone(),
+            function: modify_expression(call_expr.function.clone(), modifier),
+            arguments: modify_list(&call_expr.arguments, modifier),
+        })),
+        Expr::Array(array_literal) => Rc::new(Expr::Array(ArrayLiteral {
+            token: array_literal.token.clone(),
+            elements: modify_list(&array_literal.elements, modifier),
+        })),
+        Expr::Hash(hash_literal) => Rc::new(Expr::Hash(HashLiteral {
+            token: hash_literal.token.clone(),
+            pairs: hash_literal
+                .pairs
+                .iter()
+                .map(|(key, value)| {
+                    (
+                        modify_expression(key.clone(), modifier),
+                        modify_expression(value.clone(), modifier),
+                    )
+                })
+                .collect(),
+        })),
+        Expr::Match(match_expr) => Rc::new(Expr::Match(MatchExpression {
+            token: match_expr.token.clone(),
+            subject: modify_expression(match_expr.subject.clone(), modifier),
+            arms: match_expr
+                .arms
+                .iter()
+                .map(|arm| MatchArm {
+                    pattern: arm.pattern.clone(),
+                    guard: modify_optional(&arm.guard, modifier),
+                    body: modify_block(&arm.body, modifier),
+                })
+                .collect(),
+        })),
+        Expr::Identifier(_)
+        | Expr::Integer(_)
+        | Expr::String(_)
+        | Expr::Boolean(_)
+        | Expr::Macro(_) => expr,
+    };
+
+    modifier(modified)
+}
+
+fn modify_optional(expr: &Option<Rc<Expr>>, modifier: &mut Modifier) -> Option<Rc<Expr>> {
+    expr.as_ref()
+        .map(|expr| modify_expression(expr.clone(), modifier))
+}
+
+fn modify_list(exprs: &[Rc<Expr>], modifier: &mut Modifier) -> Vec<Rc<Expr>> {
+    exprs
+        .iter()
+        .map(|expr| modify_expression(expr.clone(), modifier))
+        .collect()
+}
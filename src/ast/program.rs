@@ -3,13 +3,10 @@ use std::rc::Rc;
 use super::*;
 
 pub struct Program {
-    pub statements: Vec<Rc<dyn Statement>>,
+    pub statements: Vec<Rc<Stmt>>,
 }
 
 impl Node for Program {
-    fn get_type(&self) -> NodeType {
-        return NodeType::Program;
-    }
     fn token_literal(&self) -> String {
         if self.statements.len() > 0 {
             self.statements[0].token_literal()
@@ -24,10 +21,4 @@ impl Node for Program {
         }
         out
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_program(&self) -> Result<&Program, Error> {
-        Ok(self)
-    }
 }
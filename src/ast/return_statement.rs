@@ -6,13 +6,10 @@ use super::*;
 
 pub struct ReturnStatement {
     pub token: Token,
-    pub return_value: Option<Rc<dyn Expression>>,
+    pub return_value: Option<Rc<Expr>>,
 }
 
 impl Node for ReturnStatement {
-    fn get_type(&self) -> NodeType {
-        return NodeType::ReturnStatement;
-    }
     fn token_literal(&self) -> String {
         self.token.literal.clone()
     }
@@ -25,14 +22,4 @@ impl Node for ReturnStatement {
             return format!("{} ;", self.token_literal());
         }
     }
-    fn as_node(&self) -> Rc<&dyn Node> {
-        Rc::new(self)
-    }
-    fn try_into_return_stmt(&self) -> Result<&ReturnStatement, Error> {
-        Ok(self)
-    }
-}
-
-impl Statement for ReturnStatement {
-    fn statement_node(&mut self) {}
 }
@@ -0,0 +1,25 @@
+use crate::token::Token;
+
+use super::*;
+
+pub struct StructStatement {
+    pub token: Token,
+    pub name: Identifier,
+    pub fields: Vec<Identifier>,
+}
+
+impl Node for StructStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        let fields: Vec<String> = self.fields.iter().map(|field| field.to_string()).collect();
+
+        format!(
+            "{} {} {{ {} }}",
+            self.token_literal(),
+            self.name.to_string(),
+            fields.join(", ")
+        )
+    }
+}
@@ -0,0 +1,22 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+pub struct ThrowStatement {
+    pub token: Token,
+    pub value: Option<Rc<Expr>>,
+}
+
+impl Node for ThrowStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        match &self.value {
+            Some(value) => format!("{} {};", self.token_literal(), value.to_string()),
+            None => format!("{} ;", self.token_literal()),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::token::Token;
+
+use super::*;
+
+pub struct TryExpression {
+    pub token: Token,
+    pub block: BlockStatement,
+    pub catch_param: Option<Identifier>,
+    pub catch_block: Option<BlockStatement>,
+    pub finally_block: Option<BlockStatement>,
+}
+
+impl Node for TryExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        let mut out = format!("try {}", self.block.to_string());
+
+        if let Some(catch_block) = &self.catch_block {
+            match &self.catch_param {
+                Some(param) => out.push_str(&format!(
+                    " catch ({}) {}",
+                    param.to_string(),
+                    catch_block.to_string()
+                )),
+                None => out.push_str(&format!(" catch {}", catch_block.to_string())),
+            }
+        }
+
+        if let Some(finally_block) = &self.finally_block {
+            out.push_str(&format!(" finally {}", finally_block.to_string()));
+        }
+
+        out
+    }
+}
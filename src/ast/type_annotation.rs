@@ -0,0 +1,33 @@
+use super::*;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TypeAnnotation {
+    Int,
+    Bool,
+    String,
+    Null,
+    Any,
+    Array(Box<TypeAnnotation>),
+    Hash(Box<TypeAnnotation>, Box<TypeAnnotation>),
+    Function(Vec<TypeAnnotation>, Box<TypeAnnotation>),
+    Named(String),
+}
+
+impl fmt::Display for TypeAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeAnnotation::Int => write!(f, "int"),
+            TypeAnnotation::Bool => write!(f, "bool"),
+            TypeAnnotation::String => write!(f, "string"),
+            TypeAnnotation::Null => write!(f, "null"),
+            TypeAnnotation::Any => write!(f, "any"),
+            TypeAnnotation::Array(element) => write!(f, "[{}]", element),
+            TypeAnnotation::Hash(key, value) => write!(f, "{{{}: {}}}", key, value),
+            TypeAnnotation::Function(params, ret) => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "fn({}) -> {}", params.join(", "), ret)
+            }
+            TypeAnnotation::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
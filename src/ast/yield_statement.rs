@@ -0,0 +1,29 @@
+use std::rc::Rc;
+
+use crate::token::Token;
+
+use super::*;
+
+pub struct YieldStatement {
+    pub token: Token,
+    pub value: Option<Rc<Expr>>,
+    pub delegate: bool,
+}
+
+impl Node for YieldStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+    fn to_string(&self) -> String {
+        let keyword = if self.delegate {
+            format!("{}*", self.token_literal())
+        } else {
+            self.token_literal()
+        };
+
+        match &self.value {
+            Some(value) => format!("{} {};", keyword, value.to_string()),
+            None => format!("{} ;", keyword),
+        }
+    }
+}
@@ -0,0 +1,621 @@
+use std::{collections::HashSet, rc::Rc};
+
+use crate::{
+    code::{read_u16, Opcode, NO_TARGET},
+    object::{CompiledFunction, EnumType, MatchPattern, StructType, Value, Variant},
+};
+
+// A bytecode file starts with a header of the magic bytes, the format version, the
+// length of the body and a checksum over the body. The body is the main function:
+//
+//   literal, parameters, locals, generator flag
+//   constant pool
+//   nested functions, each laid out the same way
+//   match patterns
+//   instruction stream
+//   line table
+//
+// Numbers are big-endian like instruction operands, lengths and counts are u32.
+pub const MAGIC: &[u8; 4] = b"RSC\0";
+// bump whenever the layout or the opcode numbering changes
+pub const VERSION: u16 = 2;
+
+const HEADER_LEN: usize = 4 + 2 + 4 + 8;
+
+// slots are u16 operands, so no scope can need more
+const MAX_SLOTS: usize = 1 << 16;
+// how deeply functions, constants and patterns may nest, so reading a file can't
+// overflow the stack
+const MAX_NESTING: usize = 256;
+
+const CONST_INTEGER: u8 = 0;
+const CONST_STRING: u8 = 1;
+const CONST_BOOLEAN: u8 = 2;
+const CONST_NULL: u8 = 3;
+const CONST_ARRAY: u8 = 4;
+const CONST_STRUCT_TYPE: u8 = 5;
+const CONST_ENUM_TYPE: u8 = 6;
+
+const PATTERN_WILDCARD: u8 = 0;
+const PATTERN_LITERAL: u8 = 1;
+const PATTERN_BINDING: u8 = 2;
+const PATTERN_ARRAY: u8 = 3;
+const PATTERN_HASH: u8 = 4;
+const PATTERN_VARIANT: u8 = 5;
+const PATTERN_OR: u8 = 6;
+
+pub fn encode(main: &CompiledFunction) -> Result<Vec<u8>, String> {
+    let mut body = Writer::default();
+    body.function(main)?;
+
+    let mut file = Writer::default();
+    file.bytes.extend_from_slice(MAGIC);
+    file.u16(VERSION);
+    file.len(body.bytes.len())?;
+    file.bytes.extend_from_slice(&checksum(&body.bytes).to_be_bytes());
+    file.bytes.extend(body.bytes);
+
+    Ok(file.bytes)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Rc<CompiledFunction>, String> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+        return Err("not a bytecode file: missing header".to_string());
+    }
+
+    let mut header = Reader::new(&bytes[4..HEADER_LEN]);
+    let version = header.u16()?;
+    if version != VERSION {
+        return Err(format!(
+            "unsupported bytecode version {}, expected {}",
+            version, VERSION
+        ));
+    }
+
+    let len = header.u32()? as usize;
+    let expected = u64::from_be_bytes(header.take(8)?.try_into().unwrap());
+    let body = &bytes[HEADER_LEN..];
+    if body.len() != len {
+        return Err(corrupted(format!("body is {} bytes, header says {}", body.len(), len)));
+    }
+    if checksum(body) != expected {
+        return Err(corrupted("checksum mismatch"));
+    }
+
+    let mut reader = Reader::new(body);
+    let main = reader.function()?;
+    if reader.pos != body.len() {
+        return Err(corrupted("trailing data after the main function"));
+    }
+
+    Ok(main)
+}
+
+fn corrupted(detail: impl std::fmt::Display) -> String {
+    format!("corrupted bytecode file: {}", detail)
+}
+
+// FNV-1a, like object::get_fnv_a_hash but over bytes
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn len(&mut self, value: usize) -> Result<(), String> {
+        let value = u32::try_from(value).map_err(|_| "too large for a bytecode file".to_string())?;
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+        Ok(())
+    }
+
+    fn string(&mut self, value: &str) -> Result<(), String> {
+        self.len(value.len())?;
+        self.bytes.extend_from_slice(value.as_bytes());
+        Ok(())
+    }
+
+    fn strings(&mut self, values: &[String]) -> Result<(), String> {
+        self.len(values.len())?;
+        for value in values {
+            self.string(value)?;
+        }
+        Ok(())
+    }
+
+    fn function(&mut self, function: &CompiledFunction) -> Result<(), String> {
+        self.string(&function.literal)?;
+        self.strings(&function.parameters)?;
+        self.len(function.num_locals)?;
+        self.u8(function.generator as u8);
+
+        self.len(function.constants.len())?;
+        for constant in &function.constants {
+            self.constant(constant)?;
+        }
+
+        self.len(function.functions.len())?;
+        for nested in &function.functions {
+            self.function(nested)?;
+        }
+
+        self.len(function.patterns.len())?;
+        for pattern in &function.patterns {
+            self.pattern(pattern)?;
+        }
+
+        self.len(function.instructions.len())?;
+        self.bytes.extend_from_slice(&function.instructions);
+
+        self.len(function.lines.len())?;
+        for (offset, line) in &function.lines {
+            self.len(*offset)?;
+            self.len(*line)?;
+        }
+
+        Ok(())
+    }
+
+    fn constant(&mut self, constant: &Value) -> Result<(), String> {
+        match constant {
+            Value::Integer(value) => {
+                self.u8(CONST_INTEGER);
+                self.bytes.extend_from_slice(&value.to_be_bytes());
+            }
+            Value::String(value) => {
+                self.u8(CONST_STRING);
+                self.string(value)?;
+            }
+            Value::Boolean(value) => {
+                self.u8(CONST_BOOLEAN);
+                self.u8(*value as u8);
+            }
+            Value::Null => self.u8(CONST_NULL),
+            Value::Array(array) => {
+                self.u8(CONST_ARRAY);
+                self.len(array.elements.len())?;
+                for element in &array.elements {
+                    self.constant(element)?;
+                }
+            }
+            Value::StructType(struct_type) => {
+                self.u8(CONST_STRUCT_TYPE);
+                self.string(&struct_type.name)?;
+                self.strings(&struct_type.fields)?;
+            }
+            Value::EnumType(enum_type) => {
+                self.u8(CONST_ENUM_TYPE);
+                self.string(&enum_type.name)?;
+                self.len(enum_type.variants.len())?;
+                for variant in &enum_type.variants {
+                    self.string(&variant.name)?;
+                    self.strings(&variant.fields)?;
+                }
+            }
+            // quoted code and macros hold syntax trees, which have no binary form
+            _ => {
+                return Err(format!(
+                    "cannot write a {} constant to a bytecode file",
+                    constant.get_type()
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn pattern(&mut self, pattern: &MatchPattern) -> Result<(), String> {
+        match pattern {
+            MatchPattern::Wildcard => self.u8(PATTERN_WILDCARD),
+            MatchPattern::Literal(value) => {
+                self.u8(PATTERN_LITERAL);
+                self.constant(value)?;
+            }
+            MatchPattern::Binding { slot, name, shadowed } => {
+                self.u8(PATTERN_BINDING);
+                self.len(*slot)?;
+                self.string(name)?;
+                self.len(shadowed.len())?;
+                for (depth, slot) in shadowed {
+                    self.len(*depth)?;
+                    self.len(*slot)?;
+                }
+            }
+            MatchPattern::Array { elements, rest } => {
+                self.u8(PATTERN_ARRAY);
+                self.patterns(elements)?;
+                match rest {
+                    Some(rest) => {
+                        self.u8(1);
+                        self.pattern(rest)?;
+                    }
+                    None => self.u8(0),
+                }
+            }
+            MatchPattern::Hash(pairs) => {
+                self.u8(PATTERN_HASH);
+                self.len(pairs.len())?;
+                for (key, value) in pairs {
+                    self.constant(key)?;
+                    self.pattern(value)?;
+                }
+            }
+            MatchPattern::Variant {
+                enum_name,
+                name,
+                fields,
+            } => {
+                self.u8(PATTERN_VARIANT);
+                match enum_name {
+                    Some(enum_name) => {
+                        self.u8(1);
+                        self.string(enum_name)?;
+                    }
+                    None => self.u8(0),
+                }
+                self.string(name)?;
+                self.patterns(fields)?;
+            }
+            MatchPattern::Or(alternatives) => {
+                self.u8(PATTERN_OR);
+                self.patterns(alternatives)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn patterns(&mut self, patterns: &[MatchPattern]) -> Result<(), String> {
+        self.len(patterns.len())?;
+        for pattern in patterns {
+            self.pattern(pattern)?;
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0, depth: 0 }
+    }
+
+    fn nested<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.depth == MAX_NESTING {
+            return Err(corrupted("nested too deeply"));
+        }
+        self.depth += 1;
+        let result = read(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.pos < n {
+            return Err(corrupted("unexpected end of data"));
+        }
+        self.pos += n;
+        Ok(&self.bytes[self.pos - n..self.pos])
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn flag(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(corrupted(format!("invalid flag {}", byte))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.len()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| corrupted("string is not valid UTF-8"))
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>, String> {
+        let count = self.len()?;
+        (0..count).map(|_| self.string()).collect()
+    }
+
+    fn function(&mut self) -> Result<Rc<CompiledFunction>, String> {
+        self.nested(Self::read_function)
+    }
+
+    fn read_function(&mut self) -> Result<Rc<CompiledFunction>, String> {
+        let literal = self.string()?;
+        let parameters = self.strings()?;
+        let num_locals = self.len()?;
+        let generator = self.flag()?;
+
+        let count = self.len()?;
+        let constants = (0..count).map(|_| self.constant()).collect::<Result<Vec<_>, _>>()?;
+
+        let count = self.len()?;
+        let functions = (0..count).map(|_| self.function()).collect::<Result<Vec<_>, _>>()?;
+
+        let patterns = self.patterns()?;
+
+        let len = self.len()?;
+        let instructions = self.take(len)?.to_vec();
+
+        let count = self.len()?;
+        let lines = (0..count)
+            .map(|_| Ok((self.len()?, self.len()?)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let function = CompiledFunction {
+            instructions,
+            constants,
+            functions,
+            patterns,
+            parameters,
+            num_locals,
+            generator,
+            literal,
+            lines,
+        };
+        validate(&function)?;
+
+        Ok(Rc::new(function))
+    }
+
+    fn constant(&mut self) -> Result<Value, String> {
+        self.nested(Self::read_constant)
+    }
+
+    fn read_constant(&mut self) -> Result<Value, String> {
+        let constant = match self.u8()? {
+            CONST_INTEGER => Value::Integer(i64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+            CONST_STRING => Value::String(self.string()?.into()),
+            CONST_BOOLEAN => Value::Boolean(self.flag()?),
+            CONST_NULL => Value::Null,
+            CONST_ARRAY => {
+                let count = self.len()?;
+                let elements = (0..count).map(|_| self.constant()).collect::<Result<_, _>>()?;
+                Value::Array(Rc::new(crate::object::Array { elements }))
+            }
+            CONST_STRUCT_TYPE => Value::StructType(Rc::new(StructType {
+                name: self.string()?,
+                fields: self.strings()?,
+                methods: Default::default(),
+            })),
+            CONST_ENUM_TYPE => {
+                let name = self.string()?;
+                let count = self.len()?;
+                let variants = (0..count)
+                    .map(|_| {
+                        Ok(Variant {
+                            name: self.string()?,
+                            fields: self.strings()?,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+                Value::EnumType(Rc::new(EnumType {
+                    name,
+                    variants,
+                    methods: Default::default(),
+                }))
+            }
+            tag => return Err(corrupted(format!("unknown constant tag {}", tag))),
+        };
+
+        Ok(constant)
+    }
+
+    fn pattern(&mut self) -> Result<MatchPattern, String> {
+        self.nested(Self::read_pattern)
+    }
+
+    fn read_pattern(&mut self) -> Result<MatchPattern, String> {
+        let pattern = match self.u8()? {
+            PATTERN_WILDCARD => MatchPattern::Wildcard,
+            PATTERN_LITERAL => MatchPattern::Literal(self.constant()?),
+            PATTERN_BINDING => {
+                let slot = self.len()?;
+                let name = self.string()?;
+                let count = self.len()?;
+                let shadowed = (0..count)
+                    .map(|_| Ok((self.len()?, self.len()?)))
+                    .collect::<Result<_, String>>()?;
+                MatchPattern::Binding { slot, name, shadowed }
+            }
+            PATTERN_ARRAY => MatchPattern::Array {
+                elements: self.patterns()?,
+                rest: match self.flag()? {
+                    true => Some(Box::new(self.pattern()?)),
+                    false => None,
+                },
+            },
+            PATTERN_HASH => {
+                let count = self.len()?;
+                let pairs = (0..count)
+                    .map(|_| Ok((self.constant()?, self.pattern()?)))
+                    .collect::<Result<_, String>>()?;
+                MatchPattern::Hash(pairs)
+            }
+            PATTERN_VARIANT => MatchPattern::Variant {
+                enum_name: match self.flag()? {
+                    true => Some(self.string()?),
+                    false => None,
+                },
+                name: self.string()?,
+                fields: self.patterns()?,
+            },
+            PATTERN_OR => MatchPattern::Or(self.patterns()?),
+            tag => return Err(corrupted(format!("unknown pattern tag {}", tag))),
+        };
+
+        Ok(pattern)
+    }
+
+    fn patterns(&mut self) -> Result<Vec<MatchPattern>, String> {
+        let count = self.len()?;
+        (0..count).map(|_| self.pattern()).collect()
+    }
+}
+
+// checks every instruction decodes and only refers to constants, functions, patterns,
+// variants, slots and jump targets that exist, so the VM never indexes out of bounds
+// or makes room for more locals than a compiled program could use
+fn validate(function: &CompiledFunction) -> Result<(), String> {
+    if function.num_locals > MAX_SLOTS {
+        return Err(corrupted(format!("function has {} locals", function.num_locals)));
+    }
+
+    let ins = &function.instructions;
+    let mut starts = HashSet::new();
+    let mut targets = Vec::new();
+    // the slots set in the function's own scope or in a scope it pushes
+    let mut slots = Vec::new();
+    let mut scope_size = function.num_locals;
+    // OpVariant picks a variant of the enum the last OpEnum made
+    let mut last_enum: Option<&EnumType> = None;
+    let mut ip = 0;
+
+    while ip < ins.len() {
+        let invalid = || corrupted(format!("invalid instruction at {}", ip));
+        let op = Opcode::from_byte(ins[ip]).ok_or_else(invalid)?;
+        let width: usize = op.definition().operand_widths.iter().sum();
+        if ip + 1 + width > ins.len() {
+            return Err(invalid());
+        }
+        starts.insert(ip);
+
+        let operands = &ins[ip + 1..];
+        let operand = |offset: usize| read_u16(&operands[offset..]);
+        let constant = |idx: usize, valid: fn(&Value) -> bool| {
+            function.constants.get(idx).is_some_and(valid).then_some(()).ok_or_else(invalid)
+        };
+        let any = |_: &Value| true;
+        let string = |value: &Value| matches!(value, Value::String(_));
+
+        match op {
+            Opcode::Constant => constant(operand(0), any)?,
+            Opcode::GetLocal => constant(operand(3), string)?,
+            Opcode::SetLocal | Opcode::SetConst => {
+                constant(operand(2), string)?;
+                slots.push((ip, operand(0)));
+            }
+            Opcode::Error => constant(operand(1), string)?,
+            Opcode::GetBuiltin
+            | Opcode::Member
+            | Opcode::SetMember
+            | Opcode::Call
+            | Opcode::TailCall
+            | Opcode::CallMethod
+            | Opcode::TailCallMethod
+            | Opcode::Import => constant(operand(0), string)?,
+            Opcode::Struct => constant(operand(0), |value| matches!(value, Value::StructType(_)))?,
+            Opcode::Enum => match function.constants.get(operand(0)) {
+                Some(Value::EnumType(enum_type)) => last_enum = Some(enum_type),
+                _ => return Err(invalid()),
+            },
+            Opcode::Variant if last_enum.is_none_or(|enum_type| operand(0) >= enum_type.variants.len()) => {
+                return Err(invalid())
+            }
+            Opcode::PushScope => scope_size = scope_size.max(operand(0)),
+            Opcode::Impl => constant(operand(0), |value| matches!(value, Value::Array(_)))?,
+            Opcode::Quote => constant(operand(0), |value| matches!(value, Value::Quote(_)))?,
+            Opcode::Closure if operand(0) >= function.functions.len() => return Err(invalid()),
+            Opcode::Match => {
+                if operand(0) >= function.patterns.len() {
+                    return Err(invalid());
+                }
+                targets.push((ip, operand(2)));
+            }
+            Opcode::Jump | Opcode::JumpNotTruthy => targets.push((ip, operand(0))),
+            Opcode::GetLocalOr => targets.push((ip, operand(3))),
+            Opcode::Try => {
+                for offset in [0, 2, 4] {
+                    if operand(offset) != NO_TARGET {
+                        targets.push((ip, operand(offset)));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        ip += 1 + width;
+    }
+
+    for (ip, target) in targets {
+        if target != ins.len() && !starts.contains(&target) {
+            return Err(corrupted(format!("invalid jump target {} at {}", target, ip)));
+        }
+    }
+
+    for (ip, slot) in slots {
+        if slot >= scope_size {
+            return Err(corrupted(format!("invalid slot {} at {}", slot, ip)));
+        }
+    }
+
+    let mut bound = Vec::new();
+    for pattern in &function.patterns {
+        pattern_slots(pattern, &mut bound);
+    }
+    if let Some(slot) = bound.into_iter().find(|slot| *slot >= scope_size) {
+        return Err(corrupted(format!("invalid slot {} in a pattern", slot)));
+    }
+
+    Ok(())
+}
+
+fn pattern_slots(pattern: &MatchPattern, slots: &mut Vec<usize>) {
+    match pattern {
+        MatchPattern::Binding { slot, .. } => slots.push(*slot),
+        MatchPattern::Array { elements, rest } => {
+            for element in elements.iter().chain(rest.as_deref()) {
+                pattern_slots(element, slots);
+            }
+        }
+        MatchPattern::Hash(pairs) => {
+            for (_, value) in pairs {
+                pattern_slots(value, slots);
+            }
+        }
+        MatchPattern::Variant { fields: patterns, .. } | MatchPattern::Or(patterns) => {
+            for pattern in patterns {
+                pattern_slots(pattern, slots);
+            }
+        }
+        MatchPattern::Wildcard | MatchPattern::Literal(_) => {}
+    }
+}
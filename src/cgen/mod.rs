@@ -0,0 +1,492 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::{
+    code::{read_operands, Opcode, NO_TARGET},
+    object::{CompiledFunction, ErrorKind, MatchPattern, Value},
+};
+
+const RUNTIME: &str = include_str!("runtime.c");
+
+// A program becomes a single C file: the runtime, then every compiled function as C
+// code running the same instructions, with jumps turned into gotos. The try, catch
+// and finally blocks of a try get C functions of their own, so the runtime can run
+// them under a handler the way the VM nests its dispatch loop.
+pub fn emit(main: &CompiledFunction) -> Result<String, String> {
+    let mut emitter = Emitter::default();
+    let main = emitter.function(main)?;
+
+    let mut out = String::from(RUNTIME);
+    out.push_str(&emitter.declarations);
+    if !emitter.strings.is_empty() {
+        let _ = writeln!(out, "\nstatic Value strings[{}];", emitter.strings.len());
+    }
+    out.push_str(&emitter.definitions);
+
+    out.push_str("\nint main(void) {\n");
+    for (idx, string) in emitter.strings.iter().enumerate() {
+        let _ = writeln!(out, "    strings[{}] = rs_string({}, {});", idx, c_string(string), string.len());
+    }
+    let _ = writeln!(out, "    return rs_main(&function_{});\n}}", main);
+
+    Ok(out)
+}
+
+#[derive(Default)]
+struct Emitter {
+    declarations: String,
+    definitions: String,
+    strings: Vec<String>,
+    functions: usize,
+    patterns: usize,
+    types: usize,
+}
+
+// a compiled function while its blocks are written, with the ids its nested
+// functions and patterns were given
+struct Unit<'a> {
+    id: usize,
+    function: &'a CompiledFunction,
+    children: Vec<usize>,
+    patterns: Vec<String>,
+}
+
+struct Block {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+impl Emitter {
+    fn function(&mut self, function: &CompiledFunction) -> Result<usize, String> {
+        if function.generator {
+            return Err("cannot translate generators to C".to_string());
+        }
+
+        let children = function
+            .functions
+            .iter()
+            .map(|child| self.function(child))
+            .collect::<Result<_, _>>()?;
+        let patterns = function
+            .patterns
+            .iter()
+            .map(|pattern| self.pattern(pattern))
+            .collect::<Result<_, _>>()?;
+
+        let id = self.functions;
+        self.functions += 1;
+        let unit = Unit {
+            id,
+            function,
+            children,
+            patterns,
+        };
+
+        let mut blocks = vec![Block {
+            name: format!("fn_{}", id),
+            start: 0,
+            end: function.instructions.len(),
+        }];
+        while let Some(block) = blocks.pop() {
+            self.block(&unit, &block, &mut blocks)?;
+        }
+
+        let takes_self = function.parameters.first().is_some_and(|param| param == "self");
+        let _ = writeln!(
+            self.declarations,
+            "static const RsFunction function_{} = {{fn_{}, {}, {}, {}, {}}};",
+            id,
+            id,
+            function.num_locals,
+            function.parameters.len(),
+            takes_self,
+            c_string(&function.literal)
+        );
+        Ok(id)
+    }
+
+    // a try inside the block is skipped over, its blocks are queued to be written on
+    // their own
+    fn block(&mut self, unit: &Unit, block: &Block, pending: &mut Vec<Block>) -> Result<(), String> {
+        let instructions = &unit.function.instructions;
+        let mut decoded = Vec::new();
+        let mut ip = block.start;
+
+        while ip < block.end {
+            let op = Opcode::from_byte(instructions[ip]).ok_or_else(|| format!("invalid instruction at {}", ip))?;
+            let (operands, read) = read_operands(&op.definition(), &instructions[ip + 1..]);
+            let next = if op == Opcode::Try { operands[2] } else { ip + 1 + read };
+            decoded.push((ip, op, operands));
+            ip = next;
+        }
+
+        let targets: BTreeSet<usize> = decoded
+            .iter()
+            .filter_map(|(_, op, operands)| match op {
+                Opcode::Jump | Opcode::JumpNotTruthy => Some(operands[0]),
+                Opcode::Match => Some(operands[1]),
+                Opcode::GetLocalOr => Some(operands[2]),
+                _ => None,
+            })
+            .collect();
+
+        let mut code = String::new();
+        for (ip, op, operands) in &decoded {
+            if targets.contains(ip) {
+                let _ = writeln!(code, "L{}:", ip);
+            }
+            let statement = self.instruction(unit, *ip, *op, operands, pending)?;
+            let _ = writeln!(code, "    {}", statement);
+        }
+
+        let _ = writeln!(self.declarations, "static int {}(RsFrame *frame);", block.name);
+        let _ = write!(self.definitions, "\nstatic int {}(RsFrame *frame) {{\n{}}}\n", block.name, code);
+        Ok(())
+    }
+
+    fn instruction(
+        &mut self,
+        unit: &Unit,
+        ip: usize,
+        op: Opcode,
+        operands: &[usize],
+        pending: &mut Vec<Block>,
+    ) -> Result<String, String> {
+        let function = unit.function;
+        let name = |idx: usize| match &function.constants[idx] {
+            Value::String(value) => c_string(value),
+            _ => "\"\"".to_string(),
+        };
+
+        let statement = match op {
+            Opcode::Constant => format!("rs_push({});", self.constant(&function.constants[operands[0]])?),
+            Opcode::Pop => "rs_drop();".to_string(),
+            Opcode::Dup => "rs_dup();".to_string(),
+            Opcode::Add => "rs_infix(\"+\");".to_string(),
+            Opcode::Sub => "rs_infix(\"-\");".to_string(),
+            Opcode::Mul => "rs_infix(\"*\");".to_string(),
+            Opcode::Div => "rs_infix(\"/\");".to_string(),
+            Opcode::Equal => "rs_infix(\"==\");".to_string(),
+            Opcode::NotEqual => "rs_infix(\"!=\");".to_string(),
+            Opcode::GreaterThan => "rs_infix(\">\");".to_string(),
+            Opcode::LessThan => "rs_infix(\"<\");".to_string(),
+            Opcode::Minus => "rs_minus();".to_string(),
+            Opcode::Bang => "rs_bang();".to_string(),
+            Opcode::True => "rs_push(rs_boolean(true));".to_string(),
+            Opcode::False => "rs_push(rs_boolean(false));".to_string(),
+            Opcode::Null => "rs_push(rs_null());".to_string(),
+            Opcode::Jump => format!("goto L{};", operands[0]),
+            Opcode::JumpNotTruthy => format!("if (!rs_pop_truthy()) goto L{};", operands[0]),
+            Opcode::GetLocal => format!(
+                "rs_get_local(frame, {}, {}, {});",
+                operands[0],
+                operands[1],
+                name(operands[2])
+            ),
+            Opcode::GetLocalOr => format!(
+                "if (rs_get_local_or(frame, {}, {})) goto L{};",
+                operands[0],
+                operands[1],
+                operands[2]
+            ),
+            Opcode::SetLocal | Opcode::SetConst => format!(
+                "rs_set_local(frame, {}, {}, {});",
+                operands[0],
+                op == Opcode::SetConst,
+                name(operands[1])
+            ),
+            Opcode::GetBuiltin => format!("rs_get_builtin({});", name(operands[0])),
+            Opcode::Error => format!(
+                "rs_error({}, \"%s\", {});",
+                error_kind(ErrorKind::from_byte(operands[0] as u8).unwrap_or(ErrorKind::Error)),
+                name(operands[1])
+            ),
+            Opcode::Array => format!("rs_array({});", operands[0]),
+            Opcode::Hash => format!("rs_hash({});", operands[0]),
+            Opcode::Index => "rs_index();".to_string(),
+            Opcode::Member => format!("rs_member({});", name(operands[0])),
+            Opcode::SetMember => format!("rs_set_member({});", name(operands[0])),
+            Opcode::Call | Opcode::TailCall => format!("rs_call({});", operands[1]),
+            Opcode::CallMethod | Opcode::TailCallMethod => format!("rs_call_method({}, {});", name(operands[0]), operands[1]),
+            Opcode::ReturnValue => "return RS_RETURN;".to_string(),
+            Opcode::Return => "rs_push(rs_null()); return RS_RETURN;".to_string(),
+            Opcode::Closure => format!("rs_closure(frame, &function_{});", unit.children[operands[0]]),
+            Opcode::Struct => {
+                let Value::StructType(struct_type) = &function.constants[operands[0]] else {
+                    return Err(format!("invalid struct type at {}", ip));
+                };
+                let def = self.type_def("RsStructDef", format!(
+                    "{{{}, {}, {}}}",
+                    c_string(&struct_type.name),
+                    struct_type.fields.len(),
+                    c_names(&struct_type.fields)
+                ));
+                format!("rs_struct_type(&{});", def)
+            }
+            Opcode::Enum => {
+                let Value::EnumType(enum_type) = &function.constants[operands[0]] else {
+                    return Err(format!("invalid enum type at {}", ip));
+                };
+                let variants: Vec<String> = enum_type
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        format!(
+                            "{{{}, {}, {}}}",
+                            c_string(&variant.name),
+                            variant.fields.len(),
+                            c_names(&variant.fields)
+                        )
+                    })
+                    .collect();
+                let def = self.type_def("RsEnumDef", format!(
+                    "{{{}, {}, (const RsVariantDef[]){{{}}}}}",
+                    c_string(&enum_type.name),
+                    variants.len(),
+                    variants.join(", ")
+                ));
+                format!("rs_enum_type(&{});", def)
+            }
+            Opcode::Variant => format!("rs_variant({});", operands[0]),
+            Opcode::Impl => {
+                let Value::Array(names) = &function.constants[operands[0]] else {
+                    return Err(format!("invalid method names at {}", ip));
+                };
+                let names: Vec<String> = names
+                    .elements
+                    .iter()
+                    .map(|name| match name {
+                        Value::String(name) => name.to_string(),
+                        _ => String::new(),
+                    })
+                    .collect();
+                format!("rs_impl({}, {});", c_names(&names), names.len())
+            }
+            Opcode::Throw => "rs_throw();".to_string(),
+            Opcode::Try => {
+                let [catch, finally, end] = [operands[0], operands[1], operands[2]];
+                let present = |target: usize| target != NO_TARGET;
+                let body_end = [catch, finally].into_iter().find(|target| present(*target)).unwrap_or(end);
+
+                let mut block = |start: usize, block_end: usize| {
+                    let name = format!("fn_{}_{}", unit.id, start);
+                    pending.push(Block {
+                        name: name.clone(),
+                        start,
+                        end: block_end,
+                    });
+                    name
+                };
+
+                let body = block(ip + 1 + op.definition().operand_widths.iter().sum::<usize>(), body_end);
+                let catch = match present(catch) {
+                    true => block(catch, if present(finally) { finally } else { end }),
+                    false => "NULL".to_string(),
+                };
+                let finally = match present(finally) {
+                    true => block(finally, end),
+                    false => "NULL".to_string(),
+                };
+                format!(
+                    "if (rs_try(frame, {}, {}, {}) == RS_RETURN) return RS_RETURN;",
+                    body, catch, finally
+                )
+            }
+            Opcode::EndTry => "return RS_NORMAL;".to_string(),
+            Opcode::PushScope => format!("rs_push_scope(frame, {});", operands[0]),
+            Opcode::PopScope => "rs_pop_scope(frame);".to_string(),
+            Opcode::Match => format!(
+                "if (!rs_match(frame, &{})) goto L{};",
+                unit.patterns[operands[0]],
+                operands[1]
+            ),
+            Opcode::Import => return Err("cannot translate import to C".to_string()),
+            Opcode::Quote => return Err("cannot translate quote to C".to_string()),
+            Opcode::Yield | Opcode::YieldDelegate => return Err("cannot translate generators to C".to_string()),
+        };
+
+        Ok(statement)
+    }
+
+    fn constant(&mut self, constant: &Value) -> Result<String, String> {
+        let value = match constant {
+            Value::Integer(value) => format!("rs_integer({})", c_integer(*value)),
+            Value::Boolean(value) => format!("rs_boolean({})", value),
+            Value::Null => "rs_null()".to_string(),
+            Value::String(value) => {
+                let idx = match self.strings.iter().position(|string| string.as_str() == value.as_ref()) {
+                    Some(idx) => idx,
+                    None => {
+                        self.strings.push(value.to_string());
+                        self.strings.len() - 1
+                    }
+                };
+                format!("rs_retain(strings[{}])", idx)
+            }
+            _ => return Err(format!("cannot translate a {} constant to C", constant.get_type())),
+        };
+
+        Ok(value)
+    }
+
+    fn type_def(&mut self, c_type: &str, initializer: String) -> String {
+        let name = format!("type_{}", self.types);
+        self.types += 1;
+
+        let _ = writeln!(self.declarations, "static const {} {} = {};", c_type, name, initializer);
+        name
+    }
+
+    // nested patterns are written first, so a pattern can point at them
+    fn pattern(&mut self, pattern: &MatchPattern) -> Result<String, String> {
+        let fields = match pattern {
+            MatchPattern::Wildcard => ".kind = RS_PATTERN_WILDCARD".to_string(),
+            MatchPattern::Literal(value) => format!(".kind = RS_PATTERN_LITERAL, .literal = {}", c_literal(value)?),
+            MatchPattern::Binding { slot, name, shadowed } => {
+                let items: Vec<String> = shadowed
+                    .iter()
+                    .map(|(depth, slot)| format!("{{{}, {}}}", depth, slot))
+                    .collect();
+                let items = match items.is_empty() {
+                    true => "NULL".to_string(),
+                    false => format!("(const RsShadowed[]){{{}}}", items.join(", ")),
+                };
+                format!(
+                    ".kind = RS_PATTERN_BINDING, .slot = {}, .name = {}, .count = {}, .shadowed = {}",
+                    slot,
+                    c_string(name),
+                    shadowed.len(),
+                    items
+                )
+            }
+            MatchPattern::Array { elements, rest } => {
+                let items = self.pattern_list(elements)?;
+                let rest = match rest {
+                    Some(rest) => format!("&{}", self.pattern(rest)?),
+                    None => "NULL".to_string(),
+                };
+                format!(
+                    ".kind = RS_PATTERN_ARRAY, .count = {}, .items = {}, .rest = {}",
+                    elements.len(),
+                    items,
+                    rest
+                )
+            }
+            MatchPattern::Hash(pairs) => {
+                let keys = pairs
+                    .iter()
+                    .map(|(key, _)| c_literal(key))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let values: Vec<&MatchPattern> = pairs.iter().map(|(_, value)| value).collect();
+                let items = self.pattern_list(values)?;
+                let keys = match keys.is_empty() {
+                    true => "NULL".to_string(),
+                    false => format!("(const RsLiteral[]){{{}}}", keys.join(", ")),
+                };
+                format!(
+                    ".kind = RS_PATTERN_HASH, .count = {}, .items = {}, .keys = {}",
+                    pairs.len(),
+                    items,
+                    keys
+                )
+            }
+            MatchPattern::Variant {
+                enum_name,
+                name,
+                fields,
+            } => {
+                let items = self.pattern_list(fields)?;
+                let enum_name = match enum_name {
+                    Some(enum_name) => c_string(enum_name),
+                    None => "NULL".to_string(),
+                };
+                format!(
+                    ".kind = RS_PATTERN_VARIANT, .count = {}, .items = {}, .enum_name = {}, .name = {}",
+                    fields.len(),
+                    items,
+                    enum_name,
+                    c_string(name)
+                )
+            }
+            MatchPattern::Or(alternatives) => {
+                let items = self.pattern_list(alternatives)?;
+                format!(".kind = RS_PATTERN_OR, .count = {}, .items = {}", alternatives.len(), items)
+            }
+        };
+
+        let name = format!("pattern_{}", self.patterns);
+        self.patterns += 1;
+        let _ = writeln!(self.declarations, "static const RsPattern {} = {{{}}};", name, fields);
+        Ok(name)
+    }
+
+    fn pattern_list<'p>(&mut self, patterns: impl IntoIterator<Item = &'p MatchPattern>) -> Result<String, String> {
+        let names = patterns
+            .into_iter()
+            .map(|pattern| Ok(format!("&{}", self.pattern(pattern)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if names.is_empty() {
+            return Ok("NULL".to_string());
+        }
+        Ok(format!("(const RsPattern *const[]){{{}}}", names.join(", ")))
+    }
+}
+
+fn error_kind(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::Error => "RS_ERROR",
+        ErrorKind::TypeError => "RS_TYPE_ERROR",
+        ErrorKind::NameError => "RS_NAME_ERROR",
+        ErrorKind::ArgumentError => "RS_ARGUMENT_ERROR",
+        ErrorKind::ConstantError => "RS_CONSTANT_ERROR",
+        ErrorKind::ImportError => "RS_IMPORT_ERROR",
+        ErrorKind::ZeroDivisionError => "RS_ZERO_DIVISION_ERROR",
+        ErrorKind::OverflowError => "RS_OVERFLOW_ERROR",
+    }
+}
+
+// INT64_MIN can't be written as a negated literal
+fn c_integer(value: i64) -> String {
+    match value {
+        i64::MIN => "INT64_MIN".to_string(),
+        value => format!("INT64_C({})", value),
+    }
+}
+
+fn c_literal(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Integer(value) => Ok(format!("{{RS_INTEGER, {}, NULL, 0}}", c_integer(*value))),
+        Value::Boolean(value) => Ok(format!("{{RS_BOOLEAN, {}, NULL, 0}}", *value as i64)),
+        Value::String(value) => Ok(format!("{{RS_STRING, 0, {}, {}}}", c_string(value), value.len())),
+        _ => Err(format!("cannot translate a {} pattern to C", value.get_type())),
+    }
+}
+
+fn c_names(names: &[String]) -> String {
+    if names.is_empty() {
+        return "NULL".to_string();
+    }
+
+    let names: Vec<String> = names.iter().map(|name| c_string(name)).collect();
+    format!("(const char *const[]){{{}}}", names.join(", "))
+}
+
+// anything outside printable ASCII is written as an octal escape, which can't run
+// into the characters after it the way a hex escape can
+fn c_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for byte in value.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'?' => out.push_str("\\?"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => {
+                let _ = write!(out, "\\{:03o}", byte);
+            }
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,1709 @@
+// runtime for rust_script programs translated to C. It works like the VM: values live
+// on one stack, every call gets a scope of its own and errors unwind to the nearest
+// try. Heap values are reference counted
+#include <inttypes.h>
+#include <setjmp.h>
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// a program only uses part of the runtime, and not every block touches its frame
+#pragma GCC diagnostic ignored "-Wunused-function"
+#pragma GCC diagnostic ignored "-Wunused-parameter"
+
+typedef enum {
+    RS_NULL,
+    RS_INTEGER,
+    RS_BOOLEAN,
+    RS_BUILTIN,
+    RS_STRING,
+    RS_ARRAY,
+    RS_HASH,
+    RS_CLOSURE,
+    RS_STRUCT_TYPE,
+    RS_STRUCT,
+    RS_ENUM_TYPE,
+    RS_CONSTRUCTOR,
+    RS_ENUM_VALUE,
+    RS_EXCEPTION,
+} RsTag;
+
+typedef enum {
+    RS_ERROR,
+    RS_TYPE_ERROR,
+    RS_NAME_ERROR,
+    RS_ARGUMENT_ERROR,
+    RS_CONSTANT_ERROR,
+    RS_IMPORT_ERROR,
+    RS_ZERO_DIVISION_ERROR,
+    RS_OVERFLOW_ERROR,
+} RsErrorKind;
+
+static const char *const rs_error_kinds[] = {
+    "Error", "TypeError", "NameError", "ArgumentError", "ConstantError", "ImportError",
+    "ZeroDivisionError", "OverflowError",
+};
+
+// how a block of generated code finished, the value it produced is on the stack
+enum { RS_NORMAL, RS_RETURN, RS_THROW };
+
+typedef struct RsBuiltin RsBuiltin;
+
+typedef struct {
+    RsTag tag;
+    union {
+        int64_t integer;
+        bool boolean;
+        const RsBuiltin *builtin;
+        void *object;
+    } as;
+} Value;
+
+// every heap value starts with its reference count
+typedef struct {
+    size_t refs;
+} RsObject;
+
+typedef struct {
+    size_t refs;
+    size_t length;
+    char data[];
+} RsString;
+
+typedef struct {
+    size_t refs;
+    size_t length;
+    Value items[];
+} RsArray;
+
+typedef struct {
+    Value key;
+    Value value;
+} RsPair;
+
+// pairs are kept in insertion order
+typedef struct {
+    size_t refs;
+    size_t length;
+    RsPair pairs[];
+} RsHash;
+
+typedef struct {
+    Value value;
+    bool bound;
+    bool constant;
+} RsBinding;
+
+typedef struct RsScope {
+    size_t refs;
+    struct RsScope *outer;
+    size_t size;
+    RsBinding *bindings;
+} RsScope;
+
+typedef struct RsFrame {
+    RsScope *scope;
+    struct RsFrame *prev;
+} RsFrame;
+
+typedef int (*RsBlock)(RsFrame *frame);
+
+typedef struct {
+    RsBlock code;
+    size_t num_locals;
+    size_t num_params;
+    bool takes_self;
+    const char *literal;
+} RsFunction;
+
+typedef struct {
+    size_t refs;
+    const RsFunction *function;
+    RsScope *env;
+} RsClosure;
+
+struct RsBuiltin {
+    const char *name;
+    Value (*function)(Value *args, size_t count);
+};
+
+typedef struct {
+    const char *name;
+    size_t count;
+    const char *const *fields;
+} RsStructDef;
+
+typedef struct {
+    const char *name;
+    size_t count;
+    const char *const *fields;
+} RsVariantDef;
+
+typedef struct {
+    const char *name;
+    size_t count;
+    const RsVariantDef *variants;
+} RsEnumDef;
+
+typedef struct {
+    const char *name;
+    Value value;
+} RsMethod;
+
+typedef struct {
+    size_t length;
+    RsMethod *items;
+} RsMethods;
+
+typedef struct {
+    size_t refs;
+    const RsStructDef *def;
+    RsMethods methods;
+} RsStructType;
+
+typedef struct {
+    size_t refs;
+    RsStructType *type;
+    Value values[];
+} RsStruct;
+
+typedef struct {
+    size_t refs;
+    const RsEnumDef *def;
+    RsMethods methods;
+} RsEnumType;
+
+typedef struct {
+    size_t refs;
+    RsEnumType *type;
+    size_t variant;
+} RsConstructor;
+
+typedef struct {
+    size_t refs;
+    RsEnumType *type;
+    size_t variant;
+    size_t count;
+    Value values[];
+} RsEnumValue;
+
+typedef struct {
+    size_t refs;
+    RsErrorKind kind;
+    RsString *message;
+    Value value;
+} RsException;
+
+typedef enum {
+    RS_PATTERN_WILDCARD,
+    RS_PATTERN_LITERAL,
+    RS_PATTERN_BINDING,
+    RS_PATTERN_ARRAY,
+    RS_PATTERN_HASH,
+    RS_PATTERN_VARIANT,
+    RS_PATTERN_OR,
+} RsPatternKind;
+
+// an integer, boolean or string from a pattern
+typedef struct {
+    RsTag tag;
+    int64_t integer;
+    const char *string;
+    size_t length;
+} RsLiteral;
+
+// a binding the name of a binding pattern shadows
+typedef struct {
+    size_t depth;
+    size_t slot;
+} RsShadowed;
+
+typedef struct RsPattern {
+    RsPatternKind kind;
+    size_t slot;
+    RsLiteral literal;
+    size_t count;
+    const struct RsPattern *const *items;
+    const struct RsPattern *rest;
+    const RsLiteral *keys;
+    const char *enum_name;
+    const char *name;
+    const RsShadowed *shadowed;
+} RsPattern;
+
+static void *rs_alloc(size_t size) {
+    void *memory = malloc(size);
+    if (!memory) {
+        fputs("out of memory\n", stderr);
+        exit(1);
+    }
+    return memory;
+}
+
+static Value rs_null(void) {
+    return (Value){.tag = RS_NULL};
+}
+
+static Value rs_integer(int64_t integer) {
+    return (Value){.tag = RS_INTEGER, .as.integer = integer};
+}
+
+static Value rs_boolean(bool boolean) {
+    return (Value){.tag = RS_BOOLEAN, .as.boolean = boolean};
+}
+
+static Value rs_object(RsTag tag, void *object) {
+    ((RsObject *)object)->refs = 1;
+    return (Value){.tag = tag, .as.object = object};
+}
+
+static bool rs_is_object(Value value) {
+    return value.tag > RS_BUILTIN;
+}
+
+static Value rs_retain(Value value) {
+    if (rs_is_object(value)) {
+        ((RsObject *)value.as.object)->refs++;
+    }
+    return value;
+}
+
+static void rs_release(Value value);
+
+static void rs_release_methods(RsMethods *methods) {
+    for (size_t i = 0; i < methods->length; i++) {
+        rs_release(methods->items[i].value);
+    }
+    free(methods->items);
+}
+
+// scopes are released in a loop, a long chain of them doesn't recurse
+static void rs_scope_release(RsScope *scope) {
+    while (scope && --scope->refs == 0) {
+        RsScope *outer = scope->outer;
+        for (size_t i = 0; i < scope->size; i++) {
+            rs_release(scope->bindings[i].value);
+        }
+        free(scope->bindings);
+        free(scope);
+        scope = outer;
+    }
+}
+
+static void rs_release(Value value) {
+    if (!rs_is_object(value) || --((RsObject *)value.as.object)->refs > 0) {
+        return;
+    }
+
+    switch (value.tag) {
+    case RS_ARRAY: {
+        RsArray *array = value.as.object;
+        for (size_t i = 0; i < array->length; i++) {
+            rs_release(array->items[i]);
+        }
+        break;
+    }
+    case RS_HASH: {
+        RsHash *hash = value.as.object;
+        for (size_t i = 0; i < hash->length; i++) {
+            rs_release(hash->pairs[i].key);
+            rs_release(hash->pairs[i].value);
+        }
+        break;
+    }
+    case RS_CLOSURE:
+        rs_scope_release(((RsClosure *)value.as.object)->env);
+        break;
+    case RS_STRUCT_TYPE:
+        rs_release_methods(&((RsStructType *)value.as.object)->methods);
+        break;
+    case RS_STRUCT: {
+        RsStruct *instance = value.as.object;
+        for (size_t i = 0; i < instance->type->def->count; i++) {
+            rs_release(instance->values[i]);
+        }
+        rs_release((Value){.tag = RS_STRUCT_TYPE, .as.object = instance->type});
+        break;
+    }
+    case RS_ENUM_TYPE:
+        rs_release_methods(&((RsEnumType *)value.as.object)->methods);
+        break;
+    case RS_CONSTRUCTOR:
+        rs_release((Value){.tag = RS_ENUM_TYPE, .as.object = ((RsConstructor *)value.as.object)->type});
+        break;
+    case RS_ENUM_VALUE: {
+        RsEnumValue *enum_value = value.as.object;
+        for (size_t i = 0; i < enum_value->count; i++) {
+            rs_release(enum_value->values[i]);
+        }
+        rs_release((Value){.tag = RS_ENUM_TYPE, .as.object = enum_value->type});
+        break;
+    }
+    case RS_EXCEPTION: {
+        RsException *exception = value.as.object;
+        rs_release((Value){.tag = RS_STRING, .as.object = exception->message});
+        rs_release(exception->value);
+        break;
+    }
+    default:
+        break;
+    }
+
+    free(value.as.object);
+}
+
+static RsString *rs_new_string(const char *data, size_t length) {
+    RsString *string = rs_alloc(sizeof(RsString) + length);
+    string->refs = 1;
+    string->length = length;
+    memcpy(string->data, data, length);
+    return string;
+}
+
+static Value rs_string(const char *data, size_t length) {
+    return (Value){.tag = RS_STRING, .as.object = rs_new_string(data, length)};
+}
+
+typedef struct {
+    char *data;
+    size_t length;
+    size_t capacity;
+} RsBuffer;
+
+static void rs_buffer_append(RsBuffer *buffer, const char *data, size_t length) {
+    if (buffer->length + length + 1 > buffer->capacity) {
+        buffer->capacity = (buffer->length + length + 1) * 2;
+        buffer->data = realloc(buffer->data, buffer->capacity);
+        if (!buffer->data) {
+            fputs("out of memory\n", stderr);
+            exit(1);
+        }
+    }
+    memcpy(buffer->data + buffer->length, data, length);
+    buffer->length += length;
+    buffer->data[buffer->length] = '\0';
+}
+
+static void rs_buffer_puts(RsBuffer *buffer, const char *text) {
+    rs_buffer_append(buffer, text, strlen(text));
+}
+
+static void rs_buffer_vprintf(RsBuffer *buffer, const char *format, va_list args) {
+    va_list copy;
+    va_copy(copy, args);
+    int length = vsnprintf(NULL, 0, format, copy);
+    va_end(copy);
+
+    char *text = rs_alloc((size_t)length + 1);
+    vsnprintf(text, (size_t)length + 1, format, args);
+    rs_buffer_append(buffer, text, (size_t)length);
+    free(text);
+}
+
+static void rs_buffer_printf(RsBuffer *buffer, const char *format, ...) {
+    va_list args;
+    va_start(args, format);
+    rs_buffer_vprintf(buffer, format, args);
+    va_end(args);
+}
+
+static const char *rs_type_name(Value value) {
+    switch (value.tag) {
+    case RS_NULL:
+        return "NULL";
+    case RS_INTEGER:
+        return "INTEGER";
+    case RS_BOOLEAN:
+        return "BOOLEAN";
+    case RS_BUILTIN:
+        return "BUILTIN";
+    case RS_STRING:
+        return "STRING";
+    case RS_ARRAY:
+        return "ARRAY";
+    case RS_HASH:
+        return "HASH";
+    case RS_CLOSURE:
+        return "FUNCTION";
+    case RS_STRUCT_TYPE:
+    case RS_ENUM_TYPE:
+        return "TYPE";
+    case RS_STRUCT:
+        return "STRUCT";
+    case RS_CONSTRUCTOR:
+        return "CONSTRUCTOR";
+    case RS_ENUM_VALUE:
+        return "ENUM";
+    case RS_EXCEPTION:
+        return "EXCEPTION";
+    }
+    return "NULL";
+}
+
+static void rs_inspect(RsBuffer *buffer, Value value);
+
+static void rs_inspect_names(RsBuffer *buffer, const char *const *names, size_t count) {
+    for (size_t i = 0; i < count; i++) {
+        if (i > 0) {
+            rs_buffer_puts(buffer, ", ");
+        }
+        rs_buffer_puts(buffer, names[i]);
+    }
+}
+
+static void rs_inspect_variant(RsBuffer *buffer, const RsVariantDef *variant) {
+    rs_buffer_puts(buffer, variant->name);
+    if (variant->count > 0) {
+        rs_buffer_puts(buffer, "(");
+        rs_inspect_names(buffer, variant->fields, variant->count);
+        rs_buffer_puts(buffer, ")");
+    }
+}
+
+static void rs_inspect_values(RsBuffer *buffer, const Value *values, size_t count) {
+    for (size_t i = 0; i < count; i++) {
+        if (i > 0) {
+            rs_buffer_puts(buffer, ", ");
+        }
+        rs_inspect(buffer, values[i]);
+    }
+}
+
+// the same text Value::inspect gives
+static void rs_inspect(RsBuffer *buffer, Value value) {
+    switch (value.tag) {
+    case RS_NULL:
+        rs_buffer_puts(buffer, "null");
+        break;
+    case RS_INTEGER:
+        rs_buffer_printf(buffer, "%" PRId64, value.as.integer);
+        break;
+    case RS_BOOLEAN:
+        rs_buffer_puts(buffer, value.as.boolean ? "true" : "false");
+        break;
+    case RS_BUILTIN:
+        rs_buffer_puts(buffer, "[builtin function]");
+        break;
+    case RS_STRING: {
+        RsString *string = value.as.object;
+        rs_buffer_append(buffer, string->data, string->length);
+        break;
+    }
+    case RS_ARRAY: {
+        RsArray *array = value.as.object;
+        rs_buffer_puts(buffer, "[");
+        rs_inspect_values(buffer, array->items, array->length);
+        rs_buffer_puts(buffer, "]");
+        break;
+    }
+    case RS_HASH: {
+        RsHash *hash = value.as.object;
+        rs_buffer_puts(buffer, "{");
+        for (size_t i = 0; i < hash->length; i++) {
+            if (i > 0) {
+                rs_buffer_puts(buffer, ", ");
+            }
+            rs_inspect(buffer, hash->pairs[i].key);
+            rs_buffer_puts(buffer, ": ");
+            rs_inspect(buffer, hash->pairs[i].value);
+        }
+        rs_buffer_puts(buffer, "}");
+        break;
+    }
+    case RS_CLOSURE:
+        rs_buffer_puts(buffer, ((RsClosure *)value.as.object)->function->literal);
+        break;
+    case RS_STRUCT_TYPE: {
+        const RsStructDef *def = ((RsStructType *)value.as.object)->def;
+        rs_buffer_printf(buffer, "struct %s { ", def->name);
+        rs_inspect_names(buffer, def->fields, def->count);
+        rs_buffer_puts(buffer, " }");
+        break;
+    }
+    case RS_STRUCT: {
+        RsStruct *instance = value.as.object;
+        const RsStructDef *def = instance->type->def;
+        rs_buffer_printf(buffer, "%s { ", def->name);
+        for (size_t i = 0; i < def->count; i++) {
+            if (i > 0) {
+                rs_buffer_puts(buffer, ", ");
+            }
+            rs_buffer_printf(buffer, "%s: ", def->fields[i]);
+            rs_inspect(buffer, instance->values[i]);
+        }
+        rs_buffer_puts(buffer, " }");
+        break;
+    }
+    case RS_ENUM_TYPE: {
+        const RsEnumDef *def = ((RsEnumType *)value.as.object)->def;
+        rs_buffer_printf(buffer, "enum %s { ", def->name);
+        for (size_t i = 0; i < def->count; i++) {
+            if (i > 0) {
+                rs_buffer_puts(buffer, ", ");
+            }
+            rs_inspect_variant(buffer, &def->variants[i]);
+        }
+        rs_buffer_puts(buffer, " }");
+        break;
+    }
+    case RS_CONSTRUCTOR: {
+        RsConstructor *constructor = value.as.object;
+        rs_buffer_printf(buffer, "%s.", constructor->type->def->name);
+        rs_inspect_variant(buffer, &constructor->type->def->variants[constructor->variant]);
+        break;
+    }
+    case RS_ENUM_VALUE: {
+        RsEnumValue *enum_value = value.as.object;
+        rs_buffer_puts(buffer, enum_value->type->def->variants[enum_value->variant].name);
+        if (enum_value->count > 0) {
+            rs_buffer_puts(buffer, "(");
+            rs_inspect_values(buffer, enum_value->values, enum_value->count);
+            rs_buffer_puts(buffer, ")");
+        }
+        break;
+    }
+    case RS_EXCEPTION: {
+        RsException *exception = value.as.object;
+        rs_buffer_printf(buffer, "%s: ", rs_error_kinds[exception->kind]);
+        rs_buffer_append(buffer, exception->message->data, exception->message->length);
+        break;
+    }
+    }
+}
+
+// errors are thrown with longjmp to the innermost handler, which takes the pending
+// error. Without a handler the program stops like `rust_script run` does
+typedef struct RsHandler {
+    jmp_buf jump;
+    struct RsHandler *prev;
+} RsHandler;
+
+static RsHandler *rs_handler;
+static RsFrame *rs_frames;
+
+static RsErrorKind rs_error_kind;
+static RsString *rs_error_message;
+static Value rs_error_value;
+
+static void rs_raise(RsErrorKind kind, RsString *message, Value value) __attribute__((noreturn));
+
+static void rs_raise(RsErrorKind kind, RsString *message, Value value) {
+    if (!rs_handler) {
+        fflush(stdout);
+        fprintf(stderr, "uncaught %s: %.*s\n", rs_error_kinds[kind], (int)message->length, message->data);
+        exit(1);
+    }
+
+    rs_error_kind = kind;
+    rs_error_message = message;
+    rs_error_value = value;
+    longjmp(rs_handler->jump, 1);
+}
+
+static void rs_error(RsErrorKind kind, const char *format, ...) __attribute__((noreturn, format(printf, 2, 3)));
+
+static void rs_error(RsErrorKind kind, const char *format, ...) {
+    RsBuffer buffer = {0};
+    va_list args;
+    va_start(args, format);
+    rs_buffer_vprintf(&buffer, format, args);
+    va_end(args);
+
+    RsString *message = rs_new_string(buffer.data, buffer.length);
+    free(buffer.data);
+    rs_raise(kind, message, rs_null());
+}
+
+// the stack every block and call shares
+static Value *rs_stack;
+static size_t rs_sp;
+static size_t rs_capacity;
+
+static void rs_push(Value value) {
+    if (rs_sp == rs_capacity) {
+        rs_capacity = rs_capacity ? rs_capacity * 2 : 256;
+        rs_stack = realloc(rs_stack, rs_capacity * sizeof(Value));
+        if (!rs_stack) {
+            fputs("out of memory\n", stderr);
+            exit(1);
+        }
+    }
+    rs_stack[rs_sp++] = value;
+}
+
+static Value rs_pop(void) {
+    return rs_sp > 0 ? rs_stack[--rs_sp] : rs_null();
+}
+
+static Value rs_peek(void) {
+    return rs_sp > 0 ? rs_stack[rs_sp - 1] : rs_null();
+}
+
+static void rs_truncate(size_t height) {
+    while (rs_sp > height) {
+        rs_release(rs_stack[--rs_sp]);
+    }
+}
+
+static void rs_drop(void) {
+    rs_release(rs_pop());
+}
+
+static void rs_dup(void) {
+    rs_push(rs_retain(rs_peek()));
+}
+
+static RsScope *rs_scope_new(size_t size, RsScope *outer) {
+    RsScope *scope = rs_alloc(sizeof(RsScope));
+    scope->refs = 1;
+    scope->outer = outer;
+    scope->size = size;
+    scope->bindings = calloc(size ? size : 1, sizeof(RsBinding));
+    if (!scope->bindings) {
+        fputs("out of memory\n", stderr);
+        exit(1);
+    }
+    return scope;
+}
+
+// takes the value, refuses to overwrite a constant like Scope::set
+static bool rs_scope_set(RsScope *scope, size_t slot, Value value, bool constant) {
+    if (slot >= scope->size) {
+        scope->bindings = realloc(scope->bindings, (slot + 1) * sizeof(RsBinding));
+        if (!scope->bindings) {
+            fputs("out of memory\n", stderr);
+            exit(1);
+        }
+        memset(scope->bindings + scope->size, 0, (slot + 1 - scope->size) * sizeof(RsBinding));
+        scope->size = slot + 1;
+    }
+
+    RsBinding *binding = &scope->bindings[slot];
+    if (binding->constant) {
+        rs_release(value);
+        return false;
+    }
+
+    rs_release(binding->value);
+    binding->value = value;
+    binding->bound = true;
+    binding->constant = constant;
+    return true;
+}
+
+// NULL while the slot is unset, e.g. for a let in a branch that didn't run
+static RsBinding *rs_local(RsFrame *frame, size_t depth, size_t slot) {
+    RsScope *scope = frame->scope;
+    while (scope && depth > 0) {
+        scope = scope->outer;
+        depth--;
+    }
+
+    if (!scope || slot >= scope->size || !scope->bindings[slot].bound) {
+        return NULL;
+    }
+    return &scope->bindings[slot];
+}
+
+static void rs_get_local(RsFrame *frame, size_t depth, size_t slot, const char *name) {
+    RsBinding *binding = rs_local(frame, depth, slot);
+    if (!binding) {
+        rs_error(RS_NAME_ERROR, "identifier not found: %s", name);
+    }
+    rs_push(rs_retain(binding->value));
+}
+
+static bool rs_get_local_or(RsFrame *frame, size_t depth, size_t slot) {
+    RsBinding *binding = rs_local(frame, depth, slot);
+    if (binding) {
+        rs_push(rs_retain(binding->value));
+    }
+    return binding != NULL;
+}
+
+static void rs_set_local(RsFrame *frame, size_t slot, bool constant, const char *name) {
+    if (!rs_scope_set(frame->scope, slot, rs_pop(), constant)) {
+        rs_error(RS_CONSTANT_ERROR, "cannot redefine constant: %s", name);
+    }
+}
+
+static void rs_push_scope(RsFrame *frame, size_t size) {
+    frame->scope = rs_scope_new(size, frame->scope);
+}
+
+static void rs_pop_scope(RsFrame *frame) {
+    RsScope *scope = frame->scope;
+    frame->scope = scope->outer;
+    frame->scope->refs++;
+    rs_scope_release(scope);
+}
+
+static bool rs_truthy(Value value) {
+    switch (value.tag) {
+    case RS_BOOLEAN:
+        return value.as.boolean;
+    case RS_INTEGER:
+        return value.as.integer != 0;
+    case RS_NULL:
+        return false;
+    default:
+        return true;
+    }
+}
+
+static bool rs_pop_truthy(void) {
+    Value value = rs_pop();
+    bool truthy = rs_truthy(value);
+    rs_release(value);
+    return truthy;
+}
+
+static bool rs_string_equal(Value left, Value right) {
+    RsString *a = left.as.object;
+    RsString *b = right.as.object;
+    return a->length == b->length && memcmp(a->data, b->data, a->length) == 0;
+}
+
+static bool rs_equal(Value left, Value right) {
+    if (left.tag != right.tag) {
+        return false;
+    }
+
+    switch (left.tag) {
+    case RS_INTEGER:
+        return left.as.integer == right.as.integer;
+    case RS_BOOLEAN:
+        return left.as.boolean == right.as.boolean;
+    case RS_STRING:
+        return rs_string_equal(left, right);
+    case RS_NULL:
+        return true;
+    case RS_ENUM_VALUE: {
+        RsEnumValue *a = left.as.object;
+        RsEnumValue *b = right.as.object;
+        if (a->type != b->type || a->variant != b->variant) {
+            return false;
+        }
+        for (size_t i = 0; i < a->count && i < b->count; i++) {
+            if (!rs_equal(a->values[i], b->values[i])) {
+                return false;
+            }
+        }
+        return true;
+    }
+    default:
+        return false;
+    }
+}
+
+// errors are raised from inside rs_infix, which leaves the operands for the handler
+static Value rs_integer_infix(const char *op, int64_t left, int64_t right) {
+    int64_t result;
+    bool overflow;
+
+    switch (op[0]) {
+    case '+':
+        overflow = __builtin_add_overflow(left, right, &result);
+        break;
+    case '-':
+        overflow = __builtin_sub_overflow(left, right, &result);
+        break;
+    case '*':
+        overflow = __builtin_mul_overflow(left, right, &result);
+        break;
+    case '/':
+        if (right == 0) {
+            rs_error(RS_ZERO_DIVISION_ERROR, "division by zero");
+        }
+        overflow = left == INT64_MIN && right == -1;
+        result = overflow ? 0 : left / right;
+        break;
+    case '<':
+        return rs_boolean(left < right);
+    case '>':
+        return rs_boolean(left > right);
+    case '=':
+        return rs_boolean(left == right);
+    default:
+        return rs_boolean(left != right);
+    }
+
+    if (overflow) {
+        rs_error(RS_OVERFLOW_ERROR, "integer overflow: %" PRId64 " %s %" PRId64, left, op, right);
+    }
+    return rs_integer(result);
+}
+
+static bool rs_comparable(Value value) {
+    return value.tag == RS_BOOLEAN || value.tag == RS_INTEGER || value.tag == RS_NULL;
+}
+
+static bool rs_same_type(Value left, Value right) {
+    return strcmp(rs_type_name(left), rs_type_name(right)) == 0;
+}
+
+// errors leave the operands on the stack, so the handler releases them
+static void rs_infix(const char *op) {
+    Value left = rs_sp > 1 ? rs_stack[rs_sp - 2] : rs_null();
+    Value right = rs_peek();
+    Value result;
+
+    if (left.tag == RS_INTEGER && right.tag == RS_INTEGER) {
+        result = rs_integer_infix(op, left.as.integer, right.as.integer);
+    } else if (left.tag == RS_ENUM_VALUE || right.tag == RS_ENUM_VALUE) {
+        if (strcmp(op, "==") == 0) {
+            result = rs_boolean(rs_equal(left, right));
+        } else if (strcmp(op, "!=") == 0) {
+            result = rs_boolean(!rs_equal(left, right));
+        } else if (!rs_same_type(left, right)) {
+            rs_error(RS_TYPE_ERROR, "type mismatch: %s %s %s", rs_type_name(left), op, rs_type_name(right));
+        } else {
+            rs_error(RS_TYPE_ERROR, "unknown operator: %s %s %s", rs_type_name(left), op, rs_type_name(right));
+        }
+    } else if (rs_comparable(left) && rs_comparable(right) && strcmp(op, "==") == 0) {
+        result = rs_boolean(rs_truthy(left) == rs_truthy(right));
+    } else if (rs_comparable(left) && rs_comparable(right) && strcmp(op, "!=") == 0) {
+        result = rs_boolean(rs_truthy(left) != rs_truthy(right));
+    } else if (left.tag == RS_STRING && right.tag == RS_STRING && strcmp(op, "==") == 0) {
+        result = rs_boolean(rs_string_equal(left, right));
+    } else if (left.tag == RS_STRING && right.tag == RS_STRING && strcmp(op, "!=") == 0) {
+        result = rs_boolean(!rs_string_equal(left, right));
+    } else if (left.tag == RS_STRING && right.tag == RS_STRING) {
+        if (strcmp(op, "+") != 0) {
+            rs_error(RS_TYPE_ERROR, "unknown operator: STRING %s STRING", op);
+        }
+        RsString *a = left.as.object;
+        RsString *b = right.as.object;
+        RsString *joined = rs_alloc(sizeof(RsString) + a->length + b->length);
+        joined->length = a->length + b->length;
+        memcpy(joined->data, a->data, a->length);
+        memcpy(joined->data + a->length, b->data, b->length);
+        result = rs_object(RS_STRING, joined);
+    } else if (!rs_same_type(left, right)) {
+        rs_error(RS_TYPE_ERROR, "type mismatch: %s %s %s", rs_type_name(left), op, rs_type_name(right));
+    } else {
+        rs_error(RS_TYPE_ERROR, "unknown operator: %s %s %s", rs_type_name(left), op, rs_type_name(right));
+    }
+
+    rs_drop();
+    rs_drop();
+    rs_push(result);
+}
+
+static void rs_minus(void) {
+    Value right = rs_peek();
+    if (right.tag != RS_INTEGER) {
+        rs_error(RS_TYPE_ERROR, "unknown operator: -%s", rs_type_name(right));
+    }
+    if (right.as.integer == INT64_MIN) {
+        rs_error(RS_OVERFLOW_ERROR, "integer overflow: -(%" PRId64 ")", right.as.integer);
+    }
+    rs_stack[rs_sp - 1] = rs_integer(-right.as.integer);
+}
+
+static void rs_bang(void) {
+    Value right = rs_pop();
+    bool result = right.tag == RS_BOOLEAN ? !right.as.boolean : right.tag == RS_NULL;
+    rs_release(right);
+    rs_push(rs_boolean(result));
+}
+
+// pops `count` values into a new array, keeping their order
+static Value rs_collect(size_t count) {
+    RsArray *array = rs_alloc(sizeof(RsArray) + count * sizeof(Value));
+    array->length = count;
+    rs_sp -= count;
+    memcpy(array->items, rs_stack + rs_sp, count * sizeof(Value));
+    return rs_object(RS_ARRAY, array);
+}
+
+static void rs_array(size_t count) {
+    rs_push(rs_collect(count));
+}
+
+static bool rs_hashable(Value value) {
+    return value.tag == RS_INTEGER || value.tag == RS_BOOLEAN || value.tag == RS_STRING;
+}
+
+// keys are equal when their type and value are, like HashKey
+static bool rs_same_key(Value left, Value right) {
+    return left.tag == right.tag && rs_equal(left, right);
+}
+
+static void rs_hash(size_t count) {
+    Value *items = rs_stack + rs_sp - count * 2;
+    for (size_t i = 0; i < count; i++) {
+        if (!rs_hashable(items[i * 2])) {
+            rs_error(RS_TYPE_ERROR, "unusable as hash key: %s", rs_type_name(items[i * 2]));
+        }
+    }
+
+    RsHash *hash = rs_alloc(sizeof(RsHash) + count * sizeof(RsPair));
+    hash->length = 0;
+    for (size_t i = 0; i < count; i++) {
+        Value key = items[i * 2];
+        Value value = items[i * 2 + 1];
+
+        size_t idx = 0;
+        while (idx < hash->length && !rs_same_key(hash->pairs[idx].key, key)) {
+            idx++;
+        }
+        if (idx < hash->length) {
+            rs_release(key);
+            rs_release(hash->pairs[idx].value);
+            hash->pairs[idx].value = value;
+        } else {
+            hash->pairs[hash->length++] = (RsPair){key, value};
+        }
+    }
+
+    rs_sp -= count * 2;
+    rs_push(rs_object(RS_HASH, hash));
+}
+
+static RsPair *rs_hash_get(RsHash *hash, Value key) {
+    for (size_t i = 0; i < hash->length; i++) {
+        if (rs_same_key(hash->pairs[i].key, key)) {
+            return &hash->pairs[i];
+        }
+    }
+    return NULL;
+}
+
+static void rs_index(void) {
+    Value left = rs_sp > 1 ? rs_stack[rs_sp - 2] : rs_null();
+    Value index = rs_peek();
+    Value result = rs_null();
+
+    if (left.tag == RS_ARRAY && index.tag == RS_INTEGER) {
+        RsArray *array = left.as.object;
+        if (index.as.integer >= 0 && (uint64_t)index.as.integer < array->length) {
+            result = rs_retain(array->items[index.as.integer]);
+        }
+    } else if (left.tag == RS_HASH) {
+        if (!rs_hashable(index)) {
+            rs_error(RS_TYPE_ERROR, "unusable as hash key: %s", rs_type_name(index));
+        }
+        RsPair *pair = rs_hash_get(left.as.object, index);
+        if (pair) {
+            result = rs_retain(pair->value);
+        }
+    } else {
+        rs_error(RS_TYPE_ERROR, "index operator not suported: %s", rs_type_name(left));
+    }
+
+    rs_drop();
+    rs_drop();
+    rs_push(result);
+}
+
+static Value rs_variant_object(RsEnumType *type, size_t variant) {
+    type->refs++;
+    if (type->def->variants[variant].count > 0) {
+        RsConstructor *constructor = rs_alloc(sizeof(RsConstructor));
+        constructor->type = type;
+        constructor->variant = variant;
+        return rs_object(RS_CONSTRUCTOR, constructor);
+    }
+
+    RsEnumValue *enum_value = rs_alloc(sizeof(RsEnumValue));
+    enum_value->type = type;
+    enum_value->variant = variant;
+    enum_value->count = 0;
+    return rs_object(RS_ENUM_VALUE, enum_value);
+}
+
+static Value *rs_method(RsMethods *methods, const char *name) {
+    for (size_t i = 0; i < methods->length; i++) {
+        if (strcmp(methods->items[i].name, name) == 0) {
+            return &methods->items[i].value;
+        }
+    }
+    return NULL;
+}
+
+static long rs_field_index(const char *const *fields, size_t count, const char *name) {
+    for (size_t i = 0; i < count; i++) {
+        if (strcmp(fields[i], name) == 0) {
+            return (long)i;
+        }
+    }
+    return -1;
+}
+
+// the property of a value as eval_member_expression finds it, the value is kept
+static Value rs_member_value(Value object, const char *name) {
+    switch (object.tag) {
+    case RS_STRUCT: {
+        RsStruct *instance = object.as.object;
+        const RsStructDef *def = instance->type->def;
+        long idx = rs_field_index(def->fields, def->count, name);
+        if (idx < 0) {
+            rs_error(RS_NAME_ERROR, "struct %s has no field named %s", def->name, name);
+        }
+        return rs_retain(instance->values[idx]);
+    }
+    case RS_ENUM_VALUE: {
+        RsEnumValue *enum_value = object.as.object;
+        const RsVariantDef *variant = &enum_value->type->def->variants[enum_value->variant];
+        long idx = rs_field_index(variant->fields, variant->count, name);
+        if (idx < 0) {
+            rs_error(RS_NAME_ERROR, "variant %s has no field named %s", variant->name, name);
+        }
+        return rs_retain(enum_value->values[idx]);
+    }
+    case RS_ENUM_TYPE: {
+        RsEnumType *type = object.as.object;
+        for (size_t i = 0; i < type->def->count; i++) {
+            if (strcmp(type->def->variants[i].name, name) == 0) {
+                return rs_variant_object(type, i);
+            }
+        }
+        Value *method = rs_method(&type->methods, name);
+        if (!method) {
+            rs_error(RS_NAME_ERROR, "enum %s has no variant named %s", type->def->name, name);
+        }
+        return rs_retain(*method);
+    }
+    case RS_STRUCT_TYPE: {
+        RsStructType *type = object.as.object;
+        Value *method = rs_method(&type->methods, name);
+        if (!method) {
+            rs_error(RS_NAME_ERROR, "struct %s has no method named %s", type->def->name, name);
+        }
+        return rs_retain(*method);
+    }
+    case RS_EXCEPTION: {
+        RsException *exception = object.as.object;
+        if (strcmp(name, "message") == 0) {
+            return rs_retain((Value){.tag = RS_STRING, .as.object = exception->message});
+        }
+        if (strcmp(name, "kind") == 0) {
+            const char *kind = rs_error_kinds[exception->kind];
+            return rs_string(kind, strlen(kind));
+        }
+        if (strcmp(name, "value") == 0) {
+            return rs_retain(exception->value);
+        }
+        rs_error(RS_NAME_ERROR, "exception has no property named %s", name);
+    }
+    default:
+        rs_error(RS_TYPE_ERROR, "property access not supported: %s", rs_type_name(object));
+    }
+}
+
+static void rs_member(const char *name) {
+    Value value = rs_member_value(rs_peek(), name);
+    rs_drop();
+    rs_push(value);
+}
+
+static void rs_set_member(const char *name) {
+    Value object = rs_sp > 1 ? rs_stack[rs_sp - 2] : rs_null();
+    if (object.tag != RS_STRUCT) {
+        rs_error(RS_TYPE_ERROR, "field assignment not supported: %s", rs_type_name(object));
+    }
+
+    RsStruct *instance = object.as.object;
+    const RsStructDef *def = instance->type->def;
+    long idx = rs_field_index(def->fields, def->count, name);
+    if (idx < 0) {
+        rs_error(RS_NAME_ERROR, "struct %s has no field named %s", def->name, name);
+    }
+
+    Value value = rs_pop();
+    rs_release(instance->values[idx]);
+    instance->values[idx] = rs_retain(value);
+    rs_drop();
+    rs_push(value);
+}
+
+static Value rs_len(Value *args, size_t count);
+static Value rs_first(Value *args, size_t count);
+static Value rs_last(Value *args, size_t count);
+static Value rs_rest(Value *args, size_t count);
+static Value rs_push_builtin(Value *args, size_t count);
+static Value rs_next(Value *args, size_t count);
+static Value rs_puts(Value *args, size_t count);
+
+static const RsBuiltin rs_builtins[] = {
+    {"len", rs_len},
+    {"first", rs_first},
+    {"last", rs_last},
+    {"rest", rs_rest},
+    {"push", rs_push_builtin},
+    {"next", rs_next},
+    {"puts", rs_puts},
+};
+
+static void rs_get_builtin(const char *name) {
+    for (size_t i = 0; i < sizeof(rs_builtins) / sizeof(rs_builtins[0]); i++) {
+        if (strcmp(rs_builtins[i].name, name) == 0) {
+            rs_push((Value){.tag = RS_BUILTIN, .as.builtin = &rs_builtins[i]});
+            return;
+        }
+    }
+    rs_error(RS_NAME_ERROR, "identifier not found: %s", name);
+}
+
+static void rs_check_arguments(size_t count, size_t want) {
+    if (count != want) {
+        rs_error(RS_ARGUMENT_ERROR, "wrong number of arguments. got=%zu, want=%zu", count, want);
+    }
+}
+
+// the messages are the interpreter's, which names `first` for all of them
+static RsArray *rs_array_argument(Value *args, size_t count, size_t want, const char *name) {
+    rs_check_arguments(count, want);
+    if (args[0].tag != RS_ARRAY) {
+        rs_error(RS_TYPE_ERROR, "argument to `%s` must be ARRAY, got=%s", name, rs_type_name(args[0]));
+    }
+    return args[0].as.object;
+}
+
+static Value rs_len(Value *args, size_t count) {
+    rs_check_arguments(count, 1);
+    switch (args[0].tag) {
+    case RS_STRING:
+        return rs_integer((int64_t)((RsString *)args[0].as.object)->length);
+    case RS_ARRAY:
+        return rs_integer((int64_t)((RsArray *)args[0].as.object)->length);
+    default:
+        rs_error(RS_TYPE_ERROR, "argument to `len` not supported, got %s", rs_type_name(args[0]));
+    }
+}
+
+static Value rs_first(Value *args, size_t count) {
+    RsArray *array = rs_array_argument(args, count, 1, "first");
+    return array->length > 0 ? rs_retain(array->items[0]) : rs_null();
+}
+
+static Value rs_last(Value *args, size_t count) {
+    RsArray *array = rs_array_argument(args, count, 1, "first");
+    return array->length > 0 ? rs_retain(array->items[array->length - 1]) : rs_null();
+}
+
+static Value rs_rest(Value *args, size_t count) {
+    RsArray *array = rs_array_argument(args, count, 1, "first");
+    if (array->length == 0) {
+        return rs_null();
+    }
+
+    RsArray *rest = rs_alloc(sizeof(RsArray) + (array->length - 1) * sizeof(Value));
+    rest->length = array->length - 1;
+    for (size_t i = 1; i < array->length; i++) {
+        rest->items[i - 1] = rs_retain(array->items[i]);
+    }
+    return rs_object(RS_ARRAY, rest);
+}
+
+static Value rs_push_builtin(Value *args, size_t count) {
+    RsArray *array = rs_array_argument(args, count, 2, "first");
+
+    RsArray *pushed = rs_alloc(sizeof(RsArray) + (array->length + 1) * sizeof(Value));
+    pushed->length = array->length + 1;
+    for (size_t i = 0; i < array->length; i++) {
+        pushed->items[i] = rs_retain(array->items[i]);
+    }
+    pushed->items[array->length] = rs_retain(args[1]);
+    return rs_object(RS_ARRAY, pushed);
+}
+
+// translated programs have no generators to resume
+static Value rs_next(Value *args, size_t count) {
+    rs_check_arguments(count, 1);
+    rs_error(RS_TYPE_ERROR, "argument to `next` must be GENERATOR, got=%s", rs_type_name(args[0]));
+}
+
+static Value rs_puts(Value *args, size_t count) {
+    for (size_t i = 0; i < count; i++) {
+        RsBuffer buffer = {0};
+        rs_inspect(&buffer, args[i]);
+        fwrite(buffer.data, 1, buffer.length, stdout);
+        fputc('\n', stdout);
+        free(buffer.data);
+    }
+    return rs_null();
+}
+
+static void rs_closure(RsFrame *frame, const RsFunction *function) {
+    RsClosure *closure = rs_alloc(sizeof(RsClosure));
+    closure->function = function;
+    closure->env = frame->scope;
+    closure->env->refs++;
+    rs_push(rs_object(RS_CLOSURE, closure));
+}
+
+static void rs_check_fields(const char *name, size_t count, size_t want) {
+    if (count != want) {
+        rs_error(RS_ARGUMENT_ERROR, "wrong number of arguments for %s. got=%zu, want=%zu", name, count, want);
+    }
+}
+
+// calls the callee at `at` with the values above it, which are replaced by the result
+static void rs_invoke(size_t at) {
+    Value callee = rs_stack[at];
+    Value *args = rs_stack + at + 1;
+    size_t count = rs_sp - at - 1;
+    Value result;
+
+    switch (callee.tag) {
+    case RS_CLOSURE: {
+        RsClosure *closure = callee.as.object;
+        const RsFunction *function = closure->function;
+
+        // missing arguments leave their parameters unbound, extra ones are dropped.
+        // Frames live on the heap, so a handler can still release them after a throw
+        closure->env->refs++;
+        RsFrame *frame = rs_alloc(sizeof(RsFrame));
+        frame->scope = rs_scope_new(function->num_locals, closure->env);
+        frame->prev = rs_frames;
+        for (size_t i = 0; i < count; i++) {
+            if (i < function->num_params) {
+                rs_scope_set(frame->scope, i, args[i], false);
+            } else {
+                rs_release(args[i]);
+            }
+        }
+        rs_sp = at + 1;
+
+        rs_frames = frame;
+        function->code(frame);
+        rs_frames = frame->prev;
+
+        result = rs_pop();
+        rs_scope_release(frame->scope);
+        free(frame);
+        break;
+    }
+    case RS_BUILTIN:
+        result = callee.as.builtin->function(args, count);
+        break;
+    case RS_STRUCT_TYPE: {
+        RsStructType *type = callee.as.object;
+        rs_check_fields(type->def->name, count, type->def->count);
+
+        RsStruct *instance = rs_alloc(sizeof(RsStruct) + count * sizeof(Value));
+        instance->type = type;
+        type->refs++;
+        memcpy(instance->values, args, count * sizeof(Value));
+        rs_sp = at + 1;
+        result = rs_object(RS_STRUCT, instance);
+        break;
+    }
+    case RS_CONSTRUCTOR: {
+        RsConstructor *constructor = callee.as.object;
+        const RsVariantDef *variant = &constructor->type->def->variants[constructor->variant];
+        rs_check_fields(variant->name, count, variant->count);
+
+        RsEnumValue *enum_value = rs_alloc(sizeof(RsEnumValue) + count * sizeof(Value));
+        enum_value->type = constructor->type;
+        enum_value->type->refs++;
+        enum_value->variant = constructor->variant;
+        enum_value->count = count;
+        memcpy(enum_value->values, args, count * sizeof(Value));
+        rs_sp = at + 1;
+        result = rs_object(RS_ENUM_VALUE, enum_value);
+        break;
+    }
+    default:
+        rs_error(RS_TYPE_ERROR, "not a function %s", rs_type_name(callee));
+    }
+
+    rs_truncate(at);
+    rs_push(result);
+}
+
+static void rs_call(size_t count) {
+    rs_invoke(rs_sp - count - 1);
+}
+
+// fields shadow methods, and only functions taking `self` can be called on an instance
+static Value *rs_find_method(Value receiver, const char *name) {
+    RsMethods *methods;
+
+    if (receiver.tag == RS_STRUCT) {
+        RsStruct *instance = receiver.as.object;
+        const RsStructDef *def = instance->type->def;
+        if (rs_field_index(def->fields, def->count, name) >= 0) {
+            return NULL;
+        }
+        methods = &instance->type->methods;
+    } else if (receiver.tag == RS_ENUM_VALUE) {
+        RsEnumValue *enum_value = receiver.as.object;
+        const RsVariantDef *variant = &enum_value->type->def->variants[enum_value->variant];
+        if (rs_field_index(variant->fields, variant->count, name) >= 0) {
+            return NULL;
+        }
+        methods = &enum_value->type->methods;
+    } else {
+        return NULL;
+    }
+
+    Value *method = rs_method(methods, name);
+    if (!method || method->tag != RS_CLOSURE || !((RsClosure *)method->as.object)->function->takes_self) {
+        return NULL;
+    }
+    return method;
+}
+
+// a method gets the receiver as its first argument, anything else found on the
+// receiver is called without it
+static void rs_call_method(const char *name, size_t count) {
+    size_t at = rs_sp - count - 1;
+    Value receiver = rs_stack[at];
+
+    Value *method = rs_find_method(receiver, name);
+    if (method) {
+        rs_push(rs_null());
+        memmove(rs_stack + at + 1, rs_stack + at, (count + 1) * sizeof(Value));
+        rs_stack[at] = rs_retain(*method);
+    } else {
+        rs_stack[at] = rs_member_value(receiver, name);
+        rs_release(receiver);
+    }
+
+    rs_invoke(at);
+}
+
+static void rs_struct_type(const RsStructDef *def) {
+    RsStructType *type = rs_alloc(sizeof(RsStructType));
+    type->def = def;
+    type->methods = (RsMethods){0, NULL};
+    rs_push(rs_object(RS_STRUCT_TYPE, type));
+}
+
+static void rs_enum_type(const RsEnumDef *def) {
+    RsEnumType *type = rs_alloc(sizeof(RsEnumType));
+    type->def = def;
+    type->methods = (RsMethods){0, NULL};
+    rs_push(rs_object(RS_ENUM_TYPE, type));
+}
+
+static void rs_variant(size_t variant) {
+    rs_push(rs_variant_object(rs_peek().as.object, variant));
+}
+
+static void rs_impl(const char *const *names, size_t count) {
+    Value type = rs_stack[rs_sp - count - 1];
+    RsMethods *methods;
+    if (type.tag == RS_STRUCT_TYPE) {
+        methods = &((RsStructType *)type.as.object)->methods;
+    } else if (type.tag == RS_ENUM_TYPE) {
+        methods = &((RsEnumType *)type.as.object)->methods;
+    } else {
+        rs_error(RS_TYPE_ERROR, "cannot implement methods for %s", rs_type_name(type));
+    }
+
+    for (size_t i = 0; i < count; i++) {
+        Value method = rs_stack[rs_sp - count + i];
+        Value *existing = rs_method(methods, names[i]);
+        if (existing) {
+            rs_release(*existing);
+            *existing = method;
+            continue;
+        }
+
+        methods->items = realloc(methods->items, (methods->length + 1) * sizeof(RsMethod));
+        if (!methods->items) {
+            fputs("out of memory\n", stderr);
+            exit(1);
+        }
+        methods->items[methods->length++] = (RsMethod){names[i], method};
+    }
+
+    rs_sp -= count;
+    rs_drop();
+}
+
+// like throw_value: an exception is rethrown as it is, anything else becomes the
+// message of an Error
+static void rs_throw(void) {
+    Value value = rs_pop();
+
+    if (value.tag == RS_EXCEPTION) {
+        RsException *exception = value.as.object;
+        RsErrorKind kind = exception->kind;
+        RsString *message = exception->message;
+        Value inner = rs_retain(exception->value);
+        message->refs++;
+        rs_release(value);
+        rs_raise(kind, message, inner);
+    }
+
+    if (value.tag == RS_STRING) {
+        RsString *string = value.as.object;
+        rs_raise(RS_ERROR, rs_new_string(string->data, string->length), value);
+    }
+
+    RsBuffer buffer = {0};
+    rs_inspect(&buffer, value);
+    RsString *message = rs_new_string(buffer.data, buffer.length);
+    free(buffer.data);
+    rs_raise(RS_ERROR, message, value);
+}
+
+// runs a block under a handler. A throw unwinds the calls, stack and scopes the
+// block left behind, and the pending error is left for the caller
+static int rs_guard(RsFrame *frame, RsBlock block) {
+    RsHandler handler;
+    size_t height = rs_sp;
+    RsScope *scope = frame->scope;
+    RsFrame *frames = rs_frames;
+
+    scope->refs++;
+    handler.prev = rs_handler;
+    rs_handler = &handler;
+
+    if (setjmp(handler.jump) != 0) {
+        rs_handler = handler.prev;
+        while (rs_frames != frames) {
+            RsFrame *unwound = rs_frames;
+            rs_frames = unwound->prev;
+            rs_scope_release(unwound->scope);
+            free(unwound);
+        }
+        rs_truncate(height);
+        rs_scope_release(frame->scope);
+        frame->scope = scope;
+        return RS_THROW;
+    }
+
+    int completion = block(frame);
+    rs_handler = handler.prev;
+    rs_scope_release(scope);
+    return completion;
+}
+
+// the counterpart of Vm::execute_try. A return or throw from the finally block
+// replaces how the try and catch blocks finished
+static int rs_try(RsFrame *frame, RsBlock body, RsBlock catch_block, RsBlock finally_block) {
+    int completion = rs_guard(frame, body);
+
+    if (completion == RS_THROW && catch_block) {
+        RsException *exception = rs_alloc(sizeof(RsException));
+        exception->kind = rs_error_kind;
+        exception->message = rs_error_message;
+        exception->value = rs_error_value;
+        rs_push(rs_object(RS_EXCEPTION, exception));
+        completion = rs_guard(frame, catch_block);
+    }
+
+    if (finally_block) {
+        RsErrorKind kind = rs_error_kind;
+        RsString *message = rs_error_message;
+        Value value = rs_error_value;
+
+        int finally_completion = rs_guard(frame, finally_block);
+        if (finally_completion == RS_NORMAL) {
+            rs_drop();
+            rs_error_kind = kind;
+            rs_error_message = message;
+            rs_error_value = value;
+        } else {
+            if (completion == RS_THROW) {
+                rs_release((Value){.tag = RS_STRING, .as.object = message});
+                rs_release(value);
+            }
+            completion = finally_completion;
+        }
+    }
+
+    if (completion == RS_THROW) {
+        rs_raise(rs_error_kind, rs_error_message, rs_error_value);
+    }
+    return completion;
+}
+
+static bool rs_literal_equal(const RsLiteral *literal, Value value) {
+    if (literal->tag != value.tag) {
+        return false;
+    }
+
+    switch (literal->tag) {
+    case RS_INTEGER:
+        return literal->integer == value.as.integer;
+    case RS_BOOLEAN:
+        return (literal->integer != 0) == value.as.boolean;
+    default: {
+        RsString *string = value.as.object;
+        return string->length == literal->length && memcmp(string->data, literal->string, literal->length) == 0;
+    }
+    }
+}
+
+typedef struct {
+    size_t length;
+    size_t capacity;
+    size_t *slots;
+    Value *values;
+} RsBindings;
+
+static void rs_bind(RsBindings *bindings, size_t slot, Value value) {
+    if (bindings->length == bindings->capacity) {
+        bindings->capacity = bindings->capacity ? bindings->capacity * 2 : 4;
+        bindings->slots = realloc(bindings->slots, bindings->capacity * sizeof(size_t));
+        bindings->values = realloc(bindings->values, bindings->capacity * sizeof(Value));
+        if (!bindings->slots || !bindings->values) {
+            fputs("out of memory\n", stderr);
+            exit(1);
+        }
+    }
+    bindings->slots[bindings->length] = slot;
+    bindings->values[bindings->length] = rs_retain(value);
+    bindings->length++;
+}
+
+static void rs_unbind(RsBindings *bindings, size_t length) {
+    while (bindings->length > length) {
+        rs_release(bindings->values[--bindings->length]);
+    }
+}
+
+static bool rs_names_unit_variant(const char *name, Value value) {
+    if (value.tag != RS_ENUM_VALUE) {
+        return false;
+    }
+
+    RsEnumValue *enum_value = value.as.object;
+    const RsEnumDef *def = enum_value->type->def;
+    return enum_value->count == 0 && strcmp(name, def->variants[enum_value->variant].name) == 0;
+}
+
+// bindings are only made once the whole pattern matched, like in the evaluator
+static bool rs_match_pattern(RsFrame *frame, const RsPattern *pattern, Value value, RsBindings *bindings) {
+    switch (pattern->kind) {
+    case RS_PATTERN_WILDCARD:
+        return true;
+    case RS_PATTERN_BINDING:
+        // a name holding the unit variant of that name matches the variant
+        for (size_t i = 0; i < pattern->count; i++) {
+            RsBinding *binding = rs_local(frame, pattern->shadowed[i].depth, pattern->shadowed[i].slot);
+            if (!binding) {
+                continue;
+            }
+            if (rs_names_unit_variant(pattern->name, binding->value) && !rs_equal(binding->value, value)) {
+                return false;
+            }
+            break;
+        }
+        rs_bind(bindings, pattern->slot, value);
+        return true;
+    case RS_PATTERN_LITERAL:
+        return rs_literal_equal(&pattern->literal, value);
+    case RS_PATTERN_OR:
+        for (size_t i = 0; i < pattern->count; i++) {
+            size_t length = bindings->length;
+            if (rs_match_pattern(frame, pattern->items[i], value, bindings)) {
+                return true;
+            }
+            rs_unbind(bindings, length);
+        }
+        return false;
+    case RS_PATTERN_VARIANT: {
+        if (value.tag != RS_ENUM_VALUE) {
+            return false;
+        }
+
+        RsEnumValue *enum_value = value.as.object;
+        const RsEnumDef *def = enum_value->type->def;
+        if (pattern->enum_name && strcmp(pattern->enum_name, def->name) != 0) {
+            return false;
+        }
+        if (strcmp(pattern->name, def->variants[enum_value->variant].name) != 0) {
+            return false;
+        }
+        if (pattern->count != enum_value->count) {
+            return false;
+        }
+
+        for (size_t i = 0; i < pattern->count; i++) {
+            if (!rs_match_pattern(frame, pattern->items[i], enum_value->values[i], bindings)) {
+                return false;
+            }
+        }
+        return true;
+    }
+    case RS_PATTERN_ARRAY: {
+        if (value.tag != RS_ARRAY) {
+            return false;
+        }
+
+        RsArray *array = value.as.object;
+        if (array->length < pattern->count || (!pattern->rest && array->length != pattern->count)) {
+            return false;
+        }
+
+        for (size_t i = 0; i < pattern->count; i++) {
+            if (!rs_match_pattern(frame, pattern->items[i], array->items[i], bindings)) {
+                return false;
+            }
+        }
+
+        if (!pattern->rest) {
+            return true;
+        }
+
+        size_t length = array->length - pattern->count;
+        RsArray *remaining = rs_alloc(sizeof(RsArray) + length * sizeof(Value));
+        remaining->length = length;
+        for (size_t i = 0; i < length; i++) {
+            remaining->items[i] = rs_retain(array->items[pattern->count + i]);
+        }
+
+        Value rest = rs_object(RS_ARRAY, remaining);
+        bool matched = rs_match_pattern(frame, pattern->rest, rest, bindings);
+        rs_release(rest);
+        return matched;
+    }
+    case RS_PATTERN_HASH: {
+        if (value.tag != RS_HASH) {
+            return false;
+        }
+
+        RsHash *hash = value.as.object;
+        for (size_t i = 0; i < pattern->count; i++) {
+            RsPair *pair = NULL;
+            for (size_t j = 0; j < hash->length && !pair; j++) {
+                if (rs_literal_equal(&pattern->keys[i], hash->pairs[j].key)) {
+                    pair = &hash->pairs[j];
+                }
+            }
+
+            if (!pair || !rs_match_pattern(frame, pattern->items[i], pair->value, bindings)) {
+                return false;
+            }
+        }
+        return true;
+    }
+    }
+    return false;
+}
+
+// the subject stays on the stack, the bindings go into the arm's scope
+static bool rs_match(RsFrame *frame, const RsPattern *pattern) {
+    RsBindings bindings = {0};
+    bool matched = rs_match_pattern(frame, pattern, rs_peek(), &bindings);
+
+    for (size_t i = 0; i < bindings.length; i++) {
+        if (matched) {
+            rs_scope_set(frame->scope, bindings.slots[i], bindings.values[i], false);
+        } else {
+            rs_release(bindings.values[i]);
+        }
+    }
+    free(bindings.slots);
+    free(bindings.values);
+    return matched;
+}
+
+static int rs_main(const RsFunction *main_function) {
+    RsFrame frame = {rs_scope_new(main_function->num_locals, NULL), NULL};
+    rs_frames = &frame;
+    main_function->code(&frame);
+    fflush(stdout);
+    return 0;
+}
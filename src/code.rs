@@ -0,0 +1,257 @@
+use std::fmt::Write;
+
+pub type Instructions = Vec<u8>;
+
+// operand value standing in for a missing jump target, e.g. a try without catch
+pub const NO_TARGET: usize = u16::MAX as usize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum Opcode {
+    Constant,
+    Pop,
+    Dup,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    LessThan,
+    Minus,
+    Bang,
+    True,
+    False,
+    Null,
+    Jump,
+    JumpNotTruthy,
+    GetLocal,
+    SetLocal,
+    SetConst,
+    GetBuiltin,
+    Error,
+    Array,
+    Hash,
+    Index,
+    Member,
+    SetMember,
+    Call,
+    CallMethod,
+    ReturnValue,
+    Return,
+    Closure,
+    Struct,
+    Enum,
+    Variant,
+    Impl,
+    Import,
+    Throw,
+    Try,
+    EndTry,
+    PushScope,
+    PopScope,
+    Match,
+    Quote,
+    Yield,
+    YieldDelegate,
+    GetLocalOr,
+    TailCall,
+    TailCallMethod,
+}
+
+pub struct Definition {
+    pub name: &'static str,
+    pub operand_widths: &'static [usize],
+}
+
+const OPCODES: [Opcode; 50] = [
+    Opcode::Constant,
+    Opcode::Pop,
+    Opcode::Dup,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::Equal,
+    Opcode::NotEqual,
+    Opcode::GreaterThan,
+    Opcode::LessThan,
+    Opcode::Minus,
+    Opcode::Bang,
+    Opcode::True,
+    Opcode::False,
+    Opcode::Null,
+    Opcode::Jump,
+    Opcode::JumpNotTruthy,
+    Opcode::GetLocal,
+    Opcode::SetLocal,
+    Opcode::SetConst,
+    Opcode::GetBuiltin,
+    Opcode::Error,
+    Opcode::Array,
+    Opcode::Hash,
+    Opcode::Index,
+    Opcode::Member,
+    Opcode::SetMember,
+    Opcode::Call,
+    Opcode::CallMethod,
+    Opcode::ReturnValue,
+    Opcode::Return,
+    Opcode::Closure,
+    Opcode::Struct,
+    Opcode::Enum,
+    Opcode::Variant,
+    Opcode::Impl,
+    Opcode::Import,
+    Opcode::Throw,
+    Opcode::Try,
+    Opcode::EndTry,
+    Opcode::PushScope,
+    Opcode::PopScope,
+    Opcode::Match,
+    Opcode::Quote,
+    Opcode::Yield,
+    Opcode::YieldDelegate,
+    Opcode::GetLocalOr,
+    Opcode::TailCall,
+    Opcode::TailCallMethod,
+];
+
+impl Opcode {
+    pub fn from_byte(byte: u8) -> Option<Opcode> {
+        OPCODES.get(byte as usize).copied()
+    }
+
+    pub fn definition(&self) -> Definition {
+        let (name, operand_widths): (&'static str, &'static [usize]) = match self {
+            Opcode::Constant => ("OpConstant", &[2]),
+            Opcode::Pop => ("OpPop", &[]),
+            Opcode::Dup => ("OpDup", &[]),
+            Opcode::Add => ("OpAdd", &[]),
+            Opcode::Sub => ("OpSub", &[]),
+            Opcode::Mul => ("OpMul", &[]),
+            Opcode::Div => ("OpDiv", &[]),
+            Opcode::Equal => ("OpEqual", &[]),
+            Opcode::NotEqual => ("OpNotEqual", &[]),
+            Opcode::GreaterThan => ("OpGreaterThan", &[]),
+            Opcode::LessThan => ("OpLessThan", &[]),
+            Opcode::Minus => ("OpMinus", &[]),
+            Opcode::Bang => ("OpBang", &[]),
+            Opcode::True => ("OpTrue", &[]),
+            Opcode::False => ("OpFalse", &[]),
+            Opcode::Null => ("OpNull", &[]),
+            Opcode::Jump => ("OpJump", &[2]),
+            Opcode::JumpNotTruthy => ("OpJumpNotTruthy", &[2]),
+            // scope depth, slot, name constant
+            Opcode::GetLocal => ("OpGetLocal", &[1, 2, 2]),
+            // slot, name constant
+            Opcode::SetLocal => ("OpSetLocal", &[2, 2]),
+            Opcode::SetConst => ("OpSetConst", &[2, 2]),
+            Opcode::GetBuiltin => ("OpGetBuiltin", &[2]),
+            // error kind, message constant
+            Opcode::Error => ("OpError", &[1, 2]),
+            Opcode::Array => ("OpArray", &[2]),
+            Opcode::Hash => ("OpHash", &[2]),
+            Opcode::Index => ("OpIndex", &[]),
+            Opcode::Member => ("OpMember", &[2]),
+            Opcode::SetMember => ("OpSetMember", &[2]),
+            // name constant for the call chain of errors, number of arguments
+            Opcode::Call => ("OpCall", &[2, 1]),
+            // method name constant, number of arguments
+            Opcode::CallMethod => ("OpCallMethod", &[2, 1]),
+            Opcode::ReturnValue => ("OpReturnValue", &[]),
+            Opcode::Return => ("OpReturn", &[]),
+            Opcode::Closure => ("OpClosure", &[2]),
+            Opcode::Struct => ("OpStruct", &[2]),
+            Opcode::Enum => ("OpEnum", &[2]),
+            Opcode::Variant => ("OpVariant", &[2]),
+            // constant holding the method names
+            Opcode::Impl => ("OpImpl", &[2]),
+            Opcode::Import => ("OpImport", &[2]),
+            Opcode::Throw => ("OpThrow", &[]),
+            // catch, finally and end addresses
+            Opcode::Try => ("OpTry", &[2, 2, 2]),
+            Opcode::EndTry => ("OpEndTry", &[]),
+            Opcode::PushScope => ("OpPushScope", &[2]),
+            Opcode::PopScope => ("OpPopScope", &[]),
+            // pattern, address to continue at when it doesn't match
+            Opcode::Match => ("OpMatch", &[2, 2]),
+            // quoted node constant, number of unquoted values
+            Opcode::Quote => ("OpQuote", &[2, 2]),
+            Opcode::Yield => ("OpYield", &[]),
+            // whether the yield* is the last thing the generator does
+            Opcode::YieldDelegate => ("OpYieldDelegate", &[1]),
+            // scope depth, slot, address to continue at when the slot is set
+            Opcode::GetLocalOr => ("OpGetLocalOr", &[1, 2, 2]),
+            // calls whose result the caller returns, they replace the caller's frame
+            Opcode::TailCall => ("OpTailCall", &[2, 1]),
+            Opcode::TailCallMethod => ("OpTailCallMethod", &[2, 1]),
+        };
+
+        Definition {
+            name,
+            operand_widths,
+        }
+    }
+}
+
+pub fn make(op: Opcode, operands: &[usize]) -> Instructions {
+    let definition = op.definition();
+
+    let mut instruction = vec![op as u8];
+    for (operand, width) in operands.iter().zip(definition.operand_widths) {
+        match width {
+            1 => instruction.push(*operand as u8),
+            2 => instruction.extend_from_slice(&(*operand as u16).to_be_bytes()),
+            _ => unreachable!("operands are one or two bytes wide"),
+        }
+    }
+
+    instruction
+}
+
+pub fn read_operands(definition: &Definition, ins: &[u8]) -> (Vec<usize>, usize) {
+    let mut operands = Vec::with_capacity(definition.operand_widths.len());
+    let mut offset = 0;
+
+    for width in definition.operand_widths {
+        match width {
+            1 => operands.push(ins[offset] as usize),
+            _ => operands.push(read_u16(&ins[offset..])),
+        }
+        offset += width;
+    }
+
+    (operands, offset)
+}
+
+pub fn read_u16(ins: &[u8]) -> usize {
+    u16::from_be_bytes([ins[0], ins[1]]) as usize
+}
+
+pub fn disassemble(ins: &[u8]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < ins.len() {
+        let Some(op) = Opcode::from_byte(ins[i]) else {
+            let _ = writeln!(out, "ERROR: unknown opcode {}", ins[i]);
+            i += 1;
+            continue;
+        };
+
+        let definition = op.definition();
+        let (operands, read) = read_operands(&definition, &ins[i + 1..]);
+
+        let _ = write!(out, "{:04} {}", i, definition.name);
+        for operand in operands {
+            let _ = write!(out, " {}", operand);
+        }
+        out.push('\n');
+
+        i += 1 + read;
+    }
+
+    out
+}
@@ -0,0 +1,708 @@
+mod symbol_table;
+
+use symbol_table::SymbolTable;
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    ast::{
+        modify::modify_expression, BlockStatement, CallExpression, Expr, FunctionLiteral,
+        IfExpression, MatchExpression, Node, Pattern, Program, Stmt, TryExpression,
+    },
+    code::{make, Instructions, Opcode, NO_TARGET},
+    evaluator::{is_builtin, is_quote_call, unquote_call},
+    object::{self, CompiledFunction, Environment, ErrorKind, MatchPattern, Value},
+    resolver::{collect_pattern_bindings, Scope},
+};
+
+#[derive(Default)]
+struct CompilationUnit {
+    instructions: Instructions,
+    constants: Vec<Value>,
+    functions: Vec<Rc<CompiledFunction>>,
+    patterns: Vec<MatchPattern>,
+    lines: Vec<(usize, usize)>,
+}
+
+// where a statement sits. Like the evaluator, a generator can only be suspended at the
+// statements of its body and of the if and match statements in it, and only calls in
+// those places are tail calls
+#[derive(Clone, Copy, Default)]
+struct Position {
+    yields: bool,
+    tail: bool,
+}
+
+pub struct Compiler {
+    units: Vec<CompilationUnit>,
+    symbols: SymbolTable,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            units: Vec::new(),
+            symbols: SymbolTable::new(),
+        }
+    }
+
+    // globals keep their slots from one program to the next, so a compiler can be
+    // reused for every line of a REPL session as long as the VM is reused too
+    pub fn compile(&mut self, program: &Program) -> Result<Rc<CompiledFunction>, String> {
+        self.units = vec![CompilationUnit::default()];
+        self.symbols.collect_globals(&program.statements);
+
+        if self.compile_statements(&program.statements, Position::default())? {
+            self.emit(Opcode::ReturnValue, &[]);
+        } else {
+            self.emit(Opcode::Return, &[]);
+        }
+
+        let unit = self.units.pop().unwrap();
+        Ok(Rc::new(CompiledFunction {
+            instructions: unit.instructions,
+            constants: unit.constants,
+            functions: unit.functions,
+            patterns: unit.patterns,
+            parameters: Vec::new(),
+            num_locals: self.symbols.num_slots(),
+            generator: false,
+            literal: String::new(),
+            lines: unit.lines,
+        }))
+    }
+
+    pub fn global_names(&self) -> Vec<String> {
+        self.symbols.global_names()
+    }
+
+    pub fn global_slot(&self, name: &str) -> Option<usize> {
+        self.symbols.global_slot(name)
+    }
+
+    fn unit(&mut self) -> &mut CompilationUnit {
+        self.units.last_mut().unwrap()
+    }
+
+    fn position(&mut self) -> usize {
+        self.unit().instructions.len()
+    }
+
+    fn emit(&mut self, op: Opcode, operands: &[usize]) -> usize {
+        let instruction = make(op, operands);
+        let position = self.position();
+        self.unit().instructions.extend(instruction);
+        position
+    }
+
+    fn patch(&mut self, position: usize, operands: &[usize]) {
+        let op = Opcode::from_byte(self.unit().instructions[position]).unwrap();
+        let instruction = make(op, operands);
+        self.unit().instructions[position..position + instruction.len()].copy_from_slice(&instruction);
+    }
+
+    fn add_constant(&mut self, value: Value) -> usize {
+        self.unit().constants.push(value);
+        self.unit().constants.len() - 1
+    }
+
+    fn name_constant(&mut self, name: &str) -> usize {
+        let existing = self
+            .unit()
+            .constants
+            .iter()
+            .position(|constant| matches!(constant, Value::String(value) if value.as_ref() == name));
+
+        match existing {
+            Some(idx) => idx,
+            None => self.add_constant(Value::String(name.into())),
+        }
+    }
+
+    fn emit_error(&mut self, kind: ErrorKind, message: String) {
+        let message = self.add_constant(Value::String(message.into()));
+        self.emit(Opcode::Error, &[kind as usize, message]);
+    }
+
+    fn emit_binding(&mut self, op: Opcode, name: &str) {
+        let slot = self.symbols.declare(name);
+        let name = self.name_constant(name);
+        self.emit(op, &[slot, name]);
+    }
+
+    // returns whether the statements leave a value on the stack
+    fn compile_statements(&mut self, statements: &[Rc<Stmt>], position: Position) -> Result<bool, String> {
+        let mut pushed = false;
+
+        for (idx, stmt) in statements.iter().enumerate() {
+            if pushed {
+                self.emit(Opcode::Pop, &[]);
+            }
+
+            // like in eval_tail_block, a return anywhere in a tail block is a tail
+            let last = idx + 1 == statements.len() || matches!(stmt.as_ref(), Stmt::Return(_));
+            let tail = position.tail && last;
+            pushed = self.compile_statement(stmt, Position { tail, ..position })?;
+        }
+
+        Ok(pushed)
+    }
+
+    fn compile_block(&mut self, block: &BlockStatement, position: Position) -> Result<(), String> {
+        if !self.compile_statements(&block.statements, position)? {
+            self.emit(Opcode::Null, &[]);
+        }
+        Ok(())
+    }
+
+    fn compile_value(&mut self, value: Option<&Expr>) -> Result<(), String> {
+        match value {
+            Some(value) => self.compile_expression(value),
+            None => {
+                self.emit(Opcode::Null, &[]);
+                Ok(())
+            }
+        }
+    }
+
+    // nodes built by macros have no position in the source and are left out
+    fn record_line(&mut self, line: usize) {
+        let position = self.position();
+        let unit = self.unit();
+        if line == 0 || unit.lines.last().is_some_and(|(_, last)| *last == line) {
+            return;
+        }
+        unit.lines.push((position, line));
+    }
+
+    fn compile_statement(&mut self, stmt: &Stmt, position: Position) -> Result<bool, String> {
+        self.record_line(stmt.token().line);
+
+        match stmt {
+            Stmt::Expression(expr_stmt) => {
+                let Some(expression) = &expr_stmt.expression else {
+                    return Ok(false);
+                };
+
+                match expression.as_ref() {
+                    Expr::If(if_expr) => self.compile_if(if_expr, position)?,
+                    Expr::Match(match_expr) => self.compile_match(match_expr, position)?,
+                    Expr::Call(call_expr) => self.compile_call(call_expr, position.tail)?,
+                    expression => self.compile_expression(expression)?,
+                }
+                return Ok(true);
+            }
+            Stmt::Block(block) => return self.compile_statements(&block.statements, position),
+            Stmt::Let(let_stmt) => {
+                self.compile_value(let_stmt.value.as_deref())?;
+                self.emit_binding(Opcode::SetLocal, &let_stmt.name.value);
+            }
+            Stmt::Const(const_stmt) => {
+                self.compile_value(const_stmt.value.as_deref())?;
+                self.emit_binding(Opcode::SetConst, &const_stmt.name.value);
+            }
+            Stmt::Return(return_stmt) => {
+                match return_stmt.return_value.as_deref() {
+                    Some(Expr::Call(call_expr)) => self.compile_call(call_expr, position.tail)?,
+                    value => self.compile_value(value)?,
+                }
+                self.emit(Opcode::ReturnValue, &[]);
+            }
+            Stmt::Throw(throw_stmt) => {
+                self.compile_value(throw_stmt.value.as_deref())?;
+                self.emit(Opcode::Throw, &[]);
+            }
+            Stmt::Yield(yield_stmt) => {
+                if !position.yields {
+                    self.emit_error(
+                        ErrorKind::Error,
+                        "yield is only supported as a statement of a generator body".to_string(),
+                    );
+                    return Ok(false);
+                }
+
+                self.compile_value(yield_stmt.value.as_deref())?;
+                if yield_stmt.delegate {
+                    self.emit(Opcode::YieldDelegate, &[position.tail as usize]);
+                } else {
+                    self.emit(Opcode::Yield, &[]);
+                }
+            }
+            Stmt::Struct(struct_stmt) => {
+                let struct_type = self.add_constant(Value::StructType(Rc::new(object::StructType {
+                    name: struct_stmt.name.value.clone(),
+                    fields: struct_stmt.fields.iter().map(|field| field.value.clone()).collect(),
+                    methods: RefCell::new(HashMap::new()),
+                })));
+                self.emit(Opcode::Struct, &[struct_type]);
+                self.emit_binding(Opcode::SetLocal, &struct_stmt.name.value);
+            }
+            Stmt::Enum(enum_stmt) => {
+                let enum_type = self.add_constant(Value::EnumType(Rc::new(object::EnumType {
+                    name: enum_stmt.name.value.clone(),
+                    variants: enum_stmt
+                        .variants
+                        .iter()
+                        .map(|variant| object::Variant {
+                            name: variant.name.value.clone(),
+                            fields: variant.fields.iter().map(|field| field.value.clone()).collect(),
+                        })
+                        .collect(),
+                    methods: RefCell::new(HashMap::new()),
+                })));
+
+                self.emit(Opcode::Enum, &[enum_type]);
+                self.emit(Opcode::Dup, &[]);
+                self.emit_binding(Opcode::SetLocal, &enum_stmt.name.value);
+                for (idx, variant) in enum_stmt.variants.iter().enumerate() {
+                    self.emit(Opcode::Dup, &[]);
+                    self.emit(Opcode::Variant, &[idx]);
+                    self.emit_binding(Opcode::SetLocal, &variant.name.value);
+                }
+                self.emit(Opcode::Pop, &[]);
+            }
+            Stmt::Impl(impl_stmt) => {
+                self.compile_identifier(&impl_stmt.type_name.value);
+
+                let mut names = Vec::new();
+                for method in &impl_stmt.methods {
+                    let function = self.compile_function(&method.function)?;
+                    self.emit(Opcode::Closure, &[function]);
+                    names.push(Value::String(method.name.value.as_str().into()));
+                }
+
+                let names = self.add_constant(Value::Array(Rc::new(object::Array { elements: names })));
+                self.emit(Opcode::Impl, &[names]);
+            }
+            Stmt::Import(import_stmt) => {
+                let path = self.add_constant(Value::String(import_stmt.path.as_str().into()));
+                self.emit(Opcode::Import, &[path]);
+                self.emit_binding(Opcode::SetLocal, &import_stmt.alias.value);
+            }
+            Stmt::Export(export_stmt) => return self.compile_statement(&export_stmt.statement, position),
+        }
+
+        Ok(false)
+    }
+
+    fn compile_expression(&mut self, expr: &Expr) -> Result<(), String> {
+        match expr {
+            Expr::Integer(int_literal) => {
+                let constant = self.add_constant(Value::Integer(int_literal.value));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            Expr::String(str_literal) => {
+                let constant = self.add_constant(Value::String(str_literal.value.as_str().into()));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            Expr::Boolean(bool_node) => {
+                self.emit(if bool_node.value { Opcode::True } else { Opcode::False }, &[]);
+            }
+            Expr::Identifier(ident) => self.compile_identifier(&ident.value),
+            Expr::Prefix(prefix_expr) => {
+                self.compile_expression(&prefix_expr.right)?;
+                match prefix_expr.operator.as_str() {
+                    "!" => self.emit(Opcode::Bang, &[]),
+                    "-" => self.emit(Opcode::Minus, &[]),
+                    operator => return Err(format!("unknown operator: {}", operator)),
+                };
+            }
+            Expr::Infix(infix_expr) => {
+                let (Some(left), Some(right)) = (&infix_expr.left, &infix_expr.right) else {
+                    return Err(format!("incomplete expression: {}", infix_expr.to_string()));
+                };
+                self.compile_expression(left)?;
+                self.compile_expression(right)?;
+
+                let op = match infix_expr.operator.as_str() {
+                    "+" => Opcode::Add,
+                    "-" => Opcode::Sub,
+                    "*" => Opcode::Mul,
+                    "/" => Opcode::Div,
+                    "==" => Opcode::Equal,
+                    "!=" => Opcode::NotEqual,
+                    ">" => Opcode::GreaterThan,
+                    "<" => Opcode::LessThan,
+                    operator => return Err(format!("unknown operator: {}", operator)),
+                };
+                self.emit(op, &[]);
+            }
+            Expr::If(if_expr) => self.compile_if(if_expr, Position::default())?,
+            Expr::Match(match_expr) => self.compile_match(match_expr, Position::default())?,
+            Expr::Try(try_expr) => self.compile_try(try_expr)?,
+            Expr::Function(fn_literal) => {
+                let function = self.compile_function(fn_literal)?;
+                self.emit(Opcode::Closure, &[function]);
+            }
+            Expr::Macro(macro_literal) => {
+                let constant = self.add_constant(Value::Macro(Rc::new(object::Macro {
+                    parameters: macro_literal.parameters.clone(),
+                    body: macro_literal.body.clone(),
+                    env: Rc::new(RefCell::new(Environment::new())),
+                })));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            Expr::Call(call_expr) => self.compile_call(call_expr, false)?,
+            Expr::Array(array_literal) => {
+                for element in &array_literal.elements {
+                    self.compile_expression(element)?;
+                }
+                self.emit(Opcode::Array, &[array_literal.elements.len()]);
+            }
+            Expr::Hash(hash_literal) => {
+                for (key, value) in &hash_literal.pairs {
+                    self.compile_expression(key)?;
+                    self.compile_expression(value)?;
+                }
+                self.emit(Opcode::Hash, &[hash_literal.pairs.len()]);
+            }
+            Expr::Index(index_expr) => {
+                self.compile_expression(&index_expr.left)?;
+                self.compile_expression(&index_expr.index)?;
+                self.emit(Opcode::Index, &[]);
+            }
+            Expr::Member(member_expr) => {
+                self.compile_expression(&member_expr.object)?;
+                let name = self.name_constant(&member_expr.property.value);
+                self.emit(Opcode::Member, &[name]);
+            }
+            Expr::Assign(assign_expr) => {
+                let Expr::Member(member_expr) = assign_expr.target.as_ref() else {
+                    return Err(format!("cannot assign to {}", assign_expr.target.to_string()));
+                };
+                self.compile_expression(&member_expr.object)?;
+                self.compile_expression(&assign_expr.value)?;
+                let name = self.name_constant(&member_expr.property.value);
+                self.emit(Opcode::SetMember, &[name]);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_identifier(&mut self, name: &str) {
+        let mut bindings = self.symbols.lookup_all(name);
+        if let Some((depth, slot)) = bindings.pop() {
+            // the inner bindings are tried first, the first one that is set is used
+            let shadowing: Vec<(usize, usize, usize)> = bindings
+                .into_iter()
+                .map(|(depth, slot)| (self.emit(Opcode::GetLocalOr, &[depth, slot, NO_TARGET]), depth, slot))
+                .collect();
+
+            let name = self.name_constant(name);
+            self.emit(Opcode::GetLocal, &[depth, slot, name]);
+
+            let after = self.position();
+            for (position, depth, slot) in shadowing {
+                self.patch(position, &[depth, slot, after]);
+            }
+        } else if is_builtin(name) {
+            let name = self.name_constant(name);
+            self.emit(Opcode::GetBuiltin, &[name]);
+        } else {
+            self.emit_error(ErrorKind::NameError, format!("identifier not found: {}", name));
+        }
+    }
+
+    // a tail call's result is returned as it is, so the VM can drop the caller's frame
+    fn compile_call(&mut self, call_expr: &CallExpression, tail: bool) -> Result<(), String> {
+        if is_quote_call(call_expr) {
+            return self.compile_quote(&call_expr.arguments[0]);
+        }
+
+        // a method is looked up on the receiver, which is then passed as `self`
+        if let Expr::Member(member_expr) = call_expr.function.as_ref() {
+            self.compile_expression(&member_expr.object)?;
+            for arg in &call_expr.arguments {
+                self.compile_expression(arg)?;
+            }
+            let name = self.name_constant(&member_expr.property.value);
+            let op = if tail { Opcode::TailCallMethod } else { Opcode::CallMethod };
+            self.emit(op, &[name, call_expr.arguments.len()]);
+            return Ok(());
+        }
+
+        // named like the evaluator names the calls it counts
+        let name = match call_expr.function.as_ref() {
+            Expr::Identifier(ident) => ident.value.as_str(),
+            _ => "fn",
+        };
+        let name = self.name_constant(name);
+
+        self.compile_expression(&call_expr.function)?;
+        for arg in &call_expr.arguments {
+            self.compile_expression(arg)?;
+        }
+        let op = if tail { Opcode::TailCall } else { Opcode::Call };
+        self.emit(op, &[name, call_expr.arguments.len()]);
+        Ok(())
+    }
+
+    // the unquoted expressions are compiled in the order quote substitutes them in
+    fn compile_quote(&mut self, node: &Rc<Expr>) -> Result<(), String> {
+        let mut unquoted = Vec::new();
+        modify_expression(node.clone(), &mut |node: Rc<Expr>| {
+            if let Some(call_expr) = unquote_call(&node) {
+                if call_expr.arguments.len() == 1 {
+                    unquoted.push(call_expr.arguments[0].clone());
+                }
+            }
+            node
+        });
+
+        for expr in &unquoted {
+            self.compile_expression(expr)?;
+        }
+
+        let node = self.add_constant(Value::Quote(node.clone()));
+        self.emit(Opcode::Quote, &[node, unquoted.len()]);
+        Ok(())
+    }
+
+    fn compile_function(&mut self, fn_literal: &FunctionLiteral) -> Result<usize, String> {
+        let parameters: Vec<String> = fn_literal.parameters.iter().map(|param| param.value.clone()).collect();
+        for (idx, param) in parameters.iter().enumerate() {
+            if parameters[..idx].contains(param) {
+                return Err(format!("duplicate parameter name: {}", param));
+            }
+        }
+
+        self.units.push(CompilationUnit::default());
+        self.symbols.enter(Scope::new(parameters.clone(), &fn_literal.body.statements, true));
+
+        let position = Position {
+            yields: fn_literal.generator,
+            tail: true,
+        };
+        self.compile_block(&fn_literal.body, position)?;
+        self.emit(Opcode::ReturnValue, &[]);
+
+        let num_locals = self.symbols.leave();
+        let unit = self.units.pop().unwrap();
+
+        let params: Vec<String> = fn_literal.parameters.iter().map(|param| param.to_string()).collect();
+        let function = CompiledFunction {
+            instructions: unit.instructions,
+            constants: unit.constants,
+            functions: unit.functions,
+            patterns: unit.patterns,
+            parameters,
+            num_locals,
+            generator: fn_literal.generator,
+            literal: format!(
+                "{}({}) {{\n {} \n}}",
+                if fn_literal.generator { "fn*" } else { "fn" },
+                params.join(", "),
+                fn_literal.body.to_string()
+            ),
+            lines: unit.lines,
+        };
+
+        self.unit().functions.push(Rc::new(function));
+        Ok(self.unit().functions.len() - 1)
+    }
+
+    fn compile_if(&mut self, if_expr: &IfExpression, position: Position) -> Result<(), String> {
+        let Some(condition) = &if_expr.condition else {
+            return Err("if expression without a condition".to_string());
+        };
+        self.compile_expression(condition)?;
+
+        let jump_not_truthy = self.emit(Opcode::JumpNotTruthy, &[NO_TARGET]);
+        match &if_expr.consequence {
+            Some(consequence) => self.compile_block(consequence, position)?,
+            None => {
+                self.emit(Opcode::Null, &[]);
+            }
+        }
+        let jump = self.emit(Opcode::Jump, &[NO_TARGET]);
+
+        let alternative_start = self.position();
+        self.patch(jump_not_truthy, &[alternative_start]);
+        match &if_expr.alternative {
+            Some(alternative) => self.compile_block(alternative, position)?,
+            None => {
+                self.emit(Opcode::Null, &[]);
+            }
+        }
+
+        let end = self.position();
+        self.patch(jump, &[end]);
+        Ok(())
+    }
+
+    // the subject stays on the stack while the arms are tried. Every arm runs in a
+    // scope of its own, which holds the pattern's bindings
+    fn compile_match(&mut self, match_expr: &MatchExpression, position: Position) -> Result<(), String> {
+        self.compile_expression(&match_expr.subject)?;
+
+        let mut end_jumps = Vec::new();
+        for arm in &match_expr.arms {
+            let mut bindings = Vec::new();
+            collect_pattern_bindings(&arm.pattern, &mut bindings);
+            self.symbols.enter(Scope::new(bindings, &arm.body.statements, false));
+
+            let pattern = self.compile_pattern(&arm.pattern)?;
+            self.unit().patterns.push(pattern);
+            let pattern = self.unit().patterns.len() - 1;
+
+            let push_scope = self.emit(Opcode::PushScope, &[0]);
+            let match_op = self.emit(Opcode::Match, &[pattern, NO_TARGET]);
+            let guard_jump = match &arm.guard {
+                Some(guard) => {
+                    self.compile_expression(guard)?;
+                    Some(self.emit(Opcode::JumpNotTruthy, &[NO_TARGET]))
+                }
+                None => None,
+            };
+
+            self.emit(Opcode::Pop, &[]);
+            self.compile_block(&arm.body, position)?;
+            self.emit(Opcode::PopScope, &[]);
+            end_jumps.push(self.emit(Opcode::Jump, &[NO_TARGET]));
+
+            let next_arm = self.position();
+            self.patch(match_op, &[pattern, next_arm]);
+            if let Some(guard_jump) = guard_jump {
+                self.patch(guard_jump, &[next_arm]);
+            }
+            self.emit(Opcode::PopScope, &[]);
+
+            let num_slots = self.symbols.leave();
+            self.patch(push_scope, &[num_slots]);
+        }
+
+        self.emit(Opcode::Pop, &[]);
+        self.emit(Opcode::Null, &[]);
+
+        let end = self.position();
+        for jump in end_jumps {
+            self.patch(jump, &[end]);
+        }
+        Ok(())
+    }
+
+    fn compile_pattern(&mut self, pattern: &Pattern) -> Result<MatchPattern, String> {
+        let pattern = match pattern {
+            Pattern::Wildcard(_) => MatchPattern::Wildcard,
+            Pattern::Binding(ident) => {
+                // the arm's own scope is the innermost one, the pattern is matched in it
+                let shadowed = self
+                    .symbols
+                    .lookup_all(&ident.value)
+                    .into_iter()
+                    .filter(|(depth, _)| *depth > 0)
+                    .collect();
+                MatchPattern::Binding {
+                    slot: self.symbols.declare(&ident.value),
+                    name: ident.value.clone(),
+                    shadowed,
+                }
+            }
+            Pattern::Literal(expr) => MatchPattern::Literal(pattern_literal(expr)?),
+            Pattern::Array { elements, rest, .. } => MatchPattern::Array {
+                elements: elements
+                    .iter()
+                    .map(|element| self.compile_pattern(element))
+                    .collect::<Result<_, _>>()?,
+                rest: match rest {
+                    Some(rest) => Some(Box::new(self.compile_pattern(rest)?)),
+                    None => None,
+                },
+            },
+            Pattern::Hash { pairs, .. } => MatchPattern::Hash(
+                pairs
+                    .iter()
+                    .map(|(key, value)| Ok((pattern_literal(key)?, self.compile_pattern(value)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            Pattern::Variant {
+                enum_name,
+                name,
+                fields,
+                ..
+            } => MatchPattern::Variant {
+                enum_name: enum_name.as_ref().map(|enum_name| enum_name.value.clone()),
+                name: name.value.clone(),
+                fields: fields
+                    .iter()
+                    .map(|field| self.compile_pattern(field))
+                    .collect::<Result<_, _>>()?,
+            },
+            Pattern::Or(alternatives) => MatchPattern::Or(
+                alternatives
+                    .iter()
+                    .map(|alternative| self.compile_pattern(alternative))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+
+        Ok(pattern)
+    }
+
+    // the try block ends in EndTry, the catch block binds the exception in a scope of
+    // its own. OpTry holds where catch, finally and the code after them start
+    fn compile_try(&mut self, try_expr: &TryExpression) -> Result<(), String> {
+        let try_op = self.emit(Opcode::Try, &[NO_TARGET, NO_TARGET, NO_TARGET]);
+        self.compile_block(&try_expr.block, Position::default())?;
+        self.emit(Opcode::EndTry, &[]);
+
+        let catch = match &try_expr.catch_block {
+            Some(catch_block) => {
+                let start = self.position();
+                let param: Vec<String> = try_expr.catch_param.iter().map(|param| param.value.clone()).collect();
+                self.symbols.enter(Scope::new(param.clone(), &catch_block.statements, false));
+
+                let push_scope = self.emit(Opcode::PushScope, &[0]);
+                match param.first() {
+                    Some(name) => self.emit_binding(Opcode::SetLocal, name),
+                    None => {
+                        self.emit(Opcode::Pop, &[]);
+                    }
+                }
+                self.compile_block(catch_block, Position::default())?;
+                self.emit(Opcode::PopScope, &[]);
+                self.emit(Opcode::EndTry, &[]);
+
+                let num_slots = self.symbols.leave();
+                self.patch(push_scope, &[num_slots]);
+                start
+            }
+            None => NO_TARGET,
+        };
+
+        let finally = match &try_expr.finally_block {
+            Some(finally_block) => {
+                let start = self.position();
+                self.compile_block(finally_block, Position::default())?;
+                self.emit(Opcode::EndTry, &[]);
+                start
+            }
+            None => NO_TARGET,
+        };
+
+        let end = self.position();
+        self.patch(try_op, &[catch, finally, end]);
+        Ok(())
+    }
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// literals in patterns are compared as values, so they are evaluated while compiling
+fn pattern_literal(expr: &Expr) -> Result<Value, String> {
+    match expr {
+        Expr::Integer(int_literal) => Ok(Value::Integer(int_literal.value)),
+        Expr::String(str_literal) => Ok(Value::String(str_literal.value.as_str().into())),
+        Expr::Boolean(bool_node) => Ok(Value::Boolean(bool_node.value)),
+        Expr::Prefix(prefix_expr) if prefix_expr.operator == "-" => match pattern_literal(&prefix_expr.right)? {
+            Value::Integer(value) => Ok(Value::Integer(-value)),
+            _ => Err(format!("unsupported literal in pattern: {}", expr.to_string())),
+        },
+        _ => Err(format!("unsupported literal in pattern: {}", expr.to_string())),
+    }
+}
@@ -0,0 +1,87 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::Stmt,
+    resolver::{collect_declarations, Scope},
+};
+
+// the compiler uses the resolver's scopes, so a name resolves to the same depth and
+// slot in both. Each scope becomes one runtime Scope in the VM
+pub(crate) struct SymbolTable {
+    scopes: Vec<Scope>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable {
+            scopes: vec![Scope::new(Vec::new(), &[], true)],
+        }
+    }
+
+    // the global scope outlives a single program, so each program adds its names to it
+    pub fn collect_globals(&mut self, statements: &[Rc<Stmt>]) {
+        self.scopes.truncate(1);
+        collect_declarations(statements, &mut self.scopes[0].slots);
+    }
+
+    pub fn enter(&mut self, scope: Scope) {
+        self.scopes.push(scope);
+    }
+
+    // returns how many slots the scope needs
+    pub fn leave(&mut self) -> usize {
+        self.scopes.pop().map_or(0, |scope| scope.slots.len())
+    }
+
+    pub fn num_slots(&self) -> usize {
+        self.scopes.last().map_or(0, |scope| scope.slots.len())
+    }
+
+    // names bound by a let nested in an expression aren't collected up front, they get
+    // a slot when they are first declared
+    pub fn declare(&mut self, name: &str) -> usize {
+        let scope = self.scopes.last_mut().unwrap();
+        let next = scope.slots.len();
+        let slot = *scope.slots.entry(name.to_string()).or_insert(next);
+        scope.declared.insert(name.to_string());
+        slot
+    }
+
+    // every binding the name can refer to, innermost first. A let in a branch that
+    // didn't run leaves its slot unset, and the name then means the next binding out,
+    // as it does in Environment
+    pub fn lookup_all(&self, name: &str) -> Vec<(usize, usize)> {
+        let mut bindings = Vec::new();
+        let mut crossed_function = false;
+
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if crossed_function || scope.declared.contains(name) {
+                if let Some(slot) = scope.slots.get(name) {
+                    bindings.push((depth, *slot));
+                }
+            }
+            crossed_function |= scope.function;
+        }
+
+        bindings
+    }
+
+    pub fn global_slot(&self, name: &str) -> Option<usize> {
+        let globals = &self.scopes[0];
+        globals.declared.contains(name).then(|| globals.slots[name])
+    }
+
+    // the declared globals in slot order, as the resolver expects them
+    pub fn global_names(&self) -> Vec<String> {
+        let globals = &self.scopes[0];
+        let mut names: Vec<&String> = globals.declared.iter().collect();
+        names.sort_by_key(|name| globals.slots[*name]);
+        names.into_iter().cloned().collect()
+    }
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
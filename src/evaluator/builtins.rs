@@ -1,171 +1,227 @@
 use super::*;
 
-pub const BUILTINS: LazyLock<HashMap<String, Rc<Builtin>>> = LazyLock::new(|| {
+pub static BUILTINS: LazyLock<HashMap<String, Builtin>> = LazyLock::new(|| {
     HashMap::from([
         (
             "len".to_string(),
-            Rc::new(object::Builtin {
+            object::Builtin {
                 function: len_builtin_fn,
-            }),
+            },
         ),
         (
             "first".to_string(),
-            Rc::new(object::Builtin {
+            object::Builtin {
                 function: first_builtin_fn,
-            }),
+            },
         ),
         (
             "last".to_string(),
-            Rc::new(object::Builtin {
+            object::Builtin {
                 function: last_builtin_fn,
-            }),
+            },
         ),
         (
             "rest".to_string(),
-            Rc::new(object::Builtin {
+            object::Builtin {
                 function: rest_builtin_fn,
-            }),
+            },
         ),
         (
             "push".to_string(),
-            Rc::new(object::Builtin {
+            object::Builtin {
                 function: push_builtin_fn,
-            }),
+            },
+        ),
+        (
+            "next".to_string(),
+            object::Builtin {
+                function: next_builtin_fn,
+            },
         ),
         (
             "puts".to_string(),
-            Rc::new(object::Builtin {
+            object::Builtin {
                 function: puts_builtin_fn,
-            }),
+            },
         ),
     ])
 });
 
-fn len_builtin_fn(objects: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
+fn len_builtin_fn(objects: Vec<Value>) -> Value {
     if objects.len() != 1 {
-        return Rc::new(new_error(format!(
+        return new_error(ErrorKind::ArgumentError, format!(
             "wrong number of arguments. got={}, want=1",
             objects.len()
-        )));
+        ));
     }
 
-    match objects[0].get_type() {
-        ObjectType::STRING => Rc::new(Integer {
-            value: objects[0].try_into_str().unwrap().value.len() as i64,
-        }),
-        ObjectType::ARRAY => Rc::new(Integer {
-            value: objects[0].try_into_array().unwrap().elements.len() as i64,
-        }),
+    match &objects[0] {
+        Value::String(value) => Value::Integer(value.len() as i64),
+        Value::Array(array) => Value::Integer(array.elements.len() as i64),
         _ => {
-            return Rc::new(new_error(format!(
+            return new_error(ErrorKind::TypeError, format!(
                 "argument to `len` not supported, got {}",
                 objects[0].get_type()
-            )))
+            ))
         }
     }
 }
 
-fn first_builtin_fn(objects: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
+fn first_builtin_fn(objects: Vec<Value>) -> Value {
     if objects.len() != 1 {
-        return Rc::new(new_error(format!(
+        return new_error(ErrorKind::ArgumentError, format!(
             "wrong number of arguments. got={}, want=1",
             objects.len()
-        )));
-    }
-
-    if objects[0].get_type() != ObjectType::ARRAY {
-        return Rc::new(new_error(format!(
-            "argument to `first` must be ARRAY, got={}",
-            objects[0].get_type()
-        )));
+        ));
     }
 
-    let arr = objects[0].try_into_array().unwrap();
+    let arr = match &objects[0] {
+        Value::Array(arr) => arr,
+        _ => {
+            return new_error(ErrorKind::TypeError, format!(
+                "argument to `first` must be ARRAY, got={}",
+                objects[0].get_type()
+            ))
+        }
+    };
     if arr.elements.len() > 0 {
         return arr.elements[0].clone();
     }
 
-    return Rc::new(NULL);
+    return Value::Null;
 }
 
-fn last_builtin_fn(objects: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
+fn last_builtin_fn(objects: Vec<Value>) -> Value {
     if objects.len() != 1 {
-        return Rc::new(new_error(format!(
+        return new_error(ErrorKind::ArgumentError, format!(
             "wrong number of arguments. got={}, want=1",
             objects.len()
-        )));
-    }
-
-    if objects[0].get_type() != ObjectType::ARRAY {
-        return Rc::new(new_error(format!(
-            "argument to `first` must be ARRAY, got={}",
-            objects[0].get_type()
-        )));
+        ));
     }
 
-    let arr = objects[0].try_into_array().unwrap();
+    let arr = match &objects[0] {
+        Value::Array(arr) => arr,
+        _ => {
+            return new_error(ErrorKind::TypeError, format!(
+                "argument to `first` must be ARRAY, got={}",
+                objects[0].get_type()
+            ))
+        }
+    };
     if arr.elements.len() > 0 {
         return arr.elements.last().unwrap().clone();
     }
 
-    return Rc::new(NULL);
+    return Value::Null;
 }
 
-fn rest_builtin_fn(objects: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
+fn rest_builtin_fn(objects: Vec<Value>) -> Value {
     if objects.len() != 1 {
-        return Rc::new(new_error(format!(
+        return new_error(ErrorKind::ArgumentError, format!(
             "wrong number of arguments. got={}, want=1",
             objects.len()
-        )));
-    }
-
-    if objects[0].get_type() != ObjectType::ARRAY {
-        return Rc::new(new_error(format!(
-            "argument to `first` must be ARRAY, got={}",
-            objects[0].get_type()
-        )));
+        ));
     }
 
-    let arr = objects[0].try_into_array().unwrap();
+    let arr = match &objects[0] {
+        Value::Array(arr) => arr,
+        _ => {
+            return new_error(ErrorKind::TypeError, format!(
+                "argument to `first` must be ARRAY, got={}",
+                objects[0].get_type()
+            ))
+        }
+    };
     let length = arr.elements.len();
     if length > 0 {
         let new_elements = arr.elements[1..length].to_vec();
-        return Rc::new(object::Array {
+        return Value::Array(Rc::new(object::Array {
             elements: new_elements,
-        });
+        }));
     }
 
-    return Rc::new(NULL);
+    return Value::Null;
 }
 
-fn push_builtin_fn(objects: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
+fn push_builtin_fn(objects: Vec<Value>) -> Value {
     if objects.len() != 2 {
-        return Rc::new(new_error(format!(
+        return new_error(ErrorKind::ArgumentError, format!(
             "wrong number of arguments. got={}, want=2",
             objects.len()
-        )));
+        ));
     }
 
-    if objects[0].get_type() != ObjectType::ARRAY {
-        return Rc::new(new_error(format!(
-            "argument to `first` must be ARRAY, got={}",
-            objects[0].get_type()
-        )));
-    }
-
-    let arr = objects[0].try_into_array().unwrap();
+    let arr = match &objects[0] {
+        Value::Array(arr) => arr,
+        _ => {
+            return new_error(ErrorKind::TypeError, format!(
+                "argument to `first` must be ARRAY, got={}",
+                objects[0].get_type()
+            ))
+        }
+    };
     let mut new_elements = arr.elements.to_vec();
 
     new_elements.push(objects[1].clone());
 
-    Rc::new(object::Array {
+    Value::Array(Rc::new(object::Array {
         elements: new_elements,
+    }))
+}
+
+fn next_builtin_fn(objects: Vec<Value>) -> Value {
+    if objects.len() != 1 {
+        return new_error(ErrorKind::ArgumentError, format!(
+            "wrong number of arguments. got={}, want=1",
+            objects.len()
+        ));
+    }
+
+    match &objects[0] {
+        Value::Generator(generator) => resume_generator(generator),
+        _ => new_error(ErrorKind::TypeError, format!(
+            "argument to `next` must be GENERATOR, got={}",
+            objects[0].get_type()
+        )),
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    // what puts printed while `capture_output` runs
+    static CAPTURED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// runs `run` with puts writing to a string instead of stdout, and returns that string too
+#[cfg(test)]
+pub(crate) fn capture_output<T>(run: impl FnOnce() -> T) -> (T, String) {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(String::new()));
+    let result = run();
+    let output = CAPTURED.with(|captured| captured.borrow_mut().take());
+    (result, output.unwrap_or_default())
+}
+
+// whether `capture_output` took the line
+#[cfg(test)]
+fn capture(line: &str) -> bool {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(output) => {
+            output.push_str(line);
+            output.push('\n');
+            true
+        }
+        None => false,
     })
 }
 
-fn puts_builtin_fn(objects: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
+fn puts_builtin_fn(objects: Vec<Value>) -> Value {
     for obj in objects {
-        println!("{}", obj.inspect())
+        let line = obj.inspect();
+        #[cfg(test)]
+        if capture(&line) {
+            continue;
+        }
+        println!("{}", line)
     }
-    Rc::new(object::NULL {})
+    Value::Null
 }
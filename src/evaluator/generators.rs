@@ -0,0 +1,239 @@
+use std::mem;
+
+use crate::{
+    object::{Generator, GeneratorFrame, GeneratorState},
+    vm::Resumed,
+};
+
+use super::*;
+
+enum Step {
+    Yield(Value),
+    Done(Value),
+}
+
+pub fn new_generator(
+    function: &object::Function,
+    env: Rc<RefCell<object::Environment>>,
+) -> Value {
+    Value::Generator(Rc::new(Generator {
+        state: RefCell::new(GeneratorState {
+            frames: vec![GeneratorFrame::Block {
+                statements: function.body.statements.clone(),
+                index: 0,
+                env,
+            }],
+            running: false,
+            done: false,
+        }),
+    }))
+}
+
+// Runs the generator body until the next yield. The body is executed as a stack of
+// frames rather than through eval, so that it can be suspended and picked up again.
+pub fn resume_generator(generator: &Generator) -> Value {
+    let mut frames = {
+        let mut state = generator.state.borrow_mut();
+        if state.running {
+            return new_error(
+                ErrorKind::Error,
+                "generator is already running".to_string(),
+            );
+        }
+        if state.done {
+            return Value::Null;
+        }
+        state.running = true;
+        mem::take(&mut state.frames)
+    };
+
+    let step = run_frames(&mut frames);
+
+    let mut state = generator.state.borrow_mut();
+    state.running = false;
+    match step {
+        Step::Yield(value) => {
+            state.frames = frames;
+            value
+        }
+        Step::Done(value) => {
+            state.done = true;
+            value
+        }
+    }
+}
+
+fn run_frames(frames: &mut Vec<GeneratorFrame>) -> Step {
+    loop {
+        let Some(frame) = frames.last_mut() else {
+            return Step::Done(Value::Null);
+        };
+
+        match frame {
+            GeneratorFrame::Elements { elements, index } => {
+                if *index < elements.len() {
+                    *index += 1;
+                    return Step::Yield(elements[*index - 1].clone());
+                }
+                frames.pop();
+            }
+            GeneratorFrame::Delegate(inner) => {
+                let inner_generator = inner.clone();
+                let value = resume_generator(&inner_generator);
+
+                if value.get_type() == ObjectType::ERROR {
+                    return Step::Done(value);
+                }
+                if !inner_generator.state.borrow().done {
+                    return Step::Yield(value);
+                }
+                frames.pop();
+            }
+            GeneratorFrame::Compiled(vm) => match vm.resume() {
+                Resumed::Yield(value) => return Step::Yield(value),
+                Resumed::Delegate(value) => {
+                    if let Some(step) = delegate_to(frames, value) {
+                        return step;
+                    }
+                }
+                Resumed::Done(value) => return Step::Done(value),
+            },
+            GeneratorFrame::Block {
+                statements,
+                index,
+                env,
+            } => {
+                if *index >= statements.len() {
+                    frames.pop();
+                    continue;
+                }
+
+                let stmt = statements[*index].clone();
+                let env = env.clone();
+                *index += 1;
+
+                if let Some(step) = exec_statement(frames, stmt.as_ref(), env) {
+                    return step;
+                }
+            }
+        }
+    }
+}
+
+fn exec_statement(
+    frames: &mut Vec<GeneratorFrame>,
+    stmt: &Stmt,
+    env: Rc<RefCell<object::Environment>>,
+) -> Option<Step> {
+    if let Stmt::Yield(yield_stmt) = stmt {
+        let value = match &yield_stmt.value {
+            Some(value) => eval_expression(value, env).unwrap_or_else(|| Value::Null),
+            None => Value::Null,
+        };
+
+        if value.get_type() == ObjectType::ERROR {
+            return Some(Step::Done(value));
+        }
+        if !yield_stmt.delegate {
+            return Some(Step::Yield(value));
+        }
+
+        return delegate_to(frames, value);
+    }
+
+    if let Stmt::Return(return_stmt) = stmt {
+        let value = match &return_stmt.return_value {
+            Some(value) => eval_expression(value, env).unwrap_or_else(|| Value::Null),
+            None => Value::Null,
+        };
+
+        if value.get_type() == ObjectType::ERROR {
+            return Some(Step::Done(value));
+        }
+        return Some(Step::Done(Value::Null));
+    }
+
+    // branches are entered as frames of their own, so they may contain yields too
+    if let Stmt::Expression(ast::ExpressionStetement {
+        expression: Some(expression),
+        ..
+    }) = stmt
+    {
+        if let Expr::If(if_expr) = expression.as_ref() {
+            let condition = eval_expression(if_expr.condition.as_ref().unwrap(), env.clone());
+            if is_error(&condition) {
+                return Some(Step::Done(condition.unwrap()));
+            }
+
+            let block = if is_truthy(condition.unwrap()) {
+                if_expr.consequence.as_ref()
+            } else {
+                if_expr.alternative.as_ref()
+            };
+
+            if let Some(block) = block {
+                frames.push(GeneratorFrame::Block {
+                    statements: block.statements.clone(),
+                    index: 0,
+                    env,
+                });
+            }
+            return None;
+        }
+
+        if let Expr::Match(match_expr) = expression.as_ref() {
+            match select_match_arm(match_expr, env) {
+                Err(err) => return Some(Step::Done(err)),
+                Ok(Some(MatchedArm { arm, env })) => frames.push(GeneratorFrame::Block {
+                    statements: arm.body.statements.clone(),
+                    index: 0,
+                    env,
+                }),
+                Ok(None) => {}
+            }
+            return None;
+        }
+    }
+
+    let result = eval_statement(stmt, env)?;
+    match result.get_type() {
+        ObjectType::ERROR => Some(Step::Done(result)),
+        ObjectType::RETURN => Some(Step::Done(Value::Null)),
+        _ => None,
+    }
+}
+
+fn delegate_to(frames: &mut Vec<GeneratorFrame>, value: Value) -> Option<Step> {
+    match value {
+        Value::Generator(inner) => {
+            let in_tail_position = frames.iter().all(|frame| frame.is_exhausted());
+
+            // a generator nobody else can see is taken over instead of nested, so that
+            // recursive delegation like `yield* pages(n + 1)` runs in constant depth
+            if in_tail_position && Rc::strong_count(&inner) == 1 {
+                let mut inner_state = inner.state.borrow_mut();
+                if !inner_state.running {
+                    frames.clear();
+                    if !inner_state.done {
+                        frames.append(&mut inner_state.frames);
+                    }
+                    return None;
+                }
+            }
+
+            frames.push(GeneratorFrame::Delegate(inner));
+            None
+        }
+        Value::Array(array) => {
+            frames.push(GeneratorFrame::Elements {
+                elements: array.elements.clone(),
+                index: 0,
+            });
+            None
+        }
+        _ => Some(Step::Done(new_error(
+            ErrorKind::TypeError,
+            format!("cannot delegate to {}", value.get_type()),
+        ))),
+    }
+}
@@ -0,0 +1,109 @@
+use crate::ast::{modify::modify_program, Program};
+
+use super::*;
+
+pub fn define_macros(program: &mut Program, env: Rc<RefCell<object::Environment>>) {
+    program.statements.retain(|stmt| {
+        let Some((let_stmt, macro_literal)) = macro_definition(stmt) else {
+            return true;
+        };
+
+        let _ = env.borrow_mut().set(
+            let_stmt.name.value.clone(),
+            Value::Macro(Rc::new(object::Macro {
+                parameters: macro_literal.parameters.clone(),
+                body: macro_literal.body.clone(),
+                env: env.clone(),
+            })),
+        );
+        false
+    });
+}
+
+fn macro_definition(stmt: &Stmt) -> Option<(&ast::LetStatement, &ast::MacroLiteral)> {
+    let Stmt::Let(let_stmt) = stmt else {
+        return None;
+    };
+
+    match let_stmt.value.as_deref()? {
+        Expr::Macro(macro_literal) => Some((let_stmt, macro_literal)),
+        _ => None,
+    }
+}
+
+pub fn expand_macros(
+    program: &Program,
+    env: Rc<RefCell<object::Environment>>,
+) -> Result<Program, Vec<String>> {
+    let mut errors = Vec::new();
+
+    let expanded = modify_program(program, &mut |node: Rc<Expr>| {
+        let Some((call_expr, macro_fn)) = macro_call(&node, &env) else {
+            return node;
+        };
+
+        if call_expr.arguments.len() != macro_fn.parameters.len() {
+            errors.push(format!(
+                "wrong number of macro arguments. got={}, want={}",
+                call_expr.arguments.len(),
+                macro_fn.parameters.len()
+            ));
+            return node;
+        }
+
+        let eval_env = extend_macro_env(&macro_fn, &call_expr.arguments);
+        let evaluated = eval_block_statement(&macro_fn.body, eval_env);
+
+        match evaluated {
+            Some(Value::Error(error)) => {
+                errors.push(error.message.clone());
+                node
+            }
+            Some(Value::Quote(quoted)) => quoted,
+            _ => {
+                errors.push("we only support returning AST-nodes from macros".to_string());
+                node
+            }
+        }
+    });
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(expanded)
+}
+
+fn macro_call<'a>(
+    node: &'a Expr,
+    env: &Rc<RefCell<object::Environment>>,
+) -> Option<(&'a ast::CallExpression, Rc<object::Macro>)> {
+    let Expr::Call(call_expr) = node else {
+        return None;
+    };
+
+    let Expr::Identifier(ident) = call_expr.function.as_ref() else {
+        return None;
+    };
+
+    match env.borrow().get(&ident.value).ok()? {
+        Value::Macro(macro_fn) => Some((call_expr, macro_fn)),
+        _ => None,
+    }
+}
+
+fn extend_macro_env(
+    macro_fn: &object::Macro,
+    args: &[Rc<Expr>],
+) -> Rc<RefCell<object::Environment>> {
+    let env = object::Environment::new_enclosed_env(macro_fn.env.clone());
+
+    for (param, arg) in macro_fn.parameters.iter().zip(args) {
+        let _ = env.borrow_mut().set(
+            param.value.clone(),
+            Value::Quote(arg.clone()),
+        );
+    }
+
+    env
+}
@@ -1,143 +1,256 @@
 mod builtins;
+mod generators;
+mod macro_expansion;
+mod modules;
+mod quote_unquote;
+mod tail_calls;
 
 use builtins::BUILTINS;
+#[cfg(test)]
+pub(crate) use builtins::capture_output;
+use generators::{new_generator, resume_generator};
+pub use macro_expansion::{define_macros, expand_macros};
+pub(crate) use modules::{load_module, module_exports};
+use modules::eval_import_statement;
+pub(crate) use quote_unquote::{convert_object_to_ast_node, unquote_call};
+use quote_unquote::quote;
+use tail_calls::{eval_tail_block, PendingCall, TailResult};
 
 use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::LazyLock, vec};
 
 use crate::{
-    ast::{self, Identifier, Node},
-    object::{self, Builtin, Integer, Object, ObjectType, StringObj},
+    ast::{self, Expr, Identifier, Slot, Stmt},
+    object::{self, Builtin, ErrorKind, ObjectType, Value},
 };
 
-const NULL: object::NULL = object::NULL {};
-const TRUE: object::Boolean = object::Boolean { value: true };
-const FALSE: object::Boolean = object::Boolean { value: false };
+pub fn eval(program: &ast::Program, env: Rc<RefCell<object::Environment>>) -> Option<Value> {
+    // top-level and module environments are created outside the evaluator
+    object::gc::track(&env);
 
-pub fn eval(node: Rc<&dyn Node>, env: Rc<RefCell<object::Environment>>) -> Option<Rc<dyn Object>> {
-    match node.get_type() {
-        ast::NodeType::BlockStatement => {
-            return eval_block_statement(node.try_into_block_stmt().unwrap(), env);
+    let limits = env.borrow().limits();
+    limits.start_run();
+    let result = eval_program(program, env);
+    limits.end_run();
+    result
+}
+
+fn eval_program(program: &ast::Program, env: Rc<RefCell<object::Environment>>) -> Option<Value> {
+    let mut result = None;
+
+    for stmt in &program.statements {
+        result = eval_statement(stmt, env.clone());
+
+        if result.is_none() {
+            continue;
         }
 
-        ast::NodeType::IfExpression => {
-            return Some(eval_if_expression(node.try_into_if_expr().unwrap(), env));
+        match result {
+            Some(Value::Return(value)) => return Some(*value),
+            Some(Value::Error(_)) => return result,
+            _ => continue,
         }
+    }
 
-        ast::NodeType::PrefixExpression => {
-            let prefix_expr = node.try_into_prefix_expr().unwrap();
-            let right = eval(prefix_expr.right.as_node(), env);
-            if is_error(&right) {
-                return right;
+    result
+}
+
+fn eval_statement(stmt: &Stmt, env: Rc<RefCell<object::Environment>>) -> Option<Value> {
+    if let Err(msg) = env.borrow().step() {
+        return Some(new_error(ErrorKind::Error, msg));
+    }
+
+    match stmt {
+        Stmt::Block(block_stmt) => {
+            return eval_block_statement(block_stmt, env);
+        }
+
+        Stmt::Expression(expr_stmt) => {
+            return eval_expression(expr_stmt.expression.as_ref().unwrap(), env);
+        }
+
+        Stmt::Return(return_stmt) => {
+            let value = eval_expression(return_stmt.return_value.as_ref()?, env);
+            if is_error(&value) {
+                return value;
             }
-            return Some(eval_prefix_expression(&prefix_expr.operator, right?));
+
+            return Some(Value::Return(Box::new(value?)));
         }
 
-        ast::NodeType::InfixExpression => {
-            let infix_expr = node.try_into_infix_expr().unwrap();
-            let left = eval(infix_expr.left.as_ref().unwrap().as_node(), env.clone());
-            if is_error(&left) {
-                return left;
+        Stmt::Let(let_stmt) => {
+            let value = eval_expression(let_stmt.value.as_ref().unwrap(), env.clone());
+            if is_error(&value) {
+                return value;
             }
-            let right = eval(infix_expr.right.as_ref().unwrap().as_node(), env);
-            if is_error(&right) {
-                return right;
+            let result = env
+                .borrow_mut()
+                .set(let_stmt.name.value.clone(), value.unwrap());
+            if let Err(msg) = result {
+                return Some(new_error(ErrorKind::ConstantError, msg));
             }
-            return Some(eval_infix_expression(&infix_expr.operator, left?, right?));
+            return None;
         }
 
-        ast::NodeType::ExpressionStetement => {
-            let expr_stmt = node.try_into_expr_stmt().unwrap();
-            return eval(expr_stmt.expression.as_ref().unwrap().as_node(), env);
+        Stmt::Const(const_stmt) => {
+            let value = eval_expression(const_stmt.value.as_ref()?, env.clone());
+            if is_error(&value) {
+                return value;
+            }
+            let result = env
+                .borrow_mut()
+                .set_const(const_stmt.name.value.clone(), value?);
+            if let Err(msg) = result {
+                return Some(new_error(ErrorKind::ConstantError, msg));
+            }
+            return None;
         }
 
-        ast::NodeType::IntegerLiteral => {
-            let value = node.try_into_int_literal().unwrap().value;
-            return Some(Rc::new(Integer { value }));
+        Stmt::Struct(struct_stmt) => {
+            let struct_type = Value::StructType(Rc::new(object::StructType {
+                name: struct_stmt.name.value.clone(),
+                fields: struct_stmt
+                    .fields
+                    .iter()
+                    .map(|field| field.value.clone())
+                    .collect(),
+                methods: RefCell::new(HashMap::new()),
+            }));
+            let result = env
+                .borrow_mut()
+                .set(struct_stmt.name.value.clone(), struct_type);
+            if let Err(msg) = result {
+                return Some(new_error(ErrorKind::ConstantError, msg));
+            }
+            return None;
         }
 
-        ast::NodeType::StringLiteral => {
-            let value = node.try_into_str_literal().unwrap().value.clone();
-            return Some(Rc::new(object::StringObj { value }));
+        Stmt::Impl(impl_stmt) => {
+            return eval_impl_statement(impl_stmt, env);
         }
 
-        ast::NodeType::Program => {
-            return eval_program(node.try_into_program().unwrap(), env);
+        Stmt::Enum(enum_stmt) => {
+            return eval_enum_statement(enum_stmt, env);
         }
 
-        ast::NodeType::Boolean => {
-            let bool_node = node.try_into_boolean().unwrap();
-            return Some(Rc::new(native_bool_to_boolean_object(bool_node.value)));
+        Stmt::Import(import_stmt) => {
+            return eval_import_statement(import_stmt, env);
         }
 
-        ast::NodeType::ReturnStatement => {
-            let return_val = &node.try_into_return_stmt().unwrap().return_value;
-            let value = eval(return_val.as_ref()?.as_node(), env);
+        Stmt::Export(export_stmt) => {
+            return eval_statement(&export_stmt.statement, env);
+        }
+
+        Stmt::Yield(_) => {
+            return Some(new_error(
+                ErrorKind::Error,
+                "yield is only supported as a statement of a generator body".to_string(),
+            ));
+        }
+
+        Stmt::Throw(throw_stmt) => {
+            let value = eval_expression(throw_stmt.value.as_ref()?, env);
             if is_error(&value) {
                 return value;
             }
+            return Some(throw_value(value.unwrap_or_else(|| Value::Null)));
+        }
+    };
+}
 
-            return Some(Rc::new(object::ReturnValue { value: value? }));
+fn eval_expression(expr: &Expr, env: Rc<RefCell<object::Environment>>) -> Option<Value> {
+    if let Err(msg) = env.borrow().step() {
+        return Some(new_error(ErrorKind::Error, msg));
+    }
+
+    match expr {
+        Expr::If(if_expr) => {
+            return Some(eval_if_expression(if_expr, env));
         }
-        ast::NodeType::LetStatement => {
-            let let_stmt = &node.try_into_let_statement().unwrap();
-            let value = eval(let_stmt.value.as_ref().unwrap().as_node(), env.clone());
-            if is_error(&value) {
-                return value;
+
+        Expr::Prefix(prefix_expr) => {
+            let right = eval_expression(&prefix_expr.right, env);
+            if is_error(&right) {
+                return right;
             }
-            env.borrow_mut()
-                .set(let_stmt.name.value.clone(), value.unwrap().into());
-            return None;
+            return Some(eval_prefix_expression(&prefix_expr.operator, right?));
+        }
+
+        Expr::Infix(infix_expr) => {
+            let left = eval_expression(infix_expr.left.as_ref().unwrap(), env.clone());
+            if is_error(&left) {
+                return left;
+            }
+            let right = eval_expression(infix_expr.right.as_ref().unwrap(), env.clone());
+            if is_error(&right) {
+                return right;
+            }
+            let result = eval_infix_expression(&infix_expr.operator, left?, right?);
+            return Some(allocate(result, &env));
+        }
+
+        Expr::Integer(int_literal) => {
+            return Some(Value::Integer(int_literal.value));
         }
 
-        ast::NodeType::Identifier => {
-            return Some(eval_identifier(node.try_into_identifier().unwrap(), env).into());
+        Expr::String(str_literal) => {
+            let value = str_literal.value.clone();
+            return Some(allocate(Value::String(value.into()), &env));
         }
 
-        ast::NodeType::FunctionLiteral => {
-            let fn_literal = node.try_into_fn_literal().unwrap();
+        Expr::Boolean(bool_node) => {
+            return Some(Value::Boolean(bool_node.value));
+        }
 
-            return Some(Rc::new(object::Function {
+        Expr::Identifier(ident) => {
+            return Some(eval_identifier(ident, env));
+        }
+
+        Expr::Function(fn_literal) => {
+            return Some(Value::Function(Rc::new(object::Function {
                 parameters: fn_literal.parameters.clone(),
                 body: fn_literal.body.clone(),
-                env,
-            }));
+                env: closure_env(fn_literal, env),
+                generator: fn_literal.generator,
+            })));
         }
 
-        ast::NodeType::CallExpression => {
-            let call_expr = node.try_into_call_expr().unwrap();
-            let function = eval(call_expr.function.clone().as_node(), env.clone());
-
-            if is_error(&function) {
-                return function;
-            }
+        Expr::Macro(macro_literal) => {
+            return Some(Value::Macro(Rc::new(object::Macro {
+                parameters: macro_literal.parameters.clone(),
+                body: macro_literal.body.clone(),
+                env,
+            })));
+        }
 
-            let args = eval_expression(&call_expr.arguments, env);
-            if args.len() == 1 && is_error(&Some(args[0].clone())) {
-                return Some(args[0].clone());
+        Expr::Call(call_expr) => {
+            if is_quote_call(call_expr) {
+                return Some(quote(call_expr.arguments[0].clone(), env));
             }
 
-            return Some(apply_function(function?, args));
+            return match eval_call_target(call_expr, env) {
+                Ok(call) => Some(apply_call(call)),
+                Err(err) => Some(err),
+            };
         }
 
-        ast::NodeType::ArrayLiteral => {
-            let elements = eval_expression(&node.try_into_array_literal().unwrap().elements, env);
+        Expr::Array(array_literal) => {
+            let elements = eval_expressions(&array_literal.elements, env.clone());
 
             if elements.len() == 1 && is_error(&Some(elements[0].clone())) {
                 return Some(elements[0].clone());
             }
 
-            return Some(Rc::new(object::Array { elements }));
+            return Some(allocate(Value::Array(Rc::new(object::Array { elements })), &env));
         }
 
-        ast::NodeType::IndexExpression => {
-            let index_expr = node.try_into_index_expr().unwrap();
-            let left = eval(index_expr.left.as_node(), env.clone());
+        Expr::Index(index_expr) => {
+            let left = eval_expression(&index_expr.left, env.clone());
 
             if is_error(&left) {
                 return left;
             }
 
-            let index = eval(index_expr.index.as_node(), env);
+            let index = eval_expression(&index_expr.index, env);
 
             if is_error(&index) {
                 return index;
@@ -146,303 +259,679 @@ pub fn eval(node: Rc<&dyn Node>, env: Rc<RefCell<object::Environment>>) -> Optio
             return Some(eval_index_expression(left.unwrap(), index.unwrap()));
         }
 
-        ast::NodeType::HashLiteral => {
-            return eval_hash_literal(node.try_into_hash_literal().unwrap(), env)
+        Expr::Hash(hash_literal) => return eval_hash_literal(hash_literal, env),
+
+        Expr::Member(member_expr) => {
+            let object = eval_expression(&member_expr.object, env);
+
+            if is_error(&object) {
+                return object;
+            }
+
+            return Some(eval_member_expression(object?, &member_expr.property.value));
+        }
+
+        Expr::Assign(assign_expr) => {
+            let Expr::Member(member_expr) = assign_expr.target.as_ref() else {
+                unreachable!("the parser only builds assignments to members")
+            };
+            let object = eval_expression(&member_expr.object, env.clone());
+
+            if is_error(&object) {
+                return object;
+            }
+
+            let value = eval_expression(&assign_expr.value, env);
+
+            if is_error(&value) {
+                return value;
+            }
+
+            return Some(eval_field_assignment(
+                object?,
+                &member_expr.property.value,
+                value.unwrap_or_else(|| Value::Null),
+            ));
+        }
+
+        Expr::Try(try_expr) => {
+            return Some(eval_try_expression(try_expr, env));
+        }
+
+        Expr::Match(match_expr) => {
+            return Some(eval_match_expression(match_expr, env));
         }
     };
 }
 
-fn eval_index_expression(left: Rc<dyn Object>, index: Rc<dyn Object>) -> Rc<dyn Object> {
-    if left.get_type() == ObjectType::ARRAY && index.get_type() == ObjectType::INTEGER {
-        return eval_array_index_expression(left, index);
-    } else if left.get_type() == ObjectType::HASH {
-        return eval_hash_index_expression(left, index);
-    } else {
-        return Rc::new(new_error(format!(
+pub(crate) fn eval_index_expression(left: Value, index: Value) -> Value {
+    match (&left, &index) {
+        (Value::Array(array), Value::Integer(idx)) => eval_array_index_expression(array, *idx),
+        (Value::Hash(hash), _) => eval_hash_index_expression(hash, &index),
+        _ => new_error(ErrorKind::TypeError, format!(
             "index operator not suported: {}",
             left.get_type()
-        )));
+        )),
     }
 }
 
-fn eval_array_index_expression(array: Rc<dyn Object>, index: Rc<dyn Object>) -> Rc<dyn Object> {
-    let array_obj = array.try_into_array().unwrap();
-    let idx = index.try_into_int().unwrap().value;
-    let max = (array_obj.elements.len() - 1) as i64;
+pub(crate) fn eval_member_expression(object: Value, property: &str) -> Value {
+    match &object {
+        Value::Module(module) => match module.exports.get(property) {
+            Some(value) => value.clone(),
+            None => new_error(ErrorKind::NameError, format!(
+                "module \"{}\" has no export named {}",
+                module.name, property
+            )),
+        },
+        Value::Struct(instance) => match instance.definition().field_index(property) {
+            Some(idx) => instance.values.borrow()[idx].clone(),
+            None => new_error(ErrorKind::NameError, format!(
+                "struct {} has no field named {}",
+                instance.definition().name, property
+            )),
+        },
+        Value::EnumValue(value) => {
+            let variant = value.definition();
+            match variant.fields.iter().position(|field| field == property) {
+                Some(idx) => value.values[idx].clone(),
+                None => new_error(ErrorKind::NameError, format!(
+                    "variant {} has no field named {}",
+                    variant.name, property
+                )),
+            }
+        }
+        Value::EnumType(enum_type) => {
+            if let Some(idx) = enum_type.variant_index(property) {
+                return variant_object(enum_type.clone(), idx);
+            }
+            match enum_type.methods.borrow().get(property) {
+                Some(method) => method.clone(),
+                None => new_error(ErrorKind::NameError, format!(
+                    "enum {} has no variant named {}",
+                    enum_type.name, property
+                )),
+            }
+        }
+        Value::StructType(struct_type) => match struct_type.methods.borrow().get(property) {
+            Some(method) => method.clone(),
+            None => new_error(ErrorKind::NameError, format!(
+                "struct {} has no method named {}",
+                struct_type.name, property
+            )),
+        },
+        Value::Generator(generator) => match property {
+            "done" => Value::Boolean(generator.state.borrow().done),
+            _ => new_error(
+                ErrorKind::NameError,
+                format!("generator has no property named {}", property),
+            ),
+        },
+        Value::Exception(exception) => match property {
+            "message" => Value::String(exception.message.clone().into()),
+            "kind" => Value::String(exception.kind.to_string().into()),
+            "value" => exception.value.clone(),
+            _ => new_error(
+                ErrorKind::NameError,
+                format!("exception has no property named {}", property),
+            ),
+        },
+        _ => new_error(ErrorKind::TypeError, format!(
+            "property access not supported: {}",
+            object.get_type()
+        )),
+    }
+}
+
+fn eval_impl_statement(
+    impl_stmt: &ast::ImplStatement,
+    env: Rc<RefCell<object::Environment>>,
+) -> Option<Value> {
+    let type_object = eval_identifier(&impl_stmt.type_name, env.clone());
+    if is_error(&Some(type_object.clone())) {
+        return Some(type_object);
+    }
+
+    let methods = match &type_object {
+        Value::StructType(struct_type) => &struct_type.methods,
+        Value::EnumType(enum_type) => &enum_type.methods,
+        _ => {
+            return Some(new_error(ErrorKind::TypeError, format!(
+                "cannot implement methods for {}",
+                type_object.get_type()
+            )))
+        }
+    };
+
+    for method in &impl_stmt.methods {
+        methods.borrow_mut().insert(
+            method.name.value.clone(),
+            Value::Function(Rc::new(object::Function {
+                parameters: method.function.parameters.clone(),
+                body: method.function.body.clone(),
+                env: closure_env(&method.function, env.clone()),
+                generator: method.function.generator,
+            })),
+        );
+    }
+
+    None
+}
+
+fn eval_enum_statement(
+    enum_stmt: &ast::EnumStatement,
+    env: Rc<RefCell<object::Environment>>,
+) -> Option<Value> {
+    let enum_type = Rc::new(object::EnumType {
+        name: enum_stmt.name.value.clone(),
+        variants: enum_stmt
+            .variants
+            .iter()
+            .map(|variant| object::Variant {
+                name: variant.name.value.clone(),
+                fields: variant.fields.iter().map(|field| field.value.clone()).collect(),
+            })
+            .collect(),
+        methods: RefCell::new(HashMap::new()),
+    });
+
+    let mut bindings = vec![(enum_stmt.name.value.clone(), Value::EnumType(enum_type.clone()))];
+    for (idx, variant) in enum_stmt.variants.iter().enumerate() {
+        bindings.push((variant.name.value.clone(), variant_object(enum_type.clone(), idx)));
+    }
+
+    for (name, value) in bindings {
+        if let Err(msg) = env.borrow_mut().set(name, value) {
+            return Some(new_error(ErrorKind::ConstantError, msg));
+        }
+    }
+
+    None
+}
+
+// unit variants are plain values, variants with fields need to be called first
+pub(crate) fn variant_object(enum_type: Rc<object::EnumType>, variant: usize) -> Value {
+    let has_fields = !enum_type.variants[variant].fields.is_empty();
+
+    if has_fields {
+        Value::VariantConstructor(Rc::new(object::VariantConstructor { enum_type, variant }))
+    } else {
+        Value::EnumValue(Rc::new(object::EnumValue {
+            enum_type,
+            variant,
+            values: vec![],
+        }))
+    }
+}
+
+pub(crate) fn eval_field_assignment(
+    object: Value,
+    property: &str,
+    value: Value,
+) -> Value {
+    let instance = match &object {
+        Value::Struct(instance) => instance,
+        _ => {
+            return new_error(ErrorKind::TypeError, format!(
+                "field assignment not supported: {}",
+                object.get_type()
+            ))
+        }
+    };
+
+    match instance.definition().field_index(property) {
+        Some(idx) => {
+            instance.values.borrow_mut()[idx] = value.clone();
+            value
+        }
+        None => new_error(ErrorKind::NameError, format!(
+            "struct {} has no field named {}",
+            instance.definition().name, property
+        )),
+    }
+}
+
+fn eval_array_index_expression(array: &object::Array, idx: i64) -> Value {
+    let max = array.elements.len() as i64 - 1;
 
     if idx < 0 || idx > max {
-        return Rc::new(NULL);
+        return Value::Null;
     }
 
-    return array_obj.elements[idx as usize].clone();
+    return array.elements[idx as usize].clone();
 }
 
-fn eval_hash_index_expression(hash: Rc<dyn Object>, index: Rc<dyn Object>) -> Rc<dyn Object> {
-    let hash_obj = hash.try_into_hash().unwrap();
-    let hash_key = match index.try_hash_key() {
+fn eval_hash_index_expression(hash: &object::Hash, index: &Value) -> Value {
+    let hash_key = match index.hash_key() {
         Ok(val) => val,
         Err(_) => {
-            return Rc::new(new_error(format!(
+            return new_error(ErrorKind::TypeError, format!(
                 "unusable as hash key: {}",
                 index.get_type()
-            )))
+            ))
         }
     };
 
-    match hash_obj.pairs.get(&hash_key) {
-        None => return Rc::new(NULL),
+    match hash.pairs.get(&hash_key) {
+        None => return Value::Null,
         Some(pair) => return pair.value.clone(),
     };
 }
 
-fn apply_function(function: Rc<dyn Object>, args: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
-    match function.get_type() {
-        ObjectType::FUNCTION => {
-            let val = function.try_into_function().unwrap();
-            let ext_env = extend_function_env(val, args);
-            let evaluated = eval(val.body.as_node(), ext_env).unwrap();
-            return unwrap_return_value(evaluated);
+pub(crate) fn is_quote_call(call_expr: &ast::CallExpression) -> bool {
+    let is_quote = matches!(call_expr.function.as_ref(), Expr::Identifier(ident) if ident.value == "quote");
+
+    is_quote && call_expr.arguments.len() == 1
+}
+
+fn eval_call_target(
+    call_expr: &ast::CallExpression,
+    env: Rc<RefCell<object::Environment>>,
+) -> Result<PendingCall, Value> {
+    let (function, receiver) = match call_expr.function.as_ref() {
+        Expr::Member(member_expr) => {
+            let receiver =
+                eval_expression(&member_expr.object, env.clone()).unwrap_or_else(|| Value::Null);
+            if receiver.get_type() == ObjectType::ERROR {
+                return Err(receiver);
+            }
+
+            match find_method(&receiver, &member_expr.property.value) {
+                Some(method) => (method, Some(receiver)),
+                None => (eval_member_expression(receiver, &member_expr.property.value), None),
+            }
+        }
+        function => (
+            eval_expression(function, env.clone()).unwrap_or_else(|| Value::Null),
+            None,
+        ),
+    };
+
+    if function.get_type() == ObjectType::ERROR {
+        return Err(function);
+    }
+
+    let args = eval_expressions(&call_expr.arguments, env.clone());
+    if args.len() == 1 && is_error(&Some(args[0].clone())) {
+        return Err(args[0].clone());
+    }
+
+    let name = match call_expr.function.as_ref() {
+        Expr::Identifier(ident) => ident.value.clone(),
+        Expr::Member(member_expr) => member_expr.property.value.clone(),
+        _ => "fn".to_string(),
+    };
+
+    Ok(PendingCall {
+        name,
+        limits: Some(env.borrow().limits()),
+        function,
+        args,
+        receiver,
+    })
+}
+
+pub(crate) fn apply_function(
+    function: Value,
+    args: Vec<Value>,
+    receiver: Option<Value>,
+) -> Value {
+    apply_call(PendingCall {
+        name: "fn".to_string(),
+        limits: None,
+        function,
+        args,
+        receiver,
+    })
+}
+
+fn apply_call(mut call: PendingCall) -> Value {
+    // calls in tail position come back as the next call to make instead of recursing
+    loop {
+        let function = call.function.clone();
+        let args = call.args;
+
+        match &function {
+            Value::Function(val) => {
+                let ext_env = extend_function_env(val, args, call.receiver);
+                if val.generator {
+                    return new_generator(val, ext_env);
+                }
+
+                // a tail call replaces its caller, so it doesn't count as a level
+                let limits = val.env.borrow().limits();
+                if let Err(msg) = limits.enter_call(&call.name) {
+                    return new_error(ErrorKind::Error, msg);
+                }
+                let result = eval_tail_block(&val.body, ext_env);
+                limits.exit_call();
+
+                match result {
+                    TailResult::Call(next) => call = next,
+                    TailResult::Value(evaluated) => {
+                        return unwrap_return_value(evaluated.unwrap_or_else(|| Value::Null));
+                    }
+                }
+            }
+            Value::Builtin(val) => {
+                let args = match call.receiver {
+                    Some(receiver) => [vec![receiver], args].concat(),
+                    None => args,
+                };
+                let result = (val.function)(args);
+                return match call.limits.map(|limits| limits.allocate(&result)) {
+                    Some(Err(msg)) => new_error(ErrorKind::Error, msg),
+                    _ => result,
+                };
+            }
+            Value::VariantConstructor(constructor) => {
+                let variant = constructor.definition();
+                if args.len() != variant.fields.len() {
+                    return new_error(ErrorKind::ArgumentError, format!(
+                        "wrong number of arguments for {}. got={}, want={}",
+                        variant.name,
+                        args.len(),
+                        variant.fields.len()
+                    ));
+                }
+                return Value::EnumValue(Rc::new(object::EnumValue {
+                    enum_type: constructor.enum_type.clone(),
+                    variant: constructor.variant,
+                    values: args,
+                }));
+            }
+            Value::StructType(struct_type) => {
+                if args.len() != struct_type.fields.len() {
+                    return new_error(ErrorKind::ArgumentError, format!(
+                        "wrong number of arguments for {}. got={}, want={}",
+                        struct_type.name,
+                        args.len(),
+                        struct_type.fields.len()
+                    ));
+                }
+                return Value::Struct(Rc::new(object::Struct {
+                    struct_type: struct_type.clone(),
+                    values: RefCell::new(args),
+                }));
+            }
+            _ => {
+                return new_error(ErrorKind::TypeError, format!(
+                    "not a function {}",
+                    function.get_type()
+                ))
+            }
+        }
+    }
+}
+
+// fields shadow methods, and only functions taking `self` can be called on an instance
+pub(crate) fn find_method(receiver: &Value, name: &str) -> Option<Value> {
+    let methods = match receiver {
+        Value::Struct(instance) => {
+            if instance.definition().field_index(name).is_some() {
+                return None;
+            }
+            &instance.struct_type.methods
+        }
+        Value::EnumValue(value) => {
+            if value.definition().fields.iter().any(|field| field == name) {
+                return None;
+            }
+            &value.enum_type.methods
         }
-        ObjectType::BUILTIN => {
-            let val = function.try_into_builtin().unwrap();
-            return (val.function)(args);
+        Value::Generator(_) if name == "next" => {
+            return BUILTINS.get("next").map(|builtin| Value::Builtin(*builtin));
         }
-        _ => Rc::new(new_error(format!("not a function {}", function.get_type()))),
+        _ => return None,
+    };
+
+    let method = methods.borrow().get(name).cloned()?;
+    let takes_self = match &method {
+        Value::Function(function) => function.parameters.first().is_some_and(|param| param.value == "self"),
+        Value::Closure(closure) => closure.function.parameters.first().is_some_and(|param| param == "self"),
+        _ => false,
+    };
+
+    takes_self.then_some(method)
+}
+
+// a function keeps only the variables its body reads, unless the resolver hasn't run
+// or couldn't see all of the body
+fn closure_env(
+    function: &ast::FunctionLiteral,
+    env: Rc<RefCell<object::Environment>>,
+) -> Rc<RefCell<object::Environment>> {
+    match &*function.captures.borrow() {
+        ast::Captures::Resolved(captures) => object::Environment::new_captured_env(&env, captures),
+        _ => env,
     }
 }
 
 fn extend_function_env(
     function: &object::Function,
-    args: Vec<Rc<dyn Object>>,
+    args: Vec<Value>,
+    receiver: Option<Value>,
 ) -> Rc<RefCell<object::Environment>> {
     let env = object::Environment::new_enclosed_env(function.env.clone());
+    let mut parameters = function.parameters.iter();
+
+    // the enclosed scope is fresh, so binding parameters can't hit a constant
+    if let Some(receiver) = receiver {
+        parameters.next();
+        let _ = env.borrow_mut().set("self".to_string(), receiver);
+    }
 
-    for (param_idx, param) in function.parameters.as_ref().iter().enumerate() {
-        env.borrow_mut()
-            .set(param.value.to_string(), args[param_idx].clone())
+    for (param, arg) in parameters.zip(args) {
+        let _ = env.borrow_mut().set(param.value.to_string(), arg);
     }
 
     return env;
 }
 
-fn unwrap_return_value(obj: Rc<dyn Object>) -> Rc<dyn Object> {
-    match obj.try_into_return_value() {
-        Ok(val) => val.value.clone(),
-        Err(_) => obj,
+fn unwrap_return_value(obj: Value) -> Value {
+    match obj {
+        Value::Return(val) => *val,
+        _ => obj,
     }
 }
 
-fn eval_expression(
-    exprs: &Vec<Rc<dyn ast::Expression>>,
+fn eval_expressions(
+    exprs: &Vec<Rc<Expr>>,
     env: Rc<RefCell<object::Environment>>,
-) -> Vec<Rc<dyn Object>> {
+) -> Vec<Value> {
     let mut result = vec![];
 
     for expr in exprs {
-        let evaluated = eval(expr.as_node().clone(), env.clone());
+        let evaluated = eval_expression(expr, env.clone());
 
         if is_error(&evaluated) {
-            return vec![evaluated.unwrap().into()];
+            return vec![evaluated.unwrap()];
         }
 
-        result.push(evaluated.unwrap().into());
+        result.push(evaluated.unwrap());
     }
 
     return result;
 }
 
-fn eval_identifier(node: &Identifier, env: Rc<RefCell<object::Environment>>) -> Rc<dyn Object> {
-    match env.borrow_mut().get(node.value.clone()) {
+fn eval_identifier(node: &Identifier, env: Rc<RefCell<object::Environment>>) -> Value {
+    if let Slot::Resolved(depth, slot) = node.slot.get() {
+        if let Some(val) = env.borrow().get_slot(depth, slot, &node.value) {
+            return val;
+        }
+    }
+
+    match env.borrow().get(&node.value) {
         Ok(val) => val.clone(),
-        Err(_) => match BUILTINS.get(&node.value.clone()) {
-            Some(builtin) => builtin.clone(),
-            None => Rc::new(new_error(format!("identifier not found: {}", node.value))),
+        Err(_) => match builtin(&node.value) {
+            Some(builtin) => Value::Builtin(builtin),
+            None => new_error(ErrorKind::NameError, format!("identifier not found: {}", node.value)),
         },
     }
 }
 
-fn native_bool_to_boolean_object(input: bool) -> object::Boolean {
-    if input {
-        return TRUE;
-    } else {
-        return FALSE;
-    }
+pub fn is_builtin(name: &str) -> bool {
+    BUILTINS.contains_key(name)
 }
 
-fn eval_program(
-    program: &ast::Program,
-    env: Rc<RefCell<object::Environment>>,
-) -> Option<Rc<dyn Object>> {
-    let mut result = None;
-
-    for stmt in &program.statements {
-        result = eval(stmt.as_node(), env.clone());
-
-        if result.is_none() {
-            continue;
-        }
-
-        match result.as_ref().unwrap().get_type() {
-            object::ObjectType::RETURN => {
-                return Some(
-                    result
-                        .unwrap()
-                        .try_into_return_value()
-                        .unwrap()
-                        .value
-                        .clone()
-                        .into(),
-                )
-            }
-            object::ObjectType::ERROR => return result,
-            _ => continue,
-        }
-    }
-
-    result
+pub(crate) fn builtin(name: &str) -> Option<Builtin> {
+    BUILTINS.get(name).copied()
 }
 
-fn eval_prefix_expression(operator: &str, right: Rc<dyn Object>) -> Rc<dyn Object> {
+pub(crate) fn eval_prefix_expression(operator: &str, right: Value) -> Value {
     match operator {
         "!" => eval_bang_operator_expression(right),
         "-" => eval_minus_prefix_operator_expression(right),
-        _ => Rc::new(new_error(format!(
+        _ => new_error(ErrorKind::TypeError, format!(
             "unknown operator: {}{}",
             operator,
             right.get_type()
-        ))),
+        )),
     }
 }
 
-fn eval_bang_operator_expression(right: Rc<dyn Object>) -> Rc<dyn Object> {
-    if right.try_into_bool().is_ok() {
-        if right.try_into_bool().unwrap().value {
-            return Rc::new(FALSE);
-        } else {
-            return Rc::new(TRUE);
-        }
-    }
-
-    if right.try_into_null().is_ok() {
-        return Rc::new(TRUE);
+fn eval_bang_operator_expression(right: Value) -> Value {
+    match right {
+        Value::Boolean(value) => Value::Boolean(!value),
+        Value::Null => Value::Boolean(true),
+        _ => Value::Boolean(false),
     }
-
-    return Rc::new(FALSE);
 }
 
-fn eval_minus_prefix_operator_expression(right: Rc<dyn Object>) -> Rc<dyn Object> {
-    if right.get_type() != object::ObjectType::INTEGER {
-        return Rc::new(new_error(format!(
+fn eval_minus_prefix_operator_expression(right: Value) -> Value {
+    match right {
+        Value::Integer(value) => match value.checked_neg() {
+            Some(negated) => Value::Integer(negated),
+            None => new_error(ErrorKind::OverflowError, format!("integer overflow: -({})", value)),
+        },
+        _ => new_error(ErrorKind::TypeError, format!(
             "unknown operator: -{}",
             right.get_type()
-        )));
+        )),
     }
-
-    let value = right.try_into_int().unwrap().value;
-
-    return Rc::new(object::Integer { value: -value });
 }
 
-fn eval_infix_expression(
+pub(crate) fn eval_infix_expression(
     operator: &str,
-    left: Rc<dyn Object>,
-    right: Rc<dyn Object>,
-) -> Rc<dyn Object> {
-    if right.try_into_int().is_ok() && left.try_into_int().is_ok() {
-        return eval_int_infix_expression(operator, left.as_int(), right.as_int());
+    left: Value,
+    right: Value,
+) -> Value {
+    if let (Value::Integer(left), Value::Integer(right)) = (&left, &right) {
+        return eval_int_infix_expression(operator, *left, *right);
     }
 
-    if operator == "==" {
-        return Rc::new(native_bool_to_boolean_object(
-            left.as_bool().value == right.as_bool().value,
-        ));
+    if left.get_type() == ObjectType::ENUM || right.get_type() == ObjectType::ENUM {
+        return eval_enum_infix_expression(operator, left, right);
     }
 
-    if operator == "!=" {
-        return Rc::new(native_bool_to_boolean_object(
-            left.as_bool().value != right.as_bool().value,
-        ));
+    let comparable = |value: &Value| matches!(value, Value::Boolean(_) | Value::Integer(_) | Value::Null);
+    if comparable(&left) && comparable(&right) {
+        if operator == "==" {
+            return Value::Boolean(is_truthy(left) == is_truthy(right));
+        }
+
+        if operator == "!=" {
+            return Value::Boolean(is_truthy(left) != is_truthy(right));
+        }
     }
 
-    if left.get_type() == ObjectType::STRING && right.get_type() == ObjectType::STRING {
-        return eval_string_infix_expression(
-            operator,
-            left.try_into_str().unwrap(),
-            right.try_into_str().unwrap(),
-        );
+    if let (Value::String(left), Value::String(right)) = (&left, &right) {
+        return eval_string_infix_expression(operator, left, right);
     }
 
     if left.get_type() != right.get_type() {
-        return Rc::new(new_error(format!(
+        return new_error(ErrorKind::TypeError, format!(
             "type mismatch: {} {} {}",
             left.get_type(),
             operator,
             right.get_type()
-        )));
+        ));
     }
 
-    return Rc::new(new_error(format!(
+    return new_error(ErrorKind::TypeError, format!(
         "unknown operator: {} {} {}",
         left.get_type(),
         operator,
         right.get_type()
-    )));
+    ));
 }
 
-fn eval_string_infix_expression(
+fn eval_enum_infix_expression(
     operator: &str,
-    left: &object::StringObj,
-    right: &object::StringObj,
-) -> Rc<dyn Object> {
+    left: Value,
+    right: Value,
+) -> Value {
     match operator {
-        "+" => Rc::new(StringObj {
-            value: format!("{}{}", left.value, right.value),
-        }),
-        _ => Rc::new(new_error(format!(
+        "==" => Value::Boolean(objects_equal(&left, &right)),
+        "!=" => Value::Boolean(!objects_equal(&left, &right)),
+        _ if left.get_type() != right.get_type() => new_error(ErrorKind::TypeError, format!(
+            "type mismatch: {} {} {}",
+            left.get_type(),
+            operator,
+            right.get_type()
+        )),
+        _ => new_error(ErrorKind::TypeError, format!(
             "unknown operator: {} {} {}",
             left.get_type(),
             operator,
             right.get_type()
-        ))),
+        )),
     }
 }
 
-fn eval_int_infix_expression(
-    operator: &str,
-    left: &object::Integer,
-    right: &object::Integer,
-) -> Rc<dyn Object> {
+fn eval_string_infix_expression(operator: &str, left: &str, right: &str) -> Value {
     match operator {
-        "+" => Rc::new(Integer {
-            value: left.value + right.value,
-        }),
-        "-" => Rc::new(Integer {
-            value: left.value - right.value,
-        }),
-        "*" => Rc::new(Integer {
-            value: left.value * right.value,
-        }),
-        "/" => Rc::new(Integer {
-            value: left.value / right.value,
-        }),
-        "<" => Rc::new(native_bool_to_boolean_object(left.value < right.value)),
-        ">" => Rc::new(native_bool_to_boolean_object(left.value > right.value)),
-        "==" => Rc::new(native_bool_to_boolean_object(left.value == right.value)),
-        "!=" => Rc::new(native_bool_to_boolean_object(left.value != right.value)),
-        _ => Rc::new(new_error(format!(
+        "+" => Value::String(format!("{}{}", left, right).into()),
+        "==" => Value::Boolean(left == right),
+        "!=" => Value::Boolean(left != right),
+        _ => new_error(ErrorKind::TypeError, format!(
             "unknown operator: {} {} {}",
-            left.get_type(),
+            ObjectType::STRING,
             operator,
-            right.get_type()
-        ))),
+            ObjectType::STRING
+        )),
+    }
+}
+
+fn eval_int_infix_expression(operator: &str, left: i64, right: i64) -> Value {
+    let result = match operator {
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "/" if right == 0 => return new_error(ErrorKind::ZeroDivisionError, "division by zero".to_string()),
+        "/" => left.checked_div(right),
+        _ => return eval_int_comparison(operator, left, right),
+    };
+
+    match result {
+        Some(value) => Value::Integer(value),
+        None => new_error(ErrorKind::OverflowError, format!(
+            "integer overflow: {} {} {}",
+            left, operator, right
+        )),
+    }
+}
+
+fn eval_int_comparison(operator: &str, left: i64, right: i64) -> Value {
+    match operator {
+        "<" => Value::Boolean(left < right),
+        ">" => Value::Boolean(left > right),
+        "==" => Value::Boolean(left == right),
+        "!=" => Value::Boolean(left != right),
+        _ => new_error(ErrorKind::TypeError, format!(
+            "unknown operator: {} {} {}",
+            ObjectType::INTEGER,
+            operator,
+            ObjectType::INTEGER
+        )),
     }
 }
 
 fn eval_if_expression(
     if_expr: &ast::IfExpression,
     env: Rc<RefCell<object::Environment>>,
-) -> Rc<dyn Object> {
-    let condition = eval(if_expr.condition.as_ref().unwrap().as_node(), env.clone());
+) -> Value {
+    let condition = eval_expression(if_expr.condition.as_ref().unwrap(), env.clone());
 
     if is_error(&condition) {
         return condition.unwrap();
@@ -450,27 +939,306 @@ fn eval_if_expression(
 
     if is_truthy(condition.unwrap()) {
         let block_stmt = if_expr.consequence.as_ref().unwrap();
-        return eval(block_stmt.as_node(), env).unwrap();
+        return eval_block_statement(block_stmt, env).unwrap();
     } else if if_expr.alternative.is_some() {
         let alt = if_expr.alternative.as_ref().unwrap();
-        return eval(alt.as_node(), env).unwrap();
+        return eval_block_statement(alt, env).unwrap();
     } else {
-        return Rc::new(NULL);
+        return Value::Null;
+    }
+}
+
+fn eval_try_expression(
+    try_expr: &ast::TryExpression,
+    env: Rc<RefCell<object::Environment>>,
+) -> Value {
+    let mut result = eval_block_statement(&try_expr.block, env.clone()).unwrap_or_else(|| Value::Null);
+
+    let stopped = env.borrow().limits().stopped();
+    if let (Value::Error(error), Some(catch_block), false) = (&result, &try_expr.catch_block, stopped) {
+        let catch_env = object::Environment::new_enclosed_env(env.clone());
+        if let Some(param) = &try_expr.catch_param {
+            let exception = Value::Exception(Rc::new(object::Exception {
+                kind: error.kind,
+                message: error.message.clone(),
+                value: error.value.clone().unwrap_or_else(|| Value::Null),
+            }));
+            let _ = catch_env.borrow_mut().set(param.value.clone(), exception);
+        }
+
+        result = eval_block_statement(catch_block, catch_env).unwrap_or_else(|| Value::Null);
     }
+
+    if let Some(finally_block) = &try_expr.finally_block {
+        let finally_result = eval_block_statement(finally_block, env);
+        if let Some(finally_result) = finally_result {
+            let rt = finally_result.get_type();
+            if rt == ObjectType::RETURN || rt == ObjectType::ERROR {
+                return finally_result;
+            }
+        }
+    }
+
+    result
 }
 
-fn is_truthy(obj: Rc<dyn Object>) -> bool {
-    return obj.as_bool().value;
+pub(crate) fn throw_value(value: Value) -> Value {
+    if let Value::Exception(exception) = &value {
+        return Value::Error(Rc::new(object::Error {
+            message: exception.message.clone(),
+            kind: exception.kind,
+            value: Some(exception.value.clone()),
+        }));
+    }
+
+    let message = match &value {
+        Value::String(value) => value.to_string(),
+        _ => value.inspect(),
+    };
+
+    Value::Error(Rc::new(object::Error {
+        message,
+        kind: ErrorKind::Error,
+        value: Some(value),
+    }))
+}
+
+fn eval_match_expression(
+    match_expr: &ast::MatchExpression,
+    env: Rc<RefCell<object::Environment>>,
+) -> Value {
+    match select_match_arm(match_expr, env) {
+        Err(err) => err,
+        Ok(Some(MatchedArm { arm, env })) => {
+            eval_block_statement(&arm.body, env).unwrap_or_else(|| Value::Null)
+        }
+        Ok(None) => Value::Null,
+    }
+}
+
+struct MatchedArm<'a> {
+    arm: &'a ast::MatchArm,
+    env: Rc<RefCell<object::Environment>>,
+}
+
+fn select_match_arm(
+    match_expr: &ast::MatchExpression,
+    env: Rc<RefCell<object::Environment>>,
+) -> Result<Option<MatchedArm<'_>>, Value> {
+    let subject = eval_expression(&match_expr.subject, env.clone());
+
+    if is_error(&subject) {
+        return Err(subject.unwrap());
+    }
+
+    let subject = subject.unwrap_or_else(|| Value::Null);
+
+    for arm in &match_expr.arms {
+        let mut bindings = Vec::new();
+
+        if !match_pattern(&arm.pattern, subject.clone(), &mut bindings, env.clone())? {
+            continue;
+        }
+
+        let arm_env = object::Environment::new_enclosed_env(env.clone());
+        for (name, value) in bindings {
+            let _ = arm_env.borrow_mut().set(name, value);
+        }
+
+        if let Some(guard) = &arm.guard {
+            let condition = eval_expression(guard, arm_env.clone());
+            if is_error(&condition) {
+                return Err(condition.unwrap());
+            }
+            if !is_truthy(condition.unwrap()) {
+                continue;
+            }
+        }
+
+        return Ok(Some(MatchedArm { arm, env: arm_env }));
+    }
+
+    Ok(None)
+}
+
+fn match_pattern(
+    pattern: &ast::Pattern,
+    value: Value,
+    bindings: &mut Vec<(String, Value)>,
+    env: Rc<RefCell<object::Environment>>,
+) -> Result<bool, Value> {
+    match pattern {
+        ast::Pattern::Wildcard(_) => Ok(true),
+        ast::Pattern::Binding(ident) => {
+            if let Ok(shadowed) = env.borrow().get(&ident.value) {
+                if names_unit_variant(&ident.value, &shadowed) && !objects_equal(&shadowed, &value) {
+                    return Ok(false);
+                }
+            }
+            bindings.push((ident.value.clone(), value));
+            Ok(true)
+        }
+        ast::Pattern::Literal(expr) => {
+            let literal = eval_expression(expr, env);
+            if is_error(&literal) {
+                return Err(literal.unwrap());
+            }
+            Ok(objects_equal(&literal.unwrap(), &value))
+        }
+        ast::Pattern::Or(alternatives) => {
+            for alternative in alternatives {
+                let mut alt_bindings = Vec::new();
+                if match_pattern(alternative, value.clone(), &mut alt_bindings, env.clone())? {
+                    bindings.append(&mut alt_bindings);
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        ast::Pattern::Variant {
+            enum_name,
+            name,
+            fields,
+            ..
+        } => {
+            let enum_value = match &value {
+                Value::EnumValue(enum_value) => enum_value,
+                _ => return Ok(false),
+            };
+
+            let wrong_enum = enum_name
+                .as_ref()
+                .is_some_and(|enum_name| enum_name.value != enum_value.enum_name());
+            if wrong_enum || enum_value.definition().name != name.value {
+                return Ok(false);
+            }
+
+            if fields.len() != enum_value.values.len() {
+                return Ok(false);
+            }
+
+            for (field_pattern, field) in fields.iter().zip(&enum_value.values) {
+                if !match_pattern(field_pattern, field.clone(), bindings, env.clone())? {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        }
+        ast::Pattern::Array { elements, rest, .. } => {
+            let array = match &value {
+                Value::Array(array) => array,
+                _ => return Ok(false),
+            };
+
+            let too_short = array.elements.len() < elements.len();
+            let wrong_len = rest.is_none() && array.elements.len() != elements.len();
+            if too_short || wrong_len {
+                return Ok(false);
+            }
+
+            for (element_pattern, element) in elements.iter().zip(&array.elements) {
+                if !match_pattern(element_pattern, element.clone(), bindings, env.clone())? {
+                    return Ok(false);
+                }
+            }
+
+            match rest {
+                Some(rest_pattern) => {
+                    let remaining = Value::Array(Rc::new(object::Array {
+                        elements: array.elements[elements.len()..].to_vec(),
+                    }));
+                    let remaining = allocate(remaining, &env);
+                    if is_error(&Some(remaining.clone())) {
+                        return Err(remaining);
+                    }
+                    match_pattern(rest_pattern, remaining, bindings, env)
+                }
+                None => Ok(true),
+            }
+        }
+        ast::Pattern::Hash { pairs, .. } => {
+            let hash = match &value {
+                Value::Hash(hash) => hash,
+                _ => return Ok(false),
+            };
+
+            for (key_expr, value_pattern) in pairs {
+                let key = eval_expression(key_expr, env.clone());
+                if is_error(&key) {
+                    return Err(key.unwrap());
+                }
+
+                let hash_key = match key.as_ref().unwrap().hash_key() {
+                    Ok(hash_key) => hash_key,
+                    Err(_) => {
+                        return Err(new_error(ErrorKind::TypeError, format!(
+                            "unusable as hash key: {}",
+                            key.unwrap().get_type()
+                        )))
+                    }
+                };
+
+                let pair_value = match hash.pairs.get(&hash_key) {
+                    Some(pair) => pair.value.clone(),
+                    None => return Ok(false),
+                };
+
+                if !match_pattern(value_pattern, pair_value, bindings, env.clone())? {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        }
+    }
+}
+
+// a bare name in a pattern whose binding holds the unit variant of that name, like
+// `None`, matches the variant instead of binding any subject
+pub(crate) fn names_unit_variant(name: &str, value: &Value) -> bool {
+    match value {
+        Value::EnumValue(enum_value) => enum_value.values.is_empty() && enum_value.definition().name == name,
+        _ => false,
+    }
+}
+
+pub(crate) fn objects_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => left == right,
+        (Value::Boolean(left), Value::Boolean(right)) => left == right,
+        (Value::String(left), Value::String(right)) => left == right,
+        (Value::Null, Value::Null) => true,
+        (Value::EnumValue(left), Value::EnumValue(right)) => {
+            Rc::ptr_eq(&left.enum_type, &right.enum_type)
+                && left.variant == right.variant
+                && left
+                    .values
+                    .iter()
+                    .zip(&right.values)
+                    .all(|(left, right)| objects_equal(left, right))
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn is_truthy(obj: Value) -> bool {
+    match obj {
+        Value::Boolean(value) => value,
+        Value::Integer(value) => value != 0,
+        Value::Null => false,
+        _ => true,
+    }
 }
 
 fn eval_block_statement(
     block: &ast::BlockStatement,
     env: Rc<RefCell<object::Environment>>,
-) -> Option<Rc<dyn Object>> {
+) -> Option<Value> {
     let mut result = None;
 
     for stmt in &block.statements {
-        result = eval(stmt.as_node(), env.clone());
+        result = eval_statement(stmt, env.clone());
 
         if result.is_some() {
             let rt = result.as_ref().unwrap().get_type();
@@ -486,29 +1254,30 @@ fn eval_block_statement(
 fn eval_hash_literal(
     hash: &ast::HashLiteral,
     env: Rc<RefCell<object::Environment>>,
-) -> Option<Rc<dyn Object>> {
+) -> Option<Value> {
     let mut pairs = HashMap::new();
 
     for (key_node, key_value) in &hash.pairs {
-        let key = eval(key_node.as_node(), env.clone());
+        let key = eval_expression(key_node, env.clone());
         if is_error(&key) {
             return key;
         }
 
-        if key.as_ref().unwrap().try_hash_key().is_err() {
-            return Some(Rc::new(new_error(format!(
-                "unusable as hash key: {}",
-                key_node.get_type()
-            ))));
-        }
+        let hash_key = match key.as_ref().unwrap().hash_key() {
+            Ok(hash_key) => hash_key,
+            Err(_) => {
+                return Some(new_error(ErrorKind::TypeError, format!(
+                    "unusable as hash key: {}",
+                    key.unwrap().get_type()
+                )))
+            }
+        };
 
-        let value = eval(key_value.as_node(), env.clone());
+        let value = eval_expression(key_value, env.clone());
         if is_error(&value) {
             return value;
         }
 
-        let hash_key = key.as_ref().unwrap().hash_key();
-
         pairs.insert(
             hash_key,
             object::hash::HashPair {
@@ -518,19 +1287,25 @@ fn eval_hash_literal(
         );
     }
 
-    Some(Rc::new(object::Hash { pairs }))
+    Some(allocate(Value::Hash(Rc::new(object::Hash { pairs })), &env))
 }
 
-fn new_error(message: String) -> object::Error {
-    return object::Error { message };
+// counts a new array, string or hash against the interpreter's memory quota
+fn allocate(value: Value, env: &Rc<RefCell<object::Environment>>) -> Value {
+    match env.borrow().limits().allocate(&value) {
+        Ok(()) => value,
+        Err(msg) => new_error(ErrorKind::Error, msg),
+    }
 }
 
-fn is_error(obj: &Option<Rc<dyn Object>>) -> bool {
-    if obj
-        .as_ref()
-        .is_some_and(|val| val.get_type() == object::ObjectType::ERROR)
-    {
-        return true;
-    }
-    return false;
+pub(crate) fn new_error(kind: ErrorKind, message: String) -> Value {
+    Value::Error(Rc::new(object::Error {
+        message,
+        kind,
+        value: None,
+    }))
+}
+
+fn is_error(obj: &Option<Value>) -> bool {
+    matches!(obj, Some(Value::Error(_)))
 }
@@ -0,0 +1,169 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ast::{ImportStatement, Program}, lexer::Lexer, optimizer::optimize, parser::Parser, resolver::resolve,
+    typecheck::typecheck,
+};
+
+use super::*;
+
+pub fn eval_import_statement(
+    import: &ImportStatement,
+    env: Rc<RefCell<object::Environment>>,
+) -> Option<Value> {
+    let modules = env.borrow().modules();
+    let limits = env.borrow().limits();
+    let module = load_module(&import.path, modules, |program, modules| {
+        let mut module_env = object::Environment::new_with_modules(modules);
+        module_env.set_limits(limits);
+        let module_env = Rc::new(RefCell::new(module_env));
+        let evaluated = eval(program, module_env.clone());
+        if is_error(&evaluated) {
+            return Err(evaluated.unwrap());
+        }
+
+        let module_env = module_env.borrow();
+        Ok(module_exports(program, |name| module_env.get(name).ok()))
+    });
+    if module.get_type() == ObjectType::ERROR {
+        return Some(module);
+    }
+
+    if let Err(msg) = env.borrow_mut().set(import.alias.value.clone(), module) {
+        return Some(new_error(ErrorKind::ConstantError, msg));
+    }
+
+    None
+}
+
+// everything up to running the module's code is shared between the engines, `run`
+// evaluates the checked program and hands back its exports
+pub(crate) fn load_module(
+    path: &str,
+    modules: Rc<RefCell<object::ModuleCache>>,
+    run: impl FnOnce(&Program, Rc<RefCell<object::ModuleCache>>) -> Result<HashMap<String, Value>, Value>,
+) -> Value {
+    let resolved = resolve_module_path(path, &modules.borrow().loading);
+
+    let canonical = match fs::canonicalize(&resolved) {
+        Ok(canonical) => canonical,
+        Err(err) => return new_error(ErrorKind::ImportError, format!("cannot import \"{}\": {}", path, err)),
+    };
+
+    if let Some(module) = modules.borrow().modules.get(&canonical) {
+        return Value::Module(module.clone());
+    }
+
+    if modules.borrow().loading.contains(&canonical) {
+        let mut chain: Vec<String> = modules
+            .borrow()
+            .loading
+            .iter()
+            .skip_while(|loading| **loading != canonical)
+            .map(|loading| loading.display().to_string())
+            .collect();
+        chain.push(canonical.display().to_string());
+
+        return new_error(ErrorKind::ImportError, format!(
+            "import cycle detected: {}",
+            chain.join(" -> ")
+        ));
+    }
+
+    let source = match fs::read_to_string(&canonical) {
+        Ok(source) => source,
+        Err(err) => return new_error(ErrorKind::ImportError, format!("cannot import \"{}\": {}", path, err)),
+    };
+
+    let mut parser = Parser::new(Lexer::new(source));
+    let mut program = parser.parse_program();
+
+    let errors = parser.get_errors();
+    if !errors.is_empty() {
+        return new_error(ErrorKind::ImportError, format!(
+            "parse errors in module \"{}\": {}",
+            path,
+            errors.join("; ")
+        ));
+    }
+
+    let macro_env = Rc::new(RefCell::new(object::Environment::new_with_modules(
+        modules.clone(),
+    )));
+    define_macros(&mut program, macro_env.clone());
+    let program = match expand_macros(&program, macro_env) {
+        Ok(expanded) => expanded,
+        Err(errors) => {
+            return new_error(ErrorKind::ImportError, format!(
+                "macro expansion errors in module \"{}\": {}",
+                path,
+                errors.join("; ")
+            ))
+        }
+    };
+
+    let errors = resolve(&program, Vec::new());
+    if !errors.is_empty() {
+        return new_error(ErrorKind::ImportError, format!(
+            "name errors in module \"{}\": {}",
+            path,
+            errors.join("; ")
+        ));
+    }
+
+    let errors = typecheck(&program, &[]);
+    if !errors.is_empty() {
+        return new_error(ErrorKind::ImportError, format!(
+            "type errors in module \"{}\": {}",
+            path,
+            errors.join("; ")
+        ));
+    }
+
+    let program = optimize(&program);
+    modules.borrow_mut().loading.push(canonical.clone());
+    let exports = run(&program, modules.clone());
+    modules.borrow_mut().loading.pop();
+
+    let exports = match exports {
+        Ok(exports) => exports,
+        Err(err) => return err,
+    };
+
+    let module = Rc::new(object::Module {
+        name: path.to_string(),
+        exports,
+    });
+    modules.borrow_mut().modules.insert(canonical, module.clone());
+
+    Value::Module(module)
+}
+
+pub(crate) fn module_exports(
+    program: &Program,
+    lookup: impl Fn(&str) -> Option<Value>,
+) -> HashMap<String, Value> {
+    let mut exports = HashMap::new();
+    for stmt in &program.statements {
+        if let Stmt::Export(export_stmt) = stmt.as_ref() {
+            let name = export_stmt.name.value.clone();
+            if let Some(value) = lookup(&name) {
+                exports.insert(name, value);
+            }
+        }
+    }
+
+    exports
+}
+
+fn resolve_module_path(path: &str, loading: &[PathBuf]) -> PathBuf {
+    let path = Path::new(path);
+
+    match loading.last().and_then(|importer| importer.parent()) {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    }
+}
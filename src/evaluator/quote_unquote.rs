@@ -0,0 +1,84 @@
+use crate::{
+    ast::{self, modify::modify_expression, Expr},
+    token::{Token, TokenType},
+};
+
+use super::*;
+
+pub fn quote(node: Rc<Expr>, env: Rc<RefCell<object::Environment>>) -> Value {
+    let node = eval_unquote_calls(node, env);
+    Value::Quote(node)
+}
+
+fn eval_unquote_calls(quoted: Rc<Expr>, env: Rc<RefCell<object::Environment>>) -> Rc<Expr> {
+    modify_expression(quoted, &mut |node: Rc<Expr>| {
+        let Some(call_expr) = unquote_call(&node) else {
+            return node;
+        };
+        if call_expr.arguments.len() != 1 {
+            return node;
+        }
+
+        let unquoted = eval_expression(&call_expr.arguments[0], env.clone());
+        match unquoted.and_then(convert_object_to_ast_node) {
+            Some(converted) => converted,
+            None => node,
+        }
+    })
+}
+
+pub(crate) fn unquote_call(node: &Expr) -> Option<&ast::CallExpression> {
+    match node {
+        Expr::Call(call_expr) => match call_expr.function.as_ref() {
+            Expr::Identifier(ident) if ident.value == "unquote" => Some(call_expr),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn convert_object_to_ast_node(obj: Value) -> Option<Rc<Expr>> {
+    match obj {
+        Value::Integer(value) => {
+            Some(Rc::new(Expr::Integer(ast::IntegerLiteral {
+                token: Token {
+                    token_type: TokenType::INT,
+                    literal: value.to_string(),
+                    line: 0,
+                    column: 0,
+                },
+                value,
+            })))
+        }
+        Value::Boolean(value) => {
+            let token_type = if value {
+                TokenType::TRUE
+            } else {
+                TokenType::FALSE
+            };
+            Some(Rc::new(Expr::Boolean(ast::Boolean {
+                token: Token {
+                    token_type,
+                    literal: value.to_string(),
+                    line: 0,
+                    column: 0,
+                },
+                value,
+            })))
+        }
+        Value::String(value) => {
+            let value = value.to_string();
+            Some(Rc::new(Expr::String(ast::StringLiteral {
+                token: Token {
+                    token_type: TokenType::STRING,
+                    literal: value.clone(),
+                    line: 0,
+                    column: 0,
+                },
+                value,
+            })))
+        }
+        Value::Quote(node) => Some(node),
+        _ => None,
+    }
+}
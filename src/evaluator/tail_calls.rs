@@ -0,0 +1,94 @@
+use super::*;
+
+pub struct PendingCall {
+    // what the call site calls the function, for the call chain in errors
+    pub name: String,
+    // for the memory used by what builtins return, unset when the VM calls in
+    pub limits: Option<Rc<object::Limits>>,
+    pub function: Value,
+    pub args: Vec<Value>,
+    pub receiver: Option<Value>,
+}
+
+pub enum TailResult {
+    Value(Option<Value>),
+    Call(PendingCall),
+}
+
+// Evaluates a function body like eval_block_statement, except that a call in tail
+// position is handed back to apply_function instead of being applied on the Rust stack.
+pub fn eval_tail_block(
+    block: &ast::BlockStatement,
+    env: Rc<RefCell<object::Environment>>,
+) -> TailResult {
+    let Some((last, init)) = block.statements.split_last() else {
+        return TailResult::Value(None);
+    };
+
+    for stmt in init {
+        if let Stmt::Return(_) = stmt.as_ref() {
+            return eval_tail_statement(stmt, env);
+        }
+
+        let result = eval_statement(stmt, env.clone());
+
+        if let Some(obj) = &result {
+            let rt = obj.get_type();
+            if rt == ObjectType::RETURN || rt == ObjectType::ERROR {
+                return TailResult::Value(result);
+            }
+        }
+    }
+
+    eval_tail_statement(last, env)
+}
+
+fn eval_tail_statement(stmt: &Stmt, env: Rc<RefCell<object::Environment>>) -> TailResult {
+    match stmt {
+        Stmt::Return(return_stmt) => match &return_stmt.return_value {
+            Some(value) => eval_tail_expression(value, env),
+            None => TailResult::Value(None),
+        },
+        Stmt::Expression(ast::ExpressionStetement {
+            expression: Some(expression),
+            ..
+        }) => eval_tail_expression(expression, env),
+        _ => TailResult::Value(eval_statement(stmt, env)),
+    }
+}
+
+fn eval_tail_expression(expr: &Expr, env: Rc<RefCell<object::Environment>>) -> TailResult {
+    match expr {
+        Expr::Call(call_expr) => {
+            if is_quote_call(call_expr) {
+                return TailResult::Value(eval_expression(expr, env));
+            }
+
+            match eval_call_target(call_expr, env) {
+                Ok(call) => TailResult::Call(call),
+                Err(err) => TailResult::Value(Some(err)),
+            }
+        }
+        Expr::If(if_expr) => {
+            let condition = eval_expression(if_expr.condition.as_ref().unwrap(), env.clone());
+
+            if is_error(&condition) {
+                return TailResult::Value(condition);
+            }
+
+            if is_truthy(condition.unwrap()) {
+                eval_tail_block(if_expr.consequence.as_ref().unwrap(), env)
+            } else if let Some(alternative) = &if_expr.alternative {
+                eval_tail_block(alternative, env)
+            } else {
+                TailResult::Value(Some(Value::Null))
+            }
+        }
+        Expr::Match(match_expr) => match select_match_arm(match_expr, env) {
+            Err(err) => TailResult::Value(Some(err)),
+            Ok(Some(MatchedArm { arm, env })) => eval_tail_block(&arm.body, env),
+            Ok(None) => TailResult::Value(Some(Value::Null)),
+        },
+        _ => TailResult::Value(eval_expression(expr, env)),
+    }
+}
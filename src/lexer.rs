@@ -5,6 +5,8 @@ pub struct Lexer {
     position: usize,
     read_position: usize,
     ch: char,
+    line: usize,
+    column: usize,
 }
 
 impl Lexer {
@@ -14,6 +16,8 @@ impl Lexer {
             position: 0,
             read_position: 0,
             ch: '\0',
+            line: 1,
+            column: 0,
         };
         l.read_char();
         return l;
@@ -29,6 +33,13 @@ impl Lexer {
     }
 
     fn read_char(&mut self) {
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
         if self.read_position >= self.input.len() {
             self.ch = '\0';
         } else {
@@ -68,35 +79,67 @@ impl Lexer {
         }
     }
 
+    fn peek_second_char(&mut self) -> char {
+        if self.read_position + 1 >= self.input.len() {
+            '\0'
+        } else {
+            self.input.chars().nth(self.read_position + 1).unwrap()
+        }
+    }
+
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
 
+        let (line, column) = (self.line, self.column);
         let token = match self.ch {
-            '+' => new_char_token(TokenType::PLUS, self.ch),
-            '-' => new_char_token(TokenType::MINUS, self.ch),
-            '/' => new_char_token(TokenType::SLASH, self.ch),
-            '*' => new_char_token(TokenType::ASTERISK, self.ch),
-            '<' => new_char_token(TokenType::LT, self.ch),
-            '>' => new_char_token(TokenType::GT, self.ch),
-            ';' => new_char_token(TokenType::SEMICOLON, self.ch),
-            ',' => new_char_token(TokenType::COMMA, self.ch),
-            ':' => new_char_token(TokenType::COLON, self.ch),
-            '(' => new_char_token(TokenType::LPAREN, self.ch),
-            ')' => new_char_token(TokenType::RPAREN, self.ch),
-            '{' => new_char_token(TokenType::LBRACE, self.ch),
-            '}' => new_char_token(TokenType::RBRACE, self.ch),
-            '[' => new_char_token(TokenType::LBRACKET, self.ch),
-            ']' => new_char_token(TokenType::RBRACKET, self.ch),
-            '\0' => new_char_token(TokenType::EOF, self.ch),
+            '+' => new_char_token(TokenType::PLUS, self.ch, line, column),
+            '-' => {
+                if self.peek_char() == '>' {
+                    self.read_char();
+                    Token {
+                        token_type: TokenType::RARROW,
+                        literal: "->".to_string(),
+                        line,
+                        column,
+                    }
+                } else {
+                    new_char_token(TokenType::MINUS, self.ch, line, column)
+                }
+            }
+            '/' => new_char_token(TokenType::SLASH, self.ch, line, column),
+            '*' => new_char_token(TokenType::ASTERISK, self.ch, line, column),
+            '<' => new_char_token(TokenType::LT, self.ch, line, column),
+            '>' => new_char_token(TokenType::GT, self.ch, line, column),
+            ';' => new_char_token(TokenType::SEMICOLON, self.ch, line, column),
+            ',' => new_char_token(TokenType::COMMA, self.ch, line, column),
+            ':' => new_char_token(TokenType::COLON, self.ch, line, column),
+            '(' => new_char_token(TokenType::LPAREN, self.ch, line, column),
+            ')' => new_char_token(TokenType::RPAREN, self.ch, line, column),
+            '{' => new_char_token(TokenType::LBRACE, self.ch, line, column),
+            '}' => new_char_token(TokenType::RBRACE, self.ch, line, column),
+            '[' => new_char_token(TokenType::LBRACKET, self.ch, line, column),
+            ']' => new_char_token(TokenType::RBRACKET, self.ch, line, column),
+            '|' => new_char_token(TokenType::PIPE, self.ch, line, column),
+            '\0' => new_char_token(TokenType::EOF, self.ch, line, column),
             '=' => {
                 if self.peek_char() == '=' {
                     self.read_char();
                     Token {
                         token_type: TokenType::EQ,
                         literal: "==".to_string(),
+                        line,
+                        column,
+                    }
+                } else if self.peek_char() == '>' {
+                    self.read_char();
+                    Token {
+                        token_type: TokenType::ARROW,
+                        literal: "=>".to_string(),
+                        line,
+                        column,
                     }
                 } else {
-                    new_char_token(TokenType::ASSIGN, self.ch)
+                    new_char_token(TokenType::ASSIGN, self.ch, line, column)
                 }
             }
             '!' => {
@@ -105,14 +148,32 @@ impl Lexer {
                     Token {
                         token_type: TokenType::NotEQ,
                         literal: "!=".to_string(),
+                        line,
+                        column,
+                    }
+                } else {
+                    new_char_token(TokenType::BANG, self.ch, line, column)
+                }
+            }
+            '.' => {
+                if self.peek_char() == '.' && self.peek_second_char() == '.' {
+                    self.read_char();
+                    self.read_char();
+                    Token {
+                        token_type: TokenType::ELLIPSIS,
+                        literal: "...".to_string(),
+                        line,
+                        column,
                     }
                 } else {
-                    new_char_token(TokenType::BANG, self.ch)
+                    new_char_token(TokenType::DOT, self.ch, line, column)
                 }
             }
             '\"' => Token {
                 token_type: TokenType::STRING,
                 literal: self.read_string(),
+                line,
+                column,
             },
             _ => {
                 if is_letter(self.ch) {
@@ -121,14 +182,18 @@ impl Lexer {
                     return Token {
                         token_type,
                         literal,
+                        line,
+                        column,
                     };
                 } else if is_digit(self.ch) {
                     return Token {
                         token_type: TokenType::INT,
                         literal: self.read_number(),
+                        line,
+                        column,
                     };
                 } else {
-                    new_char_token(TokenType::ILLEGAL, self.ch)
+                    new_char_token(TokenType::ILLEGAL, self.ch, line, column)
                 }
             }
         };
@@ -139,10 +204,12 @@ impl Lexer {
     }
 }
 
-fn new_char_token(token_type: TokenType, ch: char) -> Token {
+fn new_char_token(token_type: TokenType, ch: char, line: usize, column: usize) -> Token {
     Token {
         token_type,
         literal: ch.to_string(),
+        line,
+        column,
     }
 }
 
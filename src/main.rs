@@ -5,13 +5,182 @@ pub mod ast;
 pub mod parser;
 pub mod object;
 pub mod evaluator;
+pub mod optimizer;
+pub mod resolver;
+pub mod typecheck;
+pub mod code;
+pub mod bytecode;
+pub mod cgen;
+pub mod compiler;
+pub mod vm;
+
+use std::{
+    cell::RefCell,
+    env, fs, panic,
+    path::Path,
+    process,
+    rc::Rc,
+    str::FromStr,
+    thread,
+};
+
+use compiler::Compiler;
+use object::{Environment, Value};
+use repl::{Engine, Session};
+
+const USAGE: &str = "usage: rust_script [--engine eval|vm]
+       rust_script run [--engine eval|vm] <script or .rsc file>
+       rust_script compile [--emit bytecode|c] <script> [-o <output>]";
+
+// every script call recurses in the evaluator, so scripts run on a thread with room
+// for the default call depth limit
+const STACK_SIZE: usize = 1 << 30;
+
+enum Command {
+    Repl(Engine),
+    Run { engine: Engine, path: String },
+    Compile { path: String, output: Option<String>, emit: Emit },
+}
+
+// what `compile` writes: a .rsc file for the VM or a C file for the system compiler
+enum Emit {
+    Bytecode,
+    C,
+}
+
+impl FromStr for Emit {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bytecode" => Ok(Emit::Bytecode),
+            "c" => Ok(Emit::C),
+            _ => Err(format!("unknown output format: {} (expected bytecode or c)", name)),
+        }
+    }
+}
 
 fn main() {
-    println!("Hello! This is the RustScript programming language!");
-    println!("Feel free to type in commands");
-    repl::start();
+    let command = match parse_args(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let interpreter = thread::Builder::new().stack_size(STACK_SIZE).spawn(move || match command {
+        Command::Repl(engine) => {
+            println!("Hello! This is the RustScript programming language!");
+            println!("Feel free to type in commands");
+            repl::start(engine);
+            Ok(())
+        }
+        Command::Run { engine, path } => run_file(engine, &path),
+        Command::Compile { path, output, emit } => compile_file(&path, output, emit),
+    });
+    let result = match interpreter.map(|interpreter| interpreter.join()) {
+        Ok(Ok(result)) => result,
+        Ok(Err(panic)) => panic::resume_unwind(panic),
+        Err(err) => Err(format!("can't start the interpreter: {}", err)),
+    };
+
+    if let Err(msg) = result {
+        eprintln!("{}", msg);
+        process::exit(1);
+    }
 }
 
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut engine = Engine::Eval;
+    let mut output = None;
+    let mut emit = Emit::Bytecode;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--engine" => {
+                let name = args.next().ok_or("--engine needs a value")?;
+                engine = name.parse()?;
+            }
+            "--emit" => {
+                let name = args.next().ok_or("--emit needs a value")?;
+                emit = name.parse()?;
+            }
+            "-o" => output = Some(args.next().ok_or("-o needs a path")?),
+            _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        None => Command::Repl(engine),
+        Some("run") => Command::Run {
+            engine,
+            path: positional.next().ok_or("run needs a file")?,
+        },
+        Some("compile") => Command::Compile {
+            path: positional.next().ok_or("compile needs a script")?,
+            output,
+            emit,
+        },
+        Some(other) => return Err(format!("unknown command: {}", other)),
+    };
+
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument: {}", extra));
+    }
+    Ok(command)
+}
+
+// .rsc files were checked when they were compiled and always run on the VM
+fn run_file(engine: Engine, path: &str) -> Result<(), String> {
+    let (result, line) = if Path::new(path).extension().is_some_and(|ext| ext == "rsc") {
+        let bytes = fs::read(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+        let main = bytecode::decode(&bytes).map_err(|msg| format!("cannot load {}: {}", path, msg))?;
+        let mut vm = vm::Vm::new();
+        vm.modules().borrow_mut().enter_script(Path::new(path))?;
+        (vm.run(main), vm.error_line())
+    } else {
+        let program = load_script(path)?;
+        let mut session = Session::new(engine);
+        session.modules().borrow_mut().enter_script(Path::new(path))?;
+        (session.run(&program)?, session.error_line())
+    };
+
+    match (result, line) {
+        (Some(Value::Error(error)), Some(line)) => Err(format!(
+            "uncaught {}: {} at line {}",
+            error.kind, error.message, line
+        )),
+        (Some(Value::Error(error)), None) => Err(format!("uncaught {}: {}", error.kind, error.message)),
+        _ => Ok(()),
+    }
+}
+
+fn compile_file(path: &str, output: Option<String>, emit: Emit) -> Result<(), String> {
+    let program = load_script(path)?;
+    let main = Compiler::new().compile(&program)?;
+    let (bytes, extension) = match emit {
+        Emit::Bytecode => (bytecode::encode(&main)?, "rsc"),
+        Emit::C => (cgen::emit(&main)?.into_bytes(), "c"),
+    };
+
+    let output = output.unwrap_or_else(|| Path::new(path).with_extension(extension).display().to_string());
+    fs::write(&output, bytes).map_err(|err| format!("cannot write {}: {}", output, err))
+}
+
+fn load_script(path: &str) -> Result<ast::Program, String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+    let macro_env = Rc::new(RefCell::new(Environment::new()));
+
+    repl::prepare(source, macro_env, Vec::new()).map_err(|errors| {
+        errors.print();
+        format!("{} has errors", path)
+    })
+}
 
 #[cfg(test)]
 mod tests;
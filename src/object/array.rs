@@ -1,11 +1,11 @@
 use super::*;
 
 pub struct Array {
-    pub elements: Vec<Rc<dyn Object>>
+    pub elements: Vec<Value>
 }
 
-impl Object for Array {
-    fn inspect(&self) -> String {
+impl Array {
+    pub fn inspect(&self) -> String {
         let mut elements = vec![];
 
         for el in &self.elements {
@@ -14,14 +14,4 @@ impl Object for Array {
 
         format!("[{}]", elements.join(", "))
     }
-    fn get_type(&self) -> ObjectType {
-        ObjectType::ARRAY
-    }
-    fn as_object(&self) -> &dyn Object {
-        self
-    }
-    fn try_into_array(&self) -> Result<&Array, ErrorType> {
-        Ok(self)
-    }
 }
-
@@ -1,22 +1,8 @@
 use super::*;
 
-type BuiltinFunction = fn(objects: Vec<Rc<dyn Object>>) -> Rc<dyn Object>;
+pub type BuiltinFunction = fn(objects: Vec<Value>) -> Value;
 
+#[derive(Clone, Copy)]
 pub struct Builtin {
     pub function: BuiltinFunction,
 }
-
-impl Object for Builtin {
-    fn inspect(&self) -> String {
-        "[builtin function]".to_string()
-    }
-    fn get_type(&self) -> ObjectType {
-        ObjectType::BUILTIN
-    }
-    fn as_object(&self) -> &dyn Object {
-        self
-    }
-    fn try_into_builtin(&self) -> Result<&Builtin, ErrorType> {
-        Ok(self)
-    }
-}
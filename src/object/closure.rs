@@ -0,0 +1,113 @@
+use crate::code::Instructions;
+
+use super::*;
+
+// a function body lowered by the compiler. Constants, nested functions and match
+// patterns are kept per function, so a function never refers outside of itself
+pub struct CompiledFunction {
+    pub instructions: Instructions,
+    pub constants: Vec<Value>,
+    pub functions: Vec<Rc<CompiledFunction>>,
+    pub patterns: Vec<MatchPattern>,
+    pub parameters: Vec<String>,
+    pub num_locals: usize,
+    pub generator: bool,
+    // the function literal as the tree-walking interpreter prints it
+    pub literal: String,
+    // (instruction offset, source line) for every offset a new line starts at
+    pub lines: Vec<(usize, usize)>,
+}
+
+impl CompiledFunction {
+    pub fn line_at(&self, ip: usize) -> Option<usize> {
+        let idx = self.lines.partition_point(|(offset, _)| *offset <= ip);
+        idx.checked_sub(1).map(|idx| self.lines[idx].1)
+    }
+}
+
+// a match pattern with its literals evaluated and its bindings resolved to slots
+pub enum MatchPattern {
+    Wildcard,
+    Literal(Value),
+    // `shadowed` are the depths and slots of the bindings the name hides, innermost
+    // first. If the first one that is set holds the unit variant `name`, the pattern
+    // only matches that variant
+    Binding {
+        slot: usize,
+        name: String,
+        shadowed: Vec<(usize, usize)>,
+    },
+    Array {
+        elements: Vec<MatchPattern>,
+        rest: Option<Box<MatchPattern>>,
+    },
+    Hash(Vec<(Value, MatchPattern)>),
+    Variant {
+        enum_name: Option<String>,
+        name: String,
+        fields: Vec<MatchPattern>,
+    },
+    Or(Vec<MatchPattern>),
+}
+
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub env: Rc<Scope>,
+}
+
+impl Closure {
+    pub fn inspect(&self) -> String {
+        self.function.literal.clone()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Binding {
+    pub value: Option<Value>,
+    pub constant: bool,
+}
+
+// the runtime counterpart of a compiler scope. Every name the scope declares has a
+// slot from the start, which stays empty until the name's let has run
+pub struct Scope {
+    bindings: RefCell<Vec<Binding>>,
+    outer: Option<Rc<Scope>>,
+}
+
+impl Scope {
+    pub fn new(size: usize, outer: Option<Rc<Scope>>) -> Rc<Scope> {
+        Rc::new(Scope {
+            bindings: RefCell::new(vec![Binding::default(); size]),
+            outer,
+        })
+    }
+
+    pub fn outer(&self) -> Option<&Rc<Scope>> {
+        self.outer.as_ref()
+    }
+
+    pub fn get(&self, depth: usize, slot: usize) -> Option<Value> {
+        if depth > 0 {
+            return self.outer.as_ref()?.get(depth - 1, slot);
+        }
+
+        self.bindings.borrow().get(slot)?.value.clone()
+    }
+
+    // refuses to overwrite a constant, like Environment::set
+    pub fn set(&self, slot: usize, value: Value, constant: bool) -> bool {
+        let mut bindings = self.bindings.borrow_mut();
+        if slot >= bindings.len() {
+            bindings.resize(slot + 1, Binding::default());
+        }
+
+        let binding = &mut bindings[slot];
+        if binding.constant {
+            return false;
+        }
+
+        binding.value = Some(value);
+        binding.constant = constant;
+        true
+    }
+}
@@ -0,0 +1,87 @@
+use super::*;
+
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.fields.is_empty() {
+            return write!(f, "{}", self.name);
+        }
+        write!(f, "{}({})", self.name, self.fields.join(", "))
+    }
+}
+
+pub struct EnumType {
+    pub name: String,
+    pub variants: Vec<Variant>,
+    pub methods: RefCell<HashMap<String, Value>>,
+}
+
+impl EnumType {
+    pub fn variant_index(&self, name: &str) -> Option<usize> {
+        self.variants.iter().position(|variant| variant.name == name)
+    }
+}
+
+impl EnumType {
+    pub fn inspect(&self) -> String {
+        let variants: Vec<String> = self
+            .variants
+            .iter()
+            .map(|variant| variant.to_string())
+            .collect();
+
+        format!("enum {} {{ {} }}", self.name, variants.join(", "))
+    }
+}
+
+pub struct VariantConstructor {
+    pub enum_type: Rc<EnumType>,
+    pub variant: usize,
+}
+
+impl VariantConstructor {
+    pub fn definition(&self) -> &Variant {
+        &self.enum_type.variants[self.variant]
+    }
+}
+
+impl VariantConstructor {
+    pub fn inspect(&self) -> String {
+        format!(
+            "{}.{}",
+            self.enum_type.name,
+            self.definition()
+        )
+    }
+}
+
+pub struct EnumValue {
+    pub enum_type: Rc<EnumType>,
+    pub variant: usize,
+    pub values: Vec<Value>,
+}
+
+impl EnumValue {
+    pub fn enum_name(&self) -> &str {
+        &self.enum_type.name
+    }
+    pub fn definition(&self) -> &Variant {
+        &self.enum_type.variants[self.variant]
+    }
+}
+
+impl EnumValue {
+    pub fn inspect(&self) -> String {
+        let name = &self.definition().name;
+        if self.values.is_empty() {
+            return name.clone();
+        }
+
+        let values: Vec<String> = self.values.iter().map(|value| value.inspect()).collect();
+        format!("{}({})", name, values.join(", "))
+    }
+}
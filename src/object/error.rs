@@ -1,20 +1,61 @@
 use super::*;
 
-pub struct Error {
-    pub message: String,
+#[derive(PartialEq, Debug, Eq, Clone, Copy)]
+pub enum ErrorKind {
+    Error,
+    TypeError,
+    NameError,
+    ArgumentError,
+    ConstantError,
+    ImportError,
+    ZeroDivisionError,
+    OverflowError,
 }
 
-impl Object for Error {
-    fn inspect(&self) -> String {
-        return format!("ERROR: {}", self.message);
+const KINDS: [ErrorKind; 8] = [
+    ErrorKind::Error,
+    ErrorKind::TypeError,
+    ErrorKind::NameError,
+    ErrorKind::ArgumentError,
+    ErrorKind::ConstantError,
+    ErrorKind::ImportError,
+    ErrorKind::ZeroDivisionError,
+    ErrorKind::OverflowError,
+];
+
+impl ErrorKind {
+    pub fn from_byte(byte: u8) -> Option<ErrorKind> {
+        KINDS.get(byte as usize).copied()
     }
-    fn get_type(&self) -> ObjectType {
-        ObjectType::ERROR
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Error => "Error",
+            ErrorKind::TypeError => "TypeError",
+            ErrorKind::NameError => "NameError",
+            ErrorKind::ArgumentError => "ArgumentError",
+            ErrorKind::ConstantError => "ConstantError",
+            ErrorKind::ImportError => "ImportError",
+            ErrorKind::ZeroDivisionError => "ZeroDivisionError",
+            ErrorKind::OverflowError => "OverflowError",
+        }
     }
-    fn as_object(&self) -> &dyn Object {
-        self
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
     }
-    fn try_into_error(&self) -> Result<&Error, ErrorType> {
-        Ok(self)
+}
+
+pub struct Error {
+    pub message: String,
+    pub kind: ErrorKind,
+    pub value: Option<Value>,
+}
+
+impl Error {
+    pub fn inspect(&self) -> String {
+        return format!("ERROR: {}", self.message);
     }
 }
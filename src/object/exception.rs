@@ -0,0 +1,13 @@
+use super::*;
+
+pub struct Exception {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub value: Value,
+}
+
+impl Exception {
+    pub fn inspect(&self) -> String {
+        format!("{}: {}", self.kind, self.message)
+    }
+}
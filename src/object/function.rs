@@ -8,10 +8,11 @@ pub struct Function {
     pub parameters: Rc<Vec<Rc<Identifier>>>,
     pub body: Rc<BlockStatement>,
     pub env: Rc<RefCell<Environment>>,
+    pub generator: bool,
 }
 
-impl Object for Function {
-    fn inspect(&self) -> String {
+impl Function {
+    pub fn inspect(&self) -> String {
         let mut params = Vec::new();
 
         for p in self.parameters.as_ref() {
@@ -19,18 +20,10 @@ impl Object for Function {
         }
 
         format!(
-            "fn({}) {{\n {} \n}}",
+            "{}({}) {{\n {} \n}}",
+            if self.generator { "fn*" } else { "fn" },
             params.join(", "),
             self.body.as_ref().to_string()
         )
     }
-    fn get_type(&self) -> ObjectType {
-        ObjectType::FUNCTION
-    }
-    fn as_object(&self) -> &dyn Object {
-        self
-    }
-    fn try_into_function(&self) -> Result<&Function, ErrorType> {
-        Ok(self)
-    }
 }
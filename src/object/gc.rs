@@ -0,0 +1,357 @@
+use std::{any::Any, mem, rc::Weak};
+
+use super::*;
+
+// a recursive function keeps the environment it was defined in alive, and that
+// environment keeps the function, so reference counting alone never frees either.
+// Every environment the evaluator runs in is registered here and once enough of them are
+// alive the cycles no outside reference can reach are broken by emptying their members
+const INITIAL_THRESHOLD: usize = 1024;
+
+struct Heap {
+    environments: Vec<Weak<RefCell<Environment>>>,
+    threshold: usize,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = const {
+        RefCell::new(Heap {
+            environments: Vec::new(),
+            threshold: INITIAL_THRESHOLD,
+        })
+    };
+}
+
+pub fn track(env: &Rc<RefCell<Environment>>) {
+    match env.try_borrow_mut() {
+        Ok(mut env) if !env.tracked => env.tracked = true,
+        _ => return,
+    }
+
+    let full = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.environments.push(Rc::downgrade(env));
+        if heap.environments.len() < heap.threshold {
+            return false;
+        }
+        heap.environments.retain(|env| env.strong_count() > 0);
+        heap.environments.len() >= heap.threshold
+    });
+
+    if full {
+        collect();
+        HEAP.with(|heap| {
+            let mut heap = heap.borrow_mut();
+            heap.threshold = INITIAL_THRESHOLD.max(heap.environments.len() * 2);
+        });
+    }
+}
+
+// registered environments that haven't been freed yet
+pub fn live_environments() -> usize {
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.environments.retain(|env| env.strong_count() > 0);
+        heap.environments.len()
+    })
+}
+
+// the objects that can be part of a cycle, held by one extra reference while the
+// collector runs
+enum Node {
+    Environment(Rc<RefCell<Environment>>),
+    Variable(Variable),
+    Function(Rc<Function>),
+    Macro(Rc<Macro>),
+    Array(Rc<Array>),
+    Hash(Rc<Hash>),
+    Error(Rc<Error>),
+    Exception(Rc<Exception>),
+    Module(Rc<Module>),
+    StructType(Rc<StructType>),
+    Struct(Rc<Struct>),
+    EnumType(Rc<EnumType>),
+    VariantConstructor(Rc<VariantConstructor>),
+    EnumValue(Rc<EnumValue>),
+    Generator(Rc<Generator>),
+}
+
+impl Node {
+    fn address(&self) -> usize {
+        match self {
+            Node::Environment(env) => Rc::as_ptr(env) as *const () as usize,
+            Node::Variable(variable) => Rc::as_ptr(variable) as *const () as usize,
+            Node::Function(function) => Rc::as_ptr(function) as *const () as usize,
+            Node::Macro(macro_obj) => Rc::as_ptr(macro_obj) as *const () as usize,
+            Node::Array(array) => Rc::as_ptr(array) as *const () as usize,
+            Node::Hash(hash) => Rc::as_ptr(hash) as *const () as usize,
+            Node::Error(error) => Rc::as_ptr(error) as *const () as usize,
+            Node::Exception(exception) => Rc::as_ptr(exception) as *const () as usize,
+            Node::Module(module) => Rc::as_ptr(module) as *const () as usize,
+            Node::StructType(struct_type) => Rc::as_ptr(struct_type) as *const () as usize,
+            Node::Struct(instance) => Rc::as_ptr(instance) as *const () as usize,
+            Node::EnumType(enum_type) => Rc::as_ptr(enum_type) as *const () as usize,
+            Node::VariantConstructor(constructor) => Rc::as_ptr(constructor) as *const () as usize,
+            Node::EnumValue(value) => Rc::as_ptr(value) as *const () as usize,
+            Node::Generator(generator) => Rc::as_ptr(generator) as *const () as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Environment(env) => Rc::strong_count(env),
+            Node::Variable(variable) => Rc::strong_count(variable),
+            Node::Function(function) => Rc::strong_count(function),
+            Node::Macro(macro_obj) => Rc::strong_count(macro_obj),
+            Node::Array(array) => Rc::strong_count(array),
+            Node::Hash(hash) => Rc::strong_count(hash),
+            Node::Error(error) => Rc::strong_count(error),
+            Node::Exception(exception) => Rc::strong_count(exception),
+            Node::Module(module) => Rc::strong_count(module),
+            Node::StructType(struct_type) => Rc::strong_count(struct_type),
+            Node::Struct(instance) => Rc::strong_count(instance),
+            Node::EnumType(enum_type) => Rc::strong_count(enum_type),
+            Node::VariantConstructor(constructor) => Rc::strong_count(constructor),
+            Node::EnumValue(value) => Rc::strong_count(value),
+            Node::Generator(generator) => Rc::strong_count(generator),
+        }
+    }
+
+    // `None` when a member is borrowed, in which case nothing is collected
+    fn children(&self) -> Option<Vec<Node>> {
+        let mut children = Vec::new();
+        match self {
+            Node::Environment(env) => {
+                let env = env.try_borrow().ok()?;
+                if let Some(outer) = &env.outer {
+                    children.push(Node::Environment(outer.clone()));
+                }
+                for (_, variable) in &env.values {
+                    children.push(Node::Variable(variable.clone()));
+                }
+            }
+            Node::Variable(variable) => {
+                if let Some(value) = variable.try_borrow().ok()?.as_ref() {
+                    value_nodes(value, &mut children);
+                }
+            }
+            Node::Function(function) => children.push(Node::Environment(function.env.clone())),
+            Node::Macro(macro_obj) => children.push(Node::Environment(macro_obj.env.clone())),
+            Node::Array(array) => {
+                for element in &array.elements {
+                    value_nodes(element, &mut children);
+                }
+            }
+            Node::Hash(hash) => {
+                for pair in hash.pairs.values() {
+                    value_nodes(&pair.key, &mut children);
+                    value_nodes(&pair.value, &mut children);
+                }
+            }
+            Node::Error(error) => {
+                if let Some(value) = &error.value {
+                    value_nodes(value, &mut children);
+                }
+            }
+            Node::Exception(exception) => value_nodes(&exception.value, &mut children),
+            Node::Module(module) => {
+                for value in module.exports.values() {
+                    value_nodes(value, &mut children);
+                }
+            }
+            Node::StructType(struct_type) => {
+                for method in struct_type.methods.try_borrow().ok()?.values() {
+                    value_nodes(method, &mut children);
+                }
+            }
+            Node::Struct(instance) => {
+                children.push(Node::StructType(instance.struct_type.clone()));
+                for value in instance.values.try_borrow().ok()?.iter() {
+                    value_nodes(value, &mut children);
+                }
+            }
+            Node::EnumType(enum_type) => {
+                for method in enum_type.methods.try_borrow().ok()?.values() {
+                    value_nodes(method, &mut children);
+                }
+            }
+            Node::VariantConstructor(constructor) => {
+                children.push(Node::EnumType(constructor.enum_type.clone()))
+            }
+            Node::EnumValue(value) => {
+                children.push(Node::EnumType(value.enum_type.clone()));
+                for value in &value.values {
+                    value_nodes(value, &mut children);
+                }
+            }
+            Node::Generator(generator) => {
+                for frame in &generator.state.try_borrow().ok()?.frames {
+                    match frame {
+                        GeneratorFrame::Block { env, .. } => {
+                            children.push(Node::Environment(env.clone()))
+                        }
+                        GeneratorFrame::Delegate(generator) => {
+                            children.push(Node::Generator(generator.clone()))
+                        }
+                        GeneratorFrame::Elements { elements, .. } => {
+                            for element in elements {
+                                value_nodes(element, &mut children);
+                            }
+                        }
+                        // the VM's scopes aren't traced, so whatever they hold counts
+                        // as referenced from outside
+                        GeneratorFrame::Compiled(_) => {}
+                    }
+                }
+            }
+        }
+        Some(children)
+    }
+
+    // drops the members that can close a cycle. The caller releases them once
+    // every node is cleared, so no destructor runs while a member is borrowed
+    fn clear(&self, released: &mut Vec<Box<dyn Any>>) {
+        match self {
+            Node::Environment(env) => {
+                if let Ok(mut env) = env.try_borrow_mut() {
+                    env.slots.clear();
+                    released.push(Box::new(mem::take(&mut env.values)));
+                    released.push(Box::new(env.outer.take()));
+                }
+            }
+            Node::Variable(variable) => {
+                if let Ok(mut value) = variable.try_borrow_mut() {
+                    released.push(Box::new(value.take()));
+                }
+            }
+            Node::StructType(struct_type) => {
+                if let Ok(mut methods) = struct_type.methods.try_borrow_mut() {
+                    released.push(Box::new(mem::take(&mut *methods)));
+                }
+            }
+            Node::Struct(instance) => {
+                if let Ok(mut values) = instance.values.try_borrow_mut() {
+                    released.push(Box::new(mem::take(&mut *values)));
+                }
+            }
+            Node::EnumType(enum_type) => {
+                if let Ok(mut methods) = enum_type.methods.try_borrow_mut() {
+                    released.push(Box::new(mem::take(&mut *methods)));
+                }
+            }
+            Node::Generator(generator) => {
+                if let Ok(mut state) = generator.state.try_borrow_mut() {
+                    released.push(Box::new(mem::take(&mut state.frames)));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn value_nodes(value: &Value, nodes: &mut Vec<Node>) {
+    match value {
+        Value::Function(function) => nodes.push(Node::Function(function.clone())),
+        Value::Macro(macro_obj) => nodes.push(Node::Macro(macro_obj.clone())),
+        Value::Array(array) => nodes.push(Node::Array(array.clone())),
+        Value::Hash(hash) => nodes.push(Node::Hash(hash.clone())),
+        Value::Error(error) => nodes.push(Node::Error(error.clone())),
+        Value::Exception(exception) => nodes.push(Node::Exception(exception.clone())),
+        Value::Module(module) => nodes.push(Node::Module(module.clone())),
+        Value::StructType(struct_type) => nodes.push(Node::StructType(struct_type.clone())),
+        Value::Struct(instance) => nodes.push(Node::Struct(instance.clone())),
+        Value::EnumType(enum_type) => nodes.push(Node::EnumType(enum_type.clone())),
+        Value::VariantConstructor(constructor) => {
+            nodes.push(Node::VariantConstructor(constructor.clone()))
+        }
+        Value::EnumValue(value) => nodes.push(Node::EnumValue(value.clone())),
+        Value::Generator(generator) => nodes.push(Node::Generator(generator.clone())),
+        Value::Return(value) => value_nodes(value, nodes),
+        Value::Integer(_)
+        | Value::Boolean(_)
+        | Value::Null
+        | Value::String(_)
+        | Value::Closure(_)
+        | Value::Builtin(_)
+        | Value::Quote(_) => {}
+    }
+}
+
+// frees every registered environment, and whatever hangs off it, that is only
+// reachable through cycles. Returns how many objects were unlinked
+pub fn collect() -> usize {
+    let roots: Vec<Rc<RefCell<Environment>>> = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.environments.retain(|env| env.strong_count() > 0);
+        heap.environments.iter().filter_map(|env| env.upgrade()).collect()
+    });
+
+    // trial deletion: a node whose references all come from other nodes is only
+    // kept alive by the graph itself, unless a node referenced from outside
+    // reaches it
+    let mut index: HashMap<usize, usize> = HashMap::new();
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut edges: Vec<Vec<usize>> = Vec::new();
+    let mut internal: Vec<usize> = Vec::new();
+
+    for env in roots {
+        let node = Node::Environment(env);
+        if index.contains_key(&node.address()) {
+            continue;
+        }
+        index.insert(node.address(), nodes.len());
+        nodes.push(node);
+        internal.push(0);
+    }
+
+    let mut next = 0;
+    while next < nodes.len() {
+        let children = match nodes[next].children() {
+            Some(children) => children,
+            None => return 0,
+        };
+
+        let mut targets = Vec::with_capacity(children.len());
+        for child in children {
+            let target = match index.get(&child.address()) {
+                Some(target) => *target,
+                None => {
+                    index.insert(child.address(), nodes.len());
+                    nodes.push(child);
+                    internal.push(0);
+                    nodes.len() - 1
+                }
+            };
+            internal[target] += 1;
+            targets.push(target);
+        }
+        edges.push(targets);
+        next += 1;
+    }
+
+    // `nodes` holds one reference of its own to every node
+    let mut reachable = vec![false; nodes.len()];
+    let mut pending: Vec<usize> = (0..nodes.len())
+        .filter(|node| nodes[*node].strong_count() > internal[*node] + 1)
+        .collect();
+    while let Some(node) = pending.pop() {
+        if reachable[node] {
+            continue;
+        }
+        reachable[node] = true;
+        pending.extend(edges[node].iter().filter(|target| !reachable[**target]));
+    }
+
+    let mut released = Vec::new();
+    let mut freed = 0;
+    for (node, reachable) in nodes.iter().zip(&reachable) {
+        if !reachable {
+            node.clear(&mut released);
+            freed += 1;
+        }
+    }
+
+    drop(nodes);
+    drop(released);
+    freed
+}
@@ -0,0 +1,52 @@
+use crate::{ast::Stmt, vm::Vm};
+
+use super::*;
+
+pub enum GeneratorFrame {
+    Block {
+        statements: Vec<Rc<Stmt>>,
+        index: usize,
+        env: Rc<RefCell<Environment>>,
+    },
+    Delegate(Rc<Generator>),
+    Elements {
+        elements: Vec<Value>,
+        index: usize,
+    },
+    // the body of a generator compiled for the VM, suspended inside its own VM
+    Compiled(Box<Vm>),
+}
+
+impl GeneratorFrame {
+    pub fn is_exhausted(&self) -> bool {
+        match self {
+            GeneratorFrame::Block {
+                statements, index, ..
+            } => *index >= statements.len(),
+            GeneratorFrame::Delegate(_) => false,
+            GeneratorFrame::Elements { elements, index } => *index >= elements.len(),
+            GeneratorFrame::Compiled(vm) => vm.in_tail_delegate(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GeneratorState {
+    pub frames: Vec<GeneratorFrame>,
+    pub running: bool,
+    pub done: bool,
+}
+
+pub struct Generator {
+    pub state: RefCell<GeneratorState>,
+}
+
+impl Generator {
+    pub fn inspect(&self) -> String {
+        if self.state.borrow().done {
+            "generator (done)".to_string()
+        } else {
+            "generator".to_string()
+        }
+    }
+}
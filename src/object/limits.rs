@@ -0,0 +1,265 @@
+use std::{
+    cell::{Cell, RefCell},
+    mem,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use super::{HashKey, Value};
+use super::hash::HashPair;
+
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 10000;
+
+// reading the clock for every node would cost more than evaluating it
+const DEADLINE_INTERVAL: u64 = 1024;
+
+// the stack left for builtins, error handling and what runs between two checks
+const STACK_RESERVE: usize = 256 * 1024;
+
+// stops the eval running on another thread, e.g. from a ctrl-c handler. Triggering it
+// only stores a flag, which the evaluator checks before each node and the VM before
+// each instruction
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+}
+
+// what a script may use, shared by every environment of one interpreter the way the
+// module cache is. Hosts running untrusted scripts lower the limits before eval
+pub struct Limits {
+    pub max_call_depth: Cell<usize>,
+    // how many steps, nodes or instructions, and for how long each eval may run
+    pub max_steps: Cell<Option<u64>>,
+    pub time_limit: Cell<Option<Duration>>,
+    // how many bytes of arrays, strings and hashes the interpreter may create over its
+    // lifetime. Values that are dropped don't give their bytes back, so a long session
+    // can't build up more than this one eval at a time
+    pub max_memory: Cell<Option<usize>>,
+    // the functions being called, outermost first
+    calls: RefCell<Vec<String>>,
+    // nested evals, like the ones running imported modules, share the outermost budget
+    runs: Cell<usize>,
+    steps: Cell<u64>,
+    deadline: Cell<Option<Instant>>,
+    // why the current eval was stopped, if it was
+    stopped: Cell<Option<&'static str>>,
+    allocated: Cell<usize>,
+    interrupt: InterruptHandle,
+    // the lowest stack address the current eval may reach, if the thread's stack is known.
+    // Hosts call in on threads of any size, so the call depth limit alone can't keep a
+    // deep recursion from overflowing the stack
+    stack_floor: Cell<Option<usize>>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_call_depth: Cell::new(DEFAULT_MAX_CALL_DEPTH),
+            max_steps: Cell::new(None),
+            time_limit: Cell::new(None),
+            max_memory: Cell::new(None),
+            calls: RefCell::new(Vec::new()),
+            runs: Cell::new(0),
+            steps: Cell::new(0),
+            deadline: Cell::new(None),
+            stopped: Cell::new(None),
+            allocated: Cell::new(0),
+            interrupt: InterruptHandle::default(),
+            stack_floor: Cell::new(None),
+        }
+    }
+}
+
+impl Limits {
+    pub fn call_depth(&self) -> usize {
+        self.calls.borrow().len()
+    }
+
+    pub fn enter_call(&self, name: &str) -> Result<(), String> {
+        let mut calls = self.calls.borrow_mut();
+        let max = self.max_call_depth.get();
+        if calls.len() >= max {
+            return Err(format!(
+                "maximum call depth {} exceeded (call chain: {})",
+                max,
+                call_chain(&calls, name)
+            ));
+        }
+        if self.check_stack().is_err() {
+            return Err(format!(
+                "stack exhausted at call depth {} (call chain: {})",
+                calls.len(),
+                call_chain(&calls, name)
+            ));
+        }
+
+        calls.push(name.to_string());
+        Ok(())
+    }
+
+    pub fn exit_call(&self) {
+        self.calls.borrow_mut().pop();
+    }
+
+    pub fn start_run(&self) {
+        if self.runs.get() == 0 {
+            self.steps.set(0);
+            self.deadline.set(self.time_limit.get().map(|limit| Instant::now() + limit));
+            self.stopped.set(None);
+            self.stack_floor.set(stack_floor());
+            // an interrupt that came in between evals is meant for the one it missed
+            self.interrupt.take();
+        }
+        self.runs.set(self.runs.get() + 1);
+    }
+
+    pub fn end_run(&self) {
+        self.runs.set(self.runs.get() - 1);
+    }
+
+    pub fn check_stack(&self) -> Result<(), String> {
+        let marker = 0u8;
+        let here = &marker as *const u8 as usize;
+        match self.stack_floor.get() {
+            Some(floor) if here < floor => Err("stack exhausted".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    // counts one evaluated node or instruction against the budget. Once the budget is exhausted or the
+    // eval is interrupted every node fails, so a script can't catch the error and carry on
+    pub fn step(&self) -> Result<(), String> {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+
+        let over_steps = self.max_steps.get().is_some_and(|max| steps > max);
+        let over_time = steps.is_multiple_of(DEADLINE_INTERVAL)
+            && self.deadline.get().is_some_and(|deadline| Instant::now() >= deadline);
+        if self.stopped.get().is_none() {
+            if self.interrupt.take() {
+                self.stopped.set(Some("interrupted"));
+            } else if over_steps || over_time {
+                self.stopped.set(Some("budget exhausted"));
+            }
+        }
+
+        match self.stopped.get() {
+            Some(reason) => Err(reason.to_string()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.stopped.get().is_some()
+    }
+
+    // counts `value` against the memory quota if it was just created. A value
+    // something else still holds was counted when it was made
+    pub fn allocate(&self, value: &Value) -> Result<(), String> {
+        let size = match value {
+            Value::String(string) if Rc::strong_count(string) == 1 => string.len(),
+            Value::Array(array) if Rc::strong_count(array) == 1 => {
+                array.elements.len() * mem::size_of::<Value>()
+            }
+            Value::Hash(hash) if Rc::strong_count(hash) == 1 => {
+                hash.pairs.len() * mem::size_of::<(HashKey, HashPair)>()
+            }
+            _ => return Ok(()),
+        };
+
+        let allocated = self.allocated.get() + size;
+        self.allocated.set(allocated);
+        match self.max_memory.get() {
+            Some(max) if allocated > max => Err("memory limit exceeded".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn stack_floor() -> Option<usize> {
+    use std::{
+        os::raw::{c_int, c_void},
+        ptr,
+    };
+
+    // large enough for pthread_attr_t on every linux target
+    #[repr(C)]
+    struct PthreadAttr([u64; 8]);
+
+    extern "C" {
+        fn pthread_self() -> usize;
+        fn pthread_getattr_np(thread: usize, attr: *mut PthreadAttr) -> c_int;
+        fn pthread_attr_getstack(attr: *const PthreadAttr, addr: *mut *mut c_void, size: *mut usize) -> c_int;
+        fn pthread_attr_destroy(attr: *mut PthreadAttr) -> c_int;
+    }
+
+    let mut attr = PthreadAttr([0; 8]);
+    let (mut addr, mut size) = (ptr::null_mut(), 0);
+    unsafe {
+        if pthread_getattr_np(pthread_self(), &mut attr) != 0 {
+            return None;
+        }
+        let found = pthread_attr_getstack(&attr, &mut addr, &mut size) == 0;
+        pthread_attr_destroy(&mut attr);
+        found.then(|| addr as usize + STACK_RESERVE)
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn stack_floor() -> Option<usize> {
+    use std::os::raw::c_void;
+
+    extern "C" {
+        fn pthread_self() -> *mut c_void;
+        fn pthread_get_stackaddr_np(thread: *mut c_void) -> *mut c_void;
+        fn pthread_get_stacksize_np(thread: *mut c_void) -> usize;
+    }
+
+    unsafe {
+        let thread = pthread_self();
+        let top = pthread_get_stackaddr_np(thread) as usize;
+        Some(top - pthread_get_stacksize_np(thread) + STACK_RESERVE)
+    }
+}
+
+// without a way to find the stack only the call depth limit applies
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn stack_floor() -> Option<usize> {
+    None
+}
+
+// runs of the same function, as recursion makes them, are shown once with a count
+fn call_chain(calls: &[String], next: &str) -> String {
+    let mut runs: Vec<(&str, usize)> = Vec::new();
+    for name in calls.iter().map(|name| name.as_str()).chain([next]) {
+        match runs.last_mut() {
+            Some((last, count)) if *last == name => *count += 1,
+            _ => runs.push((name, 1)),
+        }
+    }
+
+    runs.iter()
+        .map(|(name, count)| match count {
+            1 => name.to_string(),
+            _ => format!("{} x{}", name, count),
+        })
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
@@ -0,0 +1,27 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::ast::{BlockStatement, Identifier, Node};
+
+use super::*;
+
+pub struct Macro {
+    pub parameters: Rc<Vec<Rc<Identifier>>>,
+    pub body: Rc<BlockStatement>,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+impl Macro {
+    pub fn inspect(&self) -> String {
+        let mut params = Vec::new();
+
+        for p in self.parameters.as_ref() {
+            params.push(p.to_string());
+        }
+
+        format!(
+            "macro({}) {{\n {} \n}}",
+            params.join(", "),
+            self.body.as_ref().to_string()
+        )
+    }
+}
@@ -1,32 +1,46 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use core::fmt;
 
+use crate::ast::{Expr, Node};
+
 pub use array::Array;
-pub use boolean::Boolean;
-pub use builtin::Builtin;
-pub use error::Error;
+pub use builtin::{Builtin, BuiltinFunction};
+pub use closure::{Binding, Closure, CompiledFunction, MatchPattern, Scope};
+pub use enumeration::{EnumType, EnumValue, Variant, VariantConstructor};
+pub use error::{Error, ErrorKind};
+pub use exception::Exception;
 pub use function::Function;
+pub use generator::{Generator, GeneratorFrame, GeneratorState};
 pub use hash::Hash;
-pub use integer::Integer;
-pub use null::NULL;
-pub use return_value::ReturnValue;
-pub use string::StringObj;
+pub use limits::{InterruptHandle, Limits, DEFAULT_MAX_CALL_DEPTH};
+pub use macros::Macro;
+pub use module::{Module, ModuleCache};
+pub use structure::{Struct, StructType};
 
 pub mod array;
-pub mod boolean;
 pub mod builtin;
+pub mod closure;
+pub mod enumeration;
 pub mod error;
+pub mod exception;
 pub mod function;
+pub mod gc;
+pub mod generator;
 pub mod hash;
-pub mod integer;
-pub mod null;
-pub mod return_value;
-pub mod string;
+pub mod limits;
+pub mod macros;
+pub mod module;
+pub mod structure;
 
 #[derive(PartialEq, Debug, Eq, Clone, Hash)]
 pub enum ObjectType {
     FUNCTION,
+    GENERATOR,
     BUILTIN,
     INTEGER,
     BOOLEAN,
@@ -34,6 +48,14 @@ pub enum ObjectType {
     RETURN,
     ERROR,
     ARRAY,
+    EXCEPTION,
+    MODULE,
+    STRUCT,
+    ENUM,
+    CONSTRUCTOR,
+    TYPE,
+    QUOTE,
+    MACRO,
     HASH,
     NULL,
 }
@@ -42,6 +64,7 @@ impl ObjectType {
     pub fn as_str(&self) -> &'static str {
         match self {
             ObjectType::FUNCTION => "FUNCTION",
+            ObjectType::GENERATOR => "GENERATOR",
             ObjectType::BUILTIN => "BUILTIN",
             ObjectType::BOOLEAN => "BOOLEAN",
             ObjectType::INTEGER => "INTEGER",
@@ -49,6 +72,14 @@ impl ObjectType {
             ObjectType::STRING => "STRING",
             ObjectType::ERROR => "ERROR",
             ObjectType::ARRAY => "ARRAY",
+            ObjectType::EXCEPTION => "EXCEPTION",
+            ObjectType::MODULE => "MODULE",
+            ObjectType::STRUCT => "STRUCT",
+            ObjectType::ENUM => "ENUM",
+            ObjectType::CONSTRUCTOR => "CONSTRUCTOR",
+            ObjectType::TYPE => "TYPE",
+            ObjectType::QUOTE => "QUOTE",
+            ObjectType::MACRO => "MACRO",
             ObjectType::HASH => "HASH",
             ObjectType::NULL => "NULL",
         }
@@ -69,79 +100,218 @@ pub struct HashKey {
 
 type ErrorType = String;
 
-pub trait Object {
-    fn get_type(&self) -> ObjectType;
-    fn inspect(&self) -> String;
-    fn hash_key(&self) -> HashKey {
-        panic!("can't get hash key for {}", self.get_type())
-    }
-    fn try_hash_key(&self) -> Result<HashKey, ErrorType>{
-        Err(format!("can't get hash key for {}", self.get_type()))
-    }
-    fn as_int(&self) -> &Integer {
-        panic!("can't cast {} to Integer", self.get_type())
-    }
-    fn as_bool(&self) -> &Boolean {
-        panic!("can't cast {} to Boolean", self.get_type())
-    }
-    fn as_object(&self) -> &dyn Object;
-    fn try_into_str(&self) -> Result<&StringObj, ErrorType> {
-        Err(format!("can't cast from {} to String", self.get_type()))
-    }
-    fn try_into_int(&self) -> Result<&Integer, ErrorType> {
-        Err(format!("can't cast from {} to Integer", self.get_type()))
-    }
-    fn try_into_bool(&self) -> Result<&Boolean, ErrorType> {
-        Err(format!("can't cast from {} to Boolean", self.get_type()))
-    }
-    fn try_into_null(&self) -> Result<&NULL, ErrorType> {
-        Err(format!("can't cast from {} to NULL", self.get_type()))
-    }
-    fn try_into_error(&self) -> Result<&Error, ErrorType> {
-        Err(format!("can't cast from {} to Error", self.get_type()))
-    }
-    fn try_into_return_value(&self) -> Result<&ReturnValue, ErrorType> {
-        Err(format!(
-            "can't cast from {} to ReturnValue",
-            self.get_type()
-        ))
-    }
-    fn try_into_function(&self) -> Result<&Function, ErrorType> {
-        Err(format!("can't cast from {} to Function", self.get_type()))
-    }
-    fn try_into_builtin(&self) -> Result<&Builtin, ErrorType> {
-        Err(format!("can't cast from {} to Builtin", self.get_type()))
+// integers, booleans and null live inline, everything else shares its payload so
+// cloning a value never copies more than a pointer
+#[derive(Clone)]
+pub enum Value {
+    Integer(i64),
+    Boolean(bool),
+    Null,
+    String(Rc<str>),
+    Array(Rc<Array>),
+    Hash(Rc<Hash>),
+    Function(Rc<Function>),
+    Closure(Rc<Closure>),
+    Builtin(Builtin),
+    Generator(Rc<Generator>),
+    Return(Box<Value>),
+    Error(Rc<Error>),
+    Exception(Rc<Exception>),
+    Module(Rc<Module>),
+    StructType(Rc<StructType>),
+    Struct(Rc<Struct>),
+    EnumType(Rc<EnumType>),
+    VariantConstructor(Rc<VariantConstructor>),
+    EnumValue(Rc<EnumValue>),
+    Quote(Rc<Expr>),
+    Macro(Rc<Macro>),
+}
+
+impl Value {
+    pub fn get_type(&self) -> ObjectType {
+        match self {
+            Value::Integer(_) => ObjectType::INTEGER,
+            Value::Boolean(_) => ObjectType::BOOLEAN,
+            Value::Null => ObjectType::NULL,
+            Value::String(_) => ObjectType::STRING,
+            Value::Array(_) => ObjectType::ARRAY,
+            Value::Hash(_) => ObjectType::HASH,
+            Value::Function(_) | Value::Closure(_) => ObjectType::FUNCTION,
+            Value::Builtin(_) => ObjectType::BUILTIN,
+            Value::Generator(_) => ObjectType::GENERATOR,
+            Value::Return(_) => ObjectType::RETURN,
+            Value::Error(_) => ObjectType::ERROR,
+            Value::Exception(_) => ObjectType::EXCEPTION,
+            Value::Module(_) => ObjectType::MODULE,
+            Value::StructType(_) | Value::EnumType(_) => ObjectType::TYPE,
+            Value::Struct(_) => ObjectType::STRUCT,
+            Value::VariantConstructor(_) => ObjectType::CONSTRUCTOR,
+            Value::EnumValue(_) => ObjectType::ENUM,
+            Value::Quote(_) => ObjectType::QUOTE,
+            Value::Macro(_) => ObjectType::MACRO,
+        }
     }
-    fn try_into_array(&self) -> Result<&Array, ErrorType> {
-        Err(format!("can't cast from {} to String", self.get_type()))
+
+    pub fn inspect(&self) -> String {
+        match self {
+            Value::Integer(value) => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            Value::Null => "null".to_string(),
+            Value::String(value) => value.to_string(),
+            Value::Array(array) => array.inspect(),
+            Value::Hash(hash) => hash.inspect(),
+            Value::Function(function) => function.inspect(),
+            Value::Closure(closure) => closure.inspect(),
+            Value::Builtin(_) => "[builtin function]".to_string(),
+            Value::Generator(generator) => generator.inspect(),
+            Value::Return(value) => value.inspect(),
+            Value::Error(error) => error.inspect(),
+            Value::Exception(exception) => exception.inspect(),
+            Value::Module(module) => module.inspect(),
+            Value::StructType(struct_type) => struct_type.inspect(),
+            Value::Struct(instance) => instance.inspect(),
+            Value::EnumType(enum_type) => enum_type.inspect(),
+            Value::VariantConstructor(constructor) => constructor.inspect(),
+            Value::EnumValue(value) => value.inspect(),
+            Value::Quote(node) => format!("QUOTE({})", node.to_string()),
+            Value::Macro(macro_obj) => macro_obj.inspect(),
+        }
     }
-    fn try_into_hash(&self) -> Result<&Hash, ErrorType> {
-        Err(format!("can't cast from {} to Hash", self.get_type()))
+
+    pub fn hash_key(&self) -> Result<HashKey, ErrorType> {
+        let value = match self {
+            Value::Integer(value) => *value as u64,
+            Value::Boolean(value) => *value as u64,
+            Value::String(value) => get_fnv_a_hash(value),
+            _ => return Err(format!("can't get hash key for {}", self.get_type())),
+        };
+
+        Ok(HashKey {
+            value,
+            object_type: self.get_type(),
+        })
     }
 }
 
+// a binding an environment shares with the closures that capture it. Empty while a
+// closure has captured the name ahead of the let that binds it
+pub type Variable = Rc<RefCell<Option<Value>>>;
+
+// bindings live in `values` in the order they were first defined, so a name the
+// resolver has mapped to (depth, slot) is found without hashing it
 pub struct Environment {
-    store: HashMap<String, Rc<dyn Object>>,
+    slots: HashMap<String, usize>,
+    values: Vec<(String, Variable)>,
+    constants: HashSet<String>,
     outer: Option<Rc<RefCell<Environment>>>,
+    modules: Rc<RefCell<ModuleCache>>,
+    limits: Rc<Limits>,
+    // registered with the cycle collector
+    tracked: bool,
 }
 
 impl Environment {
     pub fn new() -> Self {
+        Environment::new_with_modules(Rc::new(RefCell::new(ModuleCache::default())))
+    }
+
+    pub fn new_with_modules(modules: Rc<RefCell<ModuleCache>>) -> Self {
         Environment {
-            store: HashMap::new(),
+            slots: HashMap::new(),
+            values: Vec::new(),
+            constants: HashSet::new(),
             outer: None,
+            modules,
+            limits: Rc::new(Limits::default()),
+            tracked: false,
         }
     }
 
     pub fn new_enclosed_env(outer: Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
-        let mut env = Environment::new();
+        let mut env = Environment::new_with_modules(outer.borrow().modules());
+        env.limits = outer.borrow().limits();
         env.outer = Some(outer);
-        return Rc::new(RefCell::new(env));
+        let env = Rc::new(RefCell::new(env));
+        gc::track(&env);
+        env
+    }
+
+    // the variables a closure created in `env` reads, found `depth` environments out
+    // as the resolver computed. The environments they come from are mirrored one to
+    // one, so closures created inside the closure find theirs at the same depth
+    pub fn new_captured_env(
+        env: &Rc<RefCell<Environment>>,
+        captures: &[(String, usize)],
+    ) -> Rc<RefCell<Environment>> {
+        let levels = captures.iter().map(|(_, depth)| depth + 1).max().unwrap_or(1);
+        let mut captured: Option<Rc<RefCell<Environment>>> = None;
+
+        // the variables keep their slots, so the resolver's slots still find them. The
+        // gaps share one variable that is never bound
+        let unbound: Variable = Rc::new(RefCell::new(None));
+
+        for level in (0..levels).rev() {
+            let mut level_env = Environment::new_with_modules(env.borrow().modules());
+            level_env.limits = env.borrow().limits();
+            level_env.outer = captured.take();
+            for (name, _) in captures.iter().filter(|(_, depth)| *depth == level) {
+                let (slot, variable) = env.borrow_mut().variable(level, name);
+                if level_env.values.len() <= slot {
+                    level_env.values.resize(slot + 1, (String::new(), unbound.clone()));
+                }
+                level_env.slots.insert(name.clone(), slot);
+                level_env.values[slot] = (name.clone(), variable);
+            }
+
+            let level_env = Rc::new(RefCell::new(level_env));
+            if !level_env.borrow().values.is_empty() {
+                gc::track(&level_env);
+            }
+            captured = Some(level_env);
+        }
+
+        captured.unwrap()
+    }
+
+    fn variable(&mut self, depth: usize, name: &str) -> (usize, Variable) {
+        if depth > 0 {
+            if let Some(outer) = &self.outer {
+                return outer.borrow_mut().variable(depth - 1, name);
+            }
+        }
+
+        let slot = match self.slots.get(name) {
+            Some(slot) => *slot,
+            None => {
+                self.slots.insert(name.to_string(), self.values.len());
+                self.values.push((name.to_string(), Rc::new(RefCell::new(None))));
+                self.values.len() - 1
+            }
+        };
+        (slot, self.values[slot].1.clone())
+    }
+
+    pub fn modules(&self) -> Rc<RefCell<ModuleCache>> {
+        self.modules.clone()
+    }
+
+    pub fn limits(&self) -> Rc<Limits> {
+        self.limits.clone()
+    }
+
+    pub fn step(&self) -> Result<(), ErrorType> {
+        self.limits.step()
     }
 
-    pub fn get(&self, name: String) -> Result<Rc<dyn Object>, ErrorType> {
-        match self.store.get(&name) {
-            Some(val) => Ok(val.clone()),
+    // environments created from this one afterwards share `limits`
+    pub fn set_limits(&mut self, limits: Rc<Limits>) {
+        self.limits = limits;
+    }
+
+    pub fn get(&self, name: &str) -> Result<Value, ErrorType> {
+        let bound = self.slots.get(name).and_then(|slot| self.values[*slot].1.borrow().clone());
+        match bound {
+            Some(value) => Ok(value),
             None => match &self.outer {
                 Some(val) => val.borrow().get(name),
                 None => Err("Element not exist in env".to_string()),
@@ -149,19 +319,60 @@ impl Environment {
         }
     }
 
-    pub fn set(&mut self, name: String, val: Rc<dyn Object>) {
-        self.store.insert(name, val);
+    // `None` when the binding isn't where the resolver expected it, e.g. because a let
+    // in an untaken branch shifted the slots. Callers fall back to `get`
+    pub fn get_slot(&self, depth: usize, slot: usize, name: &str) -> Option<Value> {
+        if depth > 0 {
+            return self.outer.as_ref()?.borrow().get_slot(depth - 1, slot, name);
+        }
+
+        match self.values.get(slot) {
+            Some((slot_name, value)) if slot_name == name => value.borrow().clone(),
+            _ => None,
+        }
+    }
+
+    // names bound directly in this scope, in slot order
+    pub fn names(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn set(&mut self, name: String, val: Value) -> Result<(), ErrorType> {
+        if self.constants.contains(&name) {
+            return Err(format!("cannot redefine constant: {}", name));
+        }
+        match self.slots.get(&name) {
+            Some(slot) => *self.values[*slot].1.borrow_mut() = Some(val),
+            None => {
+                self.slots.insert(name.clone(), self.values.len());
+                self.values.push((name, Rc::new(RefCell::new(Some(val)))));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_const(&mut self, name: String, val: Value) -> Result<(), ErrorType> {
+        self.set(name.clone(), val)?;
+        self.constants.insert(name);
+        Ok(())
     }
 }
 
 const HVAL_64_PRIME: u64 = 0x00000100000001b3;
 
-pub fn get_fnv_a_hash(str: String) -> u64 {
+// FNV-1a multiplies by the prime after each byte. Masking with it instead, as this once
+// did, kept only a few bits of the last byte, so strings like "a" and "i" collided and
+// hash literals and hash patterns mixed up their keys
+pub fn get_fnv_a_hash(str: &str) -> u64 {
     let mut hash: u64 = 0xcbf29ce484222325;
 
     for char in str.as_bytes() {
         hash = hash ^ *char as u64;
-        hash = hash & HVAL_64_PRIME;
+        hash = hash.wrapping_mul(HVAL_64_PRIME);
     }
 
     hash
@@ -0,0 +1,44 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::*;
+
+pub struct Module {
+    pub name: String,
+    pub exports: HashMap<String, Value>,
+}
+
+impl Module {
+    pub fn inspect(&self) -> String {
+        let mut names: Vec<&String> = self.exports.keys().collect();
+        names.sort();
+
+        format!(
+            "module \"{}\" {{ {} }}",
+            self.name,
+            names
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct ModuleCache {
+    pub modules: HashMap<PathBuf, Rc<Module>>,
+    pub loading: Vec<PathBuf>,
+}
+
+impl ModuleCache {
+    // a script run from a file imports relative to its own directory, and importing it
+    // again is a cycle, the same as for the modules it imports
+    pub fn enter_script(&mut self, path: &Path) -> Result<(), String> {
+        let canonical = fs::canonicalize(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        self.loading.push(canonical);
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use super::*;
+
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<String>,
+    pub methods: RefCell<HashMap<String, Value>>,
+}
+
+impl StructType {
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field == name)
+    }
+}
+
+impl StructType {
+    pub fn inspect(&self) -> String {
+        format!("struct {} {{ {} }}", self.name, self.fields.join(", "))
+    }
+}
+
+pub struct Struct {
+    pub struct_type: Rc<StructType>,
+    pub values: RefCell<Vec<Value>>,
+}
+
+impl Struct {
+    pub fn definition(&self) -> &StructType {
+        &self.struct_type
+    }
+}
+
+impl Struct {
+    pub fn inspect(&self) -> String {
+        let definition = self.definition();
+        let fields: Vec<String> = definition
+            .fields
+            .iter()
+            .zip(self.values.borrow().iter())
+            .map(|(name, value)| format!("{}: {}", name, value.inspect()))
+            .collect();
+
+        format!("{} {{ {} }}", definition.name, fields.join(", "))
+    }
+}
@@ -0,0 +1,196 @@
+use std::{collections::HashSet, rc::Rc};
+
+use crate::{
+    ast::{
+        modify::{modify_expression, modify_program},
+        BlockStatement, Boolean, Expr, IfExpression, InfixExpression, IntegerLiteral,
+        PrefixExpression, Program, Stmt, StringLiteral,
+    },
+    token::{Token, TokenType},
+};
+
+enum Constant {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+}
+
+pub fn optimize(program: &Program) -> Program {
+    let quoted = quoted_literals(program);
+
+    modify_program(program, &mut |node: Rc<Expr>| {
+        match node.as_ref() {
+            Expr::Infix(infix_expr) => fold_infix(infix_expr, &quoted),
+            Expr::Prefix(prefix_expr) => fold_prefix(prefix_expr, &quoted),
+            Expr::If(if_expr) => prune_if(if_expr, &quoted),
+            _ => None,
+        }
+        .unwrap_or(node)
+    })
+}
+
+// quote hands its argument to the program as data, so nothing inside it may change.
+// modify rebuilds the nodes around literals but keeps the literals themselves, which
+// makes them the handle for spotting quoted code while folding
+fn quoted_literals(program: &Program) -> HashSet<*const ()> {
+    let mut quoted = HashSet::new();
+
+    modify_program(program, &mut |node: Rc<Expr>| {
+        let quote_call = match node.as_ref() {
+            Expr::Call(call_expr) => match call_expr.function.as_ref() {
+                Expr::Identifier(ident) if ident.value == "quote" => Some(call_expr),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(call_expr) = quote_call {
+            for arg in &call_expr.arguments {
+                modify_expression(arg.clone(), &mut |expr: Rc<Expr>| {
+                    if is_literal(&expr) {
+                        quoted.insert(Rc::as_ptr(&expr) as *const ());
+                    }
+                    expr
+                });
+            }
+        }
+        node
+    });
+
+    quoted
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Integer(_) | Expr::Boolean(_) | Expr::String(_))
+}
+
+fn constant(expr: &Rc<Expr>, quoted: &HashSet<*const ()>) -> Option<Constant> {
+    if quoted.contains(&(Rc::as_ptr(expr) as *const ())) {
+        return None;
+    }
+
+    match expr.as_ref() {
+        Expr::Integer(int_literal) => Some(Constant::Int(int_literal.value)),
+        Expr::Boolean(boolean) => Some(Constant::Bool(boolean.value)),
+        Expr::String(str_literal) => Some(Constant::Str(str_literal.value.clone())),
+        _ => None,
+    }
+}
+
+fn literal(constant: Constant, token: &Token) -> Rc<Expr> {
+    let token = |token_type: TokenType, literal: String| Token {
+        token_type,
+        literal,
+        line: token.line,
+        column: token.column,
+    };
+
+    match constant {
+        Constant::Int(value) => Rc::new(Expr::Integer(IntegerLiteral {
+            token: token(TokenType::INT, value.to_string()),
+            value,
+        })),
+        Constant::Bool(value) => Rc::new(Expr::Boolean(Boolean {
+            token: token(if value { TokenType::TRUE } else { TokenType::FALSE }, value.to_string()),
+            value,
+        })),
+        Constant::Str(value) => Rc::new(Expr::String(StringLiteral {
+            token: token(TokenType::STRING, value.clone()),
+            value,
+        })),
+    }
+}
+
+// only operations the evaluator completes without an error or a panic are folded,
+// everything else is left for the evaluator to report at runtime
+fn fold_infix(infix_expr: &InfixExpression, quoted: &HashSet<*const ()>) -> Option<Rc<Expr>> {
+    let left = constant(infix_expr.left.as_ref()?, quoted)?;
+    let right = constant(infix_expr.right.as_ref()?, quoted)?;
+
+    let folded = match (left, infix_expr.operator.as_str(), right) {
+        (Constant::Int(left), "+", Constant::Int(right)) => Constant::Int(left.checked_add(right)?),
+        (Constant::Int(left), "-", Constant::Int(right)) => Constant::Int(left.checked_sub(right)?),
+        (Constant::Int(left), "*", Constant::Int(right)) => Constant::Int(left.checked_mul(right)?),
+        (Constant::Int(left), "/", Constant::Int(right)) => Constant::Int(left.checked_div(right)?),
+        (Constant::Int(left), "<", Constant::Int(right)) => Constant::Bool(left < right),
+        (Constant::Int(left), ">", Constant::Int(right)) => Constant::Bool(left > right),
+        (Constant::Int(left), "==", Constant::Int(right)) => Constant::Bool(left == right),
+        (Constant::Int(left), "!=", Constant::Int(right)) => Constant::Bool(left != right),
+        (Constant::Bool(left), "==", Constant::Bool(right)) => Constant::Bool(left == right),
+        (Constant::Bool(left), "!=", Constant::Bool(right)) => Constant::Bool(left != right),
+        (Constant::Str(left), "+", Constant::Str(right)) => Constant::Str(left + &right),
+        (Constant::Str(left), "==", Constant::Str(right)) => Constant::Bool(left == right),
+        (Constant::Str(left), "!=", Constant::Str(right)) => Constant::Bool(left != right),
+        _ => return None,
+    };
+
+    Some(literal(folded, &infix_expr.token))
+}
+
+fn fold_prefix(prefix_expr: &PrefixExpression, quoted: &HashSet<*const ()>) -> Option<Rc<Expr>> {
+    let right = constant(&prefix_expr.right, quoted)?;
+
+    let folded = match (prefix_expr.operator.as_str(), right) {
+        ("-", Constant::Int(value)) => Constant::Int(value.checked_neg()?),
+        ("!", Constant::Bool(value)) => Constant::Bool(!value),
+        ("!", _) => Constant::Bool(false),
+        _ => return None,
+    };
+
+    Some(literal(folded, &prefix_expr.token))
+}
+
+// a branch holding a single expression replaces the whole if, otherwise the branch
+// that can't run is emptied
+fn prune_if(if_expr: &IfExpression, quoted: &HashSet<*const ()>) -> Option<Rc<Expr>> {
+    let condition = if_expr.condition.as_ref()?;
+
+    let truthy = match constant(condition, quoted)? {
+        Constant::Int(value) => value != 0,
+        Constant::Bool(value) => value,
+        Constant::Str(_) => return None,
+    };
+
+    let taken = if truthy {
+        &if_expr.consequence
+    } else {
+        &if_expr.alternative
+    };
+
+    if let Some(block) = taken {
+        if let [stmt] = block.statements.as_slice() {
+            if let Stmt::Expression(expr_stmt) = stmt.as_ref() {
+                if let Some(expr) = &expr_stmt.expression {
+                    return Some(expr.clone());
+                }
+            }
+        }
+    }
+
+    let (consequence, alternative) = if truthy {
+        (if_expr.consequence.as_ref().map(|block| copy_block(block, true)), None)
+    } else {
+        (
+            if_expr.consequence.as_ref().map(|block| copy_block(block, false)),
+            if_expr.alternative.as_ref().map(|block| copy_block(block, true)),
+        )
+    };
+
+    Some(Rc::new(Expr::If(IfExpression {
+        token: if_expr.token.clone(),
+        condition: Some(condition.clone()),
+        consequence,
+        alternative,
+    })))
+}
+
+fn copy_block(block: &BlockStatement, keep_statements: bool) -> BlockStatement {
+    BlockStatement {
+        token: block.token.clone(),
+        statements: if keep_statements {
+            block.statements.clone()
+        } else {
+            Vec::new()
+        },
+    }
+}
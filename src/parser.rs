@@ -1,11 +1,19 @@
-use std::{collections::HashMap, rc::Rc, sync::LazyLock};
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    rc::Rc,
+    sync::LazyLock,
+};
 
 use crate::{
     ast::{
-        ArrayLiteral, BlockStatement, Boolean, CallExpression, Expression, ExpressionStetement,
-        FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression,
-        IntegerLiteral, LetStatement, PrefixExpression, Program, ReturnStatement, Statement,
-        StringLiteral,
+        ArrayLiteral, AssignExpression, BlockStatement, Boolean, CallExpression, ConstStatement,
+        EnumStatement, EnumVariant, ExportStatement, Expr, ExpressionStetement,
+        FunctionLiteral, HashLiteral, Identifier, IfExpression, ImplMethod, ImplStatement,
+        ImportStatement, IndexExpression, InfixExpression, IntegerLiteral, LetStatement,
+        MacroLiteral, MatchArm, MatchExpression, MemberExpression, Node, Pattern, PrefixExpression,
+        Program, ReturnStatement, Stmt, StringLiteral, StructStatement, ThrowStatement,
+        TryExpression, TypeAnnotation, YieldStatement,
     },
     lexer::Lexer,
     token::{Token, TokenType},
@@ -13,18 +21,19 @@ use crate::{
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 enum Precedence {
-    LOWEST,
-    EQUALS,
-    LESSGREATER,
-    SUM,
-    PRODUCT,
-    PREFIX,
-    CALL,
-    INDEX,
+    Lowest,
+    Assign,
+    Equals,
+    LessGreater,
+    Sum,
+    Product,
+    Prefix,
+    Call,
+    Index,
 }
 
-type PrefixParseFn = fn(&mut Parser) -> Option<Rc<dyn Expression>>;
-type InfixParseFn = fn(&mut Parser, Option<Rc<dyn Expression>>) -> Option<Rc<dyn Expression>>;
+type PrefixParseFn = fn(&mut Parser) -> Option<Rc<Expr>>;
+type InfixParseFn = fn(&mut Parser, Option<Rc<Expr>>) -> Option<Rc<Expr>>;
 
 pub struct Parser {
     lexer: Lexer,
@@ -37,21 +46,32 @@ pub struct Parser {
     prefix_parse_fns: HashMap<String, PrefixParseFn>,
     infix_parse_fns: HashMap<String, InfixParseFn>,
 
-    precedences: LazyLock<HashMap<TokenType, Precedence>>,
+    precedences: &'static HashMap<TokenType, Precedence>,
+
+    const_scopes: Vec<HashSet<String>>,
+    block_depth: usize,
+    generator_scopes: Vec<bool>,
+    // generators only suspend at statements of their body and of the if and match
+    // statements in it, this names what the parser is in when it's somewhere else
+    no_yield: Option<&'static str>,
+    // set for an if or match that is a statement of its own
+    branch_statement: bool,
 }
 
-const PRECEDENCES: LazyLock<HashMap<TokenType, Precedence>> = LazyLock::new(|| {
+static PRECEDENCES: LazyLock<HashMap<TokenType, Precedence>> = LazyLock::new(|| {
     HashMap::from([
-        (TokenType::EQ, Precedence::EQUALS),
-        (TokenType::NotEQ, Precedence::EQUALS),
-        (TokenType::LT, Precedence::LESSGREATER),
-        (TokenType::GT, Precedence::LESSGREATER),
-        (TokenType::PLUS, Precedence::SUM),
-        (TokenType::MINUS, Precedence::SUM),
-        (TokenType::SLASH, Precedence::PRODUCT),
-        (TokenType::ASTERISK, Precedence::PRODUCT),
-        (TokenType::LPAREN, Precedence::CALL),
-        (TokenType::LBRACKET, Precedence::INDEX),
+        (TokenType::ASSIGN, Precedence::Assign),
+        (TokenType::EQ, Precedence::Equals),
+        (TokenType::NotEQ, Precedence::Equals),
+        (TokenType::LT, Precedence::LessGreater),
+        (TokenType::GT, Precedence::LessGreater),
+        (TokenType::PLUS, Precedence::Sum),
+        (TokenType::MINUS, Precedence::Sum),
+        (TokenType::SLASH, Precedence::Product),
+        (TokenType::ASTERISK, Precedence::Product),
+        (TokenType::LPAREN, Precedence::Call),
+        (TokenType::LBRACKET, Precedence::Index),
+        (TokenType::DOT, Precedence::Index),
     ])
 });
 
@@ -68,7 +88,12 @@ impl Parser {
             errors: Vec::new(),
             prefix_parse_fns: HashMap::new(),
             infix_parse_fns: HashMap::new(),
-            precedences: PRECEDENCES,
+            precedences: &PRECEDENCES,
+            const_scopes: vec![HashSet::new()],
+            block_depth: 0,
+            generator_scopes: Vec::new(),
+            no_yield: None,
+            branch_statement: false,
         };
 
         parser.register_prefix(TokenType::IDENT, Parser::parse_identifier);
@@ -83,6 +108,10 @@ impl Parser {
         parser.register_prefix(TokenType::FUNCTION, Parser::parse_function_literal);
         parser.register_prefix(TokenType::LBRACKET, Parser::parse_array_literal);
         parser.register_prefix(TokenType::LBRACE, Parser::parse_hash_literal);
+        parser.register_prefix(TokenType::MATCH, Parser::parse_match_expression);
+        parser.register_prefix(TokenType::MACRO, Parser::parse_macro_literal);
+        parser.register_prefix(TokenType::TRY, Parser::parse_try_expression);
+        parser.register_prefix(TokenType::YIELD, Parser::parse_yield_expression);
 
         parser.register_infix(TokenType::LBRACKET, Parser::parse_index_expression);
         parser.register_infix(TokenType::PLUS, Parser::parse_infix_expression);
@@ -94,6 +123,8 @@ impl Parser {
         parser.register_infix(TokenType::LT, Parser::parse_infix_expression);
         parser.register_infix(TokenType::GT, Parser::parse_infix_expression);
         parser.register_infix(TokenType::LPAREN, Parser::parse_call_expression);
+        parser.register_infix(TokenType::DOT, Parser::parse_member_expression);
+        parser.register_infix(TokenType::ASSIGN, Parser::parse_assign_expression);
 
         parser
     }
@@ -118,30 +149,41 @@ impl Parser {
         self.peek_token = self.lexer.next_token();
     }
 
-    fn parse_statement(&mut self) -> Option<Rc<dyn Statement>> {
+    fn parse_statement(&mut self) -> Option<Rc<Stmt>> {
         match self.cur_token.token_type {
             TokenType::LET => {
                 let stmt = self.parse_let_statement();
                 if stmt.is_some() {
-                    return Some(Rc::new(stmt.unwrap()));
+                    return Some(Rc::new(Stmt::Let(stmt.unwrap())));
                 }
                 return None;
             }
+            TokenType::FUNCTION if self.peek_token.token_type == TokenType::IDENT => {
+                Some(Rc::new(Stmt::Let(self.parse_function_declaration()?)))
+            }
+            TokenType::CONST => Some(Rc::new(Stmt::Const(self.parse_const_statement()?))),
+            TokenType::THROW => Some(Rc::new(Stmt::Throw(self.parse_throw_statement()?))),
+            TokenType::YIELD => Some(Rc::new(Stmt::Yield(self.parse_yield_statement()?))),
+            TokenType::STRUCT => Some(Rc::new(Stmt::Struct(self.parse_struct_statement()?))),
+            TokenType::ENUM => Some(Rc::new(Stmt::Enum(self.parse_enum_statement()?))),
+            TokenType::IMPL => Some(Rc::new(Stmt::Impl(self.parse_impl_statement()?))),
+            TokenType::IMPORT => Some(Rc::new(Stmt::Import(self.parse_import_statement()?))),
+            TokenType::EXPORT => Some(Rc::new(Stmt::Export(self.parse_export_statement()?))),
             TokenType::RETURN => {
                 let stmt = self.parse_return_statement();
                 if stmt.is_some() {
-                    return Some(Rc::new(stmt.unwrap()));
+                    return Some(Rc::new(Stmt::Return(stmt.unwrap())));
                 }
                 return None;
             }
-            _ => Some(Rc::new(self.parse_expression_statement())),
+            _ => Some(Rc::new(Stmt::Expression(self.parse_expression_statement()))),
         }
     }
 
-    fn parse_grouped_expression(&mut self) -> Option<Rc<dyn Expression>> {
+    fn parse_grouped_expression(&mut self) -> Option<Rc<Expr>> {
         self.next_token();
 
-        let expr = self.parse_expression(Precedence::LOWEST);
+        let expr = self.parse_expression(Precedence::Lowest);
 
         if !self.expect_peek(TokenType::RPAREN) {
             return None;
@@ -152,18 +194,18 @@ impl Parser {
 
     fn parse_call_expression(
         &mut self,
-        function: Option<Rc<dyn Expression>>,
-    ) -> Option<Rc<dyn Expression>> {
+        function: Option<Rc<Expr>>,
+    ) -> Option<Rc<Expr>> {
         let token = self.cur_token.clone();
-        let arguments = self.parse_expression_list(TokenType::RPAREN).unwrap();
-        Some(Rc::new(CallExpression {
+        let arguments = self.parse_expression_list(TokenType::RPAREN)?;
+        Some(Rc::new(Expr::Call(CallExpression {
             token,
             arguments,
             function: function?,
-        }))
+        })))
     }
 
-    fn parse_expression_list(&mut self, end: TokenType) -> Option<Vec<Rc<dyn Expression>>> {
+    fn parse_expression_list(&mut self, end: TokenType) -> Option<Vec<Rc<Expr>>> {
         let mut list = Vec::new();
 
         if self.peek_token_is(end.clone()) {
@@ -173,12 +215,12 @@ impl Parser {
 
         self.next_token();
 
-        list.push(self.parse_expression(Precedence::LOWEST).unwrap());
+        list.push(self.parse_expression(Precedence::Lowest)?);
 
         while self.peek_token_is(TokenType::COMMA) {
             self.next_token();
             self.next_token();
-            list.push(self.parse_expression(Precedence::LOWEST).unwrap());
+            list.push(self.parse_expression(Precedence::Lowest)?);
         }
 
         if !self.expect_peek(end) {
@@ -188,26 +230,57 @@ impl Parser {
         Some(list)
     }
 
-    fn parse_function_literal(&mut self) -> Option<Rc<dyn Expression>> {
+    fn parse_function_literal(&mut self) -> Option<Rc<Expr>> {
+        let token = self.cur_token.clone();
+
+        let generator = self.peek_token_is(TokenType::ASTERISK);
+        if generator {
+            self.next_token();
+        }
+
+        if !self.expect_peek(TokenType::LPAREN) {
+            return None;
+        }
+
+        let parameters = Rc::new(self.parse_function_parameters()?);
+        let return_type = self.parse_optional_return_type();
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+
+        let body = Rc::new(self.parse_function_body(generator));
+
+        Some(Rc::new(Expr::Function(FunctionLiteral {
+            token,
+            parameters,
+            body,
+            return_type,
+            generator,
+            captures: Default::default(),
+        })))
+    }
+
+    fn parse_macro_literal(&mut self) -> Option<Rc<Expr>> {
         let token = self.cur_token.clone();
 
         if !self.expect_peek(TokenType::LPAREN) {
             return None;
         }
 
-        let parameters = Rc::new(self.parse_function_parameters().unwrap());
+        let parameters = Rc::new(self.parse_function_parameters()?);
 
         if !self.expect_peek(TokenType::LBRACE) {
             return None;
         }
 
-        let body = Rc::new(self.parse_block_statement());
+        let body = Rc::new(self.parse_function_body(false));
 
-        Some(Rc::new(FunctionLiteral {
+        Some(Rc::new(Expr::Macro(MacroLiteral {
             token,
             parameters,
             body,
-        }))
+        })))
     }
 
     fn parse_function_parameters(&mut self) -> Option<Vec<Rc<Identifier>>> {
@@ -223,6 +296,8 @@ impl Parser {
         let ident = Rc::new(Identifier {
             token: self.cur_token.clone(),
             value: self.cur_token.literal.clone(),
+            type_annotation: self.parse_optional_annotation(),
+            slot: Default::default(),
         });
 
         identifiers.push(ident.into());
@@ -234,6 +309,8 @@ impl Parser {
             let ident = Rc::new(Identifier {
                 token: self.cur_token.clone(),
                 value: self.cur_token.literal.clone(),
+                type_annotation: self.parse_optional_annotation(),
+                slot: Default::default(),
             });
 
             identifiers.push(ident.into());
@@ -246,7 +323,97 @@ impl Parser {
         Some(identifiers)
     }
 
-    fn parse_expression(&mut self, precedence: Precedence) -> Option<Rc<dyn Expression>> {
+    // parses `: type` after the current token, if present
+    fn parse_optional_annotation(&mut self) -> Option<TypeAnnotation> {
+        if !self.peek_token_is(TokenType::COLON) {
+            return None;
+        }
+
+        self.next_token();
+        self.next_token();
+
+        self.parse_type_annotation()
+    }
+
+    // parses `-> type` after the current token, if present
+    fn parse_optional_return_type(&mut self) -> Option<TypeAnnotation> {
+        if !self.peek_token_is(TokenType::RARROW) {
+            return None;
+        }
+
+        self.next_token();
+        self.next_token();
+
+        self.parse_type_annotation()
+    }
+
+    fn parse_type_annotation(&mut self) -> Option<TypeAnnotation> {
+        match self.cur_token.token_type {
+            TokenType::IDENT => Some(match self.cur_token.literal.as_str() {
+                "int" => TypeAnnotation::Int,
+                "bool" => TypeAnnotation::Bool,
+                "string" => TypeAnnotation::String,
+                "null" => TypeAnnotation::Null,
+                "any" => TypeAnnotation::Any,
+                name => TypeAnnotation::Named(name.to_string()),
+            }),
+            TokenType::LBRACKET => {
+                self.next_token();
+                let element = self.parse_type_annotation()?;
+
+                if !self.expect_peek(TokenType::RBRACKET) {
+                    return None;
+                }
+
+                Some(TypeAnnotation::Array(Box::new(element)))
+            }
+            TokenType::LBRACE => {
+                self.next_token();
+                let key = self.parse_type_annotation()?;
+
+                if !self.expect_peek(TokenType::COLON) {
+                    return None;
+                }
+
+                self.next_token();
+                let value = self.parse_type_annotation()?;
+
+                if !self.expect_peek(TokenType::RBRACE) {
+                    return None;
+                }
+
+                Some(TypeAnnotation::Hash(Box::new(key), Box::new(value)))
+            }
+            TokenType::FUNCTION => {
+                if !self.expect_peek(TokenType::LPAREN) {
+                    return None;
+                }
+
+                let mut params = Vec::new();
+                while !self.peek_token_is(TokenType::RPAREN) {
+                    self.next_token();
+                    params.push(self.parse_type_annotation()?);
+
+                    if !self.peek_token_is(TokenType::RPAREN) && !self.expect_peek(TokenType::COMMA)
+                    {
+                        return None;
+                    }
+                }
+                self.next_token();
+
+                let ret = self.parse_optional_return_type().unwrap_or(TypeAnnotation::Any);
+
+                Some(TypeAnnotation::Function(params, Box::new(ret)))
+            }
+            _ => {
+                let msg = format!("expected type, got {} instead", self.cur_token.token_type);
+                self.errors.push(msg);
+                None
+            }
+        }
+    }
+
+    fn parse_expression(&mut self, precedence: Precedence) -> Option<Rc<Expr>> {
         let prefix = self
             .prefix_parse_fns
             .get(self.cur_token.token_type.as_str());
@@ -274,7 +441,12 @@ impl Parser {
         left_expr
     }
 
-    fn parse_if_expression(&mut self) -> Option<Rc<dyn Expression>> {
+    fn parse_if_expression(&mut self) -> Option<Rc<Expr>> {
+        let no_yield = self.branch_no_yield("an if expression used as a value");
+        self.with_no_yield(no_yield, Parser::parse_if)
+    }
+
+    fn parse_if(&mut self) -> Option<Rc<Expr>> {
         let token = self.cur_token.clone();
 
         if !self.expect_peek(TokenType::LPAREN) {
@@ -283,7 +455,7 @@ impl Parser {
 
         self.next_token();
 
-        let condition = self.parse_expression(Precedence::LOWEST);
+        let condition = self.parse_expression(Precedence::Lowest);
 
         if !self.expect_peek(TokenType::RPAREN) {
             return None;
@@ -306,18 +478,19 @@ impl Parser {
             alternative = Some(self.parse_block_statement());
         }
 
-        Some(Rc::new(IfExpression {
+        Some(Rc::new(Expr::If(IfExpression {
             token,
             consequence,
             condition,
             alternative,
-        }))
+        })))
     }
 
     fn parse_block_statement(&mut self) -> BlockStatement {
         let token = self.cur_token.clone();
-        let mut statements: Vec<Rc<dyn Statement>> = Vec::new();
+        let mut statements: Vec<Rc<Stmt>> = Vec::new();
 
+        self.block_depth += 1;
         self.next_token();
 
         while !self.cur_token_is(TokenType::RBRACE) && !self.cur_token_is(TokenType::EOF) {
@@ -327,29 +500,60 @@ impl Parser {
             }
             self.next_token();
         }
+        self.block_depth -= 1;
 
         BlockStatement { token, statements }
     }
 
-    fn parse_prefix_expression(&mut self) -> Option<Rc<dyn Expression>> {
+    fn parse_function_body(&mut self, generator: bool) -> BlockStatement {
+        self.generator_scopes.push(generator);
+        let body = self.with_no_yield(None, Parser::parse_scoped_block_statement);
+        self.generator_scopes.pop();
+        body
+    }
+
+    fn with_no_yield<T>(&mut self, no_yield: Option<&'static str>, parse: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = mem::replace(&mut self.no_yield, no_yield);
+        let parsed = parse(self);
+        self.no_yield = outer;
+        parsed
+    }
+
+    // the branches of an if or match used as a value can't suspend, those of one that
+    // is a statement can wherever the statement itself can
+    fn branch_no_yield(&mut self, as_value: &'static str) -> Option<&'static str> {
+        match mem::take(&mut self.branch_statement) {
+            true => self.no_yield,
+            false => self.no_yield.or(Some(as_value)),
+        }
+    }
+
+    fn parse_scoped_block_statement(&mut self) -> BlockStatement {
+        self.const_scopes.push(HashSet::new());
+        let block = self.parse_block_statement();
+        self.const_scopes.pop();
+        block
+    }
+
+    fn parse_prefix_expression(&mut self) -> Option<Rc<Expr>> {
         let token = self.cur_token.clone();
         let operator = self.cur_token.literal.clone();
 
         self.next_token();
 
-        let right = self.parse_expression(Precedence::PREFIX).unwrap();
+        let right = self.parse_expression(Precedence::Prefix).unwrap();
 
-        Some(Rc::new(PrefixExpression {
+        Some(Rc::new(Expr::Prefix(PrefixExpression {
             token,
             operator,
             right,
-        }))
+        })))
     }
 
     fn parse_infix_expression(
         &mut self,
-        left: Option<Rc<dyn Expression>>,
-    ) -> Option<Rc<dyn Expression>> {
+        left: Option<Rc<Expr>>,
+    ) -> Option<Rc<Expr>> {
         let token = self.cur_token.clone();
         let operator = self.cur_token.literal.clone();
 
@@ -359,29 +563,31 @@ impl Parser {
 
         let right = self.parse_expression(precedence);
 
-        Some(Rc::new(InfixExpression {
+        Some(Rc::new(Expr::Infix(InfixExpression {
             token,
             operator,
             left,
             right,
-        }))
+        })))
     }
 
-    fn parse_boolean(&mut self) -> Option<Rc<dyn Expression>> {
-        Some(Rc::new(Boolean {
+    fn parse_boolean(&mut self) -> Option<Rc<Expr>> {
+        Some(Rc::new(Expr::Boolean(Boolean {
             token: self.cur_token.clone(),
             value: self.cur_token_is(TokenType::TRUE),
-        }))
+        })))
     }
 
-    fn parse_identifier(&mut self) -> Option<Rc<dyn Expression>> {
-        Some(Rc::new(Identifier {
+    fn parse_identifier(&mut self) -> Option<Rc<Expr>> {
+        Some(Rc::new(Expr::Identifier(Identifier {
             token: self.cur_token.clone(),
             value: self.cur_token.literal.clone(),
-        }))
+            type_annotation: None,
+            slot: Default::default(),
+        })))
     }
 
-    fn parse_int_literal(&mut self) -> Option<Rc<dyn Expression>> {
+    fn parse_int_literal(&mut self) -> Option<Rc<Expr>> {
         let value = match self.cur_token.literal.parse::<i64>() {
             Ok(val) => val,
             Err(e) => {
@@ -390,17 +596,17 @@ impl Parser {
             }
         };
 
-        Some(Rc::new(IntegerLiteral {
+        Some(Rc::new(Expr::Integer(IntegerLiteral {
             token: self.cur_token.clone(),
             value,
-        }))
+        })))
     }
 
-    fn parse_str_literal(&mut self) -> Option<Rc<dyn Expression>> {
-        Some(Rc::new(StringLiteral {
+    fn parse_str_literal(&mut self) -> Option<Rc<Expr>> {
+        Some(Rc::new(Expr::String(StringLiteral {
             token: self.cur_token.clone(),
             value: self.cur_token.literal.clone(),
-        }))
+        })))
     }
 
     fn parse_let_statement(&mut self) -> Option<LetStatement> {
@@ -413,15 +619,19 @@ impl Parser {
         let name = Identifier {
             token: self.cur_token.clone(),
             value: self.cur_token.clone().literal,
+            type_annotation: self.parse_optional_annotation(),
+            slot: Default::default(),
         };
 
+        self.check_const_redefinition(&name);
+
         if !self.expect_peek(TokenType::ASSIGN) {
             return None;
         }
 
         self.next_token();
 
-        let value = self.parse_expression(Precedence::LOWEST);
+        let value = self.parse_expression(Precedence::Lowest);
 
         if !self.cur_token_is(TokenType::SEMICOLON) {
             self.next_token();
@@ -430,139 +640,961 @@ impl Parser {
         Some(LetStatement { token, value, name })
     }
 
-    fn parse_expression_statement(&mut self) -> ExpressionStetement {
-        let stmt = ExpressionStetement {
+    // `fn name(...) { ... }` is sugar for `let name = fn(...) { ... };`
+    fn parse_function_declaration(&mut self) -> Option<LetStatement> {
+        let fn_token = self.cur_token.clone();
+
+        self.next_token();
+
+        let name = Identifier {
             token: self.cur_token.clone(),
-            expression: self.parse_expression(Precedence::LOWEST),
+            value: self.cur_token.literal.clone(),
+            type_annotation: None,
+            slot: Default::default(),
         };
 
+        self.check_const_redefinition(&name);
+
+        if !self.expect_peek(TokenType::LPAREN) {
+            return None;
+        }
+
+        let parameters = Rc::new(self.parse_function_parameters()?);
+        let return_type = self.parse_optional_return_type();
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+
+        let body = Rc::new(self.parse_function_body(false));
+
         if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
         }
 
-        return stmt;
+        Some(LetStatement {
+            token: Token {
+                token_type: TokenType::LET,
+                literal: "let".to_string(),
+                line: fn_token.line,
+                column: fn_token.column,
+            },
+            name,
+            value: Some(Rc::new(Expr::Function(FunctionLiteral {
+                token: fn_token,
+                parameters,
+                body,
+                return_type,
+                generator: false,
+                captures: Default::default(),
+            }))),
+        })
     }
 
-    fn parse_return_statement(&mut self) -> Option<ReturnStatement> {
+    fn parse_const_statement(&mut self) -> Option<ConstStatement> {
         let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+
+        let name = Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+            type_annotation: self.parse_optional_annotation(),
+            slot: Default::default(),
+        };
+
+        self.check_const_redefinition(&name);
+        if let Some(scope) = self.const_scopes.last_mut() {
+            scope.insert(name.value.clone());
+        }
+
+        if !self.expect_peek(TokenType::ASSIGN) {
+            return None;
+        }
+
         self.next_token();
 
-        let return_value = self.parse_expression(Precedence::LOWEST);
+        let value = self.parse_expression(Precedence::Lowest);
 
         if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
         }
 
-        Some(ReturnStatement {
-            token,
-            return_value,
-        })
+        Some(ConstStatement { token, name, value })
     }
 
-    fn cur_token_is(&mut self, token_type: TokenType) -> bool {
-        self.cur_token.token_type == token_type
-    }
+    fn parse_throw_statement(&mut self) -> Option<ThrowStatement> {
+        let token = self.cur_token.clone();
+        self.next_token();
 
-    fn peek_token_is(&mut self, token_type: TokenType) -> bool {
-        self.peek_token.token_type == token_type
-    }
+        let value = self.parse_expression(Precedence::Lowest);
 
-    fn expect_peek(&mut self, token_type: TokenType) -> bool {
-        if self.peek_token_is(token_type.clone()) {
+        if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
-            return true;
         }
 
-        self.peek_errors(token_type);
-        false
+        Some(ThrowStatement { token, value })
     }
 
-    pub fn get_errors(&self) -> Vec<String> {
-        self.errors.clone()
-    }
+    fn parse_yield_statement(&mut self) -> Option<YieldStatement> {
+        let token = self.cur_token.clone();
 
-    pub fn peek_errors(&mut self, token: TokenType) {
-        let msg = format!(
-            "expected next token to be {}, got {} instead",
-            token, self.peek_token.token_type
-        );
-        self.errors.push(msg);
-    }
+        if self.generator_scopes.last() != Some(&true) {
+            self.errors
+                .push("yield is only allowed inside a generator function".to_string());
+            return None;
+        }
 
-    pub fn register_prefix(&mut self, token_type: TokenType, prefix_parse_fn: PrefixParseFn) {
-        self.prefix_parse_fns
-            .insert(token_type.to_string(), prefix_parse_fn);
-    }
+        if let Some(construct) = self.no_yield {
+            self.errors.push(format!("yield is not supported inside {}", construct));
+            return None;
+        }
 
-    pub fn register_infix(&mut self, token_type: TokenType, infix_parse_fn: InfixParseFn) {
-        self.infix_parse_fns
-            .insert(token_type.to_string(), infix_parse_fn);
-    }
+        let delegate = self.peek_token_is(TokenType::ASTERISK);
+        if delegate {
+            self.next_token();
+        }
 
-    fn no_prefix_parse_fn_error(&mut self, token_type: TokenType) {
-        let msg = format!("no prefix parse function for {} found", token_type);
-        self.errors.push(msg);
-    }
+        self.next_token();
 
-    fn peek_precedence(&self) -> Precedence {
-        match self.precedences.get(&self.peek_token.token_type) {
-            None => Precedence::LOWEST,
-            Some(val) => val.clone(),
+        let value = self.parse_expression(Precedence::Lowest);
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
         }
+
+        Some(YieldStatement {
+            token,
+            value,
+            delegate,
+        })
     }
 
-    fn cur_precedence(&self) -> Precedence {
-        match self.precedences.get(&self.cur_token.token_type) {
-            None => Precedence::LOWEST,
-            Some(val) => val.clone(),
+    // like `let x = yield 1`, which would need a value sent back into the generator
+    fn parse_yield_expression(&mut self) -> Option<Rc<Expr>> {
+        self.errors.push("yield is a statement and can't be used as a value".to_string());
+        if self.peek_token_is(TokenType::ASTERISK) {
+            self.next_token();
         }
+        self.next_token();
+        self.parse_expression(Precedence::Lowest);
+        None
     }
 
-    fn parse_array_literal(&mut self) -> Option<Rc<dyn Expression>> {
-        Some(Rc::new(ArrayLiteral {
-            token: self.cur_token.clone(),
-            elements: self.parse_expression_list(TokenType::RBRACKET).unwrap(),
-        }))
+    fn parse_try_expression(&mut self) -> Option<Rc<Expr>> {
+        let no_yield = self.no_yield.or(Some("a try expression"));
+        self.with_no_yield(no_yield, Parser::parse_try)
     }
 
-    fn parse_index_expression(
-        &mut self,
-        left: Option<Rc<dyn Expression>>,
-    ) -> Option<Rc<dyn Expression>> {
+    fn parse_try(&mut self) -> Option<Rc<Expr>> {
         let token = self.cur_token.clone();
 
-        self.next_token();
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
 
-        let index = self.parse_expression(Precedence::LOWEST).unwrap();
+        let block = self.parse_block_statement();
 
-        if !self.expect_peek(TokenType::RBRACKET) {
+        let mut catch_param = None;
+        let mut catch_block = None;
+        let mut finally_block = None;
+
+        if self.peek_token_is(TokenType::CATCH) {
+            self.next_token();
+
+            if self.peek_token_is(TokenType::LPAREN) {
+                self.next_token();
+
+                if !self.expect_peek(TokenType::IDENT) {
+                    return None;
+                }
+
+                catch_param = Some(Identifier {
+                    token: self.cur_token.clone(),
+                    value: self.cur_token.literal.clone(),
+                    type_annotation: None,
+                    slot: Default::default(),
+                });
+
+                if !self.expect_peek(TokenType::RPAREN) {
+                    return None;
+                }
+            }
+
+            if !self.expect_peek(TokenType::LBRACE) {
+                return None;
+            }
+
+            catch_block = Some(self.parse_scoped_block_statement());
+        }
+
+        if self.peek_token_is(TokenType::FINALLY) {
+            self.next_token();
+
+            if !self.expect_peek(TokenType::LBRACE) {
+                return None;
+            }
+
+            finally_block = Some(self.parse_block_statement());
+        }
+
+        if catch_block.is_none() && finally_block.is_none() {
+            self.errors
+                .push("expected catch or finally after try block".to_string());
             return None;
         }
 
-        Some(Rc::new(IndexExpression {
+        Some(Rc::new(Expr::Try(TryExpression {
             token,
-            index,
-            left: left?,
-        }))
+            block,
+            catch_param,
+            catch_block,
+            finally_block,
+        })))
     }
 
-    fn parse_hash_literal(&mut self) -> Option<Rc<dyn Expression>> {
+    fn parse_struct_statement(&mut self) -> Option<StructStatement> {
         let token = self.cur_token.clone();
-        let mut pairs: HashMap<Rc<dyn Expression>, Rc<dyn Expression>> = HashMap::new();
+
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+
+        let name = Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+            type_annotation: None,
+            slot: Default::default(),
+        };
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+
+        let mut fields: Vec<Identifier> = Vec::new();
 
         while !self.peek_token_is(TokenType::RBRACE) {
-            self.next_token();
-            let key = self.parse_expression(Precedence::LOWEST);
+            if !self.expect_peek(TokenType::IDENT) {
+                return None;
+            }
 
-            if !self.expect_peek(TokenType::COLON) {
+            let field = Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+                type_annotation: None,
+                slot: Default::default(),
+            };
+
+            if fields.iter().any(|existing| existing.value == field.value) {
+                self.errors.push(format!(
+                    "duplicate field {} in struct {}",
+                    field.value, name.value
+                ));
+            }
+            fields.push(field);
+
+            if !self.peek_token_is(TokenType::RBRACE) && !self.expect_peek(TokenType::COMMA) {
                 return None;
             }
+        }
 
+        self.next_token();
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
+        }
+
+        Some(StructStatement {
+            token,
+            name,
+            fields,
+        })
+    }
+
+    fn parse_enum_statement(&mut self) -> Option<EnumStatement> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+
+        let name = Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+            type_annotation: None,
+            slot: Default::default(),
+        };
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+
+        let mut variants: Vec<EnumVariant> = Vec::new();
 
-            let value = self.parse_expression(Precedence::LOWEST);
+        while !self.peek_token_is(TokenType::RBRACE) {
+            if !self.expect_peek(TokenType::IDENT) {
+                return None;
+            }
+
+            let variant_name = Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+                type_annotation: None,
+                slot: Default::default(),
+            };
+
+            if variants
+                .iter()
+                .any(|existing| existing.name.value == variant_name.value)
+            {
+                self.errors.push(format!(
+                    "duplicate variant {} in enum {}",
+                    variant_name.value, name.value
+                ));
+            }
+
+            let mut fields = Vec::new();
+            if self.peek_token_is(TokenType::LPAREN) {
+                self.next_token();
+                fields = self
+                    .parse_function_parameters()?
+                    .iter()
+                    .map(|field| field.as_ref().clone())
+                    .collect();
+            }
+
+            variants.push(EnumVariant {
+                name: variant_name,
+                fields,
+            });
+
+            if !self.peek_token_is(TokenType::RBRACE) && !self.expect_peek(TokenType::COMMA) {
+                return None;
+            }
+        }
+
+        self.next_token();
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(EnumStatement {
+            token,
+            name,
+            variants,
+        })
+    }
+
+    fn parse_impl_statement(&mut self) -> Option<ImplStatement> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+
+        let type_name = Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+            type_annotation: None,
+            slot: Default::default(),
+        };
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+
+        let mut methods: Vec<ImplMethod> = Vec::new();
+
+        while !self.peek_token_is(TokenType::RBRACE) {
+            if !self.expect_peek(TokenType::FUNCTION) {
+                return None;
+            }
+
+            let fn_token = self.cur_token.clone();
+
+            let generator = self.peek_token_is(TokenType::ASTERISK);
+            if generator {
+                self.next_token();
+            }
+
+            if !self.expect_peek(TokenType::IDENT) {
+                return None;
+            }
+
+            let name = Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+                type_annotation: None,
+                slot: Default::default(),
+            };
+
+            if methods.iter().any(|method| method.name.value == name.value) {
+                self.errors.push(format!(
+                    "duplicate method {} in impl {}",
+                    name.value, type_name.value
+                ));
+            }
+
+            if !self.expect_peek(TokenType::LPAREN) {
+                return None;
+            }
+
+            let parameters = Rc::new(self.parse_function_parameters()?);
+            let return_type = self.parse_optional_return_type();
+
+            if !self.expect_peek(TokenType::LBRACE) {
+                return None;
+            }
+
+            let body = Rc::new(self.parse_function_body(generator));
+
+            methods.push(ImplMethod {
+                name,
+                function: FunctionLiteral {
+                    token: fn_token,
+                    parameters,
+                    body,
+                    return_type,
+                    generator,
+                    captures: Default::default(),
+                },
+            });
+        }
+
+        self.next_token();
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(ImplStatement {
+            token,
+            type_name,
+            methods,
+        })
+    }
+
+    fn parse_import_statement(&mut self) -> Option<ImportStatement> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::STRING) {
+            return None;
+        }
+
+        let path = self.cur_token.literal.clone();
+
+        if !self.expect_peek(TokenType::AS) {
+            return None;
+        }
+
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+
+        let alias = Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+            type_annotation: None,
+            slot: Default::default(),
+        };
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(ImportStatement { token, path, alias })
+    }
+
+    fn parse_export_statement(&mut self) -> Option<ExportStatement> {
+        let token = self.cur_token.clone();
+
+        if self.block_depth > 0 {
+            self.errors
+                .push("export is only allowed at the top level of a module".to_string());
+            return None;
+        }
+
+        self.next_token();
+
+        let (name, statement): (Identifier, Rc<Stmt>) = match self.cur_token.token_type {
+            TokenType::LET => {
+                let stmt = self.parse_let_statement()?;
+                (stmt.name.clone(), Rc::new(Stmt::Let(stmt)))
+            }
+            TokenType::CONST => {
+                let stmt = self.parse_const_statement()?;
+                (stmt.name.clone(), Rc::new(Stmt::Const(stmt)))
+            }
+            _ => {
+                let msg = format!(
+                    "expected LET or CONST after export, got {} instead",
+                    self.cur_token.token_type
+                );
+                self.errors.push(msg);
+                return None;
+            }
+        };
+
+        Some(ExportStatement {
+            token,
+            name,
+            statement,
+        })
+    }
+
+    fn check_const_redefinition(&mut self, name: &Identifier) {
+        let is_const = self
+            .const_scopes
+            .last()
+            .is_some_and(|scope| scope.contains(&name.value));
+
+        if is_const {
+            let msg = format!("cannot redefine constant: {}", name.value);
+            self.errors.push(msg);
+        }
+    }
+
+    fn parse_expression_statement(&mut self) -> ExpressionStetement {
+        self.branch_statement = matches!(self.cur_token.token_type, TokenType::IF | TokenType::MATCH);
+        let stmt = ExpressionStetement {
+            token: self.cur_token.clone(),
+            expression: self.parse_expression(Precedence::Lowest),
+        };
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+
+        return stmt;
+    }
+
+    fn parse_return_statement(&mut self) -> Option<ReturnStatement> {
+        let token = self.cur_token.clone();
+        self.next_token();
+
+        let return_value = self.parse_expression(Precedence::Lowest);
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(ReturnStatement {
+            token,
+            return_value,
+        })
+    }
+
+    fn cur_token_is(&mut self, token_type: TokenType) -> bool {
+        self.cur_token.token_type == token_type
+    }
+
+    fn peek_token_is(&mut self, token_type: TokenType) -> bool {
+        self.peek_token.token_type == token_type
+    }
+
+    fn expect_peek(&mut self, token_type: TokenType) -> bool {
+        if self.peek_token_is(token_type.clone()) {
+            self.next_token();
+            return true;
+        }
+
+        self.peek_errors(token_type);
+        false
+    }
+
+    pub fn get_errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+
+    pub fn peek_errors(&mut self, token: TokenType) {
+        let msg = format!(
+            "expected next token to be {}, got {} instead",
+            token, self.peek_token.token_type
+        );
+        self.errors.push(msg);
+    }
+
+    pub fn register_prefix(&mut self, token_type: TokenType, prefix_parse_fn: PrefixParseFn) {
+        self.prefix_parse_fns
+            .insert(token_type.to_string(), prefix_parse_fn);
+    }
+
+    pub fn register_infix(&mut self, token_type: TokenType, infix_parse_fn: InfixParseFn) {
+        self.infix_parse_fns
+            .insert(token_type.to_string(), infix_parse_fn);
+    }
+
+    fn no_prefix_parse_fn_error(&mut self, token_type: TokenType) {
+        let msg = format!("no prefix parse function for {} found", token_type);
+        self.errors.push(msg);
+    }
+
+    fn peek_precedence(&self) -> Precedence {
+        match self.precedences.get(&self.peek_token.token_type) {
+            None => Precedence::Lowest,
+            Some(val) => val.clone(),
+        }
+    }
+
+    fn cur_precedence(&self) -> Precedence {
+        match self.precedences.get(&self.cur_token.token_type) {
+            None => Precedence::Lowest,
+            Some(val) => val.clone(),
+        }
+    }
+
+    fn parse_array_literal(&mut self) -> Option<Rc<Expr>> {
+        Some(Rc::new(Expr::Array(ArrayLiteral {
+            token: self.cur_token.clone(),
+            elements: self.parse_expression_list(TokenType::RBRACKET).unwrap(),
+        })))
+    }
+
+    fn parse_index_expression(
+        &mut self,
+        left: Option<Rc<Expr>>,
+    ) -> Option<Rc<Expr>> {
+        let token = self.cur_token.clone();
+
+        self.next_token();
+
+        let index = self.parse_expression(Precedence::Lowest).unwrap();
+
+        if !self.expect_peek(TokenType::RBRACKET) {
+            return None;
+        }
+
+        Some(Rc::new(Expr::Index(IndexExpression {
+            token,
+            index,
+            left: left?,
+        })))
+    }
+
+    fn parse_member_expression(
+        &mut self,
+        object: Option<Rc<Expr>>,
+    ) -> Option<Rc<Expr>> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+
+        let property = Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+            type_annotation: None,
+            slot: Default::default(),
+        };
+
+        Some(Rc::new(Expr::Member(MemberExpression {
+            token,
+            object: object?,
+            property,
+        })))
+    }
+
+    fn parse_assign_expression(
+        &mut self,
+        target: Option<Rc<Expr>>,
+    ) -> Option<Rc<Expr>> {
+        let token = self.cur_token.clone();
+        let target = target?;
+
+        if !matches!(target.as_ref(), Expr::Member(_)) {
+            self.errors
+                .push(format!("invalid assignment target: {}", target.to_string()));
+            return None;
+        }
+
+        self.next_token();
+
+        // parsing the value at the lowest precedence makes `a.x = b.y = 1` right-associative
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        Some(Rc::new(Expr::Assign(AssignExpression {
+            token,
+            target,
+            value,
+        })))
+    }
+
+    fn parse_hash_literal(&mut self) -> Option<Rc<Expr>> {
+        let token = self.cur_token.clone();
+        let mut pairs = Vec::new();
+
+        while !self.peek_token_is(TokenType::RBRACE) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::Lowest);
+
+            if !self.expect_peek(TokenType::COLON) {
+                return None;
+            }
+
+            self.next_token();
+
+            let value = self.parse_expression(Precedence::Lowest);
+
+            pairs.push((key.unwrap(), value.unwrap()));
+
+            if !self.peek_token_is(TokenType::RBRACE) && !self.expect_peek(TokenType::COMMA) {
+                return None;
+            }
+        }
+
+        if !self.expect_peek(TokenType::RBRACE) {
+            return None;
+        }
+
+        Some(Rc::new(Expr::Hash(HashLiteral { token, pairs })))
+    }
+
+    fn parse_match_expression(&mut self) -> Option<Rc<Expr>> {
+        let no_yield = self.branch_no_yield("a match expression used as a value");
+        self.with_no_yield(no_yield, Parser::parse_match)
+    }
+
+    fn parse_match(&mut self) -> Option<Rc<Expr>> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::LPAREN) {
+            return None;
+        }
+
+        self.next_token();
+
+        let subject = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_peek(TokenType::RPAREN) {
+            return None;
+        }
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+
+        let mut arms = Vec::new();
+
+        while !self.peek_token_is(TokenType::RBRACE) {
+            self.next_token();
+            arms.push(self.parse_match_arm()?);
+
+            if !self.peek_token_is(TokenType::RBRACE) && !self.expect_peek(TokenType::COMMA) {
+                return None;
+            }
+        }
+
+        if !self.expect_peek(TokenType::RBRACE) {
+            return None;
+        }
+
+        Some(Rc::new(Expr::Match(MatchExpression {
+            token,
+            subject,
+            arms,
+        })))
+    }
+
+    fn parse_match_arm(&mut self) -> Option<MatchArm> {
+        let mut alternatives = vec![self.parse_pattern()?];
+
+        while self.peek_token_is(TokenType::PIPE) {
+            self.next_token();
+            self.next_token();
+            alternatives.push(self.parse_pattern()?);
+        }
+
+        let pattern = if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Pattern::Or(alternatives)
+        };
+
+        self.const_scopes.push(HashSet::new());
+        let arm = self.parse_match_arm_body(pattern);
+        self.const_scopes.pop();
+        arm
+    }
+
+    fn parse_match_arm_body(&mut self, pattern: Pattern) -> Option<MatchArm> {
+        let mut guard = None;
+
+        if self.peek_token_is(TokenType::IF) {
+            self.next_token();
+            self.next_token();
+            guard = Some(self.parse_expression(Precedence::Lowest)?);
+        }
+
+        if !self.expect_peek(TokenType::ARROW) {
+            return None;
+        }
+
+        self.next_token();
+
+        let body = if self.cur_token_is(TokenType::LBRACE) {
+            self.parse_block_statement()
+        } else {
+            let token = self.cur_token.clone();
+            let expression = self.parse_expression(Precedence::Lowest);
+            BlockStatement {
+                token: token.clone(),
+                statements: vec![Rc::new(Stmt::Expression(ExpressionStetement { token, expression }))],
+            }
+        };
+
+        Some(MatchArm {
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    fn parse_pattern(&mut self) -> Option<Pattern> {
+        match self.cur_token.token_type.clone() {
+            TokenType::IDENT if self.cur_token.literal == "_" => {
+                Some(Pattern::Wildcard(self.cur_token.clone()))
+            }
+            // a bare name is a binding, match_pattern decides whether it names a unit variant
+            TokenType::IDENT
+                if self.peek_token_is(TokenType::LPAREN) || self.peek_token_is(TokenType::DOT) =>
+            {
+                self.parse_variant_pattern()
+            }
+            TokenType::IDENT => Some(Pattern::Binding(Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+                type_annotation: None,
+                slot: Default::default(),
+            })),
+            TokenType::INT
+            | TokenType::STRING
+            | TokenType::TRUE
+            | TokenType::FALSE
+            | TokenType::MINUS => Some(Pattern::Literal(
+                self.parse_expression(Precedence::Prefix)?,
+            )),
+            TokenType::LBRACKET => self.parse_array_pattern(),
+            TokenType::LBRACE => self.parse_hash_pattern(),
+            _ => {
+                let msg = format!(
+                    "unexpected token in pattern: {}",
+                    self.cur_token.token_type
+                );
+                self.errors.push(msg);
+                None
+            }
+        }
+    }
+
+    fn parse_variant_pattern(&mut self) -> Option<Pattern> {
+        let token = self.cur_token.clone();
+        let mut enum_name = None;
+        let mut name = Identifier {
+            token: self.cur_token.clone(),
+            value: self.cur_token.literal.clone(),
+            type_annotation: None,
+            slot: Default::default(),
+        };
+
+        if self.peek_token_is(TokenType::DOT) {
+            self.next_token();
+
+            if !self.expect_peek(TokenType::IDENT) {
+                return None;
+            }
+
+            enum_name = Some(name);
+            name = Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+                type_annotation: None,
+                slot: Default::default(),
+            };
+        }
+
+        let mut fields = Vec::new();
+
+        if self.peek_token_is(TokenType::LPAREN) {
+            self.next_token();
+
+            while !self.peek_token_is(TokenType::RPAREN) {
+                self.next_token();
+                fields.push(self.parse_pattern()?);
+
+                if !self.peek_token_is(TokenType::RPAREN) && !self.expect_peek(TokenType::COMMA) {
+                    return None;
+                }
+            }
+
+            if !self.expect_peek(TokenType::RPAREN) {
+                return None;
+            }
+        }
+
+        Some(Pattern::Variant {
+            token,
+            enum_name,
+            name,
+            fields,
+        })
+    }
+
+    fn parse_array_pattern(&mut self) -> Option<Pattern> {
+        let token = self.cur_token.clone();
+        let mut elements = Vec::new();
+        let mut rest = None;
+
+        while !self.peek_token_is(TokenType::RBRACKET) {
+            self.next_token();
+
+            if self.cur_token_is(TokenType::ELLIPSIS) {
+                let rest_pattern = if self.peek_token_is(TokenType::IDENT) {
+                    self.next_token();
+                    self.parse_pattern()?
+                } else {
+                    Pattern::Wildcard(self.cur_token.clone())
+                };
+                rest = Some(Box::new(rest_pattern));
+                break;
+            }
+
+            elements.push(self.parse_pattern()?);
+
+            if !self.peek_token_is(TokenType::RBRACKET) && !self.expect_peek(TokenType::COMMA) {
+                return None;
+            }
+        }
+
+        if !self.expect_peek(TokenType::RBRACKET) {
+            return None;
+        }
+
+        Some(Pattern::Array {
+            token,
+            elements,
+            rest,
+        })
+    }
+
+    fn parse_hash_pattern(&mut self) -> Option<Pattern> {
+        let token = self.cur_token.clone();
+        let mut pairs = Vec::new();
+
+        while !self.peek_token_is(TokenType::RBRACE) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::Lowest)?;
+
+            if !self.expect_peek(TokenType::COLON) {
+                return None;
+            }
+
+            self.next_token();
 
-            pairs.insert(key.unwrap(), value.unwrap());
+            pairs.push((key, self.parse_pattern()?));
 
             if !self.peek_token_is(TokenType::RBRACE) && !self.expect_peek(TokenType::COMMA) {
                 return None;
@@ -573,6 +1605,6 @@ impl Parser {
             return None;
         }
 
-        Some(Rc::new(HashLiteral { token, pairs }))
+        Some(Pattern::Hash { token, pairs })
     }
 }
@@ -2,14 +2,151 @@ use std::{
     cell::RefCell,
     io::{self, BufRead, Write},
     rc::Rc,
+    str::FromStr,
 };
 
-use crate::{ast::Node, evaluator::eval, lexer::Lexer, object::Environment, parser::Parser};
+use crate::{
+    ast::Program,
+    compiler::Compiler,
+    evaluator::{define_macros, eval, expand_macros},
+    lexer::Lexer,
+    object::{Environment, InterruptHandle, ModuleCache, Value},
+    optimizer::optimize,
+    parser::Parser,
+    resolver::resolve,
+    typecheck::typecheck,
+    vm::Vm,
+};
 
 const PROMT: &'static str = ">>";
 
-pub fn start() {
-    let env = Rc::new(RefCell::new(Environment::new()));
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Engine {
+    Eval,
+    Vm,
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "eval" => Ok(Engine::Eval),
+            "vm" => Ok(Engine::Vm),
+            _ => Err(format!("unknown engine: {} (expected eval or vm)", name)),
+        }
+    }
+}
+
+// what a session keeps from one line to the next
+pub enum Session {
+    Eval(Rc<RefCell<Environment>>),
+    Vm { compiler: Compiler, vm: Vm },
+}
+
+impl Session {
+    pub fn new(engine: Engine) -> Self {
+        match engine {
+            Engine::Eval => Session::Eval(Rc::new(RefCell::new(Environment::new()))),
+            Engine::Vm => Session::Vm {
+                compiler: Compiler::new(),
+                vm: Vm::new(),
+            },
+        }
+    }
+
+    pub fn globals(&self) -> Vec<String> {
+        match self {
+            Session::Eval(env) => env.borrow().names(),
+            Session::Vm { compiler, .. } => compiler.global_names(),
+        }
+    }
+
+    pub fn modules(&self) -> Rc<RefCell<ModuleCache>> {
+        match self {
+            Session::Eval(env) => env.borrow().modules(),
+            Session::Vm { vm, .. } => vm.modules().clone(),
+        }
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        match self {
+            Session::Eval(env) => env.borrow().limits().interrupt_handle(),
+            Session::Vm { vm, .. } => vm.limits().interrupt_handle(),
+        }
+    }
+
+    pub fn run(&mut self, program: &Program) -> Result<Option<Value>, String> {
+        match self {
+            Session::Eval(env) => Ok(eval(program, env.clone())),
+            Session::Vm { compiler, vm } => Ok(vm.run(compiler.compile(program)?)),
+        }
+    }
+
+    // the evaluator doesn't track lines
+    pub fn error_line(&self) -> Option<usize> {
+        match self {
+            Session::Eval(_) => None,
+            Session::Vm { vm, .. } => vm.error_line(),
+        }
+    }
+}
+
+// the checks a program goes through before it runs, whichever engine runs it
+pub enum ProgramErrors {
+    Parse(Vec<String>),
+    Macro(Vec<String>),
+    Name(Vec<String>),
+    Type(Vec<String>),
+}
+
+impl ProgramErrors {
+    pub fn print(self) {
+        match self {
+            ProgramErrors::Parse(errors) => print_parse_errors(errors),
+            ProgramErrors::Macro(errors) => print_macro_errors(errors),
+            ProgramErrors::Name(errors) => print_name_errors(errors),
+            ProgramErrors::Type(errors) => print_type_errors(errors),
+        }
+    }
+}
+
+// `globals` are the names bound by earlier programs of the same session
+pub fn prepare(
+    source: String,
+    macro_env: Rc<RefCell<Environment>>,
+    globals: Vec<String>,
+) -> Result<Program, ProgramErrors> {
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let mut program = parser.parse_program();
+
+    let errors = parser.get_errors();
+    if !errors.is_empty() {
+        return Err(ProgramErrors::Parse(errors));
+    }
+
+    define_macros(&mut program, macro_env.clone());
+    let expanded = expand_macros(&program, macro_env).map_err(ProgramErrors::Macro)?;
+
+    let errors = resolve(&expanded, globals.clone());
+    if !errors.is_empty() {
+        return Err(ProgramErrors::Name(errors));
+    }
+
+    let errors = typecheck(&expanded, &globals);
+    if !errors.is_empty() {
+        return Err(ProgramErrors::Type(errors));
+    }
+
+    Ok(optimize(&expanded))
+}
+
+pub fn start(engine: Engine) {
+    let mut session = Session::new(engine);
+    let macro_env = Rc::new(RefCell::new(Environment::new()));
+    interrupt_on_ctrl_c(session.interrupt_handle());
+
     loop {
         print!("{} ", PROMT);
         let _ = io::stdout().flush();
@@ -17,28 +154,93 @@ pub fn start() {
         let mut line = String::new();
         let stdin = io::stdin();
         let mut handle = stdin.lock();
-        handle.read_line(&mut line).unwrap();
-
-        let lexer = Lexer::new(line);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
-
-        let errors = parser.get_errors();
-        if errors.len() > 0 {
-            print_parse_errors(errors);
-            continue;
+        // ctrl-c no longer quits, so end of input has to
+        if handle.read_line(&mut line).unwrap() == 0 {
+            println!();
+            return;
         }
 
-        let evaluated = eval(program.as_node(), env.clone());
+        let program = match prepare(line, macro_env.clone(), session.globals()) {
+            Ok(program) => program,
+            Err(errors) => {
+                errors.print();
+                continue;
+            }
+        };
+
+        let evaluated = match session.run(&program) {
+            Ok(evaluated) => evaluated,
+            Err(msg) => {
+                print_compile_errors(vec![msg]);
+                continue;
+            }
+        };
         if evaluated.is_some() {
             println!("{}", evaluated.unwrap().inspect())
         }
     }
 }
 
+// ctrl-c cancels the line being evaluated and leaves the repl running. The handler only
+// sets the interrupt flag, which is all a signal handler can safely do
+#[cfg(unix)]
+fn interrupt_on_ctrl_c(handle: InterruptHandle) {
+    use std::{os::raw::c_int, sync::OnceLock};
+
+    const SIGINT: c_int = 2;
+    static INTERRUPT: OnceLock<InterruptHandle> = OnceLock::new();
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    }
+
+    extern "C" fn on_sigint(_: c_int) {
+        if let Some(handle) = INTERRUPT.get() {
+            handle.interrupt();
+        }
+    }
+
+    if INTERRUPT.set(handle).is_ok() {
+        unsafe {
+            signal(SIGINT, on_sigint);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn interrupt_on_ctrl_c(_: InterruptHandle) {}
+
 fn print_parse_errors(errors: Vec<String>) {
     for msg in errors {
         println!("parser errors:");
         println!("\t{}", msg)
     }
 }
+
+fn print_macro_errors(errors: Vec<String>) {
+    for msg in errors {
+        println!("macro expansion errors:");
+        println!("\t{}", msg)
+    }
+}
+
+fn print_name_errors(errors: Vec<String>) {
+    for msg in errors {
+        println!("name errors:");
+        println!("\t{}", msg)
+    }
+}
+
+fn print_type_errors(errors: Vec<String>) {
+    for msg in errors {
+        println!("type errors:");
+        println!("\t{}", msg)
+    }
+}
+
+pub fn print_compile_errors(errors: Vec<String>) {
+    for msg in errors {
+        println!("compile errors:");
+        println!("\t{}", msg)
+    }
+}
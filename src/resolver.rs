@@ -0,0 +1,402 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    ast::{BlockStatement, Captures, Expr, FunctionLiteral, Identifier, Pattern, Program, Slot, Stmt},
+    evaluator::is_builtin,
+};
+
+// a scope mirrors one runtime environment. Code running in the scope only sees names
+// declared before it, while function bodies run later and see everything their
+// enclosing scopes declare, including names bound after the function was created.
+// Slots follow the order the environment binds names in when every let runs
+pub(crate) struct Scope {
+    pub(crate) declared: HashSet<String>,
+    pub(crate) slots: HashMap<String, usize>,
+    pub(crate) function: bool,
+}
+
+impl Scope {
+    pub(crate) fn new(bound: Vec<String>, statements: &[Rc<Stmt>], function: bool) -> Self {
+        let mut slots = HashMap::new();
+        for name in &bound {
+            add_slot(&mut slots, name);
+        }
+        collect_declarations(statements, &mut slots);
+
+        Scope {
+            declared: bound.into_iter().collect(),
+            slots,
+            function,
+        }
+    }
+}
+
+// `globals` are the names already bound in the environment the program runs in, in slot order
+pub fn resolve(program: &Program, globals: Vec<String>) -> Vec<String> {
+    let mut resolver = Resolver {
+        scopes: vec![Scope::new(globals, &program.statements, true)],
+        functions: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for stmt in &program.statements {
+        resolver.resolve_statement(stmt);
+    }
+
+    resolver.errors
+}
+
+// names a sequence of statements binds in its own environment. Blocks of if and try
+// expressions share that environment, function bodies, match arms and catch blocks don't
+pub(crate) fn add_slot(slots: &mut HashMap<String, usize>, name: &str) {
+    let next = slots.len();
+    slots.entry(name.to_string()).or_insert(next);
+}
+
+pub(crate) fn collect_declarations(statements: &[Rc<Stmt>], names: &mut HashMap<String, usize>) {
+    for stmt in statements {
+        collect_statement_declarations(stmt, names);
+    }
+}
+
+fn collect_statement_declarations(stmt: &Stmt, names: &mut HashMap<String, usize>) {
+    match stmt {
+        Stmt::Let(let_stmt) => add_slot(names, &let_stmt.name.value),
+        Stmt::Const(const_stmt) => add_slot(names, &const_stmt.name.value),
+        Stmt::Struct(struct_stmt) => add_slot(names, &struct_stmt.name.value),
+        Stmt::Enum(enum_stmt) => {
+            add_slot(names, &enum_stmt.name.value);
+            for variant in &enum_stmt.variants {
+                add_slot(names, &variant.name.value);
+            }
+        }
+        Stmt::Import(import_stmt) => add_slot(names, &import_stmt.alias.value),
+        Stmt::Export(export_stmt) => collect_statement_declarations(&export_stmt.statement, names),
+        Stmt::Block(block) => collect_declarations(&block.statements, names),
+        Stmt::Expression(expr_stmt) => match expr_stmt.expression.as_deref() {
+            Some(Expr::If(if_expr)) => {
+                for block in [&if_expr.consequence, &if_expr.alternative].into_iter().flatten() {
+                    collect_declarations(&block.statements, names);
+                }
+            }
+            Some(Expr::Try(try_expr)) => {
+                collect_declarations(&try_expr.block.statements, names);
+                if let Some(finally_block) = &try_expr.finally_block {
+                    collect_declarations(&finally_block.statements, names);
+                }
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+// in the order match_pattern binds them
+pub(crate) fn collect_pattern_bindings(pattern: &Pattern, names: &mut Vec<String>) {
+    match pattern {
+        Pattern::Binding(ident) => names.push(ident.value.clone()),
+        Pattern::Array { elements, rest, .. } => {
+            for element in elements {
+                collect_pattern_bindings(element, names);
+            }
+            if let Some(rest) = rest {
+                collect_pattern_bindings(rest, names);
+            }
+        }
+        Pattern::Hash { pairs, .. } => {
+            for (_, value) in pairs {
+                collect_pattern_bindings(value, names);
+            }
+        }
+        Pattern::Variant { fields, .. } => {
+            for field in fields {
+                collect_pattern_bindings(field, names);
+            }
+        }
+        Pattern::Or(alternatives) => {
+            for alternative in alternatives {
+                collect_pattern_bindings(alternative, names);
+            }
+        }
+        Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+    }
+}
+
+// a function whose body is being resolved, with the names it reads from scopes
+// outside of it
+struct Enclosing {
+    scope: usize,
+    captures: Vec<(String, usize)>,
+    dynamic: bool,
+}
+
+struct Resolver {
+    scopes: Vec<Scope>,
+    functions: Vec<Enclosing>,
+    errors: Vec<String>,
+}
+
+impl Resolver {
+    fn declare(&mut self, name: &str) {
+        self.scopes.last_mut().unwrap().declared.insert(name.to_string());
+    }
+
+    fn lookup(&self, name: &str) -> Option<Slot> {
+        let mut crossed_function = false;
+
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if crossed_function || scope.declared.contains(name) {
+                if let Some(slot) = scope.slots.get(name) {
+                    return Some(Slot::Resolved(depth, *slot));
+                }
+            }
+            crossed_function |= scope.function;
+        }
+
+        if is_builtin(name) {
+            return Some(Slot::Unresolved);
+        }
+        None
+    }
+
+    fn resolve_identifier(&mut self, ident: &Identifier) {
+        let Some(slot) = self.lookup(&ident.value) else {
+            let msg = format!(
+                "identifier not found: {} (line {}, column {})",
+                ident.value, ident.token.line, ident.token.column
+            );
+            self.errors.push(msg);
+            return;
+        };
+
+        self.capture(&ident.value);
+
+        let resolved = match ident.slot.get() {
+            Slot::Unresolved => slot,
+            previous if previous == slot => slot,
+            _ => Slot::Dynamic,
+        };
+        ident.slot.set(resolved);
+    }
+
+    // at runtime a name the scope it resolved to hasn't bound yet, like a let in an
+    // untaken branch, is looked up further out. So a function keeps every binding of
+    // the name outside of it, not only the one the identifier resolved to
+    fn capture(&mut self, name: &str) {
+        for function in self.functions.iter_mut() {
+            for index in (0..function.scope).rev() {
+                let capture = (name.to_string(), function.scope - 1 - index);
+                if self.scopes[index].slots.contains_key(name) && !function.captures.contains(&capture) {
+                    function.captures.push(capture);
+                }
+            }
+        }
+    }
+
+    fn with_scope(&mut self, scope: Scope, resolve: impl FnOnce(&mut Resolver)) {
+        self.scopes.push(scope);
+        resolve(self);
+        self.scopes.pop();
+    }
+
+    fn resolve_statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression(expr_stmt) => {
+                if let Some(expr) = &expr_stmt.expression {
+                    self.resolve_expression(expr);
+                }
+            }
+            Stmt::Let(let_stmt) => {
+                if let Some(value) = &let_stmt.value {
+                    self.resolve_expression(value);
+                }
+                self.declare(&let_stmt.name.value);
+            }
+            Stmt::Const(const_stmt) => {
+                if let Some(value) = &const_stmt.value {
+                    self.resolve_expression(value);
+                }
+                self.declare(&const_stmt.name.value);
+            }
+            Stmt::Return(return_stmt) => {
+                if let Some(value) = &return_stmt.return_value {
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Throw(throw_stmt) => {
+                if let Some(value) = &throw_stmt.value {
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Yield(yield_stmt) => {
+                if let Some(value) = &yield_stmt.value {
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Struct(struct_stmt) => self.declare(&struct_stmt.name.value),
+            Stmt::Enum(enum_stmt) => {
+                self.declare(&enum_stmt.name.value);
+                for variant in &enum_stmt.variants {
+                    self.declare(&variant.name.value);
+                }
+            }
+            Stmt::Impl(impl_stmt) => {
+                self.resolve_identifier(&impl_stmt.type_name);
+                for method in &impl_stmt.methods {
+                    self.resolve_function(&method.function);
+                }
+            }
+            Stmt::Import(import_stmt) => self.declare(&import_stmt.alias.value),
+            Stmt::Export(export_stmt) => self.resolve_statement(&export_stmt.statement),
+            Stmt::Block(block) => self.resolve_block(block),
+        }
+    }
+
+    fn resolve_block(&mut self, block: &BlockStatement) {
+        for stmt in &block.statements {
+            self.resolve_statement(stmt);
+        }
+    }
+
+    fn resolve_function(&mut self, function: &FunctionLiteral) {
+        let params = function.parameters.iter().map(|param| param.value.clone()).collect();
+        let scope = Scope::new(params, &function.body.statements, true);
+        self.functions.push(Enclosing {
+            scope: self.scopes.len(),
+            captures: Vec::new(),
+            dynamic: false,
+        });
+        self.with_scope(scope, |resolver| resolver.resolve_block(&function.body));
+
+        let enclosing = self.functions.pop().unwrap();
+        let resolved = match enclosing.dynamic {
+            true => Captures::Dynamic,
+            false => Captures::Resolved(enclosing.captures),
+        };
+        let mut captures = function.captures.borrow_mut();
+        *captures = match &*captures {
+            Captures::Unresolved => resolved,
+            previous if *previous == resolved => resolved,
+            _ => Captures::Dynamic,
+        };
+    }
+
+    fn resolve_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Literal(expr) => self.resolve_expression(expr),
+            Pattern::Array { elements, rest, .. } => {
+                for element in elements {
+                    self.resolve_pattern(element);
+                }
+                if let Some(rest) = rest {
+                    self.resolve_pattern(rest);
+                }
+            }
+            Pattern::Hash { pairs, .. } => {
+                for (key, value) in pairs {
+                    self.resolve_expression(key);
+                    self.resolve_pattern(value);
+                }
+            }
+            Pattern::Variant { fields, .. } => {
+                for field in fields {
+                    self.resolve_pattern(field);
+                }
+            }
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives {
+                    self.resolve_pattern(alternative);
+                }
+            }
+            Pattern::Wildcard(_) | Pattern::Binding(_) => {}
+        }
+    }
+
+    fn resolve_expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Identifier(ident) => self.resolve_identifier(ident),
+            Expr::Prefix(prefix_expr) => self.resolve_expression(&prefix_expr.right),
+            Expr::Infix(infix_expr) => {
+                for operand in [&infix_expr.left, &infix_expr.right].into_iter().flatten() {
+                    self.resolve_expression(operand);
+                }
+            }
+            Expr::If(if_expr) => {
+                if let Some(condition) = &if_expr.condition {
+                    self.resolve_expression(condition);
+                }
+                for block in [&if_expr.consequence, &if_expr.alternative].into_iter().flatten() {
+                    self.resolve_block(block);
+                }
+            }
+            Expr::Function(fn_literal) => self.resolve_function(fn_literal),
+            Expr::Call(call_expr) => {
+                // quoted code is data until it is unquoted inside a macro, and unquote
+                // can read any name in scope
+                if matches!(call_expr.function.as_ref(), Expr::Identifier(ident) if ident.value == "quote") {
+                    self.functions.iter_mut().for_each(|function| function.dynamic = true);
+                    return;
+                }
+
+                self.resolve_expression(&call_expr.function);
+                for arg in &call_expr.arguments {
+                    self.resolve_expression(arg);
+                }
+            }
+            Expr::Array(array_literal) => {
+                for element in &array_literal.elements {
+                    self.resolve_expression(element);
+                }
+            }
+            Expr::Hash(hash_literal) => {
+                for (key, value) in &hash_literal.pairs {
+                    self.resolve_expression(key);
+                    self.resolve_expression(value);
+                }
+            }
+            Expr::Index(index_expr) => {
+                self.resolve_expression(&index_expr.left);
+                self.resolve_expression(&index_expr.index);
+            }
+            Expr::Member(member_expr) => self.resolve_expression(&member_expr.object),
+            Expr::Assign(assign_expr) => {
+                self.resolve_expression(&assign_expr.target);
+                self.resolve_expression(&assign_expr.value);
+            }
+            Expr::Match(match_expr) => {
+                self.resolve_expression(&match_expr.subject);
+
+                for arm in &match_expr.arms {
+                    self.resolve_pattern(&arm.pattern);
+
+                    let mut bindings = Vec::new();
+                    collect_pattern_bindings(&arm.pattern, &mut bindings);
+                    let scope = Scope::new(bindings, &arm.body.statements, false);
+                    self.with_scope(scope, |resolver| {
+                        if let Some(guard) = &arm.guard {
+                            resolver.resolve_expression(guard);
+                        }
+                        resolver.resolve_block(&arm.body);
+                    });
+                }
+            }
+            Expr::Try(try_expr) => {
+                self.resolve_block(&try_expr.block);
+
+                if let Some(catch_block) = &try_expr.catch_block {
+                    let param = try_expr.catch_param.iter().map(|param| param.value.clone()).collect();
+                    let scope = Scope::new(param, &catch_block.statements, false);
+                    self.with_scope(scope, |resolver| resolver.resolve_block(catch_block));
+                }
+                if let Some(finally_block) = &try_expr.finally_block {
+                    self.resolve_block(finally_block);
+                }
+            }
+            Expr::Macro(_) => self.functions.iter_mut().for_each(|function| function.dynamic = true),
+            Expr::Integer(_) | Expr::String(_) | Expr::Boolean(_) => {}
+        }
+    }
+}
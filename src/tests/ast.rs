@@ -3,32 +3,47 @@ mod ast_tests {
     use std::rc::Rc;
 
     use crate::{
-        ast::{Identifier, LetStatement, Node, Program, Statement},
+        ast::{
+            modify::modify_program, Expr, Identifier, IntegerLiteral, LetStatement, Node, Program,
+            Stmt,
+        },
+        lexer::Lexer,
+        parser::Parser,
         token::{Token, TokenType},
     };
 
     #[test]
     fn to_string() {
-        let statements = Vec::from([Rc::new(LetStatement {
+        let statements = Vec::from([Rc::new(Stmt::Let(LetStatement {
             token: Token {
                 token_type: TokenType::LET,
                 literal: String::from("let"),
+                line: 0,
+                column: 0,
             },
             name: Identifier {
                 token: Token {
                     token_type: TokenType::IDENT,
                     literal: String::from("myVar"),
+                    line: 0,
+                    column: 0,
                 },
                 value: String::from("myVar"),
+                type_annotation: None,
+                slot: Default::default(),
             },
-            value: Some(Rc::new(Identifier {
+            value: Some(Rc::new(Expr::Identifier(Identifier {
                 token: Token {
                     token_type: TokenType::IDENT,
                     literal: String::from("anotherVar"),
+                    line: 0,
+                    column: 0,
                 },
                 value: String::from("anotherVar"),
-            })),
-        }) as Rc<dyn Statement>]);
+                type_annotation: None,
+                slot: Default::default(),
+            }))),
+        }))]);
 
         let program = Program { statements };
 
@@ -39,4 +54,56 @@ mod ast_tests {
             program.to_string()
         )
     }
+
+    #[test]
+    fn test_modify() {
+        let turn_one_into_two = &mut |node: Rc<Expr>| -> Rc<Expr> {
+            match node.as_ref() {
+                Expr::Integer(int_literal) if int_literal.value == 1 => {
+                    Rc::new(Expr::Integer(IntegerLiteral {
+                        token: Token {
+                            token_type: TokenType::INT,
+                            literal: String::from("2"),
+                            line: 0,
+                            column: 0,
+                        },
+                        value: 2,
+                    }))
+                }
+                _ => node,
+            }
+        };
+
+        let tests = Vec::from([
+            ("1", "2"),
+            ("1 + 2", "(2 + 2)"),
+            ("-1", "(-2)"),
+            ("[1][1]", "([2][2])"),
+            ("if (1) { 1 } else { 1 }", "else 2"),
+            ("if (1) { 1 }", "if 2 2"),
+            ("return 1;", "return 2;"),
+            ("let x = 1;", "let x = 2;"),
+            ("fn() { 1 }", "fn (  ) 2"),
+            ("[1, 1]", "[2, 2]"),
+            ("{1: 1}", "{2:2}"),
+            ("add(1, 1)", "add(2, 2)"),
+            ("match (1) { x if 1 => 1 }", "match (2) { x if 2 => 2 }"),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            let modified = modify_program(&program, turn_one_into_two);
+
+            assert_eq!(
+                modified.to_string(),
+                expected,
+                "modify({}) wrong. got={}",
+                input,
+                modified.to_string()
+            );
+        }
+    }
 }
@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod bytecode_tests {
+    use std::rc::Rc;
+
+    use crate::{
+        bytecode::{decode, encode, VERSION},
+        code::{disassemble, make, Opcode},
+        compiler::Compiler,
+        lexer::Lexer,
+        object::{Array, CompiledFunction, EnumType, MatchPattern, Value, Variant},
+        parser::Parser,
+        vm::Vm,
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let input = "
+            struct Point { x, y }
+            enum Shape { Circle(r), Square(side), Empty }
+            impl Point { fn sum(self) { self.x + self.y } }
+            let area = fn(shape) {
+                match (shape) {
+                    Circle(r) => 3 * r * r,
+                    Square(s) if s > 0 => s * s,
+                    { \"kind\": k } | [k, ..._] => k,
+                    _ => -1,
+                }
+            };
+            let total = try { throw \"oops\"; } catch (e) { len(e.message) } finally { 0 };
+            [Point(1, 2).sum(), area(Circle(2)), area(Square(3)), area(Empty), total, true, \"s\"]";
+
+        let main = compile(input);
+        let decoded = decode(&encode(&main).unwrap()).unwrap();
+
+        assert_same_function(&main, &decoded);
+        assert_eq!(
+            Vm::new().run(decoded).map(|value| value.inspect()).as_deref(),
+            Some("[3, 12, 9, -1, 4, true, s]")
+        );
+    }
+
+    #[test]
+    fn test_line_table() {
+        let main = compile("let a = 1;\nlet b = 2;\n\nlet f = fn() {\n  a + b\n};\nf()");
+
+        assert_eq!(main.line_at(0), Some(1));
+        assert_eq!(main.line_at(main.instructions.len() - 1), Some(7));
+        assert_eq!(main.functions[0].lines, vec![(0, 5)]);
+
+        let decoded = decode(&encode(&main).unwrap()).unwrap();
+        assert_eq!(decoded.lines, main.lines);
+    }
+
+    #[test]
+    fn test_rejects_invalid_files() {
+        let bytes = encode(&compile("let x = 5; x * 2")).unwrap();
+
+        let mut version = bytes.clone();
+        version[4..6].copy_from_slice(&(VERSION + 1).to_be_bytes());
+
+        let mut flipped = bytes.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 0xff;
+
+        let tests = Vec::from([
+            (b"#!/bin/sh".to_vec(), "not a bytecode file: missing header".to_string()),
+            (
+                version,
+                format!("unsupported bytecode version {}, expected {}", VERSION + 1, VERSION),
+            ),
+            (flipped, "corrupted bytecode file: checksum mismatch".to_string()),
+            (
+                bytes[..bytes.len() - 3].to_vec(),
+                format!(
+                    "corrupted bytecode file: body is {} bytes, header says {}",
+                    bytes.len() - 21,
+                    bytes.len() - 18
+                ),
+            ),
+            (
+                file_for(function(make(Opcode::Constant, &[3]))),
+                "corrupted bytecode file: invalid instruction at 0".to_string(),
+            ),
+            (
+                file_for(function([make(Opcode::Null, &[]), make(Opcode::Jump, &[2])].concat())),
+                "corrupted bytecode file: invalid jump target 2 at 1".to_string(),
+            ),
+            (
+                file_for(function(vec![200])),
+                "corrupted bytecode file: invalid instruction at 0".to_string(),
+            ),
+        ]);
+
+        for (bytes, expected) in tests {
+            match decode(&bytes) {
+                Ok(_) => panic!("decoded an invalid file, expected {}", expected),
+                Err(msg) => assert_eq!(msg, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_operands() {
+        let with_enum = |instructions: Vec<u8>| CompiledFunction {
+            constants: vec![enum_type()],
+            ..function(instructions)
+        };
+        let with_name = |instructions: Vec<u8>| CompiledFunction {
+            constants: vec![Value::String("x".into())],
+            num_locals: 1,
+            ..function(instructions)
+        };
+
+        let mut nested = Value::Null;
+        for _ in 0..300 {
+            nested = Value::Array(Rc::new(Array { elements: vec![nested] }));
+        }
+
+        let tests = Vec::from([
+            (
+                with_enum([make(Opcode::Enum, &[0]), make(Opcode::Variant, &[2])].concat()),
+                "invalid instruction at 3",
+            ),
+            (with_enum(make(Opcode::Variant, &[0])), "invalid instruction at 0"),
+            (
+                CompiledFunction {
+                    num_locals: 1 << 30,
+                    ..function(make(Opcode::Null, &[]))
+                },
+                "function has 1073741824 locals",
+            ),
+            (
+                with_name([make(Opcode::Null, &[]), make(Opcode::SetLocal, &[9, 0])].concat()),
+                "invalid slot 9 at 1",
+            ),
+            (
+                CompiledFunction {
+                    patterns: vec![MatchPattern::Array {
+                        elements: vec![MatchPattern::Wildcard],
+                        rest: Some(Box::new(MatchPattern::Binding {
+                            slot: 1 << 30,
+                            name: "x".to_string(),
+                            shadowed: Vec::new(),
+                        })),
+                    }],
+                    ..function(vec![])
+                },
+                "invalid slot 1073741824 in a pattern",
+            ),
+            (
+                CompiledFunction {
+                    constants: vec![nested],
+                    ..function(vec![])
+                },
+                "nested too deeply",
+            ),
+        ]);
+
+        for (function, expected) in tests {
+            match decode(&file_for(function)) {
+                Ok(_) => panic!("decoded an invalid file, expected {}", expected),
+                Err(msg) => assert_eq!(msg, format!("corrupted bytecode file: {}", expected)),
+            }
+        }
+    }
+
+    // instructions that decode but leave the stack or the scopes in a shape the compiler
+    // never produces fail when they run instead of crashing the VM
+    #[test]
+    fn test_malformed_programs_fail_at_run_time() {
+        let tests = Vec::from([
+            CompiledFunction {
+                constants: vec![enum_type()],
+                ..function([make(Opcode::Enum, &[0]), make(Opcode::Null, &[]), make(Opcode::Variant, &[0])].concat())
+            },
+            function(make(Opcode::PopScope, &[])),
+        ]);
+
+        for function in tests {
+            let decoded = decode(&file_for(function)).unwrap();
+            let evaluated = Vm::new().run(decoded);
+            assert!(
+                matches!(&evaluated, Some(Value::Error(error)) if error.message.starts_with("invalid instruction")),
+                "wrong result: {:?}",
+                evaluated.map(|value| value.inspect())
+            );
+        }
+    }
+
+    #[test]
+    fn test_quoted_code_cannot_be_written() {
+        let main = compile("quote(1 + 2)");
+
+        match encode(&main) {
+            Ok(_) => panic!("encoded a quote constant"),
+            Err(msg) => assert_eq!(msg, "cannot write a QUOTE constant to a bytecode file"),
+        }
+    }
+
+    fn compile(input: &str) -> Rc<CompiledFunction> {
+        let program = Parser::new(Lexer::new(input.to_string())).parse_program();
+        Compiler::new().compile(&program).unwrap()
+    }
+
+    fn function(instructions: Vec<u8>) -> CompiledFunction {
+        CompiledFunction {
+            instructions,
+            constants: vec![Value::Integer(1)],
+            functions: vec![],
+            patterns: vec![],
+            parameters: vec![],
+            num_locals: 0,
+            generator: false,
+            literal: String::new(),
+            lines: vec![],
+        }
+    }
+
+    fn enum_type() -> Value {
+        Value::EnumType(Rc::new(EnumType {
+            name: "Shape".to_string(),
+            variants: vec![Variant {
+                name: "Empty".to_string(),
+                fields: vec![],
+            }],
+            methods: Default::default(),
+        }))
+    }
+
+    // encoding doesn't validate, so it writes files with a correct checksum around
+    // instructions the decoder has to reject
+    fn file_for(function: CompiledFunction) -> Vec<u8> {
+        encode(&function).unwrap()
+    }
+
+    fn assert_same_function(expected: &CompiledFunction, actual: &CompiledFunction) {
+        assert_eq!(disassemble(&actual.instructions), disassemble(&expected.instructions));
+        assert_eq!(actual.parameters, expected.parameters);
+        assert_eq!(actual.num_locals, expected.num_locals);
+        assert_eq!(actual.literal, expected.literal);
+        assert_eq!(actual.lines, expected.lines);
+        assert_eq!(actual.patterns.len(), expected.patterns.len());
+
+        let constants = |function: &CompiledFunction| -> Vec<String> {
+            function.constants.iter().map(|constant| constant.inspect()).collect()
+        };
+        assert_eq!(constants(actual), constants(expected));
+
+        assert_eq!(actual.functions.len(), expected.functions.len());
+        for (expected, actual) in expected.functions.iter().zip(&actual.functions) {
+            assert_same_function(expected, actual);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod cgen_tests {
+    use std::{
+        cell::RefCell,
+        fs,
+        path::{Path, PathBuf},
+        process::Command,
+        rc::Rc,
+    };
+
+    use crate::{
+        ast::Program,
+        cgen,
+        compiler::Compiler,
+        evaluator::{capture_output, eval},
+        lexer::Lexer,
+        load_script,
+        object::{Environment, Value},
+        parser::Parser,
+    };
+
+    // what a program prints to stdout and stderr, and the status it exits with
+    type Outcome = (String, String, i32);
+
+    #[test]
+    fn test_programs_match_the_interpreter() {
+        // the system compiler is needed to run the output
+        if Command::new("cc").arg("--version").output().is_err() {
+            return;
+        }
+
+        let tests = Vec::from([
+            ("puts(1 + 2 * 3, -4 / 2, \"a\" + \"b\", !true, !0, 1 == true, 2 > 1)", "7\n-2\nab\nfalse\nfalse\ntrue\ntrue\n", 0),
+            (
+                "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; puts(fib(20))",
+                "6765\n",
+                0,
+            ),
+            (
+                "let adder = fn(n) { let add = fn(x) { x + n }; add }; let add_two = adder(2); let add_ten = adder(10); puts(add_two(3), add_ten(1), add_two)",
+                "5\n11\nfn(x) {\n (x + n) \n}\n",
+                0,
+            ),
+            (
+                "let a = [1, \"two\", [3]]; let h = {\"k\": a, 1: true}; puts(a[1], a[5], h[\"k\"], h[1], h[2], len(a), rest(a), push(a, a[9]), first, {})",
+                "two\nnull\n[1, two, [3]]\ntrue\nnull\n3\n[two, [3]]\n[1, two, [3], null]\n[builtin function]\n{}\n",
+                0,
+            ),
+            (
+                "struct Point { x, y } impl Point { fn sum(self) { self.x + self.y } fn origin() { Point(0, 0) } }
+                let p = Point(1, 2); p.x = 5; puts(p, p.sum(), Point.origin(), Point)",
+                "Point { x: 5, y: 2 }\n7\nPoint { x: 0, y: 0 }\nstruct Point { x, y }\n",
+                0,
+            ),
+            (
+                "enum Shape { Circle(r), Rect(w, h), Empty }
+                let area = fn(s) { match (s) { Circle(r) => 3 * r * r, Shape.Rect(w, h) => w * h, _ => 0 } };
+                puts(area(Circle(2)), area(Rect(2, 5)), area(Empty), Rect(1, 2), Rect, Shape, Circle(1) == Circle(1))",
+                "12\n10\n0\nRect(1, 2)\nShape.Rect(w, h)\nenum Shape { Circle(r), Rect(w, h), Empty }\ntrue\n",
+                0,
+            ),
+            (
+                "enum Opt { some(v), none } let check = fn(none, o) { match (o) { none => 1, some(v) => v, _ => 0 } };
+                puts(check(5, none), check(none, none), check(none, some(2)), check(none, 3), match (5) { X => 1, _ => 2 })",
+                "1\n1\n2\n0\n1\n",
+                0,
+            ),
+            (
+                "let k = \"a\"; puts(k == \"a\", k != \"a\", k == \"b\", match (k) { s if s == \"a\" => 1, _ => 2 })",
+                "true\nfalse\nfalse\n1\n",
+                0,
+            ),
+            (
+                "puts(match ([1, 2, 3]) { [x, ...rest] => rest }, match ({\"k\": 4}) { {\"k\": k} if k > 3 => k, _ => 0 }, match (\"b\") { \"a\" | \"b\" => 1, _ => 2 })",
+                "[2, 3]\n4\n1\n",
+                0,
+            ),
+            (
+                "let f = fn() { try { return 1; } finally { puts(\"finally\") } };
+                puts(f(), try { throw 42 } catch (e) { [e, e.value, e.kind] }, try { len(1) } catch (e) { e.message });
+                puts(try { try { throw 7 } catch (e) { throw e } } catch (e) { e.value + 1 })",
+                "finally\n1\n[Error: 42, 42, Error]\nargument to `len` not supported, got INTEGER\n8\n",
+                0,
+            ),
+            (
+                "let x = 1; let f = fn(c) { if (c) { let x = 2; 0 } else { 0 }; x }; puts(f(false), f(true))",
+                "1\n2\n",
+                0,
+            ),
+            (
+                "let x = 0; puts(\"before\"); let r = try { x } finally { x.y }; puts(\"after\")",
+                "before\n",
+                1,
+            ),
+            (
+                "let zero = 0; let max = 9223372036854775807; let min = -max - 1;
+                puts(try { 1 / zero } catch (e) { [e.kind, e.message] }, try { max + 1 } catch (e) { e.message });
+                puts(try { min - 1 } catch (e) { e.message }, try { max * 2 } catch (e) { e.kind }, try { min / -1 } catch (e) { e.message }, try { -min } catch (e) { e.message })",
+                "[ZeroDivisionError, division by zero]\ninteger overflow: 9223372036854775807 + 1\ninteger overflow: -9223372036854775808 - 1\nOverflowError\ninteger overflow: -9223372036854775808 / -1\ninteger overflow: -(-9223372036854775808)\n",
+                0,
+            ),
+        ]);
+
+        let dir = std::env::temp_dir().join(format!("rust_script_cgen_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for (idx, (input, expected, status)) in tests.into_iter().enumerate() {
+            let program = Parser::new(Lexer::new(input.to_string())).parse_program();
+            let interpreted = interpret(&program);
+            assert_eq!((interpreted.0.as_str(), interpreted.2), (expected, status), "wrong result for {}", input);
+
+            let compiled = compile_and_run(&dir, &format!("program_{}", idx), &program);
+            assert_eq!(compiled, interpreted, "compiled program differs for {}", input);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_examples_match_the_interpreter() {
+        if Command::new("cc").arg("--version").output().is_err() {
+            return;
+        }
+
+        let dir = std::env::temp_dir().join(format!("rust_script_cgen_examples_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut examples: Vec<PathBuf> = fs::read_dir("examples")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rs_script"))
+            .collect();
+        examples.sort();
+        assert!(!examples.is_empty(), "no examples found");
+
+        for path in examples {
+            let program = load_script(&path.display().to_string()).unwrap();
+            let name = path.file_stem().unwrap().to_string_lossy();
+            let compiled = compile_and_run(&dir, &name, &program);
+            assert_eq!(compiled, interpret(&program), "compiled program differs for {}", path.display());
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_strings_are_escaped() {
+        let output = translate("puts(\"back\\slash\ttab question?\")").unwrap();
+        assert!(
+            output.contains("\"back\\\\slash\\011tab question\\?\""),
+            "string not escaped"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_programs() {
+        let tests = Vec::from([
+            ("let g = fn*() { yield 1; }; g", "cannot translate generators to C"),
+            ("import \"lib.rs_script\" as lib;", "cannot translate import to C"),
+            ("quote(1 + 2)", "cannot translate quote to C"),
+        ]);
+
+        for (input, expected) in tests {
+            assert_eq!(translate(input).err().as_deref(), Some(expected), "wrong error for {}", input);
+        }
+    }
+
+    // uncaught errors are reported the way `rust_script run` reports them
+    fn interpret(program: &Program) -> Outcome {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let (result, stdout) = capture_output(|| eval(program, env));
+        match result {
+            Some(Value::Error(error)) => (stdout, format!("uncaught {}: {}\n", error.kind, error.message), 1),
+            _ => (stdout, String::new(), 0),
+        }
+    }
+
+    fn compile_and_run(dir: &Path, name: &str, program: &Program) -> Outcome {
+        let source = dir.join(format!("{}.c", name));
+        let binary = dir.join(name);
+        let main = Compiler::new().compile(program).unwrap();
+        fs::write(&source, cgen::emit(&main).unwrap()).unwrap();
+
+        let compiled = Command::new("cc").arg("-o").arg(&binary).arg(&source).output().unwrap();
+        assert!(
+            compiled.status.success(),
+            "cc failed for {}: {}",
+            name,
+            String::from_utf8_lossy(&compiled.stderr)
+        );
+
+        let output = Command::new(&binary).output().unwrap();
+        (
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+            output.status.code().unwrap_or(-1),
+        )
+    }
+
+    fn translate(input: &str) -> Result<String, String> {
+        let program = Parser::new(Lexer::new(input.to_string())).parse_program();
+        let main = Compiler::new().compile(&program)?;
+        cgen::emit(&main)
+    }
+}
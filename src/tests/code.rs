@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod code_tests {
+    use crate::code::{disassemble, make, read_operands, Opcode};
+
+    #[test]
+    fn test_make() {
+        let tests = Vec::from([
+            (Opcode::Constant, vec![65534], vec![Opcode::Constant as u8, 255, 254]),
+            (Opcode::Add, vec![], vec![Opcode::Add as u8]),
+            (Opcode::GetLocal, vec![1, 2, 258], vec![Opcode::GetLocal as u8, 1, 0, 2, 1, 2]),
+            (Opcode::CallMethod, vec![3, 2], vec![Opcode::CallMethod as u8, 0, 3, 2]),
+        ]);
+
+        for (op, operands, expected) in tests {
+            let instruction = make(op, &operands);
+            assert_eq!(instruction, expected, "wrong encoding for {:?}", op);
+        }
+    }
+
+    #[test]
+    fn test_read_operands() {
+        let tests = Vec::from([
+            (Opcode::Constant, vec![65535], 2),
+            (Opcode::GetLocal, vec![3, 7, 300], 5),
+            (Opcode::Try, vec![10, 20, 30], 6),
+            (Opcode::Pop, vec![], 0),
+        ]);
+
+        for (op, operands, bytes) in tests {
+            let instruction = make(op, &operands);
+            let definition = op.definition();
+
+            let (read, n) = read_operands(&definition, &instruction[1..]);
+            assert_eq!(n, bytes, "wrong number of bytes read for {}", definition.name);
+            assert_eq!(read, operands, "wrong operands for {}", definition.name);
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let instructions = [
+            make(Opcode::Add, &[]),
+            make(Opcode::GetLocal, &[1, 2, 0]),
+            make(Opcode::Constant, &[65535]),
+            make(Opcode::JumpNotTruthy, &[12]),
+        ]
+        .concat();
+
+        let expected = "0000 OpAdd
+0001 OpGetLocal 1 2 0
+0007 OpConstant 65535
+0010 OpJumpNotTruthy 12
+";
+        assert_eq!(disassemble(&instructions), expected);
+        assert_eq!(disassemble(&[255]), "ERROR: unknown opcode 255\n");
+    }
+}
@@ -1,18 +1,28 @@
 #[cfg(test)]
 mod evaluator_tests {
     use std::{
-        any::{type_name_of_val, Any},
+        any::Any,
         cell::RefCell,
         collections::HashMap,
         rc::Rc,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
     };
 
     use crate::{
         ast::Node,
+        compiler::Compiler,
         evaluator::eval,
         lexer::Lexer,
-        object::{self, Environment, Object, ObjectType, NULL},
+        object::{self, Environment, ErrorKind, ObjectType, Value},
+        optimizer::optimize,
         parser::Parser,
+        resolver::resolve,
+        vm::Vm,
     };
 
     #[test]
@@ -374,12 +384,12 @@ mod evaluator_tests {
         for test in tests {
             let evaluated = test_eval(test.input);
 
-            if evaluated.try_into_error().is_err() {
+            let Value::Error(error) = &evaluated else {
                 assert!(false, "no error object returned.");
                 continue;
-            }
+            };
 
-            let msg = evaluated.try_into_error().unwrap().message.clone();
+            let msg = error.message.clone();
             assert_eq!(
                 test.expected_message, msg,
                 "wrong error message. expected: {}, got={}",
@@ -467,9 +477,9 @@ mod evaluator_tests {
         let input = "fn(x) { x + 2; };".to_string();
         let evaluated = test_eval(input);
 
-        let function = match evaluated.try_into_function() {
-            Ok(val) => val.try_into_function().unwrap(),
-            Err(_) => {
+        let function = match &evaluated {
+            Value::Function(val) => val,
+            _ => {
                 assert!(
                     false,
                     "object is not Function. got={}",
@@ -501,6 +511,21 @@ mod evaluator_tests {
         );
     }
 
+    #[test]
+    fn test_lets_in_branches_that_did_not_run() {
+        let shadow = "let x = 1; let f = fn(c) { if (c) { let x = 2; 0 } else { 0 }; x };";
+        let tests = Vec::from([
+            ("f(false)", 1),
+            ("f(true)", 2),
+            ("f(true) + f(false)", 3),
+            ("let g = fn(c) { let y = fn() { if (c) { let x = 3; 0 } else { 0 }; x }; y() }; g(false)", 1),
+        ]);
+
+        for (input, expected) in tests {
+            test_int_object(test_eval(format!("{} {}", shadow, input)), expected);
+        }
+    }
+
     #[test]
     fn test_closures() {
         let input = "
@@ -514,18 +539,32 @@ mod evaluator_tests {
         test_int_object(test_eval(input), 4);
     }
 
+    #[test]
+    fn test_type_annotations() {
+        let tests = Vec::from([
+            ("let x: int = 5; x;", 5),
+            ("fn add(a: int, b: int) -> int { a + b } add(2, 3);", 5),
+            ("let apply = fn(f: fn(int) -> int, x) { f(x) }; apply(fn(x: int) -> int { x * 2 }, 4);", 8),
+        ]);
+
+        for (input, expected) in tests {
+            test_int_object(test_eval(input.to_string()), expected);
+        }
+    }
+
     #[test]
     fn test_string_literal() {
         let input = "\"Hello World!\"".to_string();
         let evaluated = test_eval(input);
 
-        match evaluated.try_into_str() {
-            Err(_) => assert!(false, "object is not String. got={}", evaluated.get_type()),
-            Ok(val) => assert_eq!(
-                val.value, "Hello World!",
+        match &evaluated {
+            Value::String(val) => assert_eq!(
+                val.as_ref(),
+                "Hello World!",
                 "String has wrong value. got={}",
-                val.value
+                val
             ),
+            _ => assert!(false, "object is not String. got={}", evaluated.get_type()),
         }
     }
 
@@ -534,14 +573,36 @@ mod evaluator_tests {
         let input = "\"Hello\" + \" \" + \"World!\"".to_string();
         let evaluated = test_eval(input);
 
-        match evaluated.try_into_str() {
-            Err(_) => assert!(false, "object is not String. got={}", evaluated.get_type()),
-            Ok(val) => assert_eq!(
-                val.value, "Hello World!",
+        match &evaluated {
+            Value::String(val) => assert_eq!(
+                val.as_ref(),
+                "Hello World!",
                 "String has wrong value. got={}",
-                val.value
+                val
             ),
+            _ => assert!(false, "object is not String. got={}", evaluated.get_type()),
+        }
+    }
+
+    #[test]
+    fn test_string_comparison() {
+        let tests = Vec::from([
+            ("\"a\" == \"a\"", true),
+            ("\"a\" == \"b\"", false),
+            ("\"a\" != \"b\"", true),
+            ("\"ab\" != \"a\" + \"b\"", false),
+            ("let k = \"a\"; if (k == \"a\") { true } else { false }", true),
+            ("match ({\"k\": \"b\"}) { {\"k\": k} if k == \"a\" => true, _ => false }", false),
+        ]);
+
+        for (input, expected) in tests {
+            test_bool_object(test_eval(input.to_string()), expected);
         }
+
+        let evaluated = test_eval("\"a\" < \"b\"".to_string());
+        assert_eq!(test_error_object(&evaluated).message, "unknown operator: STRING < STRING");
+        let evaluated = test_eval("\"1\" == 1".to_string());
+        assert_eq!(test_error_object(&evaluated).message, "type mismatch: STRING == INTEGER");
     }
 
     #[test]
@@ -580,15 +641,15 @@ mod evaluator_tests {
                 test_int_object(evaluated, *test.expected.downcast_ref::<i64>().unwrap())
             } else if test.expected.downcast_ref::<String>().is_some() {
                 let expected = test.expected.downcast_ref::<String>().unwrap();
-                match evaluated.try_into_error() {
-                    Ok(val) => assert_eq!(
+                match &evaluated {
+                    Value::Error(val) => assert_eq!(
                         expected.clone(),
                         val.message,
                         "wrong error message. expected={}, got={}",
                         expected,
                         val.message
                     ),
-                    Err(_) => assert!(false, "object is not Error. got={}", evaluated.get_type()),
+                    _ => assert!(false, "object is not Error. got={}", evaluated.get_type()),
                 }
             }
         }
@@ -599,9 +660,8 @@ mod evaluator_tests {
         let input = "[1, 2 * 2, 3 + 3]".to_string();
         let evaluated = test_eval(input);
 
-        match evaluated.try_into_array() {
-            Err(_) => assert!(false, "object is not Array. got={}", evaluated.get_type()),
-            Ok(val) => {
+        match &evaluated {
+            Value::Array(val) => {
                 assert_eq!(
                     val.elements.len(),
                     3,
@@ -613,6 +673,7 @@ mod evaluator_tests {
                 test_int_object(val.elements[1].clone(), 4);
                 test_int_object(val.elements[2].clone(), 6);
             }
+            _ => assert!(false, "object is not Array. got={}", evaluated.get_type()),
         }
     }
 
@@ -658,11 +719,11 @@ mod evaluator_tests {
             },
             TestStruct {
                 input: "[1, 2, 3][3]".to_string(),
-                expected: Box::new(NULL {}),
+                expected: Box::new(Value::Null),
             },
             TestStruct {
                 input: "[1, 2, 3][3]".to_string(),
-                expected: Box::new(NULL {}),
+                expected: Box::new(Value::Null),
             },
         ]);
 
@@ -698,32 +759,25 @@ mod evaluator_tests {
             evaluated.get_type()
         );
 
-        let hash = evaluated.try_into_hash().unwrap();
+        let Value::Hash(hash) = &evaluated else {
+            unreachable!()
+        };
         let expected = HashMap::from([
             (
-                object::StringObj {
-                    value: "one".to_string(),
-                }
-                .hash_key(),
+                Value::String("one".into()).hash_key().unwrap(),
                 1,
             ),
             (
-                object::StringObj {
-                    value: "two".to_string(),
-                }
-                .hash_key(),
+                Value::String("two".into()).hash_key().unwrap(),
                 2,
             ),
             (
-                object::StringObj {
-                    value: "three".to_string(),
-                }
-                .hash_key(),
+                Value::String("three".into()).hash_key().unwrap(),
                 3,
             ),
-            (object::Integer { value: 4 }.hash_key(), 4),
-            (object::Boolean { value: true }.hash_key(), 5),
-            (object::Boolean { value: false }.hash_key(), 6),
+            (Value::Integer(4).hash_key().unwrap(), 4),
+            (Value::Boolean(true).hash_key().unwrap(), 5),
+            (Value::Boolean(false).hash_key().unwrap(), 6),
         ]);
 
         assert_eq!(
@@ -755,7 +809,7 @@ mod evaluator_tests {
             },
             TestStruct {
                 input: "{\"foo\": 5}[\"bar\"]".to_string(),
-                expected: Box::new(NULL {}),
+                expected: Box::new(Value::Null),
             },
             TestStruct {
                 input: "let key = \"foo\"; {\"foo\": 5}[key]".to_string(),
@@ -763,7 +817,7 @@ mod evaluator_tests {
             },
             TestStruct {
                 input: "{}[\"foo\"]".to_string(),
-                expected: Box::new(NULL {}),
+                expected: Box::new(Value::Null),
             },
             TestStruct {
                 input: "{5: 5}[5]".to_string(),
@@ -789,52 +843,833 @@ mod evaluator_tests {
         }
     }
 
-    fn test_eval(input: String) -> Rc<dyn Object> {
-        let lexer = Lexer::new(input);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
+    #[test]
+    fn test_match_expressions() {
+        struct TestStruct {
+            input: String,
+            expected: Box<dyn Any>,
+        }
+
+        let tests = Vec::from([
+            TestStruct {
+                input: "match (2) { 1 => 10, 2 => 20, _ => 30 }".to_string(),
+                expected: Box::new(20),
+            },
+            TestStruct {
+                input: "match (5) { 1 => 10, 2 => 20, _ => 30 }".to_string(),
+                expected: Box::new(30),
+            },
+            TestStruct {
+                input: "match (5) { 1 => 10 }".to_string(),
+                expected: Box::new("null"),
+            },
+            TestStruct {
+                input: "match (\"b\") { \"a\" | \"b\" => 1, _ => 2 }".to_string(),
+                expected: Box::new(1),
+            },
+            TestStruct {
+                input: "match (-3) { -3 => 1, _ => 2 }".to_string(),
+                expected: Box::new(1),
+            },
+            TestStruct {
+                input: "match (true) { false => 1, true => 2 }".to_string(),
+                expected: Box::new(2),
+            },
+            TestStruct {
+                input: "match (7) { x => x * 2 }".to_string(),
+                expected: Box::new(14),
+            },
+            TestStruct {
+                input: "match ([1, 2, 3]) { [x, ...rest] => x + len(rest) }".to_string(),
+                expected: Box::new(3),
+            },
+            TestStruct {
+                input: "match ([1, 2, 3]) { [a, b] => 1, [a, b, c] => a + b + c }".to_string(),
+                expected: Box::new(6),
+            },
+            TestStruct {
+                input: "match ([]) { [x, ..._] => x, [] => 0 }".to_string(),
+                expected: Box::new(0),
+            },
+            TestStruct {
+                input: "match ({\"kind\": 4}) { {\"kind\": k} => k, _ => 0 }".to_string(),
+                expected: Box::new(4),
+            },
+            TestStruct {
+                input: "match ({\"other\": 4}) { {\"kind\": k} => k, _ => 0 }".to_string(),
+                expected: Box::new(0),
+            },
+            TestStruct {
+                input: "match (3) { n if n > 5 => 1, n if n > 1 => 2, _ => 3 }".to_string(),
+                expected: Box::new(2),
+            },
+            TestStruct {
+                input: "let f = fn(x) { match (x) { 0 => { return 100; }, _ => 1 }; 5 }; f(0)"
+                    .to_string(),
+                expected: Box::new(100),
+            },
+            TestStruct {
+                input: "let x = 1; match (2) { x => x }; x".to_string(),
+                expected: Box::new(1),
+            },
+        ]);
+
+        for test in tests {
+            let evaluated = test_eval(test.input);
+            if test.expected.downcast_ref::<i64>().is_some() {
+                test_int_object(evaluated, *test.expected.downcast_ref::<i64>().unwrap())
+            } else {
+                test_null_object(evaluated)
+            }
+        }
+    }
+
+    #[test]
+    fn test_quote_unquote() {
+        let tests = Vec::from([
+            ("quote(5)", "5"),
+            ("quote(5 + 8)", "(5 + 8)"),
+            ("quote(foobar)", "foobar"),
+            ("quote(foobar + barfoo)", "(foobar + barfoo)"),
+            ("quote(unquote(4))", "4"),
+            ("quote(unquote(4 + 4))", "8"),
+            ("quote(8 + unquote(4 + 4))", "(8 + 8)"),
+            ("quote(unquote(4 + 4) + 8)", "(8 + 8)"),
+            ("let foobar = 8; quote(foobar)", "foobar"),
+            ("let foobar = 8; quote(unquote(foobar))", "8"),
+            ("quote(unquote(true))", "true"),
+            ("quote(unquote(true == false))", "false"),
+            ("quote(unquote(\"hi\"))", "hi"),
+            ("quote(unquote(quote(4 + 4)))", "(4 + 4)"),
+            (
+                "let quotedInfixExpression = quote(4 + 4);
+                quote(unquote(4 + 4) + unquote(quotedInfixExpression))",
+                "(8 + (4 + 4))",
+            ),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.to_string());
+
+            let quote = match &evaluated {
+                Value::Quote(quote) => quote,
+                _ => panic!("expected Quote. got={}", evaluated.get_type()),
+            };
+
+            assert_eq!(
+                quote.to_string(),
+                expected,
+                "not equal. got={}, want={}",
+                quote.to_string(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_const_statements() {
+        let tests = Vec::from([
+            ("const a = 5; a;", 5),
+            ("const a = 5 * 5; a;", 25),
+            ("const a = 5; let b = a; b;", 5),
+            ("const a = 5; let f = fn() { let a = 10; a }; f() + a;", 15),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.to_string());
+            test_int_object(evaluated, expected)
+        }
+    }
+
+    #[test]
+    fn test_const_reassignment_errors() {
+        let tests = Vec::from([
+            ("const MAX_RETRIES = 3;", "let MAX_RETRIES = 5;"),
+            ("const MAX_RETRIES = 3;", "const MAX_RETRIES = 5;"),
+            ("const MAX_RETRIES = 3;", "if (true) { let MAX_RETRIES = 5; }"),
+        ]);
+
+        for (first, second) in tests {
+            let env = Rc::new(RefCell::new(Environment::new()));
+            let program = Parser::new(Lexer::new(first.to_string())).parse_program();
+            eval(&program, env.clone());
+
+            let program = Parser::new(Lexer::new(second.to_string())).parse_program();
+            let evaluated = eval(&program, env.clone()).unwrap();
+
+            let msg = test_error_object(&evaluated).message.clone();
+            assert_eq!(
+                msg, "cannot redefine constant: MAX_RETRIES",
+                "wrong error message. got={}",
+                msg
+            );
+
+            let value = env.borrow().get("MAX_RETRIES").unwrap();
+            test_int_object(value, 3);
+        }
+    }
+
+    #[test]
+    fn test_try_catch_expressions() {
+        let tests = Vec::from([
+            ("try { 1 } catch (e) { 2 }", 1),
+            ("try { 5 + true } catch (e) { 2 }", 2),
+            ("try { 5 + true; 3 } catch { 2 }", 2),
+            (
+                "try { throw \"boom\" } catch (e) { match (e.message) { \"boom\" => 1, _ => 0 } }",
+                1,
+            ),
+            (
+                "try { foobar } catch (e) { match (e.kind) { \"NameError\" => 1, _ => 0 } }",
+                1,
+            ),
+            (
+                "try { len(1) } catch (e) { match ([e.kind, e.message]) { [\"TypeError\", \"argument to `len` not supported, got INTEGER\"] => 1, _ => 0 } }",
+                1,
+            ),
+            (
+                "try { len(1, 2) } catch (e) { match (e.kind) { \"ArgumentError\" => 1, _ => 0 } }",
+                1,
+            ),
+            (
+                "let zero = 0; try { 1 / zero } catch (e) { match (e.kind) { \"ZeroDivisionError\" => 1, _ => 0 } }",
+                1,
+            ),
+            (
+                "try { 9223372036854775807 * 2 } catch (e) { match (e.kind) { \"OverflowError\" => 1, _ => 0 } }",
+                1,
+            ),
+            ("try { throw 42 } catch (e) { e.value }", 42),
+            ("try { try { throw 7 } catch (e) { throw e } } catch (e) { e.value + 1 }", 8),
+            ("let f = fn() { throw \"x\"; 5 }; try { f() } catch (e) { 10 }", 10),
+            ("let f = fn() { try { return 3; } catch (e) { 0 }; 9 }; f()", 3),
+            ("let f = fn() { try { return 1; } finally { 2 } }; f()", 1),
+            ("let x = 0; try { x } finally { let x = 5; }; x", 5),
+            (
+                "let r = try { throw 1 } catch (e) { 2 } finally { let done = 3; }; r + done",
+                5,
+            ),
+            ("let e = 1; try { throw 2 } catch (e) { e }; e", 1),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.to_string());
+            test_int_object(evaluated, expected)
+        }
+    }
+
+    #[test]
+    fn test_uncaught_errors() {
+        let tests = Vec::from([
+            ("throw \"boom\"; 5", "boom", ErrorKind::Error),
+            ("try { throw 1 } finally { 2 }", "1", ErrorKind::Error),
+            ("try { 1 } finally { foobar }", "identifier not found: foobar", ErrorKind::NameError),
+            (
+                "try { throw 1 } catch (e) { e.foo }",
+                "exception has no property named foo",
+                ErrorKind::NameError,
+            ),
+            ("5 + true", "type mismatch: INTEGER + BOOLEAN", ErrorKind::TypeError),
+            ("let zero = 0; 1 / zero", "division by zero", ErrorKind::ZeroDivisionError),
+            ("1 / 0", "division by zero", ErrorKind::ZeroDivisionError),
+            (
+                "let min = -9223372036854775807 - 1; min / -1",
+                "integer overflow: -9223372036854775808 / -1",
+                ErrorKind::OverflowError,
+            ),
+            (
+                "9223372036854775807 + 1",
+                "integer overflow: 9223372036854775807 + 1",
+                ErrorKind::OverflowError,
+            ),
+            (
+                "let big = 4611686018427387904; big * 2",
+                "integer overflow: 4611686018427387904 * 2",
+                ErrorKind::OverflowError,
+            ),
+            (
+                "let min = -9223372036854775807 - 1; -min",
+                "integer overflow: -(-9223372036854775808)",
+                ErrorKind::OverflowError,
+            ),
+            (
+                "const a = 1; let a = 2;",
+                "cannot redefine constant: a",
+                ErrorKind::ConstantError,
+            ),
+        ]);
+
+        for (input, expected_message, expected_kind) in tests {
+            let evaluated = test_eval(input.to_string());
+            let error = test_error_object(&evaluated);
+
+            assert_eq!(
+                error.message, expected_message,
+                "wrong error message. got={}",
+                error.message
+            );
+            assert_eq!(
+                error.kind, expected_kind,
+                "wrong error kind. got={}",
+                error.kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_structs() {
+        let int_tests = Vec::from([
+            ("struct Point { x, y } let p = Point(1, 2); p.x", 1),
+            ("struct Point { x, y } let p = Point(1, 2); p.y", 2),
+            ("struct Point { x, y } let p = Point(1, 2); p.x = 5; p.x + p.y", 7),
+            ("struct Point { x, y } let p = Point(1, 2); p.y = p.x = 3", 3),
+            ("struct Point { x, y } let p = Point(1, 2); let q = p; q.x = 9; p.x", 9),
+            (
+                "struct Line { from, to } struct Point { x, y } let l = Line(Point(1, 2), Point(3, 4)); l.to.y",
+                4,
+            ),
+        ]);
+
+        for (input, expected) in int_tests {
+            let evaluated = test_eval(input.to_string());
+            test_int_object(evaluated, expected)
+        }
+
+        let inspect_tests = Vec::from([
+            ("struct Point { x, y } Point(1, 2)", "Point { x: 1, y: 2 }"),
+            ("struct Point { x, y } Point", "struct Point { x, y }"),
+            (
+                "struct Wrapper { inner } Wrapper([1, \"a\"])",
+                "Wrapper { inner: [1, a] }",
+            ),
+        ]);
+
+        for (input, expected) in inspect_tests {
+            let evaluated = test_eval(input.to_string());
+            assert_eq!(evaluated.inspect(), expected, "wrong inspect for {}", input);
+        }
+
+        let error_tests = Vec::from([
+            (
+                "struct Point { x, y } Point(1, 2).z",
+                "struct Point has no field named z",
+                ErrorKind::NameError,
+            ),
+            (
+                "struct Point { x, y } let p = Point(1, 2); p.z = 3",
+                "struct Point has no field named z",
+                ErrorKind::NameError,
+            ),
+            (
+                "struct Point { x, y } Point(1)",
+                "wrong number of arguments for Point. got=1, want=2",
+                ErrorKind::ArgumentError,
+            ),
+            (
+                "let h = {}; h.x = 1",
+                "field assignment not supported: HASH",
+                ErrorKind::TypeError,
+            ),
+            (
+                "const Point = 1; struct Point { x }",
+                "cannot redefine constant: Point",
+                ErrorKind::ConstantError,
+            ),
+        ]);
+
+        for (input, expected_message, expected_kind) in error_tests {
+            let evaluated = test_eval(input.to_string());
+            let error = test_error_object(&evaluated);
+
+            assert_eq!(error.message, expected_message, "wrong error for {}", input);
+            assert_eq!(error.kind, expected_kind, "wrong error kind for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_enums() {
+        let shapes = "enum Shape { Circle(r), Rect(w, h), Empty }
+            let area = fn(s) {
+                match (s) {
+                    Circle(r) => 3 * r * r,
+                    Shape.Rect(w, h) => w * h,
+                    Shape.Empty => 0,
+                }
+            };";
+
+        let int_tests = Vec::from([
+            ("area(Circle(2))", 12),
+            ("area(Rect(2, 5))", 10),
+            ("area(Shape.Rect(3, 3))", 9),
+            ("area(Empty)", 0),
+            ("Rect(2, 5).h", 5),
+            ("match (Circle(1)) { Rect(w, h) => 1, Circle(2) => 2, Circle(1) => 3 }", 3),
+            ("match (Circle(4)) { Empty => 0, Circle(r) => r }", 4),
+            ("match (Empty) { Empty => 1, _ => 0 }", 1),
+            ("enum Opt { Some(v), None } match (Some(1)) { None => 0, Some(v) => v }", 1),
+            ("enum Opt { some(v), none } match (some(1)) { none => 0, some(v) => v }", 1),
+            ("match (5) { X => 1, _ => 2 }", 1),
+            ("let f = fn(Empty) { match (4) { Empty => Empty, _ => 0 } }; f(2)", 4),
+            ("if (Circle(1) == Shape.Circle(1)) { 1 } else { 0 }", 1),
+            ("if (Circle(1) != Circle(2)) { 1 } else { 0 }", 1),
+            ("if (Empty == Shape.Empty) { 1 } else { 0 }", 1),
+            ("if (Empty == 0) { 1 } else { 0 }", 0),
+        ]);
+
+        for (input, expected) in int_tests {
+            let evaluated = test_eval(format!("{} {}", shapes, input));
+            test_int_object(evaluated, expected)
+        }
+
+        let inspect_tests = Vec::from([
+            ("Circle(2)", "Circle(2)"),
+            ("Rect(1, [2])", "Rect(1, [2])"),
+            ("Empty", "Empty"),
+            ("Circle", "Shape.Circle(r)"),
+            ("Shape", "enum Shape { Circle(r), Rect(w, h), Empty }"),
+        ]);
+
+        for (input, expected) in inspect_tests {
+            let evaluated = test_eval(format!("{} {}", shapes, input));
+            assert_eq!(evaluated.inspect(), expected, "wrong inspect for {}", input);
+        }
+
+        let error_tests = Vec::from([
+            (
+                "Circle(1, 2)",
+                "wrong number of arguments for Circle. got=2, want=1",
+                ErrorKind::ArgumentError,
+            ),
+            (
+                "Shape.Square",
+                "enum Shape has no variant named Square",
+                ErrorKind::NameError,
+            ),
+            (
+                "Circle(1).w",
+                "variant Circle has no field named w",
+                ErrorKind::NameError,
+            ),
+            ("Circle(1) + 1", "type mismatch: ENUM + INTEGER", ErrorKind::TypeError),
+            ("Shape(1)", "not a function TYPE", ErrorKind::TypeError),
+        ]);
+
+        for (input, expected_message, expected_kind) in error_tests {
+            let evaluated = test_eval(format!("{} {}", shapes, input));
+            let error = test_error_object(&evaluated);
+
+            assert_eq!(error.message, expected_message, "wrong error for {}", input);
+            assert_eq!(error.kind, expected_kind, "wrong error kind for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_enum_patterns_across_lines() {
         let env = Rc::new(RefCell::new(Environment::new()));
+        let tests = Vec::from([
+            ("enum Opt { some(v), none }", None),
+            ("match (some(1)) { none => 0, some(v) => v }", Some("1")),
+            ("match (none) { some(v) => v, none => 0 }", Some("0")),
+            ("match (some(none)) { some(none) => 1, _ => 2 }", Some("1")),
+            ("match (5) { none => 1, x => x }", Some("5")),
+            ("match (5) { X => 1, _ => 2 }", Some("1")),
+            ("let check = fn(none) { match (3) { none => 1, _ => 2 } }; check(3)", Some("1")),
+            ("check(none)", Some("2")),
+            ("let none = 7; match (5) { none => none }", Some("5")),
+        ]);
 
-        eval(program.as_node(), env).unwrap()
+        for (input, expected) in tests {
+            let program = Parser::new(Lexer::new(input.to_string())).parse_program();
+            let evaluated = eval(&program, env.clone()).map(|value| value.inspect());
+            assert_eq!(evaluated.as_deref(), expected, "wrong result for {}", input);
+        }
     }
 
-    fn test_int_object(obj: Rc<dyn Object>, expected: i64) {
-        let int_obj = obj.as_int();
-        let int_obj_type_name = type_name_of_val(&int_obj).split("::").last().unwrap();
+    #[test]
+    fn test_methods() {
+        let point = "struct Point { x, y }
+            impl Point {
+                fn norm(self) { self.x * self.x + self.y * self.y }
+                fn scale(self, k) { Point(self.x * k, self.y * k) }
+                fn shift(self, dx) { self.x = self.x + dx; self }
+                fn origin() { Point(0, 0) }
+            }";
+
+        let int_tests = Vec::from([
+            ("Point(1, 2).norm()", 5),
+            ("let p = Point(1, 2); p.scale(3).norm()", 45),
+            ("let p = Point(1, 2); p.shift(4); p.x", 5),
+            ("Point.origin().norm()", 0),
+            ("Point.norm(Point(2, 0))", 4),
+            ("impl Point { fn norm(self) { 42 } } Point(1, 2).norm()", 42),
+            ("struct Box { x } impl Box { fn x(self) { 0 } } Box(fn() { 7 }).x()", 7),
+            (
+                "enum Shape { Circle(r), Square(s) }
+                impl Shape {
+                    fn area(self) { match (self) { Circle(r) => 3 * r * r, Square(s) => s * s } }
+                }
+                Circle(2).area() + Square(3).area()",
+                21,
+            ),
+            ("let k = 10; impl Point { fn add_k(self) { self.x + k } } Point(1, 2).add_k()", 11),
+        ]);
+
+        for (input, expected) in int_tests {
+            let evaluated = test_eval(format!("{} {}", point, input));
+            test_int_object(evaluated, expected)
+        }
+
+        let error_tests = Vec::from([
+            (
+                "Point(1, 2).length()",
+                "struct Point has no field named length",
+                ErrorKind::NameError,
+            ),
+            (
+                "Point(1, 2).origin()",
+                "struct Point has no field named origin",
+                ErrorKind::NameError,
+            ),
+            (
+                "Point.length()",
+                "struct Point has no method named length",
+                ErrorKind::NameError,
+            ),
+            ("impl Nope { }", "identifier not found: Nope", ErrorKind::NameError),
+            (
+                "let n = 1; impl n { fn a(self) { 1 } }",
+                "cannot implement methods for INTEGER",
+                ErrorKind::TypeError,
+            ),
+        ]);
+
+        for (input, expected_message, expected_kind) in error_tests {
+            let evaluated = test_eval(format!("{} {}", point, input));
+            let error = test_error_object(&evaluated);
+
+            assert_eq!(error.message, expected_message, "wrong error for {}", input);
+            assert_eq!(error.kind, expected_kind, "wrong error kind for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_tail_calls() {
+        let tests = Vec::from([
+            (
+                "let count = fn(n, acc) { if (n == 0) { acc } else { count(n - 1, acc + 1) } }; count(100000, 0)",
+                100000,
+            ),
+            (
+                "let count = fn(n) { if (n == 0) { return 0; } return count(n - 1); }; count(20000)",
+                0,
+            ),
+            (
+                "let is_even = fn(n) { if (n == 0) { true } else { is_odd(n - 1) } };
+                let is_odd = fn(n) { if (n == 0) { false } else { is_even(n - 1) } };
+                if (is_even(20001)) { 1 } else { 2 }",
+                2,
+            ),
+            (
+                "let sum = fn(n, acc) { match (n) { 0 => acc, _ => sum(n - 1, acc + n) } }; sum(20000, 0)",
+                200010000,
+            ),
+            (
+                "struct Counter { n }
+                impl Counter { fn run(self, steps) { if (steps == 0) { self.n } else { self.n = self.n + 1; self.run(steps - 1) } } }
+                Counter(0).run(20000)",
+                20000,
+            ),
+            (
+                "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+                610,
+            ),
+            ("let f = fn() { let x = 1; }; let g = fn() { f(); 5 }; g()", 5),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input.to_string());
+            test_int_object(evaluated, expected)
+        }
 
+        let evaluated = test_eval(
+            "let f = fn(n) { if (n == 0) { len(1) } else { f(n - 1) } }; f(20000)".to_string(),
+        );
         assert_eq!(
-            "Integer", int_obj_type_name,
-            "object is not Integer. got={}",
-            int_obj_type_name
+            test_error_object(&evaluated).message,
+            "argument to `len` not supported, got INTEGER"
         );
+    }
 
+    #[test]
+    fn test_call_depth_limit() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        env.borrow().limits().max_call_depth.set(50);
+        run("let down = fn(n) { if (n == 0) { 0 } else { 1 + down(n - 1) } };", &env);
+        test_int_object(run("down(49)", &env), 49);
+
+        let evaluated = run("let outer = fn() { 1 + down(100) }; outer()", &env);
         assert_eq!(
-            expected, int_obj.value,
-            "object has wrong value. got={}, want={}",
-            expected, int_obj.value
-        )
+            test_error_object(&evaluated).message,
+            "maximum call depth 50 exceeded (call chain: outer -> down x50)"
+        );
+
+        let evaluated = run("try { down(100) } catch (e) { e.message }", &env);
+        assert_eq!(evaluated.inspect(), "maximum call depth 50 exceeded (call chain: down x51)");
+
+        // tail calls don't nest, and the failed calls left nothing behind
+        test_int_object(run("let count = fn(n, acc) { if (n == 0) { acc } else { count(n - 1, acc + 1) } }; count(1000, 0)", &env), 1000);
+        assert_eq!(env.borrow().limits().call_depth(), 0);
     }
 
-    fn test_bool_object(obj: Rc<dyn Object>, expected: bool) {
-        let bool_obj = obj.as_bool();
-        let bool_obj_type_name = type_name_of_val(&bool_obj).split("::").last().unwrap();
+    #[test]
+    fn test_deep_recursion_on_a_small_stack() {
+        // the default call depth limit needs more stack than the thread has
+        let evaluated = thread::Builder::new()
+            .stack_size(2 << 20)
+            .spawn(|| {
+                let env = Rc::new(RefCell::new(Environment::new()));
+                run("let down = fn(n) { if (n == 0) { 0 } else { 1 + down(n - 1) } };", &env);
+                ["down(100000)", "try { down(100000) } catch (e) { -1 }", "down(10)"].map(|input| run(input, &env).inspect())
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert!(evaluated[0].starts_with("ERROR: stack exhausted at call depth "), "wrong error {}", evaluated[0]);
+        assert_eq!(evaluated[1..], ["-1", "10"]);
+    }
 
-        assert_eq!(
-            "Boolean", bool_obj_type_name,
-            "object is not Boolean. got={}",
-            bool_obj_type_name
+    #[test]
+    fn test_evaluation_budget() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        run("let forever = fn(n) { forever(n + 1) }; let total = 5;", &env);
+        env.borrow().limits().max_steps.set(Some(10000));
+        for input in ["forever(0)", "try { forever(0) } catch (e) { 1 }", "try { forever(0) } catch (e) { }; total"] {
+            let evaluated = run(input, &env);
+            assert_eq!(test_error_object(&evaluated).message, "budget exhausted", "wrong error for {}", input);
+        }
+
+        // every eval starts with a fresh budget, and the environment is untouched
+        test_int_object(run("total + 1", &env), 6);
+
+        env.borrow().limits().max_steps.set(None);
+        env.borrow().limits().time_limit.set(Some(Duration::from_millis(20)));
+        let evaluated = run("forever(0)", &env);
+        assert_eq!(test_error_object(&evaluated).message, "budget exhausted");
+        test_int_object(run("total", &env), 5);
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        run("let forever = fn(n) { forever(n + 1) }; let total = 5;", &env);
+        let handle = env.borrow().limits().interrupt_handle();
+        // an interrupt between evals doesn't cancel the next one
+        handle.interrupt();
+        test_int_object(run("total", &env), 5);
+
+        // keeps interrupting until the eval is over, in case the first one lands before it starts
+        for input in ["forever(0)", "try { forever(0) } catch (e) { 1 }"] {
+            let (interrupter, done) = (handle.clone(), Arc::new(AtomicBool::new(false)));
+            let thread = thread::spawn({
+                let done = done.clone();
+                move || {
+                    while !done.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(10));
+                        interrupter.interrupt();
+                    }
+                }
+            });
+            let evaluated = run(input, &env);
+            done.store(true, Ordering::Relaxed);
+            thread.join().unwrap();
+            assert_eq!(test_error_object(&evaluated).message, "interrupted", "wrong error for {}", input);
+        }
+
+        test_int_object(run("total + 1", &env), 6);
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        env.borrow().limits().max_memory.set(Some(1 << 20));
+        run("let double = fn(s, n) { if (n == 0) { len(s) } else { double(s + s, n - 1) } };
+            let grow = fn(a, n) { if (n == 0) { len(a) } else { grow(push(a, n), n - 1) } };", &env);
+        test_int_object(run("double(\"ab\", 10)", &env), 2048);
+        test_int_object(run("grow([], 100)", &env), 100);
+
+        for input in ["double(\"ab\", 40)", "grow([], 100000)"] {
+            let evaluated = run(input, &env);
+            assert_eq!(test_error_object(&evaluated).message, "memory limit exceeded", "wrong error for {}", input);
+        }
+
+        let evaluated = run("try { double(\"ab\", 40) } catch (e) { e.message }", &env);
+        assert_eq!(evaluated.inspect(), "memory limit exceeded");
+
+        // the quota covers everything the interpreter creates, not just one eval
+        let evaluated = run("double(\"ab\", 10)", &env);
+        assert_eq!(test_error_object(&evaluated).message, "memory limit exceeded");
+
+        let env = Rc::new(RefCell::new(Environment::new()));
+        env.borrow().limits().max_memory.set(Some(1 << 16));
+        run("let grow = fn(a, n) { if (n == 0) { len(a) } else { grow(push(a, n), n - 1) } };", &env);
+        test_int_object(run("grow([], 20)", &env), 20);
+        let exceeded = (0..100).map(|_| run("grow([], 20)", &env)).any(|evaluated| match evaluated {
+            Value::Error(error) => error.message == "memory limit exceeded",
+            _ => false,
+        });
+        assert!(exceeded, "memory limit not reached over several evals");
+    }
+
+    #[test]
+    fn test_generators() {
+        let collect = "let collect = fn(g, acc) {
+                let value = g.next();
+                if (g.done) { acc } else { collect(g, push(acc, value)) }
+            };";
+
+        let tests = Vec::from([
+            ("let g = fn*() { yield 1; yield 2; }; collect(g(), [])", "[1, 2]"),
+            ("let g = fn*(n) { yield n; yield n * 2; }(5); [next(g), next(g), next(g)]", "[5, 10, null]"),
+            ("let g = fn*() { yield 1; return 5; yield 2; }; collect(g(), [])", "[1]"),
+            (
+                "let g = fn*(n) { if (n > 0) { yield 1; yield 2; } else { yield 3; } yield 4; }; [collect(g(1), []), collect(g(0), [])]",
+                "[[1, 2, 4], [3, 4]]",
+            ),
+            (
+                "let g = fn*(x) { match (x) { [a, b] => { yield b; yield a; }, _ => { yield 0; } } }; collect(g([1, 2]), [])",
+                "[2, 1]",
+            ),
+            ("let g = fn*() { yield* [1, 2]; yield* fn*() { yield 3; }(); yield 4; }; collect(g(), [])", "[1, 2, 3, 4]"),
+            (
+                "let count = 0;
+                let g = fn*() { let count = 1; yield count; let count = 2; yield count; };
+                let it = g();
+                [it.next(), count, it.next()]",
+                "[1, 0, 2]",
+            ),
+            (
+                "let naturals = fn*(n) { yield n; yield* naturals(n + 1); };
+                let take = fn(g, n, acc) { if (n == 0) { acc } else { take(g, n - 1, push(acc, g.next())) } };
+                take(naturals(1), 5, [])",
+                "[1, 2, 3, 4, 5]",
+            ),
+            ("let g = fn*() { yield 1; }(); g", "generator"),
+            ("let g = fn*() { yield 1; }(); g.next(); g.next(); g", "generator (done)"),
+            ("fn*(x) { yield x; }", "fn*(x) {\n yield x; \n}"),
+            (
+                "struct Range { from, to }
+                impl Range {
+                    fn* iter(self) { if (self.from < self.to) { yield self.from; yield* Range(self.from + 1, self.to).iter(); } }
+                }
+                collect(Range(3, 6).iter(), [])",
+                "[3, 4, 5]",
+            ),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(format!("{} {}", collect, input));
+            assert_eq!(evaluated.inspect(), expected, "wrong result for {}", input);
+        }
+
+        let evaluated = test_eval(
+            "let pages = fn*(n) { if (n < 20000) { yield n; yield* pages(n + 1); } };
+            let drain = fn(g, last) { let value = g.next(); if (g.done) { last } else { drain(g, value) } };
+            drain(pages(0), 0)"
+                .to_string(),
         );
+        test_int_object(evaluated, 19999);
+
+        let error_tests = Vec::from([
+            ("let g = fn*() { yield 1 + true; }(); g.next()", "type mismatch: INTEGER + BOOLEAN"),
+            ("let g = fn*() { yield* 5; }(); g.next()", "cannot delegate to INTEGER"),
+            ("let g = fn*() { yield g.next(); }(); g.next()", "generator is already running"),
+            ("next(1)", "argument to `next` must be GENERATOR, got=INTEGER"),
+            ("fn*() { yield 1; }().value", "generator has no property named value"),
+        ]);
 
-        assert_eq!(
-            expected, bool_obj.value,
-            "object has wrong value. got={}, want={}",
-            expected, bool_obj.value
-        )
+        for (input, expected) in error_tests {
+            let evaluated = test_eval(input.to_string());
+            assert_eq!(
+                test_error_object(&evaluated).message,
+                expected,
+                "wrong error for {}",
+                input
+            );
+        }
+    }
+
+    fn run(input: &str, env: &Rc<RefCell<Environment>>) -> Value {
+        let program = Parser::new(Lexer::new(input.to_string())).parse_program();
+        eval(&program, env.clone()).unwrap_or(Value::Null)
     }
 
-    fn test_null_object(obj: Rc<dyn Object>) {
-        if obj.try_into_null().is_err() {
+    // every program also runs resolved, through the optimiser and on the VM, which must
+    // not change its result
+    fn test_eval(input: String) -> Value {
+        let program = Parser::new(Lexer::new(input.clone())).parse_program();
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let evaluated = eval(&program, env).unwrap();
+
+        // a program with undefined names still runs, the names are looked up by hashing them
+        let resolved = Parser::new(Lexer::new(input.clone())).parse_program();
+        let _ = resolve(&resolved, Vec::new());
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let resolved_evaluated = eval(&resolved, env).unwrap();
+        assert_same_result(&resolved_evaluated, &evaluated, "resolved program", &input);
+
+        let optimized = optimize(&program);
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let optimized_evaluated = eval(&optimized, env).unwrap();
+        assert_same_result(&optimized_evaluated, &evaluated, "optimised program", &input);
+
+        let compiled = Compiler::new().compile(&optimized).unwrap();
+        let vm_evaluated = Vm::new().run(compiled).unwrap();
+        assert_same_result(&vm_evaluated, &evaluated, "vm", &input);
+
+        evaluated
+    }
+
+    fn assert_same_result(got: &Value, expected: &Value, what: &str, input: &str) {
+        assert_eq!(got.get_type(), expected.get_type(), "{} result differs for {}", what, input);
+        // hash pairs print in iteration order, which differs between two runs
+        if expected.get_type() != ObjectType::HASH {
+            assert_eq!(got.inspect(), expected.inspect(), "{} behaves differently for {}", what, input);
+        }
+    }
+
+    fn test_int_object(obj: Value, expected: i64) {
+        match obj {
+            Value::Integer(value) => assert_eq!(
+                expected, value,
+                "object has wrong value. got={}, want={}",
+                value, expected
+            ),
+            _ => panic!("object is not Integer. got={}", obj.get_type()),
+        }
+    }
+
+    fn test_bool_object(obj: Value, expected: bool) {
+        match obj {
+            Value::Boolean(value) => assert_eq!(
+                expected, value,
+                "object has wrong value. got={}, want={}",
+                value, expected
+            ),
+            _ => panic!("object is not Boolean. got={}", obj.get_type()),
+        }
+    }
+
+    fn test_null_object(obj: Value) {
+        if !matches!(obj, Value::Null) {
             eprintln!("object is not NULL");
         }
     }
+
+    fn test_error_object(obj: &Value) -> &object::Error {
+        match obj {
+            Value::Error(error) => error,
+            _ => panic!("object is not Error. got={}", obj.get_type()),
+        }
+    }
 }
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod gc_tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        evaluator::eval,
+        lexer::Lexer,
+        object::{gc, Environment, Value},
+        parser::Parser,
+    };
+
+    fn run(input: &str, env: &Rc<RefCell<Environment>>) -> Option<Value> {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+        eval(&program, env.clone())
+    }
+
+    const MAKE_COUNTER: &str = "let make = fn() {
+        let count = fn(n) { if (n == 0) { 0 } else { count(n - 1) } };
+        count
+    };";
+
+    #[test]
+    fn test_recursive_closures_are_reclaimed() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        run(MAKE_COUNTER, &env);
+
+        // every call to make leaves its environment in a cycle with count
+        for _ in 0..5000 {
+            run("let counter = make(); counter(3);", &env);
+        }
+        assert!(
+            gc::live_environments() < 2048,
+            "environments weren't collected automatically, {} alive",
+            gc::live_environments()
+        );
+
+        gc::collect();
+        // the top-level environment and the one `counter` was defined in
+        assert_eq!(gc::live_environments(), 2);
+
+        match run("counter(10)", &env) {
+            Some(Value::Integer(value)) => assert_eq!(value, 0),
+            other => panic!("expected an integer, got {:?}", other.map(|value| value.inspect())),
+        }
+    }
+
+    #[test]
+    fn test_dropped_environment_is_reclaimed() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        run(MAKE_COUNTER, &env);
+        let counter = run("let counter = make(); counter", &env).unwrap();
+        let closure_env = match &counter {
+            Value::Function(function) => Rc::downgrade(&function.env),
+            other => panic!("expected a function, got {}", other.inspect()),
+        };
+        let top_level = Rc::downgrade(&env);
+
+        gc::collect();
+        assert!(closure_env.upgrade().is_some());
+
+        drop(env);
+        drop(counter);
+        assert!(top_level.upgrade().is_some(), "the top level isn't in a cycle");
+
+        gc::collect();
+        assert!(top_level.upgrade().is_none());
+        assert!(closure_env.upgrade().is_none());
+        assert_eq!(gc::live_environments(), 0);
+    }
+}
@@ -7,6 +7,8 @@ mod lexer_tests {
         Token {
             token_type,
             literal: String::from(literal),
+            line: 0,
+            column: 0,
         }
     }
 
@@ -35,6 +37,16 @@ mod lexer_tests {
             \"foo bar\"
             [1, 2];
             { \"foo\": \"bar\" }
+            match (x) { [a, ...b] | _ => a }
+            const MAX = 3;
+            import \"lib/util.rs_script\" as util;
+            export let x = util.y;
+            try { throw x; } catch (e) {} finally {}
+            struct Point { x, y }
+            enum Shape { Circle(r) }
+            impl Shape {}
+            fn*() { yield 1; }
+            fn(x: int) -> int {}
             ",
         );
 
@@ -144,6 +156,103 @@ mod lexer_tests {
             new_token(TokenType::COLON, ":"),
             new_token(TokenType::STRING, "bar"),
             new_token(TokenType::RBRACE, "}"),
+            // match (x) { [a, ...b] | _ => a }
+            new_token(TokenType::MATCH, "match"),
+            new_token(TokenType::LPAREN, "("),
+            new_token(TokenType::IDENT, "x"),
+            new_token(TokenType::RPAREN, ")"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::LBRACKET, "["),
+            new_token(TokenType::IDENT, "a"),
+            new_token(TokenType::COMMA, ","),
+            new_token(TokenType::ELLIPSIS, "..."),
+            new_token(TokenType::IDENT, "b"),
+            new_token(TokenType::RBRACKET, "]"),
+            new_token(TokenType::PIPE, "|"),
+            new_token(TokenType::IDENT, "_"),
+            new_token(TokenType::ARROW, "=>"),
+            new_token(TokenType::IDENT, "a"),
+            new_token(TokenType::RBRACE, "}"),
+            // const MAX = 3;
+            new_token(TokenType::CONST, "const"),
+            new_token(TokenType::IDENT, "MAX"),
+            new_token(TokenType::ASSIGN, "="),
+            new_token(TokenType::INT, "3"),
+            new_token(TokenType::SEMICOLON, ";"),
+            // import \"lib/util.rs_script\" as util;
+            new_token(TokenType::IMPORT, "import"),
+            new_token(TokenType::STRING, "lib/util.rs_script"),
+            new_token(TokenType::AS, "as"),
+            new_token(TokenType::IDENT, "util"),
+            new_token(TokenType::SEMICOLON, ";"),
+            // export let x = util.y;
+            new_token(TokenType::EXPORT, "export"),
+            new_token(TokenType::LET, "let"),
+            new_token(TokenType::IDENT, "x"),
+            new_token(TokenType::ASSIGN, "="),
+            new_token(TokenType::IDENT, "util"),
+            new_token(TokenType::DOT, "."),
+            new_token(TokenType::IDENT, "y"),
+            new_token(TokenType::SEMICOLON, ";"),
+            // try { throw x; } catch (e) {} finally {}
+            new_token(TokenType::TRY, "try"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::THROW, "throw"),
+            new_token(TokenType::IDENT, "x"),
+            new_token(TokenType::SEMICOLON, ";"),
+            new_token(TokenType::RBRACE, "}"),
+            new_token(TokenType::CATCH, "catch"),
+            new_token(TokenType::LPAREN, "("),
+            new_token(TokenType::IDENT, "e"),
+            new_token(TokenType::RPAREN, ")"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::RBRACE, "}"),
+            new_token(TokenType::FINALLY, "finally"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::RBRACE, "}"),
+            // struct Point { x, y }
+            new_token(TokenType::STRUCT, "struct"),
+            new_token(TokenType::IDENT, "Point"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::IDENT, "x"),
+            new_token(TokenType::COMMA, ","),
+            new_token(TokenType::IDENT, "y"),
+            new_token(TokenType::RBRACE, "}"),
+            // enum Shape { Circle(r) }
+            new_token(TokenType::ENUM, "enum"),
+            new_token(TokenType::IDENT, "Shape"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::IDENT, "Circle"),
+            new_token(TokenType::LPAREN, "("),
+            new_token(TokenType::IDENT, "r"),
+            new_token(TokenType::RPAREN, ")"),
+            new_token(TokenType::RBRACE, "}"),
+            // impl Shape {}
+            new_token(TokenType::IMPL, "impl"),
+            new_token(TokenType::IDENT, "Shape"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::RBRACE, "}"),
+            // fn*() { yield 1; }
+            new_token(TokenType::FUNCTION, "fn"),
+            new_token(TokenType::ASTERISK, "*"),
+            new_token(TokenType::LPAREN, "("),
+            new_token(TokenType::RPAREN, ")"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::YIELD, "yield"),
+            new_token(TokenType::INT, "1"),
+            new_token(TokenType::SEMICOLON, ";"),
+            new_token(TokenType::RBRACE, "}"),
+            // fn(x: int) -> int {}
+            new_token(TokenType::FUNCTION, "fn"),
+            new_token(TokenType::LPAREN, "("),
+            new_token(TokenType::IDENT, "x"),
+            new_token(TokenType::COLON, ":"),
+            new_token(TokenType::IDENT, "int"),
+            new_token(TokenType::RPAREN, ")"),
+            new_token(TokenType::RARROW, "->"),
+            new_token(TokenType::IDENT, "int"),
+            new_token(TokenType::LBRACE, "{"),
+            new_token(TokenType::RBRACE, "}"),
             // EOF
             new_token(TokenType::EOF, "\0"),
         ]);
@@ -167,5 +276,34 @@ mod lexer_tests {
             );
         }
     }
-}
 
+    #[test]
+    fn test_token_positions() {
+        let input = "let x = 5;\n  x == \"a\";".to_string();
+        let expected = Vec::from([
+            ("let", 1, 1),
+            ("x", 1, 5),
+            ("=", 1, 7),
+            ("5", 1, 9),
+            (";", 1, 10),
+            ("x", 2, 3),
+            ("==", 2, 5),
+            ("a", 2, 8),
+            (";", 2, 11),
+        ]);
+
+        let mut lexer = Lexer::new(input);
+
+        for (literal, line, column) in expected {
+            let token = lexer.next_token();
+
+            assert_eq!(token.literal, literal);
+            assert_eq!(
+                (token.line, token.column),
+                (line, column),
+                "wrong position for {}",
+                literal
+            );
+        }
+    }
+}
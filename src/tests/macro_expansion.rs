@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod macro_expansion_tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        ast::{Node, Program},
+        evaluator::{define_macros, expand_macros},
+        lexer::Lexer,
+        object::{Environment, ObjectType, Value},
+        parser::Parser,
+    };
+
+    #[test]
+    fn test_define_macros() {
+        let input = "
+            let number = 1;
+            let function = fn(x, y) { x + y };
+            let mymacro = macro(x, y) { x + y; };
+        "
+        .to_string();
+
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let mut program = test_parse_program(input);
+
+        define_macros(&mut program, env.clone());
+
+        assert_eq!(
+            program.statements.len(),
+            2,
+            "wrong number of statements. got={}",
+            program.statements.len()
+        );
+
+        assert!(env.borrow().get("number").is_err(), "number should not be defined");
+        assert!(
+            env.borrow().get("function").is_err(),
+            "function should not be defined"
+        );
+
+        let obj = env
+            .borrow()
+            .get("mymacro")
+            .expect("macro not in environment");
+
+        assert_eq!(
+            obj.get_type(),
+            ObjectType::MACRO,
+            "object is not Macro. got={}",
+            obj.get_type()
+        );
+
+        let Value::Macro(macro_obj) = obj else {
+            unreachable!()
+        };
+        assert_eq!(
+            macro_obj.parameters.len(),
+            2,
+            "wrong number of macro parameters. got={}",
+            macro_obj.parameters.len()
+        );
+        assert_eq!(macro_obj.parameters[0].to_string(), "x");
+        assert_eq!(macro_obj.parameters[1].to_string(), "y");
+        assert_eq!(macro_obj.body.to_string(), "(x + y)");
+    }
+
+    #[test]
+    fn test_expand_macros() {
+        let tests = Vec::from([
+            (
+                "let infixExpression = macro() { quote(1 + 2); }; infixExpression();",
+                "(1 + 2)",
+            ),
+            (
+                "let reverse = macro(a, b) { quote(unquote(b) - unquote(a)); }; reverse(2 + 2, 10 - 5);",
+                "(10 - 5) - (2 + 2)",
+            ),
+            (
+                "
+                let unless = macro(condition, consequence, alternative) {
+                    quote(if (!(unquote(condition))) {
+                        unquote(consequence);
+                    } else {
+                        unquote(alternative);
+                    });
+                };
+
+                unless(10 > 5, puts(\"not greater\"), puts(\"greater\"));
+                ",
+                "if (!(10 > 5)) { puts(\"not greater\") } else { puts(\"greater\") }",
+            ),
+        ]);
+
+        for (input, expected) in tests {
+            let expected = test_parse_program(expected.to_string());
+            let mut program = test_parse_program(input.to_string());
+
+            let env = Rc::new(RefCell::new(Environment::new()));
+            define_macros(&mut program, env.clone());
+            let expanded = expand_macros(&program, env).unwrap();
+
+            assert_eq!(
+                expanded.to_string(),
+                expected.to_string(),
+                "not equal. got={}, want={}",
+                expanded.to_string(),
+                expected.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_macros_errors() {
+        let tests = Vec::from([
+            (
+                "let m = macro(a) { quote(unquote(a)); }; m(1, 2);",
+                "wrong number of macro arguments. got=2, want=1",
+            ),
+            (
+                "let m = macro() { 1 }; m();",
+                "we only support returning AST-nodes from macros",
+            ),
+            ("let m = macro() { foobar }; m();", "identifier not found: foobar"),
+        ]);
+
+        for (input, expected) in tests {
+            let mut program = test_parse_program(input.to_string());
+
+            let env = Rc::new(RefCell::new(Environment::new()));
+            define_macros(&mut program, env.clone());
+
+            match expand_macros(&program, env) {
+                Ok(_) => panic!("expected macro expansion error for {}", input),
+                Err(errors) => assert_eq!(errors, vec![expected.to_string()]),
+            }
+        }
+    }
+
+    fn test_parse_program(input: String) -> Program {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        parser.parse_program()
+    }
+}
@@ -3,3 +3,13 @@ mod parser;
 mod ast;
 mod evaluator;
 mod object;
+mod gc;
+mod macro_expansion;
+mod modules;
+mod typecheck;
+mod resolver;
+mod optimizer;
+mod code;
+mod bytecode;
+mod cgen;
+mod vm;
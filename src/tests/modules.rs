@@ -0,0 +1,219 @@
+#[cfg(test)]
+mod modules_tests {
+    use std::{
+        cell::RefCell,
+        fs,
+        path::{Path, PathBuf},
+        rc::Rc,
+    };
+
+    use crate::{
+        evaluator::eval,
+        lexer::Lexer,
+        object::{Environment, Value},
+        parser::Parser,
+    };
+
+    #[test]
+    fn test_import_exported_bindings() {
+        let dir = test_dir("exported_bindings");
+        write_module(
+            &dir,
+            "lib/util.rs_script",
+            "
+            import \"helpers.rs_script\" as helpers;
+            export const MAX_RETRIES = 3;
+            export let add = fn(a, b) { helpers.twice(a) + b };
+            let hidden = 10;
+            ",
+        );
+        write_module(
+            &dir,
+            "lib/helpers.rs_script",
+            "export let twice = fn(x) { x * 2 };",
+        );
+
+        let tests = Vec::from([
+            ("util.MAX_RETRIES", 3),
+            ("util.add(2, 1)", 5),
+            ("util.add(util.MAX_RETRIES, 0)", 6),
+        ]);
+
+        for (input, expected) in tests {
+            let input = format!("import \"{}\" as util; {}", path(&dir, "lib/util.rs_script"), input);
+            let evaluated = test_eval(input, Rc::new(RefCell::new(Environment::new())));
+            assert!(
+                matches!(evaluated, Value::Integer(value) if value == expected),
+                "wrong value. got={}",
+                evaluated.inspect()
+            );
+        }
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let dir = test_dir("errors");
+        write_module(&dir, "a.rs_script", "import \"b.rs_script\" as b; export let x = 1;");
+        write_module(&dir, "b.rs_script", "import \"a.rs_script\" as a; export let y = 2;");
+        write_module(&dir, "broken.rs_script", "let x = ;");
+        write_module(&dir, "failing.rs_script", "let add = fn(a, b) { a + b }; export let x = add(5, true);");
+        write_module(&dir, "typed.rs_script", "export let x: int = \"five\";");
+        write_module(&dir, "undefined.rs_script", "export let x = y;");
+        write_module(&dir, "ok.rs_script", "export let x = 1; let hidden = 2;");
+
+        let a = path(&dir, "a.rs_script");
+        let b = path(&dir, "b.rs_script");
+
+        let tests = Vec::from([
+            (
+                format!("import \"{}\" as a;", a),
+                format!("import cycle detected: {} -> {} -> {}", a, b, a),
+            ),
+            (
+                format!("import \"{}\" as m; m.hidden", path(&dir, "ok.rs_script")),
+                format!(
+                    "module \"{}\" has no export named hidden",
+                    path(&dir, "ok.rs_script")
+                ),
+            ),
+            (
+                format!("import \"{}\" as m;", path(&dir, "broken.rs_script")),
+                format!(
+                    "parse errors in module \"{}\": no prefix parse function for ; found",
+                    path(&dir, "broken.rs_script")
+                ),
+            ),
+            (
+                format!("import \"{}\" as m;", path(&dir, "failing.rs_script")),
+                "type mismatch: INTEGER + BOOLEAN".to_string(),
+            ),
+            (
+                format!("import \"{}\" as m;", path(&dir, "typed.rs_script")),
+                format!(
+                    "type errors in module \"{}\": type mismatch: x is declared as int but got string",
+                    path(&dir, "typed.rs_script")
+                ),
+            ),
+            (
+                format!("import \"{}\" as m;", path(&dir, "undefined.rs_script")),
+                format!(
+                    "name errors in module \"{}\": identifier not found: y (line 1, column 16)",
+                    path(&dir, "undefined.rs_script")
+                ),
+            ),
+            ("5.foo".to_string(), "property access not supported: INTEGER".to_string()),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input, Rc::new(RefCell::new(Environment::new())));
+            let Value::Error(error) = &evaluated else {
+                panic!("object is not Error. got={}", evaluated.get_type());
+            };
+            let msg = error.message.clone();
+            assert_eq!(msg, expected, "wrong error message. got={}", msg);
+        }
+
+        let evaluated = test_eval(
+            "import \"missing.rs_script\" as m;".to_string(),
+            Rc::new(RefCell::new(Environment::new())),
+        );
+        let Value::Error(error) = &evaluated else {
+            panic!("object is not Error. got={}", evaluated.get_type());
+        };
+        let msg = error.message.clone();
+        assert!(
+            msg.starts_with("cannot import \"missing.rs_script\""),
+            "wrong error message. got={}",
+            msg
+        );
+    }
+
+    #[test]
+    fn test_modules_are_cached() {
+        let dir = test_dir("cached");
+        write_module(&dir, "counter.rs_script", "export let items = [1, 2, 3];");
+
+        let module_path = path(&dir, "counter.rs_script");
+        let env = Rc::new(RefCell::new(Environment::new()));
+
+        let program = Parser::new(Lexer::new(format!("import \"{}\" as first;", module_path)))
+            .parse_program();
+        eval(&program, env.clone());
+        write_module(&dir, "counter.rs_script", "export let items = [];");
+        let evaluated = test_eval(
+            format!("import \"{}\" as second; len(second.items)", module_path),
+            env.clone(),
+        );
+
+        assert!(
+            matches!(evaluated, Value::Integer(3)),
+            "module was evaluated twice. got={}",
+            evaluated.inspect()
+        );
+        assert_eq!(env.borrow().modules().borrow().modules.len(), 1);
+
+        let first = env.borrow().get("first").unwrap();
+        let second = env.borrow().get("second").unwrap();
+        let (Value::Module(first), Value::Module(second)) = (first, second) else {
+            panic!("imports did not bind modules");
+        };
+        assert!(Rc::ptr_eq(&first, &second), "imports returned different modules");
+    }
+
+    #[test]
+    fn test_imports_relative_to_the_script() {
+        let dir = test_dir("script");
+        write_module(&dir, "lib/util.rs_script", "export let x = 4;");
+        let script = dir.join("main.rs_script");
+        write_module(&dir, "main.rs_script", "");
+
+        let tests = Vec::from([
+            ("import \"lib/util.rs_script\" as util; util.x", "4".to_string()),
+            (
+                "import \"main.rs_script\" as main;",
+                format!("import cycle detected: {} -> {}", script.display(), script.display()),
+            ),
+        ]);
+
+        for (input, expected) in tests {
+            let env = Rc::new(RefCell::new(Environment::new()));
+            env.borrow().modules().borrow_mut().enter_script(&script).unwrap();
+
+            let evaluated = test_eval(input.to_string(), env);
+            let result = match &evaluated {
+                Value::Error(error) => error.message.clone(),
+                value => value.inspect(),
+            };
+            assert_eq!(result, expected, "wrong result for {}", input);
+        }
+    }
+
+    fn test_eval(input: String, env: Rc<RefCell<Environment>>) -> Value {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        eval(&program, env).unwrap()
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rust_script_modules_{}_{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::canonicalize(dir).unwrap()
+    }
+
+    fn write_module(dir: &Path, name: &str, source: &str) {
+        let file = dir.join(name);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, source).unwrap();
+    }
+
+    fn path(dir: &Path, name: &str) -> String {
+        dir.join(name).display().to_string()
+    }
+}
@@ -4,18 +4,10 @@ mod object_tests {
 
     #[test]
     fn test_string_hash_key() {
-        let hello1 = StringObj {
-            value: "Hello World".to_string(),
-        };
-        let hello2 = StringObj {
-            value: "Hello World".to_string(),
-        };
-        let diff1 = StringObj {
-            value: "My name is johnny".to_string(),
-        };
-        let diff2 = StringObj {
-            value: "My name is johnny".to_string(),
-        };
+        let hello1 = Value::String("Hello World".into());
+        let hello2 = Value::String("Hello World".into());
+        let diff1 = Value::String("My name is johnny".into());
+        let diff2 = Value::String("My name is johnny".into());
 
         assert_eq!(
             hello1.hash_key(),
@@ -33,5 +25,10 @@ mod object_tests {
             diff1.hash_key(),
             "strings with different content have same hash keys"
         );
+
+        let keys = ["a", "i", "q", "y", "key", "kez"].map(|key| Value::String(key.into()).hash_key().unwrap());
+        for (idx, key) in keys.iter().enumerate() {
+            assert!(!keys[..idx].contains(key), "strings with different content have same hash keys");
+        }
     }
 }
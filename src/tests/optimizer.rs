@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod optimizer_tests {
+    use crate::{ast::Node, lexer::Lexer, optimizer::optimize, parser::Parser};
+
+    #[test]
+    fn test_constant_folding() {
+        let tests = Vec::from([
+            ("60 * 60 * 24", "86400"),
+            ("\"a\" + \"b\"", "ab"),
+            ("\"a\" == \"b\"", "false"),
+            ("let f = fn(x) { x * (2 + 3) };", "let f = fn ( x ) (x * 5);"),
+            ("-(1 + 2)", "-3"),
+            ("!true == false", "true"),
+            ("!5", "false"),
+            ("1 < 2", "true"),
+            ("x + 1 + 2", "((x + 1) + 2)"),
+            ("1 + true", "(1 + true)"),
+            ("10 / 0", "(10 / 0)"),
+            ("9223372036854775807 + 1", "(9223372036854775807 + 1)"),
+            ("quote(1 + 2)", "quote((1 + 2))"),
+            ("quote(unquote(1 + 2) + 3)", "quote((unquote((1 + 2)) + 3))"),
+        ]);
+
+        for (input, expected) in tests {
+            assert_eq!(optimize_input(input), expected, "wrong program for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_branch_pruning() {
+        let tests = Vec::from([
+            ("if (true) { 1 } else { 2 }", "1"),
+            ("if (1 > 2) { 1 } else { x }", "x"),
+            ("if (0) { 1 }", "if 0 "),
+            ("if (true) { let y = 1; y } else { 2 }", "if true let y = 1;y"),
+            ("if (false) { 1 } else { let y = 2; y }", "else let y = 2;y"),
+            ("if (x) { 1 + 1 }", "if x 2"),
+        ]);
+
+        for (input, expected) in tests {
+            assert_eq!(optimize_input(input), expected, "wrong program for {}", input);
+        }
+    }
+
+    fn optimize_input(input: &str) -> String {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(parser.get_errors().is_empty(), "parser errors for {}", input);
+
+        optimize(&program).to_string()
+    }
+}
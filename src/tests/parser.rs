@@ -3,11 +3,26 @@ mod parser_tests {
     use std::{any::Any, collections::HashMap, rc::Rc};
 
     use crate::{
-        ast::{Expression, LetStatement, Node, NodeType},
+        ast::{Expr, Identifier, LetStatement, Node, Stmt},
         lexer::Lexer,
         parser::Parser,
     };
 
+    // the node inside an ast enum variant, failing the test for any other variant
+    macro_rules! cast {
+        ($node:expr, $variant:path) => {
+            match $node.as_ref() {
+                $variant(inner) => inner,
+                other => panic!(
+                    "{} is not {}. got={}",
+                    stringify!($node),
+                    stringify!($variant),
+                    other.to_string()
+                ),
+            }
+        };
+    }
+
     struct InfixTest {
         input: String,
         left_value: Box<dyn Any>,
@@ -56,18 +71,9 @@ mod parser_tests {
             );
 
             let stmt = &mut program.statements[0];
-            test_let_statement(
-                stmt.try_into_let_statement().unwrap(),
-                test.expected_identifier.clone(),
-            );
+            test_let_statement(cast!(stmt, Stmt::Let), test.expected_identifier.clone());
 
-            let value = stmt
-                .try_into_let_statement()
-                .unwrap()
-                .value
-                .as_ref()
-                .unwrap()
-                .clone();
+            let value = cast!(stmt, Stmt::Let).value.as_ref().unwrap().clone();
 
             test_literal_expression(value, test.expected_value);
         }
@@ -129,27 +135,23 @@ mod parser_tests {
             statements_len,
         );
 
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            program.statements[0].get_type(),
+        assert!(
+            matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
             "program.statements[0] is not ast::ExpressionStetement. got={}",
-            program.statements[0].get_type()
+            program.statements[0].to_string()
         );
 
-        let stmt = program.statements[0]
-            .try_into_expr_stmt()
-            .unwrap()
+        let stmt = cast!(program.statements[0], Stmt::Expression)
             .expression
             .as_ref()
             .unwrap();
 
-        assert_eq!(
-            NodeType::Identifier,
-            stmt.get_type(),
+        assert!(
+            matches!(stmt.as_ref(), Expr::Identifier(_)),
             "statements[0].expression is not ast::Identifier. got={}",
-            stmt.get_type(),
+            stmt.to_string()
         );
-        let ident = stmt.try_into_identifier().unwrap();
+        let ident = cast!(stmt, Expr::Identifier);
 
         assert_eq!(
             "foobar", ident.value,
@@ -183,24 +185,22 @@ mod parser_tests {
             statements_len,
         );
 
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            program.statements[0].get_type(),
+        assert!(
+            matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
             "program.statements[0] is not ast::ExpressionStetement. got={}",
-            program.statements[0].get_type()
+            program.statements[0].to_string()
         );
 
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
         let expr = stmt.expression.as_ref().unwrap();
 
-        assert_eq!(
-            expr.get_type(),
-            NodeType::IntegerLiteral,
+        assert!(
+            matches!(expr.as_ref(), Expr::Integer(_)),
             "program.statements[0] is not ast::IntegerLiteral. got={}",
-            expr.get_type()
+            expr.to_string()
         );
 
-        let literal = expr.try_into_int_literal().unwrap();
+        let literal = cast!(expr, Expr::Integer);
 
         assert_eq!(
             5, literal.value,
@@ -262,24 +262,22 @@ mod parser_tests {
                 statements_len,
             );
 
-            assert_eq!(
-                NodeType::ExpressionStetement,
-                program.statements[0].get_type(),
+            assert!(
+                matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
                 "program.statements[0] is not ast::ExpressionStetement. got={}",
-                program.statements[0].get_type()
+                program.statements[0].to_string()
             );
 
-            let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+            let stmt = cast!(program.statements[0], Stmt::Expression);
 
             let expr = stmt.expression.as_ref().unwrap();
-            assert_eq!(
-                NodeType::PrefixExpression,
-                expr.get_type(),
+            assert!(
+                matches!(expr.as_ref(), Expr::Prefix(_)),
                 "stmt is not ast::PrefixExpression. got={}",
-                expr.get_type()
+                expr.to_string()
             );
 
-            let prefix_expr = expr.try_into_prefix_expr().unwrap();
+            let prefix_expr = cast!(expr, Expr::Prefix);
             assert_eq!(
                 test.operator, prefix_expr.operator,
                 "expr.operator is not {}. got={}",
@@ -290,15 +288,14 @@ mod parser_tests {
         }
     }
 
-    fn test_int_literal(expr: Rc<dyn Expression>, value: i64) {
-        assert_eq!(
-            NodeType::IntegerLiteral,
-            expr.get_type(),
+    fn test_int_literal(expr: Rc<Expr>, value: i64) {
+        assert!(
+            matches!(expr.as_ref(), Expr::Integer(_)),
             "stmt is not ast::IntegerLiteral. got={}",
-            expr.get_type()
+            expr.to_string()
         );
 
-        let int_literal = expr.try_into_int_literal().unwrap();
+        let int_literal = cast!(expr, Expr::Integer);
         assert_eq!(
             value, int_literal.value,
             "int_literal.value not {}. got={}",
@@ -399,14 +396,13 @@ mod parser_tests {
                 statements_len,
             );
 
-            assert_eq!(
-                NodeType::ExpressionStetement,
-                program.statements[0].get_type(),
+            assert!(
+                matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
                 "program.statements[0] is not ast::ExpressionStetement. got={}",
-                program.statements[0].get_type()
+                program.statements[0].to_string()
             );
 
-            let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+            let stmt = cast!(program.statements[0], Stmt::Expression);
 
             test_infix_expression(
                 stmt.expression.as_ref().unwrap().clone(),
@@ -438,27 +434,20 @@ mod parser_tests {
             statements_len,
         );
 
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            program.statements[0].get_type(),
+        assert!(
+            matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
             "program.statements[0] is not ast::ExpressionStetement. got={}",
-            program.statements[0].get_type()
+            program.statements[0].to_string()
         );
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
 
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            NodeType::IfExpression,
-            expr.get_type(),
+        assert!(
+            matches!(expr.as_ref(), Expr::If(_)),
             "stmt.expression is not ast::IfExpression. got={}",
-            expr.get_type()
+            expr.to_string()
         );
-        let if_expr = stmt
-            .expression
-            .as_ref()
-            .unwrap()
-            .try_into_if_expr()
-            .unwrap();
+        let if_expr = cast!(stmt.expression.as_ref().unwrap(), Expr::If);
 
         test_infix_expression(
             if_expr.condition.as_ref().unwrap().clone(),
@@ -475,13 +464,12 @@ mod parser_tests {
         );
 
         let conseq = if_expr.consequence.as_ref().unwrap();
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            conseq.statements[0].get_type(),
+        assert!(
+            matches!(conseq.statements[0].as_ref(), Stmt::Expression(_)),
             "consequence.statements[0] is not ast::ExpressionStetement. got={}",
-            conseq.statements[0].get_type()
+            conseq.statements[0].to_string()
         );
-        let consequence = conseq.statements[0].try_into_expr_stmt().unwrap();
+        let consequence = cast!(conseq.statements[0], Stmt::Expression);
 
         let conseq_expr = consequence.expression.as_ref().unwrap().clone();
         test_identifier(conseq_expr, "x".to_string());
@@ -512,22 +500,20 @@ mod parser_tests {
             statements_len,
         );
 
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            program.statements[0].get_type(),
+        assert!(
+            matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
             "program.statements[0] is not ast::ExpressionStetement. got={}",
-            program.statements[0].get_type(),
+            program.statements[0].to_string()
         );
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
 
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            NodeType::IfExpression,
-            expr.get_type(),
+        assert!(
+            matches!(expr.as_ref(), Expr::If(_)),
             "stmt.expression is not ast::IfExpression. got={}",
-            expr.get_type()
+            expr.to_string()
         );
-        let if_expr = expr.try_into_if_expr().unwrap();
+        let if_expr = cast!(expr, Expr::If);
 
         test_infix_expression(
             if_expr.condition.as_ref().unwrap().clone(),
@@ -543,16 +529,13 @@ mod parser_tests {
             conseq_len
         );
         let consequence = if_expr.consequence.as_ref().unwrap();
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            consequence.statements[0].get_type(),
+        assert!(
+            matches!(consequence.statements[0].as_ref(), Stmt::Expression(_)),
             "consequence.statements[0] is not ast::ExpressionStetement. got={}",
-            consequence.statements[0].get_type(),
+            consequence.statements[0].to_string()
         );
 
-        let conseq_expr = consequence.statements[0]
-            .try_into_expr_stmt()
-            .unwrap()
+        let conseq_expr = cast!(consequence.statements[0], Stmt::Expression)
             .expression
             .as_ref()
             .unwrap()
@@ -561,16 +544,13 @@ mod parser_tests {
         test_identifier(conseq_expr, "x".to_string());
 
         let alternative = if_expr.alternative.as_ref().unwrap();
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            alternative.statements[0].get_type(),
+        assert!(
+            matches!(alternative.statements[0].as_ref(), Stmt::Expression(_)),
             "alternative.statements[0] is not ast::ExpressionStetement. got={}",
-            alternative.statements[0].get_type()
+            alternative.statements[0].to_string()
         );
 
-        let alt_expr = alternative.statements[0]
-            .try_into_expr_stmt()
-            .unwrap()
+        let alt_expr = cast!(alternative.statements[0], Stmt::Expression)
             .expression
             .as_ref()
             .unwrap()
@@ -579,12 +559,12 @@ mod parser_tests {
     }
 
     fn test_infix_expression(
-        expr: Rc<dyn Expression>,
+        expr: Rc<Expr>,
         left: Box<dyn Any>,
         operator: String,
         right: Box<dyn Any>,
     ) {
-        let op_expr = expr.try_into_infix_expr().unwrap();
+        let op_expr = cast!(expr, Expr::Infix);
 
         test_literal_expression(op_expr.left.as_ref().unwrap().clone(), left);
 
@@ -731,9 +711,11 @@ mod parser_tests {
         }
     }
 
-    fn test_identifier(expr: Rc<dyn Expression>, value: String) {
-        let ident = expr.try_into_identifier().unwrap();
+    fn test_identifier(expr: Rc<Expr>, value: String) {
+        test_parameter(cast!(expr, Expr::Identifier), value);
+    }
 
+    fn test_parameter(ident: &Identifier, value: String) {
         assert_eq!(
             value, ident.value,
             "ident.value not {}. got={}",
@@ -749,8 +731,8 @@ mod parser_tests {
         );
     }
 
-    fn test_boolean_literal(expr: Rc<dyn Expression>, value: bool) {
-        let ident = expr.try_into_boolean().unwrap();
+    fn test_boolean_literal(expr: Rc<Expr>, value: bool) {
+        let ident = cast!(expr, Expr::Boolean);
 
         assert_eq!(
             value, ident.value,
@@ -767,7 +749,7 @@ mod parser_tests {
         );
     }
 
-    fn test_literal_expression(expr: Rc<dyn Expression>, expected: Box<dyn Any>) {
+    fn test_literal_expression(expr: Rc<Expr>, expected: Box<dyn Any>) {
         if expected.downcast_ref::<i64>().is_some() {
             test_int_literal(expr, *expected.downcast_ref::<i64>().unwrap())
         } else if expected.downcast_ref::<String>().is_some() {
@@ -804,13 +786,8 @@ mod parser_tests {
             let program = parser.parse_program();
             check_parse_errors(parser);
 
-            let stmt = program.statements[0].try_into_expr_stmt().unwrap();
-            let function = stmt
-                .expression
-                .as_ref()
-                .unwrap()
-                .try_into_fn_literal()
-                .unwrap();
+            let stmt = cast!(program.statements[0], Stmt::Expression);
+            let function = cast!(stmt.expression.as_ref().unwrap(), Expr::Function);
 
             assert_eq!(
                 test.expected_params.len(),
@@ -822,7 +799,7 @@ mod parser_tests {
 
             let mut index = 0;
             for ident in test.expected_params {
-                test_literal_expression(function.parameters[index].clone(), Box::new(ident));
+                test_parameter(&function.parameters[index], ident);
                 index += 1;
             }
         }
@@ -844,27 +821,23 @@ mod parser_tests {
             statements_len,
         );
 
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            program.statements[0].get_type(),
+        assert!(
+            matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
             "program.statements[0] is not ast::ExpressionStetement. got={}",
-            program.statements[0].get_type()
+            program.statements[0].to_string()
         );
-        let stmt = program.statements[0]
-            .try_into_expr_stmt()
-            .unwrap()
+        let stmt = cast!(program.statements[0], Stmt::Expression)
             .expression
             .as_ref()
             .unwrap();
 
-        assert_eq!(
-            NodeType::CallExpression,
-            stmt.get_type(),
+        assert!(
+            matches!(stmt.as_ref(), Expr::Call(_)),
             "stmt.expression is not ast::CallExpression. got={}",
-            stmt.get_type(),
+            stmt.to_string()
         );
 
-        let call_expr = stmt.try_into_call_expr().unwrap();
+        let call_expr = cast!(stmt, Expr::Call);
 
         test_identifier(call_expr.function.clone(), String::from("add"));
 
@@ -907,26 +880,22 @@ mod parser_tests {
             statements_len,
         );
 
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            program.statements[0].get_type(),
+        assert!(
+            matches!(program.statements[0].as_ref(), Stmt::Expression(_)),
             "program.statements[0] is not ast::ExpressionStetement. got={}",
-            program.statements[0].get_type()
+            program.statements[0].to_string()
         );
-        let stmt = program.statements[0]
-            .try_into_expr_stmt()
-            .unwrap()
+        let stmt = cast!(program.statements[0], Stmt::Expression)
             .expression
             .as_ref()
             .unwrap();
 
-        assert_eq!(
-            NodeType::FunctionLiteral,
-            stmt.get_type(),
+        assert!(
+            matches!(stmt.as_ref(), Expr::Function(_)),
             "stmt.expression is not ast::FunctionLiteral. got={}",
-            stmt.get_type()
+            stmt.to_string()
         );
-        let func_literal = stmt.try_into_fn_literal().unwrap();
+        let func_literal = cast!(stmt, Expr::Function);
 
         let param_len = func_literal.parameters.len();
         assert_eq!(
@@ -935,14 +904,8 @@ mod parser_tests {
             param_len,
         );
 
-        test_literal_expression(
-            func_literal.parameters[0].clone(),
-            Box::new("x".to_string()),
-        );
-        test_literal_expression(
-            func_literal.parameters[1].clone(),
-            Box::new("y".to_string()),
-        );
+        test_parameter(&func_literal.parameters[0], "x".to_string());
+        test_parameter(&func_literal.parameters[1], "y".to_string());
 
         let body_stmts_len = func_literal.body.statements.len();
         assert_eq!(
@@ -951,15 +914,15 @@ mod parser_tests {
             body_stmts_len,
         );
 
-        assert_eq!(
-            NodeType::ExpressionStetement,
-            func_literal.body.statements[0].get_type(),
+        assert!(
+            matches!(
+                func_literal.body.statements[0].as_ref(),
+                Stmt::Expression(_)
+            ),
             "function.body.stmt.expression is not ast::ExpressionStetement. got={}",
-            func_literal.body.statements[0].get_type()
+            func_literal.body.statements[0].to_string()
         );
-        let body_stmt_expr = func_literal.body.statements[0]
-            .try_into_expr_stmt()
-            .unwrap();
+        let body_stmt_expr = cast!(func_literal.body.statements[0], Stmt::Expression);
 
         test_infix_expression(
             body_stmt_expr.expression.as_ref().unwrap().clone(),
@@ -991,13 +954,12 @@ mod parser_tests {
         );
 
         for stmt in program.statements {
-            assert_eq!(
-                NodeType::ReturnStatement,
-                stmt.get_type(),
+            assert!(
+                matches!(stmt.as_ref(), Stmt::Return(_)),
                 "program.statement is not ast::ReturnStatement. got={}",
-                stmt.get_type()
+                stmt.to_string()
             );
-            let return_stmt = stmt.try_into_return_stmt().unwrap();
+            let return_stmt = cast!(stmt, Stmt::Return);
 
             assert_eq!(
                 "return",
@@ -1016,15 +978,14 @@ mod parser_tests {
         let program = parser.parse_program();
         check_parse_errors(parser);
 
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            expr.get_type(),
-            NodeType::StringLiteral,
+        assert!(
+            matches!(expr.as_ref(), Expr::String(_)),
             "exp not StringLiteral. got={}",
-            expr.get_type(),
+            expr.to_string()
         );
-        let str_literal = expr.try_into_str_literal().unwrap();
+        let str_literal = cast!(expr, Expr::String);
 
         assert_eq!(
             "hello world".to_string(),
@@ -1044,15 +1005,14 @@ mod parser_tests {
         let program = parser.parse_program();
         check_parse_errors(parser);
 
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            expr.get_type(),
-            NodeType::ArrayLiteral,
+        assert!(
+            matches!(expr.as_ref(), Expr::Array(_)),
             "exp not ArrayLiteral. got={}",
-            expr.get_type(),
+            expr.to_string()
         );
-        let arr_literal = expr.try_into_array_literal().unwrap();
+        let arr_literal = cast!(expr, Expr::Array);
 
         assert_eq!(
             arr_literal.elements.len(),
@@ -1085,15 +1045,14 @@ mod parser_tests {
         let program = parser.parse_program();
         check_parse_errors(parser);
 
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            expr.get_type(),
-            NodeType::IndexExpression,
+        assert!(
+            matches!(expr.as_ref(), Expr::Index(_)),
             "exp not IndexExpression. got={}",
-            expr.get_type(),
+            expr.to_string()
         );
-        let index_expr = expr.try_into_index_expr().unwrap();
+        let index_expr = cast!(expr, Expr::Index);
 
         test_identifier(index_expr.left.clone(), "myArray".to_string());
         test_infix_expression(
@@ -1113,15 +1072,14 @@ mod parser_tests {
         let program = parser.parse_program();
         check_parse_errors(parser);
 
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            expr.get_type(),
-            NodeType::HashLiteral,
+        assert!(
+            matches!(expr.as_ref(), Expr::Hash(_)),
             "exp not HashLiteral. got={}",
-            expr.get_type(),
+            expr.to_string()
         );
-        let hash_literal = expr.try_into_hash_literal().unwrap();
+        let hash_literal = cast!(expr, Expr::Hash);
 
         assert_eq!(
             hash_literal.pairs.len(),
@@ -1137,15 +1095,15 @@ mod parser_tests {
         ]);
 
         for (key, value) in hash_literal.pairs.clone() {
-            match key.try_into_str_literal() {
-                Err(_) => assert!(false, "key is not StringLiteral. got={}", key.get_type()),
-                Ok(str_literal) => {
+            match key.as_ref() {
+                Expr::String(str_literal) => {
                     let expected_value = expected.get(&str_literal.to_string());
                     match expected_value {
                         Some(val) => test_int_literal(value, *val),
                         None => assert!(false, "wrong key. got={}", str_literal.to_string()),
                     }
                 }
+                _ => assert!(false, "key is not StringLiteral. got={}", key.to_string()),
             }
         }
     }
@@ -1159,16 +1117,15 @@ mod parser_tests {
         let program = parser.parse_program();
         check_parse_errors(parser);
 
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            expr.get_type(),
-            NodeType::HashLiteral,
+        assert!(
+            matches!(expr.as_ref(), Expr::Hash(_)),
             "exp not HashLiteral. got={}",
-            expr.get_type(),
+            expr.to_string()
         );
 
-        let hash = expr.try_into_hash_literal().unwrap();
+        let hash = cast!(expr, Expr::Hash);
 
         assert_eq!(
             hash.pairs.len(),
@@ -1181,7 +1138,7 @@ mod parser_tests {
     #[test]
     fn test_parsing_hash_literal_with_expressions() {
         struct TestFn {
-            callback: fn(Rc<dyn Expression>),
+            callback: fn(Rc<Expr>),
         }
 
         let input = "{\"one\": 0 + 1, \"two\": 10 - 8, \"three\": 15 / 5}".to_string();
@@ -1191,16 +1148,15 @@ mod parser_tests {
         let program = parser.parse_program();
         check_parse_errors(parser);
 
-        let stmt = program.statements[0].try_into_expr_stmt().unwrap();
+        let stmt = cast!(program.statements[0], Stmt::Expression);
         let expr = stmt.expression.as_ref().unwrap();
-        assert_eq!(
-            expr.get_type(),
-            NodeType::HashLiteral,
+        assert!(
+            matches!(expr.as_ref(), Expr::Hash(_)),
             "exp not HashLiteral. got={}",
-            expr.get_type(),
+            expr.to_string()
         );
 
-        let hash = expr.try_into_hash_literal().unwrap();
+        let hash = cast!(expr, Expr::Hash);
 
         assert_eq!(
             hash.pairs.len(),
@@ -1213,7 +1169,7 @@ mod parser_tests {
             (
                 "one".to_string(),
                 TestFn {
-                    callback: |expr: Rc<dyn Expression>| {
+                    callback: |expr: Rc<Expr>| {
                         test_infix_expression(expr, Box::new(0), "+".to_string(), Box::new(1))
                     },
                 },
@@ -1221,7 +1177,7 @@ mod parser_tests {
             (
                 "two".to_string(),
                 TestFn {
-                    callback: |expr: Rc<dyn Expression>| {
+                    callback: |expr: Rc<Expr>| {
                         test_infix_expression(expr, Box::new(10), "-".to_string(), Box::new(8))
                     },
                 },
@@ -1229,7 +1185,7 @@ mod parser_tests {
             (
                 "three".to_string(),
                 TestFn {
-                    callback: |expr: Rc<dyn Expression>| {
+                    callback: |expr: Rc<Expr>| {
                         test_infix_expression(expr, Box::new(15), "/".to_string(), Box::new(5))
                     },
                 },
@@ -1237,9 +1193,8 @@ mod parser_tests {
         ]);
 
         for (key, value) in hash.pairs.clone() {
-            match key.try_into_str_literal() {
-                Err(_) => assert!(false, "key is not StringLiteral. got={}", key.get_type()),
-                Ok(str_literal) => {
+            match key.as_ref() {
+                Expr::String(str_literal) => {
                     let test_fn = tests.get(&str_literal.to_string());
                     match test_fn {
                         None => assert!(
@@ -1250,7 +1205,438 @@ mod parser_tests {
                         Some(function) => (function.callback)(value),
                     }
                 }
+                _ => assert!(false, "key is not StringLiteral. got={}", key.to_string()),
             }
         }
     }
+
+    #[test]
+    fn test_match_expression() {
+        let input = "match (x) { 1 | -2 => \"low\", [a, ...rest] if a > 1 => a, {\"kind\": k} => { k }, _ => 0 }".to_string();
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        check_parse_errors(parser);
+
+        let stmt = cast!(program.statements[0], Stmt::Expression);
+        let expr = stmt.expression.as_ref().unwrap();
+        assert!(
+            matches!(expr.as_ref(), Expr::Match(_)),
+            "exp not MatchExpression. got={}",
+            expr.to_string()
+        );
+
+        let match_expr = cast!(expr, Expr::Match);
+        test_identifier(match_expr.subject.clone(), "x".to_string());
+
+        let expected = [
+            ("1 | (-2)", None, "low"),
+            ("[a, ...rest]", Some("(a > 1)"), "a"),
+            ("{kind:k}", None, "k"),
+            ("_", None, "0"),
+        ];
+
+        assert_eq!(
+            match_expr.arms.len(),
+            expected.len(),
+            "match_expr.arms has wrong length. got={}",
+            match_expr.arms.len(),
+        );
+
+        for (arm, (pattern, guard, body)) in match_expr.arms.iter().zip(expected) {
+            assert_eq!(arm.pattern.to_string(), pattern, "wrong pattern");
+            assert_eq!(
+                arm.guard.as_ref().map(|guard| guard.to_string()),
+                guard.map(|guard| guard.to_string()),
+                "wrong guard"
+            );
+            assert_eq!(arm.body.to_string(), body, "wrong arm body");
+        }
+    }
+
+    #[test]
+    fn test_macro_literal_parsing() {
+        let input = "macro(x, y) { x + y; }".to_string();
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        check_parse_errors(parser);
+
+        let stmt = cast!(program.statements[0], Stmt::Expression)
+            .expression
+            .as_ref()
+            .unwrap();
+
+        assert!(
+            matches!(stmt.as_ref(), Expr::Macro(_)),
+            "stmt.expression is not ast::MacroLiteral. got={}",
+            stmt.to_string()
+        );
+        let macro_literal = cast!(stmt, Expr::Macro);
+
+        let param_len = macro_literal.parameters.len();
+        assert_eq!(
+            param_len, 2,
+            "macro literal parameters wrong. want 2, got={}",
+            param_len,
+        );
+
+        test_parameter(&macro_literal.parameters[0], "x".to_string());
+        test_parameter(&macro_literal.parameters[1], "y".to_string());
+
+        let body_stmt_expr = cast!(macro_literal.body.statements[0], Stmt::Expression);
+
+        test_infix_expression(
+            body_stmt_expr.expression.as_ref().unwrap().clone(),
+            Box::new("x".to_string()),
+            "+".to_string(),
+            Box::new("y".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_const_statements() {
+        let tests = Vec::from([
+            ("const x = 5;", "x", Box::new(5) as Box<dyn Any>),
+            ("const MAX_RETRIES = 3", "MAX_RETRIES", Box::new(3)),
+            ("const foobar = y;", "foobar", Box::new("y")),
+        ]);
+
+        for (input, expected_identifier, expected_value) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parse_errors(parser);
+
+            assert_eq!(
+                program.statements.len(),
+                1,
+                "program.statements does not contain 1 statement. got={}",
+                program.statements.len(),
+            );
+
+            let const_stmt = cast!(program.statements[0], Stmt::Const);
+            assert_eq!(
+                const_stmt.token_literal(),
+                "const",
+                "s.token_literal not 'const'. got={}",
+                const_stmt.token_literal()
+            );
+            assert_eq!(
+                const_stmt.name.value, expected_identifier,
+                "const_stmt.name.value not {}. got={}",
+                expected_identifier, const_stmt.name.value
+            );
+
+            test_literal_expression(const_stmt.value.as_ref().unwrap().clone(), expected_value);
+        }
+    }
+
+    #[test]
+    fn test_const_redefinition_errors() {
+        let tests = Vec::from([
+            (
+                "const x = 1; let x = 2;",
+                vec!["cannot redefine constant: x"],
+            ),
+            (
+                "const x = 1; const x = 2;",
+                vec!["cannot redefine constant: x"],
+            ),
+            (
+                "const x = 1; if (true) { let x = 2; }",
+                vec!["cannot redefine constant: x"],
+            ),
+            ("const x = 1; let f = fn() { let x = 2; x };", vec![]),
+            ("const x = 1; match (2) { y => { let x = y; x } }", vec![]),
+            ("let x = 1; const x = 2;", vec![]),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            assert_eq!(
+                parser.get_errors(),
+                expected,
+                "wrong parser errors for {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_import_export_statements() {
+        let input = "import \"lib/util.rs_script\" as util; export const MAX = util.limit; export let f = util.add(1, 2).x;".to_string();
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        check_parse_errors(parser);
+
+        assert_eq!(
+            program.statements.len(),
+            3,
+            "program.statements does not contain 3 statements. got={}",
+            program.statements.len(),
+        );
+
+        let import_stmt = cast!(program.statements[0], Stmt::Import);
+        assert_eq!(import_stmt.path, "lib/util.rs_script");
+        assert_eq!(import_stmt.alias.value, "util");
+
+        let export_stmt = cast!(program.statements[1], Stmt::Export);
+        assert_eq!(export_stmt.name.value, "MAX");
+        let const_stmt = cast!(export_stmt.statement, Stmt::Const);
+        let member_expr = cast!(const_stmt.value.as_ref().unwrap(), Expr::Member);
+        test_identifier(member_expr.object.clone(), "util".to_string());
+        assert_eq!(member_expr.property.value, "limit");
+
+        assert_eq!(
+            program.statements[2].to_string(),
+            "export let f = ((util.add)(1, 2).x);",
+        );
+    }
+
+    #[test]
+    fn test_export_errors() {
+        let tests = Vec::from([
+            (
+                "let f = fn() { export let x = 1; };",
+                "export is only allowed at the top level of a module",
+            ),
+            (
+                "export 5;",
+                "expected LET or CONST after export, got INT instead",
+            ),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            let errors = parser.get_errors();
+            assert_eq!(errors[0], expected, "wrong parser error for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_try_expression() {
+        let tests = Vec::from([
+            ("try { x } catch (e) { y }", "try x catch (e) y"),
+            ("try { x } catch { y }", "try x catch y"),
+            ("try { x } finally { z }", "try x finally z"),
+            (
+                "try { x } catch (e) { y } finally { z }",
+                "try x catch (e) y finally z",
+            ),
+            ("throw x + 1;", "throw (x + 1);"),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parse_errors(parser);
+
+            assert_eq!(program.to_string(), expected, "wrong program for {}", input);
+        }
+
+        let lexer = Lexer::new("try { x }".to_string());
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+        assert_eq!(
+            parser.get_errors()[0],
+            "expected catch or finally after try block"
+        );
+    }
+
+    #[test]
+    fn test_struct_statements() {
+        let tests = Vec::from([
+            ("struct Point { x, y }", "struct Point { x, y }"),
+            ("struct Empty {}", "struct Empty {  }"),
+            ("struct Line { from, to, }", "struct Line { from, to }"),
+            ("p.x = 5;", "((p.x) = 5)"),
+            ("p.x = q.y = 1 + 2;", "((p.x) = ((q.y) = (1 + 2)))"),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parse_errors(parser);
+
+            assert_eq!(program.to_string(), expected, "wrong program for {}", input);
+        }
+
+        let error_tests = Vec::from([
+            ("struct Point { x, x }", "duplicate field x in struct Point"),
+            ("x = 5;", "invalid assignment target: x"),
+        ]);
+
+        for (input, expected) in error_tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            assert_eq!(
+                parser.get_errors()[0],
+                expected,
+                "wrong error for {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_enum_statements() {
+        let tests = Vec::from([
+            (
+                "enum Shape { Circle(r), Rect(w, h), Empty }",
+                "enum Shape { Circle(r), Rect(w, h), Empty }",
+            ),
+            (
+                "match (s) { Circle(r) => r, Shape.Rect(w, _) => w, Shape.Empty => 0 }",
+                "match (s) { Circle(r) => r, Shape.Rect(w, _) => w, Shape.Empty => 0 }",
+            ),
+            (
+                "match (s) { Some([x, y]) => x }",
+                "match (s) { Some([x, y]) => x }",
+            ),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parse_errors(parser);
+
+            assert_eq!(program.to_string(), expected, "wrong program for {}", input);
+        }
+
+        let lexer = Lexer::new("enum Shape { Circle(r), Circle(d) }".to_string());
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+        assert_eq!(
+            parser.get_errors()[0],
+            "duplicate variant Circle in enum Shape"
+        );
+    }
+
+    #[test]
+    fn test_impl_statements() {
+        let tests = Vec::from([
+            (
+                "impl Point { fn norm(self) { self.x * self.x } fn origin() { Point(0, 0) } }",
+                "impl Point { fn norm ( self ) ((self.x) * (self.x)) fn origin (  ) Point(0, 0) }",
+            ),
+            ("impl Point {}", "impl Point {  }"),
+            ("p.scale(2).norm()", "((p.scale)(2).norm)()"),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parse_errors(parser);
+
+            assert_eq!(program.to_string(), expected, "wrong program for {}", input);
+        }
+
+        let lexer = Lexer::new("impl Point { fn a(self) { 1 } fn a(self) { 2 } }".to_string());
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+        assert_eq!(parser.get_errors()[0], "duplicate method a in impl Point");
+    }
+
+    #[test]
+    fn test_generator_functions() {
+        let tests = Vec::from([
+            (
+                "fn*(x) { yield x; yield* rest(x); }",
+                "fn* ( x ) yield x;yield* rest(x);",
+            ),
+            (
+                "impl Tree { fn* walk(self) { yield 1; } }",
+                "impl Tree { fn* walk ( self ) yield 1; }",
+            ),
+            ("fn*() { if (x) { yield 1; } }", "fn* (  ) if x yield 1;"),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parse_errors(parser);
+
+            assert_eq!(program.to_string(), expected, "wrong program for {}", input);
+        }
+
+        let error_tests = Vec::from([
+            ("yield 1;", "yield is only allowed inside a generator function"),
+            ("fn() { yield 1; }", "yield is only allowed inside a generator function"),
+            ("fn*() { fn() { yield 1; } }", "yield is only allowed inside a generator function"),
+            ("fn*() { try { yield 1; } catch (e) { 0 } }", "yield is not supported inside a try expression"),
+            ("fn*() { try { 0 } finally { yield 1; } }", "yield is not supported inside a try expression"),
+            (
+                "fn*() { let v = match (1) { 1 => { yield 1; 2 }, _ => 3 }; }",
+                "yield is not supported inside a match expression used as a value",
+            ),
+            (
+                "fn*() { let x = if (true) { yield 1; }; }",
+                "yield is not supported inside an if expression used as a value",
+            ),
+            (
+                "fn*() { match (1) { _ => { try { yield 1; } catch { } } } }",
+                "yield is not supported inside a try expression",
+            ),
+            ("fn*() { let x = yield 1; }", "yield is a statement and can't be used as a value"),
+            ("fn*() { puts(yield* [1]); }", "yield is a statement and can't be used as a value"),
+        ]);
+
+        for (input, expected) in error_tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            assert_eq!(parser.get_errors()[0], expected, "wrong error for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_type_annotations() {
+        let tests = Vec::from([
+            ("let x: int = 1;", "let x: int = 1;"),
+            ("const names: [string] = [];", "const names: [string] = [];"),
+            ("fn(a: int, b) -> bool { a }", "fn ( a: int, b ) -> bool a"),
+            (
+                "fn add(a: int, b: int) -> int { a + b }",
+                "let add = fn ( a: int, b: int ) -> int (a + b);",
+            ),
+            (
+                "let f: fn(int, string) -> {string: int} = g;",
+                "let f: fn(int, string) -> {string: int} = g;",
+            ),
+            ("let p: Point = q;", "let p: Point = q;"),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parse_errors(parser);
+
+            assert_eq!(program.to_string(), expected, "wrong program for {}", input);
+        }
+
+        let lexer = Lexer::new("let x: = 1;".to_string());
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+        assert_eq!(parser.get_errors()[0], "expected type, got = instead");
+    }
 }
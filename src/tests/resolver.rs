@@ -0,0 +1,197 @@
+#[cfg(test)]
+mod resolver_tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        ast::{Captures, Expr, Slot, Stmt},
+        evaluator::eval,
+        lexer::Lexer,
+        object::{Environment, Value},
+        parser::Parser,
+        resolver::resolve,
+    };
+
+    #[test]
+    fn test_unresolved_identifiers() {
+        let tests = Vec::from([
+            ("foo;", vec!["identifier not found: foo (line 1, column 1)"]),
+            (
+                "let x = 1;\nif (x > 1) {\n  prnt(x);\n}",
+                vec!["identifier not found: prnt (line 3, column 3)"],
+            ),
+            ("x; let x = 1;", vec!["identifier not found: x (line 1, column 1)"]),
+            ("let f = fn(a) { a + b };", vec!["identifier not found: b (line 1, column 21)"]),
+            (
+                "fn(a) { a }(b); c;",
+                vec![
+                    "identifier not found: b (line 1, column 13)",
+                    "identifier not found: c (line 1, column 17)",
+                ],
+            ),
+            (
+                "match (1) { n => n, _ => n }",
+                vec!["identifier not found: n (line 1, column 26)"],
+            ),
+            (
+                "try { 1 } catch (e) { e } e;",
+                vec!["identifier not found: e (line 1, column 27)"],
+            ),
+            ("impl Point {}", vec!["identifier not found: Point (line 1, column 6)"]),
+            ("p.x = y;", vec![
+                "identifier not found: p (line 1, column 1)",
+                "identifier not found: y (line 1, column 7)",
+            ]),
+        ]);
+
+        for (input, expected) in tests {
+            let errors = resolve_input(input, Vec::new());
+            assert_eq!(errors, expected, "wrong errors for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_resolved_identifiers() {
+        let tests = Vec::from([
+            "let x = 1; x + len([x]);",
+            "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(5);",
+            "let isEven = fn(n) { if (n == 0) { true } else { isOdd(n - 1) } }; let isOdd = fn(n) { isEven(n) };",
+            "fn add(a, b) { a + b } add(1, 2);",
+            "if (true) { let y = 1; } y;",
+            "match ([1, 2]) { [a, ...rest] if a > 0 => rest, {\"k\": v} => v, _ => 0 }",
+            "struct Point { x, y } impl Point { fn norm(self) { self.x } } Point(1, 2).norm();",
+            "enum Shape { Circle(r), Empty } match (Circle(1)) { Shape.Circle(r) => r, Empty => 0 }",
+            "let gen = fn*() { yield 1; }; next(gen());",
+            "try { throw 1; } catch (e) { e } finally { puts(1); }",
+            "quote(undefined + 1);",
+        ]);
+
+        for input in tests {
+            let errors = resolve_input(input, Vec::new());
+            assert!(errors.is_empty(), "unexpected errors for {}: {:?}", input, errors);
+        }
+
+        let globals = Vec::from(["defined".to_string()]);
+        assert!(resolve_input("defined + 1;", globals).is_empty());
+    }
+
+    #[test]
+    fn test_identifier_slots() {
+        let lexer = Lexer::new("let a = 1; let b = 2; let f = fn(x) { x + b }; f(a);".to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(resolve(&program, Vec::new()).is_empty());
+
+        let Stmt::Let(let_f) = program.statements[2].as_ref() else { panic!("expected let") };
+        let Some(Expr::Function(function)) = let_f.value.as_deref() else { panic!("expected fn") };
+        let Stmt::Expression(sum) = function.body.statements[0].as_ref() else { panic!("expected expression") };
+        let Some(Expr::Infix(sum)) = sum.expression.as_deref() else { panic!("expected infix") };
+        let Some(Expr::Identifier(x)) = sum.left.as_deref() else { panic!("expected identifier") };
+        let Some(Expr::Identifier(b)) = sum.right.as_deref() else { panic!("expected identifier") };
+        assert_eq!(x.slot.get(), Slot::Resolved(0, 0));
+        assert_eq!(b.slot.get(), Slot::Resolved(1, 1));
+
+        let Stmt::Expression(call) = program.statements[3].as_ref() else { panic!("expected expression") };
+        let Some(Expr::Call(call)) = call.expression.as_deref() else { panic!("expected call") };
+        let Expr::Identifier(f) = call.function.as_ref() else { panic!("expected identifier") };
+        let Expr::Identifier(a) = call.arguments[0].as_ref() else { panic!("expected identifier") };
+        assert_eq!(f.slot.get(), Slot::Resolved(0, 2));
+        assert_eq!(a.slot.get(), Slot::Resolved(0, 0));
+    }
+
+    #[test]
+    fn test_function_captures() {
+        let tests = Vec::from([
+            ("let a = 1; let f = fn(x) { x + a };", Captures::Resolved(Vec::from([("a".to_string(), 0)]))),
+            ("let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } };", Captures::Resolved(Vec::from([("f".to_string(), 0)]))),
+            ("let f = fn() { let big = [1, 2]; len(big) };", Captures::Resolved(Vec::new())),
+            (
+                "let x = 1; let f = fn() { let g = fn() { x }; let x = 2; g };",
+                Captures::Resolved(Vec::from([("x".to_string(), 0)])),
+            ),
+            ("let x = 1; let f = fn() { quote(x) };", Captures::Dynamic),
+        ]);
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            assert!(resolve(&program, Vec::new()).is_empty());
+
+            let Some(Stmt::Let(let_f)) = program.statements.last().map(|stmt| stmt.as_ref()) else { panic!("expected let") };
+            let Some(Expr::Function(function)) = let_f.value.as_deref() else { panic!("expected fn") };
+            assert_eq!(*function.captures.borrow(), expected, "wrong captures for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_closures_capture_what_they_read() {
+        let env = Rc::new(RefCell::new(Environment::new()));
+        let input = "let x = 1; let make = fn(c) { let big = [1, 2, 3]; let y = 2; fn() { x + y } }; make(true)";
+        let Value::Function(function) = eval_resolved(input, env.clone()) else { panic!("expected fn") };
+        let captured = function.env.borrow();
+        assert_eq!(captured.names(), Vec::from(["y".to_string()]));
+        // y keeps the slot it has in make's environment
+        assert_eq!(captured.get_slot(0, 2, "y").map(|value| value.inspect()), Some("2".to_string()));
+        assert_eq!(captured.get_slot(1, 0, "x").map(|value| value.inspect()), Some("1".to_string()));
+        assert_eq!(captured.get("x").ok().map(|value| value.inspect()), Some("1".to_string()));
+        assert!(captured.get("big").is_err());
+    }
+
+    #[test]
+    fn test_eval_resolved_programs() {
+        let tests = Vec::from([
+            ("let a = 1; let b = 2; let f = fn(x) { x + b }; f(a);", 3),
+            ("let f = fn(c) { if (c) { let a = 1; a } let b = 2; b }; f(false);", 2),
+            ("let x = 1; let f = fn(c) { if (c) { let x = 2; x } x }; f(false) + f(true);", 3),
+            ("let f = fn() { g() }; let g = fn() { 5 }; f();", 5),
+            ("let x = 1; let f = fn(c) { if (c) { let x = 2; x } fn() { x } }; let g = f(false); let h = f(true); g() + h();", 3),
+            ("let x = 1; let f = fn() { let g = fn() { x }; let x = 2; g() }; f();", 2),
+            ("let add = fn(a, b) { a + b }; add(1);", -1),
+            ("match ([1, 2]) { [a, b] if a < b => b - a, _ => 0 }", 1),
+            ("try { throw 4; } catch (e) { e.value }", 4),
+        ]);
+
+        for (input, expected) in tests {
+            let env = Rc::new(RefCell::new(Environment::new()));
+            let evaluated = eval_resolved(input, env);
+            match expected {
+                -1 => assert!(matches!(evaluated, Value::Error(_)), "expected an error for {}", input),
+                _ => assert!(
+                    matches!(evaluated, Value::Integer(value) if value == expected),
+                    "wrong result for {}",
+                    input
+                ),
+            }
+        }
+
+        let env = Rc::new(RefCell::new(Environment::new()));
+        eval_resolved("let a = 1; let b = 2; b;", env.clone());
+        assert_eq!(env.borrow().names(), Vec::from(["a".to_string(), "b".to_string()]));
+        let evaluated = eval_resolved("let c = a + b; c * b;", env.clone());
+        assert!(matches!(evaluated, Value::Integer(6)));
+    }
+
+    fn eval_resolved(input: &str, env: Rc<RefCell<Environment>>) -> Value {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        let errors = resolve(&program, env.borrow().names());
+        assert!(errors.is_empty(), "unexpected errors for {}: {:?}", input, errors);
+
+        eval(&program, env).unwrap()
+    }
+
+    fn resolve_input(input: &str, globals: Vec<String>) -> Vec<String> {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(
+            parser.get_errors().is_empty(),
+            "parser errors for {}: {:?}",
+            input,
+            parser.get_errors()
+        );
+
+        resolve(&program, globals)
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod typecheck_tests {
+    use crate::{lexer::Lexer, parser::Parser, typecheck::typecheck};
+
+    #[test]
+    fn test_type_errors() {
+        let tests = Vec::from([
+            (
+                "let x: int = \"hello\";",
+                "type mismatch: x is declared as int but got string",
+            ),
+            (
+                "let x: [int] = [\"a\", \"b\"];",
+                "type mismatch: x is declared as [int] but got [string]",
+            ),
+            ("1 + true;", "type mismatch: int + bool"),
+            ("true + false;", "unknown operator: bool + bool"),
+            ("\"a\" - \"b\";", "unknown operator: string - string"),
+            ("-\"a\";", "unknown operator: -string"),
+            ("fn() -> int { \"a\" }", "return type mismatch: expected int, got string"),
+            (
+                "fn(x: bool) -> int { if (x) { return true; } 1 }",
+                "return type mismatch: expected int, got bool",
+            ),
+            ("let f = fn() -> int { };", "return type mismatch: expected int, got null"),
+            ("fn() -> int { let x = 1; }", "return type mismatch: expected int, got null"),
+            (
+                "fn(x: bool) -> int { if (x) { return 1; } }",
+                "return type mismatch: expected int, got null",
+            ),
+            (
+                "fn add(a: int, b: int) -> int { a + b } add(1, \"2\");",
+                "argument 2 to add: expected int, got string",
+            ),
+            (
+                "fn add(a: int, b: int) -> int { a + b } add(1);",
+                "wrong number of arguments to add: want=2, got=1",
+            ),
+            (
+                "fn add(a: int, b: int) -> int { a + b } let s: string = add(1, 2);",
+                "type mismatch: s is declared as string but got int",
+            ),
+            ("fn(a: int) { a + \"b\" }", "type mismatch: int + string"),
+            ("len(\"abc\") + \"d\";", "type mismatch: int + string"),
+            (
+                "struct Point { x, y } let p: int = Point(1, 2);",
+                "type mismatch: p is declared as int but got Point",
+            ),
+            (
+                "let f: fn(int) -> int = fn(x: string) { x };",
+                "type mismatch: f is declared as fn(int) -> int but got fn(string) -> string",
+            ),
+            (
+                "let f = fn(x) { if (x > 0) { return 1; } 2 }; f(1) + \"a\";",
+                "type mismatch: int + string",
+            ),
+            ("let a: array = [1];", "unknown type: array"),
+            ("fn(x: [integer]) -> int { len(x) }([1]);", "unknown type: integer"),
+            ("fn() -> Point { 1 }", "unknown type: Point"),
+        ]);
+
+        for (input, expected) in tests {
+            let errors = check(input);
+            assert_eq!(errors, Vec::from([expected.to_string()]), "wrong errors for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_well_typed_programs() {
+        let tests = Vec::from([
+            "let x = 1; let y = x + 2; y * 3;",
+            "let add = fn(a, b) { a + b }; add(1, 2); add(\"a\", \"b\");",
+            "fn fib(n: int) -> int { if (n < 2) { return n; } fib(n - 1) + fib(n - 2) } fib(10);",
+            "let names: [string] = []; let counts: {string: int} = {\"a\": 1};",
+            "let x: any = 1; let y: string = x;",
+            "fn double(f: fn(int) -> int, x: int) -> int { f(f(x)) } double(fn(x) { x + 1 }, 1);",
+            "enum Shape { Circle(r), Empty } let s: Shape = Circle(1); let e: Shape = Empty;",
+            "struct Point { x, y } impl Point { fn norm(self) -> int { self.x * self.x } }",
+            "match ([1, 2]) { [a, b] => a + b, _ => 0 }",
+            "let gen: fn() -> any = fn*() -> int { yield 1; };",
+            "try { throw 1; } catch (e) { e + 1 }",
+            "let f = fn(x) { if (x > 0) { return x; } \"neg\" }; f(5) + 1;",
+            "let f = fn(x) { if (x > 0) { return 1; } \"neg\" }; f(5) + 1;",
+            "fn origin() -> Point { Point(0, 0) } struct Point { x, y }",
+            "fn(x: bool) -> int { if (x) { 1 } else { return 2; } }",
+            "let f = fn() -> any { }; let g = fn() -> null { let x = 1; };",
+        ]);
+
+        for input in tests {
+            let errors = check(input);
+            assert!(errors.is_empty(), "unexpected errors for {}: {:?}", input, errors);
+        }
+    }
+
+    #[test]
+    fn test_types_from_earlier_programs() {
+        let program = Parser::new(Lexer::new("let p: Point = 1;".to_string())).parse_program();
+        assert_eq!(
+            typecheck(&program, &["Point".to_string()]),
+            Vec::from(["type mismatch: p is declared as Point but got int".to_string()])
+        );
+    }
+
+    fn check(input: &str) -> Vec<String> {
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(
+            parser.get_errors().is_empty(),
+            "parser errors for {}: {:?}",
+            input,
+            parser.get_errors()
+        );
+
+        typecheck(&program, &[])
+    }
+}
@@ -0,0 +1,287 @@
+#[cfg(test)]
+mod vm_tests {
+    use std::{
+        fs,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        compiler::Compiler,
+        lexer::Lexer,
+        object::Value,
+        parser::Parser,
+        vm::Vm,
+    };
+
+    #[test]
+    fn test_globals_persist_between_programs() {
+        let mut compiler = Compiler::new();
+        let mut vm = Vm::new();
+
+        let tests = Vec::from([
+            ("let x = 5; const limit = 10;", None),
+            ("let add = fn(a) { a + x + later }; let later = 100;", None),
+            ("add(1)", Some("106")),
+            ("let x = 6; add(1)", Some("107")),
+            ("limit", Some("10")),
+            ("let limit = 1;", Some("cannot redefine constant: limit")),
+            ("missing", Some("identifier not found: missing")),
+            ("x", Some("6")),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = run(&mut compiler, &mut vm, input);
+            let inspected = evaluated.as_ref().map(|value| match value {
+                Value::Error(error) => error.message.clone(),
+                value => value.inspect(),
+            });
+            assert_eq!(inspected.as_deref(), expected, "wrong result for {}", input);
+        }
+
+        assert_eq!(compiler.global_names(), vec!["x", "limit", "add", "later"]);
+    }
+
+    #[test]
+    fn test_recursive_delegation_runs_in_constant_depth() {
+        let input = "
+            let pages = fn*(n) {
+                if (n < 20000) {
+                    yield n;
+                    yield* pages(n + 1);
+                }
+            };
+            let g = pages(0);
+            let count = fn(total) { if (g.done) { total } else { next(g); count(total + 1) } };
+            count(0)";
+
+        let evaluated = run(&mut Compiler::new(), &mut Vm::new(), input);
+        assert!(
+            matches!(evaluated, Some(Value::Integer(20001))),
+            "wrong value. got={}",
+            evaluated.map_or("nothing".to_string(), |value| value.inspect())
+        );
+    }
+
+    #[test]
+    fn test_call_depth_limit() {
+        let mut compiler = Compiler::new();
+        let mut vm = Vm::new();
+        vm.limits().max_call_depth.set(50);
+
+        let tests = Vec::from([
+            ("let down = fn(n) { if (n == 0) { 0 } else { 1 + down(n - 1) } };", None),
+            ("down(49)", Some("49")),
+            ("let outer = fn() { 1 + down(100) }; outer()", Some("maximum call depth 50 exceeded (call chain: outer -> down x50)")),
+            ("try { down(100) } catch (e) { e.message }", Some("maximum call depth 50 exceeded (call chain: down x51)")),
+            // tail calls replace their caller's frame
+            ("let count = fn(n, acc) { if (n == 0) { acc } else { count(n - 1, acc + 1) } }; count(1000, 0)", Some("1000")),
+            ("let countdown = fn(n) { if (n == 0) { return 0; }; return countdown(n - 1); }; countdown(1000)", Some("0")),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = run(&mut compiler, &mut vm, input);
+            let inspected = evaluated.as_ref().map(|value| match value {
+                Value::Error(error) => error.message.clone(),
+                value => value.inspect(),
+            });
+            assert_eq!(inspected.as_deref(), expected, "wrong result for {}", input);
+        }
+
+        // the failed calls left nothing behind
+        assert_eq!(vm.limits().call_depth(), 0);
+    }
+
+    #[test]
+    fn test_deep_recursion_on_a_small_stack() {
+        // calls inside try blocks nest the dispatch loop
+        let evaluated = thread::Builder::new()
+            .stack_size(2 << 20)
+            .spawn(|| {
+                let (mut compiler, mut vm) = (Compiler::new(), Vm::new());
+                run(&mut compiler, &mut vm, "let down = fn(n) { if (n == 0) { 0 } else { try { 1 + down(n - 1) } finally { } } };");
+                ["down(9000)", "down(10)"].map(|input| error_message_or_value(run(&mut compiler, &mut vm, input)))
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert!(evaluated[0].starts_with("stack exhausted at call depth "), "wrong error {}", evaluated[0]);
+        assert_eq!(evaluated[1], "10");
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut compiler = Compiler::new();
+        let mut vm = Vm::new();
+        run(&mut compiler, &mut vm, "let forever = fn(n) { forever(n + 1) }; let total = 5;");
+
+        vm.limits().max_steps.set(Some(10000));
+        for input in ["forever(0)", "try { forever(0) } catch (e) { 1 }", "try { forever(0) } catch (e) { }; total"] {
+            assert_eq!(error_message(run(&mut compiler, &mut vm, input)), "budget exhausted", "wrong error for {}", input);
+        }
+        assert_eq!(run(&mut compiler, &mut vm, "total + 1").map(|value| value.inspect()).as_deref(), Some("6"));
+
+        vm.limits().max_steps.set(None);
+        vm.limits().time_limit.set(Some(Duration::from_millis(20)));
+        assert_eq!(error_message(run(&mut compiler, &mut vm, "forever(0)")), "budget exhausted");
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut compiler = Compiler::new();
+        let mut vm = Vm::new();
+        run(&mut compiler, &mut vm, "let forever = fn(n) { forever(n + 1) }; let total = 5;");
+
+        // keeps interrupting until the run is over, in case the first one lands before it starts
+        let handle = vm.limits().interrupt_handle();
+        for input in ["forever(0)", "try { forever(0) } catch (e) { 1 }"] {
+            let (interrupter, done) = (handle.clone(), Arc::new(AtomicBool::new(false)));
+            let thread = thread::spawn({
+                let done = done.clone();
+                move || {
+                    while !done.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(10));
+                        interrupter.interrupt();
+                    }
+                }
+            });
+            let evaluated = run(&mut compiler, &mut vm, input);
+            done.store(true, Ordering::Relaxed);
+            thread.join().unwrap();
+            assert_eq!(error_message(evaluated), "interrupted", "wrong error for {}", input);
+        }
+
+        assert_eq!(run(&mut compiler, &mut vm, "total + 1").map(|value| value.inspect()).as_deref(), Some("6"));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let mut compiler = Compiler::new();
+        let mut vm = Vm::new();
+        vm.limits().max_memory.set(Some(1 << 20));
+
+        let tests = Vec::from([
+            ("let double = fn(s, n) { if (n == 0) { len(s) } else { double(s + s, n - 1) } };", None),
+            ("let grow = fn(a, n) { if (n == 0) { len(a) } else { grow(push(a, n), n - 1) } };", None),
+            ("double(\"ab\", 10)", Some("2048")),
+            ("grow([], 100)", Some("100")),
+            ("double(\"ab\", 40)", Some("memory limit exceeded")),
+            ("try { grow([], 100000) } catch (e) { e.message }", Some("memory limit exceeded")),
+            ("[1, 2, 3]", Some("memory limit exceeded")),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = run(&mut compiler, &mut vm, input);
+            let inspected = evaluated.as_ref().map(|value| match value {
+                Value::Error(error) => error.message.clone(),
+                value => value.inspect(),
+            });
+            assert_eq!(inspected.as_deref(), expected, "wrong result for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_enum_patterns_across_programs() {
+        let mut compiler = Compiler::new();
+        let mut vm = Vm::new();
+
+        let tests = Vec::from([
+            ("enum Opt { some(v), none }", None),
+            ("match (some(1)) { none => 0, some(v) => v }", Some("1")),
+            ("match (none) { some(v) => v, none => 0 }", Some("0")),
+            ("match (some(none)) { some(none) => 1, _ => 2 }", Some("1")),
+            ("match (5) { none => 1, x => x }", Some("5")),
+            ("match (5) { X => 1, _ => 2 }", Some("1")),
+            ("let check = fn(none) { match (3) { none => 1, _ => 2 } }; check(3)", Some("1")),
+            ("check(none)", Some("2")),
+            ("let none = 7; match (5) { none => none }", Some("5")),
+        ]);
+
+        for (input, expected) in tests {
+            let evaluated = run(&mut compiler, &mut vm, input);
+            assert_eq!(evaluated.map(|value| value.inspect()).as_deref(), expected, "wrong result for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_error_lines() {
+        let tests = Vec::from([
+            ("let a = 1;\nlet b = fn(x) {\n  x + \"s\"\n};\nb(a);", Some(3)),
+            ("let a = try {\n  1 / 0\n} catch (e) {\n  0\n};\na", None),
+            ("try {\n  1 / 0\n} catch (e) {\n  0\n};\nthrow \"again\";", Some(6)),
+            ("1 + 1", None),
+        ]);
+
+        for (input, expected) in tests {
+            let mut vm = Vm::new();
+            run(&mut Compiler::new(), &mut vm, input);
+            assert_eq!(vm.error_line(), expected, "wrong line for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_imports_run_on_the_vm() {
+        let dir = std::env::temp_dir().join(format!("rust_script_vm_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let dir: PathBuf = fs::canonicalize(dir).unwrap();
+        fs::write(
+            dir.join("shapes.rs_script"),
+            "struct Point { x, y }
+            impl Point { fn sum(self) { self.x + self.y } }
+            export let point = fn(x, y) { Point(x, y) };
+            export let origin = Point(0, 0);
+            let hidden = 1;",
+        )
+        .unwrap();
+
+        let input = format!(
+            "import \"{}\" as shapes; let p = shapes.point(2, 3); [p.sum(), shapes.origin.x]",
+            dir.join("shapes.rs_script").display()
+        );
+        let evaluated = run(&mut Compiler::new(), &mut Vm::new(), &input);
+        assert_eq!(evaluated.map(|value| value.inspect()).as_deref(), Some("[5, 0]"));
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let tests = Vec::from([
+            ("fn(a, a) { a }", "duplicate parameter name: a"),
+            ("match (1) { -\"a\" => 1 }", "unsupported literal in pattern: (-a)"),
+        ]);
+
+        for (input, expected) in tests {
+            let program = Parser::new(Lexer::new(input.to_string())).parse_program();
+            match Compiler::new().compile(&program) {
+                Ok(_) => panic!("{} compiled", input),
+                Err(msg) => assert_eq!(msg, expected),
+            }
+        }
+    }
+
+    fn run(compiler: &mut Compiler, vm: &mut Vm, input: &str) -> Option<Value> {
+        let program = Parser::new(Lexer::new(input.to_string())).parse_program();
+        let compiled = compiler.compile(&program).unwrap();
+        vm.run(compiled)
+    }
+
+    fn error_message(evaluated: Option<Value>) -> String {
+        match evaluated {
+            Some(Value::Error(error)) => error.message.clone(),
+            other => panic!("expected an error. got={}", other.map_or("nothing".to_string(), |value| value.inspect())),
+        }
+    }
+
+    fn error_message_or_value(evaluated: Option<Value>) -> String {
+        match evaluated {
+            Some(Value::Error(error)) => error.message.clone(),
+            other => other.map_or("nothing".to_string(), |value| value.inspect()),
+        }
+    }
+}
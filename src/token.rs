@@ -4,17 +4,33 @@ use core::fmt;
 pub struct Token {
     pub token_type: TokenType,
     pub literal: String,
+    pub line: usize,
+    pub column: usize,
 }
 
 pub fn lookup_ident(ident: &str) -> TokenType {
     match ident {
         "fn" => TokenType::FUNCTION,
         "let" => TokenType::LET,
+        "const" => TokenType::CONST,
         "if" => TokenType::IF,
         "else" => TokenType::ELSE,
         "true" => TokenType::TRUE,
         "false" => TokenType::FALSE,
         "return" => TokenType::RETURN,
+        "match" => TokenType::MATCH,
+        "macro" => TokenType::MACRO,
+        "import" => TokenType::IMPORT,
+        "export" => TokenType::EXPORT,
+        "as" => TokenType::AS,
+        "try" => TokenType::TRY,
+        "catch" => TokenType::CATCH,
+        "finally" => TokenType::FINALLY,
+        "throw" => TokenType::THROW,
+        "struct" => TokenType::STRUCT,
+        "enum" => TokenType::ENUM,
+        "impl" => TokenType::IMPL,
+        "yield" => TokenType::YIELD,
         _ => TokenType::IDENT,
     }
 }
@@ -33,7 +49,12 @@ pub enum TokenType {
     BANG,
     ASTERISK,
     SLASH,
+    PIPE,
+    ARROW,
+    RARROW,
+    ELLIPSIS,
 
+    DOT,
     COMMA,
     COLON,
     SEMICOLON,
@@ -46,11 +67,25 @@ pub enum TokenType {
     // Keywords
     FUNCTION,
     LET,
+    CONST,
     TRUE,
     FALSE,
     IF,
     ELSE,
     RETURN,
+    MATCH,
+    MACRO,
+    IMPORT,
+    EXPORT,
+    AS,
+    TRY,
+    CATCH,
+    FINALLY,
+    THROW,
+    STRUCT,
+    ENUM,
+    IMPL,
+    YIELD,
 
     LT,
     GT,
@@ -74,7 +109,12 @@ impl TokenType {
             TokenType::BANG => "!",
             TokenType::ASTERISK => "*",
             TokenType::SLASH => "/",
+            TokenType::PIPE => "|",
+            TokenType::ARROW => "=>",
+            TokenType::RARROW => "->",
+            TokenType::ELLIPSIS => "...",
 
+            TokenType::DOT => ".",
             TokenType::COLON => ":",
             TokenType::COMMA => ",",
             TokenType::SEMICOLON => ";",
@@ -87,11 +127,25 @@ impl TokenType {
             // Keywords
             TokenType::FUNCTION => "FUNCTION",
             TokenType::LET => "LET",
+            TokenType::CONST => "CONST",
             TokenType::TRUE => "TRUE",
             TokenType::FALSE => "FALSE",
             TokenType::IF => "IF",
             TokenType::ELSE => "ELSE",
             TokenType::RETURN => "RETURN",
+            TokenType::MATCH => "MATCH",
+            TokenType::MACRO => "MACRO",
+            TokenType::IMPORT => "IMPORT",
+            TokenType::EXPORT => "EXPORT",
+            TokenType::AS => "AS",
+            TokenType::TRY => "TRY",
+            TokenType::CATCH => "CATCH",
+            TokenType::FINALLY => "FINALLY",
+            TokenType::THROW => "THROW",
+            TokenType::STRUCT => "STRUCT",
+            TokenType::ENUM => "ENUM",
+            TokenType::IMPL => "IMPL",
+            TokenType::YIELD => "YIELD",
 
             TokenType::LT => "<",
             TokenType::GT => ">",
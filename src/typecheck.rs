@@ -0,0 +1,590 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::ast::{
+    BlockStatement, Expr, FunctionLiteral, Identifier, Pattern, Program, Stmt, TypeAnnotation,
+};
+
+type Type = TypeAnnotation;
+
+// `globals` are the names bound by earlier programs of the same session, which include
+// the structs and enums they declared
+pub fn typecheck(program: &Program, globals: &[String]) -> Vec<String> {
+    let mut checker = TypeChecker {
+        scopes: vec![HashMap::new()],
+        return_types: Vec::new(),
+        returned: Vec::new(),
+        type_names: globals.iter().cloned().collect(),
+        errors: Vec::new(),
+    };
+
+    // a type can be used before the statement declaring it
+    for stmt in &program.statements {
+        checker.declare_type(stmt);
+    }
+
+    for stmt in &program.statements {
+        checker.check_statement(stmt);
+    }
+
+    checker.errors
+}
+
+// `any` is what unannotated values we can't infer end up as, and it is compatible with
+// everything, so code without annotations only fails on mismatches that are certain
+fn compatible(expected: &Type, actual: &Type) -> bool {
+    match (expected, actual) {
+        (Type::Any, _) | (_, Type::Any) => true,
+        (Type::Array(expected), Type::Array(actual)) => compatible(expected, actual),
+        (Type::Hash(expected_key, expected_value), Type::Hash(actual_key, actual_value)) => {
+            compatible(expected_key, actual_key) && compatible(expected_value, actual_value)
+        }
+        (Type::Function(expected_params, expected_ret), Type::Function(actual_params, actual_ret)) => {
+            expected_params.len() == actual_params.len()
+                && expected_params
+                    .iter()
+                    .zip(actual_params)
+                    .all(|(expected, actual)| compatible(expected, actual))
+                && compatible(expected_ret, actual_ret)
+        }
+        _ => expected == actual,
+    }
+}
+
+// the common type of a set of values, or `any` if they disagree
+fn unify(types: Vec<Type>) -> Option<Type> {
+    let first = types.first()?.clone();
+    if types.iter().all(|ty| *ty == first) {
+        Some(first)
+    } else {
+        Some(Type::Any)
+    }
+}
+
+// whether running the block can finish without a value, the way an empty body or one
+// ending in a declaration or in an `if` without an `else` does
+fn falls_through(block: &BlockStatement) -> bool {
+    match block.statements.last() {
+        Some(stmt) => statement_falls_through(stmt),
+        None => true,
+    }
+}
+
+fn statement_falls_through(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Let(_) | Stmt::Const(_) | Stmt::Import(_) => true,
+        Stmt::Struct(_) | Stmt::Enum(_) | Stmt::Impl(_) => true,
+        Stmt::Export(export_stmt) => statement_falls_through(&export_stmt.statement),
+        Stmt::Block(block) => falls_through(block),
+        Stmt::Expression(expr_stmt) => match expr_stmt.expression.as_deref() {
+            Some(Expr::If(if_expr)) => match (&if_expr.consequence, &if_expr.alternative) {
+                (Some(consequence), Some(alternative)) => {
+                    falls_through(consequence) || falls_through(alternative)
+                }
+                _ => true,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+struct TypeChecker {
+    scopes: Vec<HashMap<String, Type>>,
+    return_types: Vec<Option<Type>>,
+    // the types of the values each function being checked returns
+    returned: Vec<Vec<Type>>,
+    type_names: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl TypeChecker {
+    fn declare_type(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Struct(struct_stmt) => {
+                self.type_names.insert(struct_stmt.name.value.clone());
+            }
+            Stmt::Enum(enum_stmt) => {
+                self.type_names.insert(enum_stmt.name.value.clone());
+            }
+            Stmt::Export(export_stmt) => self.declare_type(&export_stmt.statement),
+            _ => {}
+        }
+    }
+
+    // an annotation naming a type nobody declared is reported once, here, and then
+    // treated as `any` so it doesn't cause mismatches further on
+    fn check_annotation(&mut self, annotation: &Type) -> Type {
+        let mut unknown = Vec::new();
+        let known = self.known(annotation, &mut unknown);
+        for name in unknown {
+            self.errors.push(format!("unknown type: {}", name));
+        }
+        known
+    }
+
+    fn known(&self, annotation: &Type, unknown: &mut Vec<String>) -> Type {
+        match annotation {
+            Type::Named(name) if !self.type_names.contains(name) => {
+                unknown.push(name.clone());
+                Type::Any
+            }
+            Type::Array(element) => Type::Array(Box::new(self.known(element, unknown))),
+            Type::Hash(key, value) => Type::Hash(
+                Box::new(self.known(key, unknown)),
+                Box::new(self.known(value, unknown)),
+            ),
+            Type::Function(params, ret) => Type::Function(
+                params.iter().map(|param| self.known(param, unknown)).collect(),
+                Box::new(self.known(ret, unknown)),
+            ),
+            ty => ty.clone(),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Type {
+        for scope in self.scopes.iter().rev() {
+            if let Some(ty) = scope.get(name) {
+                return ty.clone();
+            }
+        }
+
+        match name {
+            "len" => Type::Function(vec![Type::Any], Box::new(Type::Int)),
+            _ => Type::Any,
+        }
+    }
+
+    fn declare(&mut self, name: &str, ty: Type) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), ty);
+    }
+
+    fn check_statement(&mut self, stmt: &Stmt) -> Type {
+        match stmt {
+            Stmt::Expression(expr_stmt) => {
+                match &expr_stmt.expression {
+                    Some(expr) => self.infer(expr),
+                    None => Type::Any,
+                }
+            }
+            Stmt::Let(let_stmt) => {
+                self.check_binding(&let_stmt.name, &let_stmt.value);
+                Type::Any
+            }
+            Stmt::Const(const_stmt) => {
+                self.check_binding(&const_stmt.name, &const_stmt.value);
+                Type::Any
+            }
+            Stmt::Return(return_stmt) => {
+                let actual = match &return_stmt.return_value {
+                    Some(value) => self.infer(value),
+                    None => Type::Null,
+                };
+
+                if let Some(Some(expected)) = self.return_types.last() {
+                    if !compatible(expected, &actual) {
+                        let msg = format!(
+                            "return type mismatch: expected {}, got {}",
+                            expected, actual
+                        );
+                        self.errors.push(msg);
+                    }
+                }
+                if let Some(returned) = self.returned.last_mut() {
+                    returned.push(actual.clone());
+                }
+                actual
+            }
+            Stmt::Struct(struct_stmt) => {
+                self.declare_type(stmt);
+                let constructor = Type::Function(
+                    vec![Type::Any; struct_stmt.fields.len()],
+                    Box::new(Type::Named(struct_stmt.name.value.clone())),
+                );
+                self.declare(&struct_stmt.name.value, constructor);
+                Type::Any
+            }
+            Stmt::Enum(enum_stmt) => {
+                self.declare_type(stmt);
+                let enum_type = Type::Named(enum_stmt.name.value.clone());
+                for variant in &enum_stmt.variants {
+                    let ty = if variant.fields.is_empty() {
+                        enum_type.clone()
+                    } else {
+                        Type::Function(
+                            vec![Type::Any; variant.fields.len()],
+                            Box::new(enum_type.clone()),
+                        )
+                    };
+                    self.declare(&variant.name.value, ty);
+                }
+                self.declare(&enum_stmt.name.value, Type::Any);
+                Type::Any
+            }
+            Stmt::Impl(impl_stmt) => {
+                let self_type = Type::Named(impl_stmt.type_name.value.clone());
+                for method in &impl_stmt.methods {
+                    self.check_function(&method.function, Some(self_type.clone()));
+                }
+                Type::Any
+            }
+            Stmt::Import(import_stmt) => {
+                self.declare(&import_stmt.alias.value, Type::Any);
+                Type::Any
+            }
+            Stmt::Export(export_stmt) => {
+                self.check_statement(&export_stmt.statement)
+            }
+            Stmt::Throw(throw_stmt) => {
+                if let Some(value) = &throw_stmt.value {
+                    self.infer(value);
+                }
+                Type::Any
+            }
+            Stmt::Yield(yield_stmt) => {
+                if let Some(value) = &yield_stmt.value {
+                    self.infer(value);
+                }
+                Type::Any
+            }
+            Stmt::Block(block) => self.check_block(block),
+        }
+    }
+
+    fn check_binding(&mut self, name: &Identifier, value: &Option<Rc<Expr>>) {
+        let Some(value) = value else {
+            return;
+        };
+
+        let annotation = name.type_annotation.as_ref().map(|annotation| self.check_annotation(annotation));
+
+        // declaring the signature up front lets a function refer to itself. Its own
+        // annotations are reported when the function is checked
+        if let Expr::Function(fn_literal) = value.as_ref() {
+            let signature = Type::Function(
+                fn_literal
+                    .parameters
+                    .iter()
+                    .map(|param| param.type_annotation.clone().unwrap_or(Type::Any))
+                    .collect(),
+                Box::new(fn_literal.return_type.clone().unwrap_or(Type::Any)),
+            );
+            let signature = self.known(&signature, &mut Vec::new());
+            self.declare(&name.value, annotation.clone().unwrap_or(signature));
+        }
+
+        let actual = self.infer(value);
+
+        match &annotation {
+            Some(expected) => {
+                if !compatible(expected, &actual) {
+                    let msg = format!(
+                        "type mismatch: {} is declared as {} but got {}",
+                        name.value, expected, actual
+                    );
+                    self.errors.push(msg);
+                }
+                self.declare(&name.value, expected.clone());
+            }
+            None => self.declare(&name.value, actual),
+        }
+    }
+
+    // blocks share their enclosing scope, the same way the evaluator runs them
+    fn check_block(&mut self, block: &BlockStatement) -> Type {
+        let mut result = Type::Any;
+
+        for stmt in &block.statements {
+            result = self.check_statement(stmt);
+        }
+
+        result
+    }
+
+    fn check_function(&mut self, function: &FunctionLiteral, self_type: Option<Type>) -> Type {
+        let mut params = Vec::new();
+        let mut scope = HashMap::new();
+
+        for (idx, param) in function.parameters.iter().enumerate() {
+            let ty = match (&param.type_annotation, &self_type) {
+                (Some(annotation), _) => self.check_annotation(annotation),
+                (None, Some(self_type)) if idx == 0 && param.value == "self" => self_type.clone(),
+                (None, _) => Type::Any,
+            };
+            scope.insert(param.value.clone(), ty.clone());
+            params.push(ty);
+        }
+
+        let return_type = function.return_type.as_ref().map(|annotation| self.check_annotation(annotation));
+
+        // a generator body produces values through yield, not through its result
+        let expected = if function.generator { None } else { return_type };
+
+        self.scopes.push(scope);
+        self.return_types.push(expected.clone());
+        self.returned.push(Vec::new());
+        let actual = self.check_block(&function.body);
+        let mut returned = self.returned.pop().unwrap_or_default();
+        self.return_types.pop();
+        self.scopes.pop();
+
+        if function.generator {
+            return Type::Function(params, Box::new(Type::Any));
+        }
+
+        let ret = match expected {
+            Some(expected) => {
+                if !compatible(&expected, &Type::Null) && falls_through(&function.body) {
+                    let msg = format!("return type mismatch: expected {}, got null", expected);
+                    self.errors.push(msg);
+                } else if !compatible(&expected, &actual) {
+                    let msg = format!(
+                        "return type mismatch: expected {}, got {}",
+                        expected, actual
+                    );
+                    self.errors.push(msg);
+                }
+                expected
+            }
+            // an early return can give back something else than the body's last value
+            None => {
+                returned.push(actual);
+                unify(returned).unwrap()
+            }
+        };
+
+        Type::Function(params, Box::new(ret))
+    }
+
+    fn infer(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Integer(_) => Type::Int,
+            Expr::String(_) => Type::String,
+            Expr::Boolean(_) => Type::Bool,
+            Expr::Identifier(ident) => self.lookup(&ident.value),
+            Expr::Prefix(prefix_expr) => {
+                let right = self.infer(&prefix_expr.right);
+
+                if prefix_expr.operator == "!" {
+                    return Type::Bool;
+                }
+                if !compatible(&Type::Int, &right) {
+                    let msg = format!("unknown operator: {}{}", prefix_expr.operator, right);
+                    self.errors.push(msg);
+                }
+                Type::Int
+            }
+            Expr::Infix(infix_expr) => {
+                let left = match &infix_expr.left {
+                    Some(left) => self.infer(left),
+                    None => Type::Any,
+                };
+                let right = match &infix_expr.right {
+                    Some(right) => self.infer(right),
+                    None => Type::Any,
+                };
+                self.infer_infix(&infix_expr.operator, left, right)
+            }
+            Expr::If(if_expr) => {
+                if let Some(condition) = &if_expr.condition {
+                    self.infer(condition);
+                }
+
+                let consequence = match &if_expr.consequence {
+                    Some(block) => self.check_block(block),
+                    None => Type::Any,
+                };
+                let alternative = match &if_expr.alternative {
+                    Some(block) => self.check_block(block),
+                    None => return Type::Any,
+                };
+
+                unify(vec![consequence, alternative]).unwrap()
+            }
+            Expr::Function(fn_literal) => self.check_function(fn_literal, None),
+            Expr::Call(call_expr) => {
+                let callee_name = match call_expr.function.as_ref() {
+                    Expr::Identifier(ident) if ident.value == "quote" => return Type::Any,
+                    Expr::Identifier(ident) => ident.value.clone(),
+                    _ => "function".to_string(),
+                };
+
+                let callee = self.infer(&call_expr.function);
+                let args: Vec<Type> = call_expr
+                    .arguments
+                    .iter()
+                    .map(|arg| self.infer(arg))
+                    .collect();
+
+                let Type::Function(params, ret) = callee else {
+                    return Type::Any;
+                };
+
+                if params.len() != args.len() {
+                    let msg = format!(
+                        "wrong number of arguments to {}: want={}, got={}",
+                        callee_name,
+                        params.len(),
+                        args.len()
+                    );
+                    self.errors.push(msg);
+                    return *ret;
+                }
+
+                for (idx, (param, arg)) in params.iter().zip(&args).enumerate() {
+                    if !compatible(param, arg) {
+                        let msg = format!(
+                            "argument {} to {}: expected {}, got {}",
+                            idx + 1,
+                            callee_name,
+                            param,
+                            arg
+                        );
+                        self.errors.push(msg);
+                    }
+                }
+
+                *ret
+            }
+            Expr::Array(array_literal) => {
+                let elements: Vec<Type> = array_literal
+                    .elements
+                    .iter()
+                    .map(|element| self.infer(element))
+                    .collect();
+
+                Type::Array(Box::new(unify(elements).unwrap_or(Type::Any)))
+            }
+            Expr::Hash(hash_literal) => {
+                let mut keys = Vec::new();
+                let mut values = Vec::new();
+                for (key, value) in &hash_literal.pairs {
+                    keys.push(self.infer(key));
+                    values.push(self.infer(value));
+                }
+
+                Type::Hash(
+                    Box::new(unify(keys).unwrap_or(Type::Any)),
+                    Box::new(unify(values).unwrap_or(Type::Any)),
+                )
+            }
+            Expr::Index(index_expr) => {
+                let left = self.infer(&index_expr.left);
+                self.infer(&index_expr.index);
+
+                match left {
+                    Type::Array(element) => *element,
+                    Type::Hash(_, value) => *value,
+                    _ => Type::Any,
+                }
+            }
+            Expr::Member(member_expr) => {
+                self.infer(&member_expr.object);
+                Type::Any
+            }
+            Expr::Assign(assign_expr) => {
+                self.infer(&assign_expr.target);
+                self.infer(&assign_expr.value)
+            }
+            Expr::Match(match_expr) => {
+                self.infer(&match_expr.subject);
+
+                let mut arms = Vec::new();
+                for arm in &match_expr.arms {
+                    let mut scope = HashMap::new();
+                    declare_pattern_bindings(&arm.pattern, &mut scope);
+
+                    self.scopes.push(scope);
+                    if let Some(guard) = &arm.guard {
+                        self.infer(guard);
+                    }
+                    arms.push(self.check_block(&arm.body));
+                    self.scopes.pop();
+                }
+
+                unify(arms).unwrap_or(Type::Any)
+            }
+            Expr::Try(try_expr) => {
+                self.check_block(&try_expr.block);
+
+                if let Some(catch_block) = &try_expr.catch_block {
+                    let mut scope = HashMap::new();
+                    if let Some(param) = &try_expr.catch_param {
+                        scope.insert(param.value.clone(), Type::Any);
+                    }
+                    self.scopes.push(scope);
+                    self.check_block(catch_block);
+                    self.scopes.pop();
+                }
+                if let Some(finally_block) = &try_expr.finally_block {
+                    self.check_block(finally_block);
+                }
+                Type::Any
+            }
+            Expr::Macro(_) => Type::Any,
+        }
+    }
+
+    fn infer_infix(&mut self, operator: &str, left: Type, right: Type) -> Type {
+        let result = match operator {
+            "==" | "!=" => return Type::Bool,
+            "<" | ">" => Type::Bool,
+            "+" if left == Type::String || right == Type::String => Type::String,
+            _ => Type::Int,
+        };
+
+        if left == Type::Any || right == Type::Any {
+            return if result == Type::String { Type::Any } else { result };
+        }
+
+        let valid = match (&left, &right) {
+            (Type::Int, Type::Int) => true,
+            (Type::String, Type::String) => operator == "+",
+            _ => false,
+        };
+
+        if !valid {
+            let msg = if left != right {
+                format!("type mismatch: {} {} {}", left, operator, right)
+            } else {
+                format!("unknown operator: {} {} {}", left, operator, right)
+            };
+            self.errors.push(msg);
+            return Type::Any;
+        }
+
+        result
+    }
+}
+
+fn declare_pattern_bindings(pattern: &Pattern, scope: &mut HashMap<String, Type>) {
+    match pattern {
+        Pattern::Binding(ident) => {
+            scope.insert(ident.value.clone(), Type::Any);
+        }
+        Pattern::Array { elements, rest, .. } => {
+            for element in elements {
+                declare_pattern_bindings(element, scope);
+            }
+            if let Some(rest) = rest {
+                declare_pattern_bindings(rest, scope);
+            }
+        }
+        Pattern::Hash { pairs, .. } => {
+            for (_, value) in pairs {
+                declare_pattern_bindings(value, scope);
+            }
+        }
+        Pattern::Variant { fields, .. } => {
+            for field in fields {
+                declare_pattern_bindings(field, scope);
+            }
+        }
+        Pattern::Or(alternatives) => {
+            for alternative in alternatives {
+                declare_pattern_bindings(alternative, scope);
+            }
+        }
+        Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+    }
+}
@@ -0,0 +1,22 @@
+use std::rc::Rc;
+
+use crate::object::{Closure, Scope};
+
+pub struct Frame {
+    pub closure: Rc<Closure>,
+    pub ip: usize,
+    pub scope: Rc<Scope>,
+    // the stack height the call started at, everything above it belongs to the call
+    pub base: usize,
+}
+
+impl Frame {
+    pub fn new(closure: Rc<Closure>, scope: Rc<Scope>, base: usize) -> Self {
+        Frame {
+            closure,
+            ip: 0,
+            scope,
+            base,
+        }
+    }
+}
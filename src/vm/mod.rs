@@ -0,0 +1,802 @@
+mod frame;
+
+use frame::Frame;
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    ast::modify::modify_expression,
+    code::{read_u16, Opcode, NO_TARGET},
+    compiler::Compiler,
+    evaluator::{
+        apply_function, builtin, convert_object_to_ast_node, eval_field_assignment,
+        eval_index_expression, eval_infix_expression, eval_member_expression,
+        eval_prefix_expression, find_method, is_truthy, load_module, module_exports, new_error,
+        names_unit_variant, objects_equal, throw_value, unquote_call, variant_object,
+    },
+    object::{
+        self, Closure, CompiledFunction, ErrorKind, Generator, GeneratorFrame, GeneratorState,
+        Limits, MatchPattern, ModuleCache, Scope, Value,
+    },
+};
+
+// how a generator's VM stopped
+pub enum Resumed {
+    Yield(Value),
+    Delegate(Value),
+    Done(Value),
+}
+
+// how a run of the dispatch loop ended. Try blocks run the loop nested, so a return
+// or throw can come back from a run that started in the middle of a function
+enum Completion {
+    Normal(Value),
+    Return(Option<Value>),
+    Throw(Value),
+    Yield(Value),
+    Delegate(Value),
+}
+
+pub struct Vm {
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    globals: Rc<Scope>,
+    modules: Rc<RefCell<ModuleCache>>,
+    // shared with the VMs running its generators and imports
+    limits: Rc<Limits>,
+    tail_delegate: bool,
+    // the source line of the instruction that raised the error being thrown
+    error_line: Option<usize>,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm::new_with_modules(Rc::new(RefCell::new(ModuleCache::default())))
+    }
+
+    pub fn new_with_modules(modules: Rc<RefCell<ModuleCache>>) -> Self {
+        Vm {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: Scope::new(0, None),
+            modules,
+            limits: Rc::new(Limits::default()),
+            tail_delegate: false,
+            error_line: None,
+        }
+    }
+
+    pub fn globals(&self) -> &Rc<Scope> {
+        &self.globals
+    }
+
+    pub fn modules(&self) -> &Rc<RefCell<ModuleCache>> {
+        &self.modules
+    }
+
+    // where the error the last run ended with was raised, if its function has a line table
+    pub fn error_line(&self) -> Option<usize> {
+        self.error_line
+    }
+
+    pub fn limits(&self) -> &Rc<Limits> {
+        &self.limits
+    }
+
+    // runs a compiled program against the globals of earlier programs, the result is
+    // what eval would return for it
+    pub fn run(&mut self, main: Rc<CompiledFunction>) -> Option<Value> {
+        let closure = Rc::new(Closure {
+            function: main,
+            env: self.globals.clone(),
+        });
+        self.stack.clear();
+        self.frames = vec![Frame::new(closure, self.globals.clone(), 0)];
+        self.error_line = None;
+
+        self.limits.start_run();
+        let completion = self.execute(1);
+        self.limits.end_run();
+        self.stack.clear();
+        self.frames.clear();
+
+        match completion {
+            Completion::Return(value) => value,
+            Completion::Throw(err) => Some(err),
+            Completion::Normal(value) | Completion::Yield(value) | Completion::Delegate(value) => Some(value),
+        }
+    }
+
+    // continues a generator's body until it yields, delegates or finishes
+    pub fn resume(&mut self) -> Resumed {
+        if self.frames.is_empty() {
+            return Resumed::Done(Value::Null);
+        }
+
+        self.tail_delegate = false;
+        self.error_line = None;
+        self.limits.start_run();
+        let completion = self.execute(1);
+        self.limits.end_run();
+        match completion {
+            Completion::Yield(value) => return Resumed::Yield(value),
+            Completion::Delegate(value) => return Resumed::Delegate(value),
+            _ => {}
+        }
+
+        self.frames.clear();
+        self.stack.clear();
+        match completion {
+            Completion::Throw(err) => Resumed::Done(err),
+            _ => Resumed::Done(Value::Null),
+        }
+    }
+
+    // whether the generator delegated as the last thing its body does
+    pub fn in_tail_delegate(&self) -> bool {
+        self.tail_delegate
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().unwrap()
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap_or(Value::Null)
+    }
+
+    fn pop_many(&mut self, count: usize) -> Vec<Value> {
+        let start = self.stack.len().saturating_sub(count);
+        self.stack.split_off(start)
+    }
+
+    // the helpers shared with the evaluator report failures as error values
+    fn push_result(&mut self, value: Value) -> Result<(), Value> {
+        if let Value::Error(_) = value {
+            return Err(value);
+        }
+        self.push(value);
+        Ok(())
+    }
+
+    // counts a new array, string or hash against the interpreter's memory quota
+    fn push_allocated(&mut self, value: Value) -> Result<(), Value> {
+        if let Err(msg) = self.limits.allocate(&value) {
+            return Err(new_error(ErrorKind::Error, msg));
+        }
+        self.push_result(value)
+    }
+
+    // runs until the frame at `floor` returns or the run reaches its EndTry. A throw
+    // unwinds everything the run pushed before handing the error back
+    fn execute(&mut self, floor: usize) -> Completion {
+        let stack_len = self.stack.len();
+        let scope = self.frame().scope.clone();
+
+        loop {
+            let result = match self.limits.step() {
+                Ok(()) => self.step(floor),
+                Err(msg) => Err(new_error(ErrorKind::Error, msg)),
+            };
+
+            match result {
+                Ok(None) => {}
+                Ok(Some(completion)) => return completion,
+                Err(err) => {
+                    self.pop_frames(floor);
+                    self.stack.truncate(stack_len);
+                    self.frame_mut().scope = scope;
+                    return Completion::Throw(err);
+                }
+            }
+        }
+    }
+
+    fn step(&mut self, floor: usize) -> Result<Option<Completion>, Value> {
+        let function = self.frame().closure.function.clone();
+        let ip = self.frame().ip;
+
+        // nested runs record the innermost instruction first
+        let result = self.dispatch(&function, ip, floor);
+        if result.is_err() && self.error_line.is_none() {
+            self.error_line = function.line_at(ip);
+        }
+        result
+    }
+
+    fn dispatch(
+        &mut self,
+        function: &Rc<CompiledFunction>,
+        ip: usize,
+        floor: usize,
+    ) -> Result<Option<Completion>, Value> {
+        let Some(op) = function.instructions.get(ip).and_then(|byte| Opcode::from_byte(*byte)) else {
+            return Err(new_error(ErrorKind::Error, format!("invalid instruction at {}", ip)));
+        };
+        let operands = &function.instructions[ip + 1..];
+        self.frame_mut().ip = ip + 1 + op.definition().operand_widths.iter().sum::<usize>();
+
+        match op {
+            Opcode::Constant => self.push(function.constants[read_u16(operands)].clone()),
+            Opcode::Pop => {
+                self.pop();
+            }
+            Opcode::Dup => {
+                let value = self.stack.last().cloned().unwrap_or(Value::Null);
+                self.push(value);
+            }
+            Opcode::Add
+            | Opcode::Sub
+            | Opcode::Mul
+            | Opcode::Div
+            | Opcode::Equal
+            | Opcode::NotEqual
+            | Opcode::GreaterThan
+            | Opcode::LessThan => {
+                let right = self.pop();
+                let left = self.pop();
+                self.push_allocated(eval_infix_expression(infix_operator(op), left, right))?;
+            }
+            Opcode::Minus => {
+                let right = self.pop();
+                self.push_result(eval_prefix_expression("-", right))?;
+            }
+            Opcode::Bang => {
+                let right = self.pop();
+                self.push_result(eval_prefix_expression("!", right))?;
+            }
+            Opcode::True => self.push(Value::Boolean(true)),
+            Opcode::False => self.push(Value::Boolean(false)),
+            Opcode::Null => self.push(Value::Null),
+            Opcode::Jump => self.frame_mut().ip = read_u16(operands),
+            Opcode::JumpNotTruthy => {
+                let condition = self.pop();
+                if !is_truthy(condition) {
+                    self.frame_mut().ip = read_u16(operands);
+                }
+            }
+            Opcode::GetLocal => {
+                let depth = operands[0] as usize;
+                match self.frame().scope.get(depth, read_u16(&operands[1..])) {
+                    Some(value) => self.push(value),
+                    None => {
+                        let name = constant_name(function, read_u16(&operands[3..]));
+                        return Err(new_error(ErrorKind::NameError, format!("identifier not found: {}", name)));
+                    }
+                }
+            }
+            Opcode::GetLocalOr => {
+                let depth = operands[0] as usize;
+                if let Some(value) = self.frame().scope.get(depth, read_u16(&operands[1..])) {
+                    self.push(value);
+                    self.frame_mut().ip = read_u16(&operands[3..]);
+                }
+            }
+            Opcode::SetLocal | Opcode::SetConst => {
+                let value = self.pop();
+                if !self.frame().scope.set(read_u16(operands), value, op == Opcode::SetConst) {
+                    let name = constant_name(function, read_u16(&operands[2..]));
+                    return Err(new_error(ErrorKind::ConstantError, format!("cannot redefine constant: {}", name)));
+                }
+            }
+            Opcode::GetBuiltin => {
+                let name = constant_name(function, read_u16(operands));
+                match builtin(name) {
+                    Some(builtin) => self.push(Value::Builtin(builtin)),
+                    None => return Err(new_error(ErrorKind::NameError, format!("identifier not found: {}", name))),
+                }
+            }
+            Opcode::Error => {
+                let kind = ErrorKind::from_byte(operands[0]).unwrap_or(ErrorKind::Error);
+                let message = constant_name(function, read_u16(&operands[1..]));
+                return Err(new_error(kind, message.to_string()));
+            }
+            Opcode::Array => {
+                let elements = self.pop_many(read_u16(operands));
+                self.push_allocated(Value::Array(Rc::new(object::Array { elements })))?;
+            }
+            Opcode::Hash => {
+                let items = self.pop_many(read_u16(operands) * 2);
+                let hash = build_hash(items)?;
+                self.push_allocated(hash)?;
+            }
+            Opcode::Index => {
+                let index = self.pop();
+                let left = self.pop();
+                self.push_result(eval_index_expression(left, index))?;
+            }
+            Opcode::Member => {
+                let object = self.pop();
+                let name = constant_name(function, read_u16(operands));
+                self.push_result(eval_member_expression(object, name))?;
+            }
+            Opcode::SetMember => {
+                let value = self.pop();
+                let object = self.pop();
+                let name = constant_name(function, read_u16(operands));
+                self.push_result(eval_field_assignment(object, name, value))?;
+            }
+            Opcode::Call | Opcode::TailCall => {
+                let name = constant_name(function, read_u16(operands));
+                let args = self.pop_many(operands[2] as usize);
+                let callee = self.pop();
+                let tail = op == Opcode::TailCall && self.frames.len() > floor;
+                self.call(name, callee, args, tail)?;
+            }
+            Opcode::CallMethod | Opcode::TailCallMethod => {
+                let name = constant_name(function, read_u16(operands));
+                let mut args = self.pop_many(operands[2] as usize);
+                let receiver = self.pop();
+                let tail = op == Opcode::TailCallMethod && self.frames.len() > floor;
+
+                match find_method(&receiver, name) {
+                    Some(method) => {
+                        args.insert(0, receiver);
+                        self.call(name, method, args, tail)?;
+                    }
+                    None => {
+                        let callee = eval_member_expression(receiver, name);
+                        if let Value::Error(_) = callee {
+                            return Err(callee);
+                        }
+                        self.call(name, callee, args, tail)?;
+                    }
+                }
+            }
+            Opcode::ReturnValue => {
+                let value = self.pop();
+                return Ok(self.return_from_frame(Some(value), floor));
+            }
+            Opcode::Return => return Ok(self.return_from_frame(None, floor)),
+            Opcode::Closure => {
+                let closure = Closure {
+                    function: function.functions[read_u16(operands)].clone(),
+                    env: self.frame().scope.clone(),
+                };
+                self.push(Value::Closure(Rc::new(closure)));
+            }
+            Opcode::Struct => {
+                let Value::StructType(template) = &function.constants[read_u16(operands)] else {
+                    unreachable!("OpStruct refers to a struct type")
+                };
+                self.push(Value::StructType(Rc::new(object::StructType {
+                    name: template.name.clone(),
+                    fields: template.fields.clone(),
+                    methods: RefCell::new(HashMap::new()),
+                })));
+            }
+            Opcode::Enum => {
+                let Value::EnumType(template) = &function.constants[read_u16(operands)] else {
+                    unreachable!("OpEnum refers to an enum type")
+                };
+                self.push(Value::EnumType(Rc::new(object::EnumType {
+                    name: template.name.clone(),
+                    variants: template
+                        .variants
+                        .iter()
+                        .map(|variant| object::Variant {
+                            name: variant.name.clone(),
+                            fields: variant.fields.clone(),
+                        })
+                        .collect(),
+                    methods: RefCell::new(HashMap::new()),
+                })));
+            }
+            // a bytecode file can put anything below OpVariant, even though the compiler
+            // never does
+            Opcode::Variant => {
+                let variant = read_u16(operands);
+                let enum_type = match self.stack.last() {
+                    Some(Value::EnumType(enum_type)) if variant < enum_type.variants.len() => enum_type.clone(),
+                    _ => return Err(new_error(ErrorKind::Error, format!("invalid instruction at {}", ip))),
+                };
+                self.push(variant_object(enum_type, variant));
+            }
+            Opcode::Impl => {
+                let Value::Array(names) = &function.constants[read_u16(operands)] else {
+                    unreachable!("OpImpl refers to the method names")
+                };
+                let methods = self.pop_many(names.elements.len());
+                let type_object = self.pop();
+                implement_methods(&type_object, &names.elements, methods)?;
+            }
+            Opcode::Import => {
+                let path = constant_name(function, read_u16(operands));
+                let module = self.import(path);
+                self.push_result(module)?;
+            }
+            Opcode::Throw => {
+                let value = self.pop();
+                return Err(throw_value(value));
+            }
+            Opcode::Try => {
+                let catch = read_u16(operands);
+                let finally = read_u16(&operands[2..]);
+                let end = read_u16(&operands[4..]);
+                return self.execute_try(catch, finally, end, floor);
+            }
+            Opcode::EndTry => {
+                let value = self.pop();
+                return Ok(Some(Completion::Normal(value)));
+            }
+            Opcode::PushScope => {
+                let frame = self.frame_mut();
+                frame.scope = Scope::new(read_u16(operands), Some(frame.scope.clone()));
+            }
+            Opcode::PopScope => {
+                let frame = self.frame_mut();
+                let Some(outer) = frame.scope.outer().cloned() else {
+                    return Err(new_error(ErrorKind::Error, format!("invalid instruction at {}", ip)));
+                };
+                frame.scope = outer;
+            }
+            Opcode::Match => {
+                let subject = self.stack.last().cloned().unwrap_or(Value::Null);
+                let mut bindings = Vec::new();
+
+                let scope = &self.frame().scope;
+                if match_pattern(&function.patterns[read_u16(operands)], subject, scope, &mut bindings)? {
+                    for (slot, value) in bindings {
+                        scope.set(slot, value, false);
+                    }
+                } else {
+                    self.frame_mut().ip = read_u16(&operands[2..]);
+                }
+            }
+            Opcode::Quote => {
+                let Value::Quote(node) = &function.constants[read_u16(operands)] else {
+                    unreachable!("OpQuote refers to a quoted node")
+                };
+                let mut values = self.pop_many(read_u16(&operands[2..])).into_iter();
+
+                let node = modify_expression(node.clone(), &mut |node| {
+                    let Some(call_expr) = unquote_call(&node) else {
+                        return node;
+                    };
+                    if call_expr.arguments.len() != 1 {
+                        return node;
+                    }
+
+                    match values.next().and_then(convert_object_to_ast_node) {
+                        Some(converted) => converted,
+                        None => node,
+                    }
+                });
+                self.push(Value::Quote(node));
+            }
+            Opcode::Yield => {
+                let value = self.pop();
+                return Ok(Some(Completion::Yield(value)));
+            }
+            Opcode::YieldDelegate => {
+                self.tail_delegate = operands[0] == 1;
+                let value = self.pop();
+                return Ok(Some(Completion::Delegate(value)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // a tail call replaces the frame making it, unless the frame is the one the current
+    // run of the dispatch loop has to return from. Calls count against the call depth
+    // under `name` like the evaluator's, until their frame is popped
+    fn call(&mut self, name: &str, callee: Value, args: Vec<Value>, tail: bool) -> Result<(), Value> {
+        let Value::Closure(closure) = callee else {
+            return self.push_allocated(apply_function(callee, args, None));
+        };
+
+        // missing arguments leave their parameters unbound, extra ones are dropped
+        let scope = Scope::new(closure.function.num_locals, Some(closure.env.clone()));
+        for (slot, arg) in args.into_iter().take(closure.function.parameters.len()).enumerate() {
+            scope.set(slot, arg, false);
+        }
+
+        if closure.function.generator {
+            let generator = self.new_generator(closure, scope);
+            self.push(generator);
+            return Ok(());
+        }
+
+        if tail {
+            let base = self.frame().base;
+            self.pop_frames(self.frames.len() - 1);
+            self.stack.truncate(base);
+        }
+
+        // frames live on the heap, but runaway recursion would still grow them until the
+        // process runs out of memory
+        if let Err(msg) = self.limits.enter_call(name) {
+            return Err(new_error(ErrorKind::Error, msg));
+        }
+
+        let base = self.stack.len();
+        self.frames.push(Frame::new(closure, scope, base));
+        Ok(())
+    }
+
+    // a generator gets a VM of its own, which keeps the suspended body between resumes
+    fn new_generator(&self, closure: Rc<Closure>, scope: Rc<Scope>) -> Value {
+        let mut vm = Vm::new_with_modules(self.modules.clone());
+        vm.limits = self.limits.clone();
+        vm.frames.push(Frame::new(closure, scope, 0));
+
+        Value::Generator(Rc::new(Generator {
+            state: RefCell::new(GeneratorState {
+                frames: vec![GeneratorFrame::Compiled(Box::new(vm))],
+                running: false,
+                done: false,
+            }),
+        }))
+    }
+
+    fn return_from_frame(&mut self, value: Option<Value>, floor: usize) -> Option<Completion> {
+        if self.frames.len() == floor {
+            return Some(Completion::Return(value));
+        }
+
+        let base = self.frame().base;
+        self.pop_frames(self.frames.len() - 1);
+        self.stack.truncate(base);
+        self.push(value.unwrap_or(Value::Null));
+        None
+    }
+
+    // only frames pushed by `call` are popped, so each of them leaves its call
+    fn pop_frames(&mut self, len: usize) {
+        while self.frames.len() > len {
+            self.frames.pop();
+            self.limits.exit_call();
+        }
+    }
+
+    // the try block, the catch block and the finally block each run the dispatch loop
+    // nested, so their outcome can be looked at before the try expression finishes
+    fn execute_try(
+        &mut self,
+        catch: usize,
+        finally: usize,
+        end: usize,
+        floor: usize,
+    ) -> Result<Option<Completion>, Value> {
+        // each try runs the dispatch loop nested, so calls made inside them use the stack
+        self.limits.check_stack().map_err(|msg| new_error(ErrorKind::Error, msg))?;
+
+        let depth = self.frames.len();
+        let mut result = self.execute(depth);
+
+        // a script can't catch being stopped
+        let catchable = catch != NO_TARGET && !self.limits.stopped();
+        if let (Completion::Throw(Value::Error(error)), true) = (&result, catchable) {
+            let exception = Value::Exception(Rc::new(object::Exception {
+                kind: error.kind,
+                message: error.message.clone(),
+                value: error.value.clone().unwrap_or(Value::Null),
+            }));
+            self.frame_mut().ip = catch;
+            self.error_line = None;
+            self.push(exception);
+            result = self.execute(depth);
+        }
+
+        if finally != NO_TARGET {
+            self.frame_mut().ip = finally;
+            let finally_result = self.execute(depth);
+            if matches!(finally_result, Completion::Return(_) | Completion::Throw(_)) {
+                result = finally_result;
+            }
+        }
+
+        self.frame_mut().ip = end;
+        match result {
+            Completion::Normal(value) => {
+                self.push(value);
+                Ok(None)
+            }
+            Completion::Return(value) => Ok(self.return_from_frame(value, floor)),
+            Completion::Throw(err) => Err(err),
+            completion => Ok(Some(completion)),
+        }
+    }
+
+    fn import(&self, path: &str) -> Value {
+        let limits = self.limits.clone();
+        load_module(path, self.modules.clone(), |program, modules| {
+            let mut compiler = Compiler::new();
+            let main = compiler.compile(program).map_err(|err| {
+                new_error(ErrorKind::ImportError, format!(
+                    "compile error in module \"{}\": {}",
+                    path, err
+                ))
+            })?;
+
+            let mut vm = Vm::new_with_modules(modules);
+            vm.limits = limits;
+            if let Some(Value::Error(err)) = vm.run(main) {
+                return Err(Value::Error(err));
+            }
+
+            Ok(module_exports(program, |name| {
+                compiler.global_slot(name).and_then(|slot| vm.globals.get(0, slot))
+            }))
+        })
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn infix_operator(op: Opcode) -> &'static str {
+    match op {
+        Opcode::Add => "+",
+        Opcode::Sub => "-",
+        Opcode::Mul => "*",
+        Opcode::Div => "/",
+        Opcode::Equal => "==",
+        Opcode::NotEqual => "!=",
+        Opcode::GreaterThan => ">",
+        _ => "<",
+    }
+}
+
+fn constant_name(function: &CompiledFunction, idx: usize) -> &str {
+    match &function.constants[idx] {
+        Value::String(value) => value,
+        _ => "",
+    }
+}
+
+fn build_hash(items: Vec<Value>) -> Result<Value, Value> {
+    let mut pairs = HashMap::new();
+    let mut items = items.into_iter();
+
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        let hash_key = key.hash_key().map_err(|_| {
+            new_error(ErrorKind::TypeError, format!("unusable as hash key: {}", key.get_type()))
+        })?;
+        pairs.insert(hash_key, object::hash::HashPair { key, value });
+    }
+
+    Ok(Value::Hash(Rc::new(object::Hash { pairs })))
+}
+
+fn implement_methods(type_object: &Value, names: &[Value], methods: Vec<Value>) -> Result<(), Value> {
+    let table = match type_object {
+        Value::StructType(struct_type) => &struct_type.methods,
+        Value::EnumType(enum_type) => &enum_type.methods,
+        _ => {
+            return Err(new_error(ErrorKind::TypeError, format!(
+                "cannot implement methods for {}",
+                type_object.get_type()
+            )))
+        }
+    };
+
+    for (name, method) in names.iter().zip(methods) {
+        if let Value::String(name) = name {
+            table.borrow_mut().insert(name.to_string(), method);
+        }
+    }
+    Ok(())
+}
+
+// bindings are only made once the whole pattern matched, like in the evaluator
+fn match_pattern(
+    pattern: &MatchPattern,
+    value: Value,
+    scope: &Scope,
+    bindings: &mut Vec<(usize, Value)>,
+) -> Result<bool, Value> {
+    match pattern {
+        MatchPattern::Wildcard => Ok(true),
+        MatchPattern::Binding { slot, name, shadowed } => {
+            let shadowed = shadowed.iter().find_map(|(depth, slot)| scope.get(*depth, *slot));
+            if let Some(shadowed) = shadowed {
+                if names_unit_variant(name, &shadowed) && !objects_equal(&shadowed, &value) {
+                    return Ok(false);
+                }
+            }
+            bindings.push((*slot, value));
+            Ok(true)
+        }
+        MatchPattern::Literal(literal) => Ok(objects_equal(literal, &value)),
+        MatchPattern::Or(alternatives) => {
+            for alternative in alternatives {
+                let mut alt_bindings = Vec::new();
+                if match_pattern(alternative, value.clone(), scope, &mut alt_bindings)? {
+                    bindings.append(&mut alt_bindings);
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        MatchPattern::Variant {
+            enum_name,
+            name,
+            fields,
+        } => {
+            let Value::EnumValue(enum_value) = &value else {
+                return Ok(false);
+            };
+
+            let wrong_enum = enum_name
+                .as_ref()
+                .is_some_and(|enum_name| enum_name != enum_value.enum_name());
+            if wrong_enum || &enum_value.definition().name != name {
+                return Ok(false);
+            }
+
+            if fields.len() != enum_value.values.len() {
+                return Ok(false);
+            }
+
+            for (field_pattern, field) in fields.iter().zip(&enum_value.values) {
+                if !match_pattern(field_pattern, field.clone(), scope, bindings)? {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        }
+        MatchPattern::Array { elements, rest } => {
+            let Value::Array(array) = &value else {
+                return Ok(false);
+            };
+
+            let too_short = array.elements.len() < elements.len();
+            let wrong_len = rest.is_none() && array.elements.len() != elements.len();
+            if too_short || wrong_len {
+                return Ok(false);
+            }
+
+            for (element_pattern, element) in elements.iter().zip(&array.elements) {
+                if !match_pattern(element_pattern, element.clone(), scope, bindings)? {
+                    return Ok(false);
+                }
+            }
+
+            match rest {
+                Some(rest_pattern) => {
+                    let remaining = Value::Array(Rc::new(object::Array {
+                        elements: array.elements[elements.len()..].to_vec(),
+                    }));
+                    match_pattern(rest_pattern, remaining, scope, bindings)
+                }
+                None => Ok(true),
+            }
+        }
+        MatchPattern::Hash(pairs) => {
+            let Value::Hash(hash) = &value else {
+                return Ok(false);
+            };
+
+            for (key, value_pattern) in pairs {
+                let hash_key = key.hash_key().map_err(|_| {
+                    new_error(ErrorKind::TypeError, format!("unusable as hash key: {}", key.get_type()))
+                })?;
+
+                let pair_value = match hash.pairs.get(&hash_key) {
+                    Some(pair) => pair.value.clone(),
+                    None => return Ok(false),
+                };
+
+                if !match_pattern(value_pattern, pair_value, scope, bindings)? {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        }
+    }
+}